@@ -0,0 +1,11 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Use the bundled protoc unless one is provided explicitly.
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+    println!("cargo:rerun-if-changed=proto/hft.proto");
+    // Embedded by `sqlx::migrate!`.
+    println!("cargo:rerun-if-changed=migrations");
+    tonic_prost_build::compile_protos("proto/hft.proto")?;
+    Ok(())
+}
@@ -0,0 +1,22 @@
+;; A synthetic spread instrument for `[[plugins]]`: stock 2 trades at the
+;; difference between stock 0 and stock 1. Stock 2's own simulated ticks are
+;; dropped, and every tick of either leg emits a new spread tick once both
+;; legs have a price. Other ticks pass unchanged.
+(module
+  (import "env" "emit" (func $emit (param i32 f64)))
+  (global $leg0 (mut f64) (f64.const nan))
+  (global $leg1 (mut f64) (f64.const nan))
+
+  (func (export "on_tick") (param $stock_id i32) (param $price f64) (result f64)
+    (if (i32.eq (local.get $stock_id) (i32.const 2))
+      (then (return (f64.const nan))))
+    (if (i32.eq (local.get $stock_id) (i32.const 0))
+      (then (global.set $leg0 (local.get $price))))
+    (if (i32.eq (local.get $stock_id) (i32.const 1))
+      (then (global.set $leg1 (local.get $price))))
+    ;; NaN is the only value not equal to itself.
+    (if (i32.and
+          (f64.eq (global.get $leg0) (global.get $leg0))
+          (f64.eq (global.get $leg1) (global.get $leg1)))
+      (then (call $emit (i32.const 2) (f64.sub (global.get $leg0) (global.get $leg1)))))
+    (local.get $price)))
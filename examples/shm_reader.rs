@@ -0,0 +1,93 @@
+//! Follows the shared-memory tick ring written by `[sinks.shm]` and prints
+//! each tick with its writer-to-reader hand-off latency.
+//!
+//! ```bash
+//! cargo run --release --example shm_reader -- /dev/shm/hft-ticks
+//! ```
+//!
+//! The reader busy-spins on one core. See `src/sinks/shm.rs` for the layout.
+
+use std::fs::File;
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use memmap2::Mmap;
+
+const MAGIC: u64 = u64::from_le_bytes(*b"HFTRING1");
+const WRITE_SEQ_WORD: usize = 8;
+const SLOTS_OFFSET_WORDS: usize = 16;
+const SLOT_WORDS: usize = 8;
+
+struct Tick {
+    stock_id: i32,
+    price: f64,
+    ts_ns: u64,
+    publish_ns: u64,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::args().nth(1).unwrap_or_else(|| "/dev/shm/hft-ticks".to_string());
+    let file = File::open(&path)?;
+    // SAFETY: the writer only modifies the file through atomics.
+    let map = unsafe { Mmap::map(&file)? };
+    if map.len() < SLOTS_OFFSET_WORDS * 8 {
+        return Err(format!("{} is too small to be a tick ring", path).into());
+    }
+    // SAFETY: page-aligned, and every index used is within the sizes checked below.
+    let word = |index: usize| unsafe { &*(map.as_ptr() as *const AtomicU64).add(index) };
+
+    if word(0).load(Ordering::Acquire) != MAGIC {
+        return Err(format!("{} is not a tick ring (is the sink running?)", path).into());
+    }
+    let capacity = word(1).load(Ordering::Relaxed);
+    if map.len() < (SLOTS_OFFSET_WORDS + capacity as usize * SLOT_WORDS) * 8 {
+        return Err(format!("{} is shorter than its {} slots", path, capacity).into());
+    }
+    let write_seq = word(WRITE_SEQ_WORD);
+    let mut pos = write_seq.load(Ordering::Acquire);
+    println!("Following {} ({} slots) from tick {}", path, capacity, pos);
+
+    loop {
+        let head = write_seq.load(Ordering::Acquire);
+        if head == pos {
+            std::hint::spin_loop();
+            continue;
+        }
+        if head < pos {
+            println!("Writer restarted, rewinding");
+            pos = 0;
+            continue;
+        }
+        if head - pos > capacity {
+            println!("Lost {} ticks", head - pos - capacity);
+            pos = head - capacity;
+        }
+
+        let slot = SLOTS_OFFSET_WORDS + (pos % capacity) as usize * SLOT_WORDS;
+        let seq = word(slot).load(Ordering::Acquire);
+        if seq != 2 * pos + 2 {
+            // Overwritten (or being overwritten) since we read `head`.
+            continue;
+        }
+        let tick = Tick {
+            stock_id: word(slot + 1).load(Ordering::Relaxed) as u32 as i32,
+            price: f64::from_bits(word(slot + 2).load(Ordering::Relaxed)),
+            ts_ns: word(slot + 3).load(Ordering::Relaxed),
+            publish_ns: word(slot + 4).load(Ordering::Relaxed),
+        };
+        fence(Ordering::Acquire);
+        if word(slot).load(Ordering::Relaxed) != seq {
+            continue;
+        }
+        pos += 1;
+
+        let now_ns = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO).as_nanos() as u64;
+        println!(
+            "stock={} price={:.4} handoff={}ns tick_age={}us",
+            tick.stock_id,
+            tick.price,
+            now_ns.saturating_sub(tick.publish_ns),
+            now_ns.saturating_sub(tick.ts_ns) / 1000
+        );
+    }
+}
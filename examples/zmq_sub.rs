@@ -0,0 +1,47 @@
+//! Subscribes to the ZeroMQ PUB sink and prints each tick with its
+//! publish-to-receive latency.
+//!
+//! ```bash
+//! cargo run --features zmq --example zmq_sub -- tcp://127.0.0.1:5556 stock:AAPL
+//! ```
+//!
+//! The optional second argument is an exact topic to filter on.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use zeromq::{Socket, SocketRecv, SubSocket};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let endpoint = args.next().unwrap_or_else(|| "tcp://127.0.0.1:5556".to_string());
+    let topic = args.next();
+
+    let mut socket = SubSocket::new();
+    socket.connect(&endpoint).await?;
+    socket.subscribe(topic.as_deref().unwrap_or("stock:")).await?;
+    println!("Subscribed to {} on {}", topic.as_deref().unwrap_or("all stocks"), endpoint);
+
+    loop {
+        let msg = socket.recv().await?;
+        let (Some(frame_topic), Some(payload)) = (msg.get(0), msg.get(1)) else {
+            continue;
+        };
+        let frame_topic = String::from_utf8_lossy(frame_topic);
+        if topic.as_deref().is_some_and(|t| t != frame_topic) {
+            continue;
+        }
+        let payload = String::from_utf8_lossy(payload);
+        let now_us = SystemTime::now().duration_since(UNIX_EPOCH)?.as_micros() as i64;
+        match ts_us(&payload) {
+            Some(ts) => println!("{} {} latency={}us", frame_topic, payload, now_us - ts),
+            None => println!("{} {}", frame_topic, payload),
+        }
+    }
+}
+
+/// Pulls `ts_us` out of the tick JSON without a JSON dependency.
+fn ts_us(payload: &str) -> Option<i64> {
+    let rest = &payload[payload.find("\"ts_us\":")? + 8..];
+    rest[..rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len())].parse().ok()
+}
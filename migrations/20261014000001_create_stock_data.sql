@@ -0,0 +1,11 @@
+-- Ticks flushed from the spool. IF NOT EXISTS keeps databases set up by hand
+-- from the readme working.
+CREATE TABLE IF NOT EXISTS stock_data (
+    id SERIAL PRIMARY KEY,
+    stock_id INT NOT NULL,
+    price REAL NOT NULL,
+    ts TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS stock_data_ts_idx ON stock_data (ts);
+CREATE INDEX IF NOT EXISTS stock_data_stock_id_ts_idx ON stock_data (stock_id, ts);
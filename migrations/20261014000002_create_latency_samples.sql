@@ -0,0 +1,10 @@
+-- One row per timed pipeline stage, as in export/latency/*.parquet.
+CREATE TABLE IF NOT EXISTS latency_samples (
+    id BIGSERIAL PRIMARY KEY,
+    stage TEXT NOT NULL,
+    stock_id INT,
+    at TIMESTAMPTZ NOT NULL,
+    nanos BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS latency_samples_stage_at_idx ON latency_samples (stage, at);
@@ -0,0 +1,11 @@
+-- Raised alerts, e.g. latency thresholds crossed or a sink going down.
+CREATE TABLE IF NOT EXISTS alerts (
+    id BIGSERIAL PRIMARY KEY,
+    raised_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    severity TEXT NOT NULL,
+    source TEXT NOT NULL,
+    message TEXT NOT NULL,
+    resolved_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS alerts_raised_at_idx ON alerts (raised_at);
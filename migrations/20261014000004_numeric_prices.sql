@@ -0,0 +1,2 @@
+-- Exact prices; REAL kept only about seven significant digits.
+ALTER TABLE stock_data ALTER COLUMN price TYPE NUMERIC(18, 6);
@@ -0,0 +1,8 @@
+-- Metadata of the configured symbols, rewritten on every start. stock_data
+-- rows join on stock_id.
+CREATE TABLE IF NOT EXISTS symbols (
+    stock_id INT PRIMARY KEY,
+    ticker TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    tick_size NUMERIC(18, 6) NOT NULL
+);
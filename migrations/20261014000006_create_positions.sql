@@ -0,0 +1,10 @@
+-- The paper strategy's holdings, upserted once a second and loaded on start.
+-- Prices are in price units; unrealized is as of updated_at.
+CREATE TABLE IF NOT EXISTS positions (
+    stock_id INT PRIMARY KEY,
+    qty BIGINT NOT NULL,
+    avg_cost NUMERIC(18, 6) NOT NULL,
+    realized NUMERIC(18, 6) NOT NULL,
+    unrealized NUMERIC(18, 6) NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Fees paid on the paper strategy's fills, in price units.
+ALTER TABLE positions ADD COLUMN IF NOT EXISTS fees NUMERIC(18, 6) NOT NULL DEFAULT 0;
@@ -0,0 +1,12 @@
+-- Trading halts of simulated symbols, scheduled or from limit-up/limit-down
+-- breaches; resumed_at is empty while the halt lasts.
+CREATE TABLE IF NOT EXISTS halts (
+    id BIGSERIAL PRIMARY KEY,
+    stock_id INT NOT NULL,
+    ticker TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    halted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resumed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS halts_halted_at_idx ON halts (halted_at);
@@ -0,0 +1,13 @@
+-- Each symbol's consolidated touch, microprice and size-weighted mid from the
+-- paper venues' displayed depth, recorded once a second.
+CREATE TABLE IF NOT EXISTS microprices (
+    id BIGSERIAL PRIMARY KEY,
+    stock_id INT NOT NULL,
+    bid NUMERIC(18, 6) NOT NULL,
+    ask NUMERIC(18, 6) NOT NULL,
+    microprice NUMERIC(18, 6) NOT NULL,
+    weighted_mid NUMERIC(18, 6) NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS microprices_stock_id_recorded_at_idx ON microprices (stock_id, recorded_at);
@@ -0,0 +1,13 @@
+-- OHLCV bars of each symbol's ticks per interval, written as they close.
+-- volume counts ticks, which carry no size.
+CREATE TABLE IF NOT EXISTS candles (
+    stock_id INT NOT NULL,
+    interval_s INT NOT NULL,
+    start_at TIMESTAMPTZ NOT NULL,
+    open NUMERIC(18, 6) NOT NULL,
+    high NUMERIC(18, 6) NOT NULL,
+    low NUMERIC(18, 6) NOT NULL,
+    close NUMERIC(18, 6) NOT NULL,
+    volume BIGINT NOT NULL,
+    PRIMARY KEY (stock_id, interval_s, start_at)
+);
@@ -0,0 +1,5 @@
+-- Each tick's ID from the pipeline, so a flush repeated after a crash or a
+-- retry inserts nothing new. Rows from before it have none, and NULLs never
+-- conflict.
+ALTER TABLE stock_data ADD COLUMN IF NOT EXISTS tick_id TEXT;
+CREATE UNIQUE INDEX IF NOT EXISTS stock_data_tick_id_idx ON stock_data (tick_id);
@@ -0,0 +1,82 @@
+//! Pins threads to the cores named in `[affinity]` and remembers the outcome
+//! for the diagnostics panel.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tracing::{info, warn};
+
+use crate::config::AffinityConfig;
+
+/// Role of the thread and the core it was pinned to, or why it was not.
+type Pinned = (String, Result<usize, String>);
+
+/// Which thread ended up where, in pinning order.
+#[derive(Clone, Default)]
+pub struct AffinityReport {
+    entries: Arc<Mutex<Vec<Pinned>>>,
+}
+
+impl AffinityReport {
+    /// Pins the calling thread to `core`, if one is configured.
+    pub fn pin_current(&self, role: &str, core: Option<usize>) {
+        let Some(core) = core else {
+            return;
+        };
+        let result = pin(core);
+        match &result {
+            Ok(core) => info!("Pinned {} to core {}", role, core),
+            Err(e) => warn!("Could not pin {} to core {}: {}", role, core, e),
+        }
+        self.entries.lock().unwrap().push((role.to_string(), result));
+    }
+
+    /// One-line summary for the TUI.
+    pub fn describe(&self) -> String {
+        let entries = self.entries.lock().unwrap();
+        if entries.is_empty() {
+            return "unpinned (set [affinity] to pin threads)".to_string();
+        }
+        entries
+            .iter()
+            .map(|(role, result)| match result {
+                Ok(core) => format!("{}=core {}", role, core),
+                Err(e) => format!("{}=FAILED ({})", role, e),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn pin(core: usize) -> Result<usize, String> {
+    let cores = core_affinity::get_core_ids().ok_or("cannot list cores")?;
+    let id = cores
+        .into_iter()
+        .find(|c| c.id == core)
+        .ok_or_else(|| format!("no core {}", core))?;
+    if core_affinity::set_for_current(id) {
+        Ok(core)
+    } else {
+        Err("rejected by the OS".to_string())
+    }
+}
+
+/// Builds the main multi-threaded runtime. With `tokio_workers` set, it gets
+/// one worker per listed core, each pinned to its core.
+pub fn build_runtime(cfg: &AffinityConfig, report: &AffinityReport) -> std::io::Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all();
+    if !cfg.tokio_workers.is_empty() {
+        let cores = cfg.tokio_workers.clone();
+        let report = report.clone();
+        let started = Arc::new(AtomicUsize::new(0));
+        builder.worker_threads(cores.len()).on_thread_start(move || {
+            // Workers are spawned first; later blocking-pool threads stay unpinned.
+            let n = started.fetch_add(1, Ordering::Relaxed);
+            if let Some(&core) = cores.get(n) {
+                report.pin_current(&format!("tokio-{}", n), Some(core));
+            }
+        });
+    }
+    builder.build()
+}
@@ -0,0 +1,426 @@
+//! Merges ticks from the `[[feeds]]` sources with the simulator's into one
+//! stream. External ticks are mapped to local stock ids, applied to the
+//! market data and published exactly like simulated ones. Each source keeps
+//! its own tick count, rate and lag for the Diagnostics panel, plus the
+//! arbitration counters of multicast A/B pairs.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use futures::StreamExt;
+use hdrhistogram::Histogram;
+use serde::Deserialize;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{error, info, info_span, warn, Instrument, Span};
+
+use crate::arbiter::{ArbStats, Arbiter, SIDES};
+use crate::config::FeedConfig;
+use crate::health::{HealthHandle, HealthRegistry};
+use crate::market::SharedMarketData;
+use crate::net::join_multicast;
+use crate::price::Price;
+use crate::publish::Publisher;
+use crate::sbe;
+use crate::spool;
+use crate::symbols::Symbol;
+use crate::ulid::Ulid;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+/// Ticks buffered between the sources and the thread applying them.
+const CHANNEL_CAPACITY: usize = 10_000;
+/// Lags above this are clamped into the top histogram bucket.
+const MAX_TRACKED_NANOS: u64 = 60_000_000_000;
+
+/// Per-source counters. Lag is how long after its source timestamp (live
+/// feeds) or its scheduled replay time a tick was published.
+pub struct FeedStats {
+    name: String,
+    ticks: AtomicU64,
+    /// Malformed messages and unknown symbols.
+    dropped: AtomicU64,
+    lag: Mutex<Histogram<u64>>,
+    /// `(since, ticks then, ticks per second over the last window)`.
+    rate: Mutex<(Instant, u64, f64)>,
+    arbitration: Option<Arc<ArbStats>>,
+}
+
+impl FeedStats {
+    pub fn record(&self, lag: Duration) {
+        self.ticks.fetch_add(1, Ordering::Relaxed);
+        let nanos = (lag.as_nanos() as u64).clamp(1, MAX_TRACKED_NANOS);
+        let _ = self.lag.lock().unwrap().record(nanos);
+    }
+
+    fn drop_one(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// `upstream 250/s lag p50 0.41ms p99 1.20ms, 3 dropped`, with the
+    /// arbitration counters in brackets for multicast feeds.
+    fn describe(&self) -> String {
+        let ticks = self.ticks.load(Ordering::Relaxed);
+        let per_sec = {
+            let mut rate = self.rate.lock().unwrap();
+            let elapsed = rate.0.elapsed();
+            if elapsed >= Duration::from_secs(1) {
+                *rate = (Instant::now(), ticks, (ticks - rate.1) as f64 / elapsed.as_secs_f64());
+            }
+            rate.2
+        };
+        let lag = self.lag.lock().unwrap();
+        let ms = |q| lag.value_at_quantile(q) as f64 / 1e6;
+        let mut out = format!("{} {:.0}/s lag p50 {:.2}ms p99 {:.2}ms", self.name, per_sec, ms(0.5), ms(0.99));
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        if dropped > 0 {
+            out += &format!(", {} dropped", dropped);
+        }
+        if let Some(arbitration) = &self.arbitration {
+            out += &format!(" [{}]", arbitration.describe());
+        }
+        out
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct FeedRegistry {
+    feeds: Arc<Mutex<Vec<Arc<FeedStats>>>>,
+}
+
+impl FeedRegistry {
+    pub fn register(&self, name: impl Into<String>) -> Arc<FeedStats> {
+        self.add(name.into(), None)
+    }
+
+    fn add(&self, name: String, arbitration: Option<Arc<ArbStats>>) -> Arc<FeedStats> {
+        let stats = Arc::new(FeedStats {
+            name,
+            ticks: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            lag: Mutex::new(Histogram::new_with_bounds(1, MAX_TRACKED_NANOS, 3).unwrap()),
+            rate: Mutex::new((Instant::now(), 0, 0.0)),
+            arbitration,
+        });
+        self.feeds.lock().unwrap().push(Arc::clone(&stats));
+        stats
+    }
+
+    pub fn describe(&self) -> String {
+        let feeds = self.feeds.lock().unwrap();
+        if feeds.is_empty() {
+            return "none".to_string();
+        }
+        feeds.iter().map(|f| f.describe()).collect::<Vec<_>>().join(" | ")
+    }
+}
+
+/// A tick from an external source, already mapped to a local stock id.
+struct Incoming {
+    stats: Arc<FeedStats>,
+    stock_id: usize,
+    price: Price,
+    ts: SystemTime,
+    /// Kept from the source, which for a replay or another instance's `/ws`
+    /// may have one.
+    id: Option<Ulid>,
+    lag: Duration,
+}
+
+/// Starts one task per configured feed and a thread applying their ticks.
+/// Must be called from within the Tokio runtime. A replay speed of 0 or
+/// less is refused before anything starts.
+pub fn spawn(
+    feeds: &[FeedConfig],
+    symbols: Arc<Vec<Symbol>>,
+    market: SharedMarketData,
+    publisher: Publisher,
+    registry: &FeedRegistry,
+    health: &HealthRegistry,
+) -> io::Result<()> {
+    for feed in feeds {
+        if let FeedConfig::Replay { name, speed, .. } = feed {
+            if !(speed.is_finite() && *speed > 0.0) {
+                let message = format!("feeds.{}.speed: must be a number above 0", name);
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+            }
+        }
+    }
+    if feeds.is_empty() {
+        return Ok(());
+    }
+    let (tx, mut rx) = mpsc::channel::<Incoming>(CHANNEL_CAPACITY);
+    let by_ticker: Arc<HashMap<String, usize>> =
+        Arc::new(symbols.iter().enumerate().map(|(id, s)| (s.ticker.clone(), id)).collect());
+
+    for feed in feeds {
+        let tx = tx.clone();
+        match feed.clone() {
+            FeedConfig::Websocket { name, url } => {
+                let stats = registry.register(name.clone());
+                let health = health.register(format!("feed {}", name));
+                let task = run_websocket(url, Arc::clone(&by_ticker), tx, stats, health);
+                tokio::spawn(task.instrument(span(&name)));
+            }
+            FeedConfig::Replay { name, path, speed, repeat } => {
+                let stats = registry.register(name.clone());
+                tokio::spawn(run_replay(path, speed, repeat, symbols.len(), tx, stats).instrument(span(&name)));
+            }
+            FeedConfig::Multicast { name, group, secondary_group, interface, stall_ms } => {
+                let groups: Vec<String> = std::iter::once(group).chain(secondary_group).collect();
+                let arbitration = Arc::new(ArbStats::new(groups.len()));
+                let stats = registry.add(name.clone(), Some(Arc::clone(&arbitration)));
+                let health = match groups.len() {
+                    1 => vec![health.register(format!("feed {}", name))],
+                    _ => SIDES.iter().map(|side| health.register(format!("feed {} {}", name, side))).collect(),
+                };
+                let arbiter = Arbiter::new(Duration::from_millis(stall_ms.max(1)), arbitration);
+                let task = run_multicast(groups, interface, arbiter, symbols.len(), tx, stats, health);
+                tokio::spawn(task.instrument(span(&name)));
+            }
+        }
+    }
+
+    let rt = Handle::current();
+    thread::spawn(move || {
+        while let Some(incoming) = rx.blocking_recv() {
+            let (stock_id, price, ts, id) = (incoming.stock_id, incoming.price, incoming.ts, incoming.id);
+            let ticks = publisher.admit(&rt, &mut market.write().unwrap(), stock_id, price, ts, id);
+            for tick in ticks {
+                publisher.enqueue(tick);
+            }
+            incoming.stats.record(incoming.lag);
+        }
+    });
+    Ok(())
+}
+
+/// Context of a feed task's log events.
+fn span(feed: &str) -> Span {
+    info_span!("feed", stage = "feed", feed)
+}
+
+/// Ticks as sent by this app's own `/ws` endpoint; other feeds need only
+/// `price` and one of `ticker` or `stock_id`.
+#[derive(Deserialize)]
+struct WireTick {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    ticker: Option<String>,
+    stock_id: Option<usize>,
+    /// Absent from the other `/ws` events.
+    price: Option<Price>,
+    ts_us: Option<i64>,
+    id: Option<Ulid>,
+}
+
+async fn run_websocket(
+    url: String,
+    by_ticker: Arc<HashMap<String, usize>>,
+    tx: mpsc::Sender<Incoming>,
+    stats: Arc<FeedStats>,
+    health: HealthHandle,
+) {
+    loop {
+        match tokio_tungstenite::connect_async(url.as_str()).await {
+            Ok((mut ws, _)) => {
+                info!("Feed {} connected to {}", stats.name, url);
+                health.up(None);
+                while let Some(msg) = ws.next().await {
+                    let text = match msg {
+                        Ok(Message::Text(text)) => text,
+                        Ok(Message::Close(_)) => {
+                            health.down("closed by server");
+                            break;
+                        }
+                        Ok(_) => continue,
+                        Err(e) => {
+                            health.down(&e);
+                            break;
+                        }
+                    };
+                    let wire: WireTick = match serde_json::from_str(&text) {
+                        Ok(wire) => wire,
+                        Err(_) => {
+                            stats.drop_one();
+                            continue;
+                        }
+                    };
+                    if wire.kind.as_deref().is_some_and(|kind| kind != "tick") {
+                        continue;
+                    }
+                    let stock_id = match (&wire.ticker, wire.stock_id) {
+                        (Some(ticker), _) => by_ticker.get(ticker).copied(),
+                        (None, Some(id)) => (id < by_ticker.len()).then_some(id),
+                        (None, None) => None,
+                    };
+                    let (Some(stock_id), Some(price)) = (stock_id, wire.price) else {
+                        stats.drop_one();
+                        continue;
+                    };
+                    let now = SystemTime::now();
+                    let ts = wire.ts_us.map_or(now, |us| UNIX_EPOCH + Duration::from_micros(us.max(0) as u64));
+                    let lag = now.duration_since(ts).unwrap_or_default();
+                    let incoming = Incoming { stats: Arc::clone(&stats), stock_id, price, ts, id: wire.id, lag };
+                    if tx.send(incoming).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Feed {} cannot connect to {}: {}", stats.name, url, e);
+                health.down(&e);
+            }
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Replays a spool-format file (`stock_id,price,unix_micros[,seq,id]`) with
+/// its original spacing divided by `speed`, restamping each tick with the
+/// time it is replayed at. The first pass keeps the recorded tick IDs;
+/// later passes of a `repeat` get new ones, so that the flush does not skip
+/// their rows as already stored.
+async fn run_replay(
+    path: String,
+    speed: f64,
+    repeat: bool,
+    n_stocks: usize,
+    tx: mpsc::Sender<Incoming>,
+    stats: Arc<FeedStats>,
+) {
+    let mut first_pass = true;
+    loop {
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) => {
+                error!("Feed {} cannot read {}: {}", stats.name, path, e);
+                return;
+            }
+        };
+        let records = spool::recorded_ticks(&content);
+        let Some(&(_, _, first, _)) = records.first() else {
+            warn!("Feed {}: no timestamped ticks in {}", stats.name, path);
+            return;
+        };
+        info!("Feed {} replaying {} ticks from {}", stats.name, records.len(), path);
+
+        let start = tokio::time::Instant::now();
+        for (stock_id, price, ts, id) in records {
+            let stock_id = stock_id as usize;
+            if stock_id >= n_stocks {
+                stats.drop_one();
+                continue;
+            }
+            let offset = (ts - first).to_std().unwrap_or_default().div_f64(speed);
+            let due = start + offset;
+            tokio::time::sleep_until(due).await;
+            let lag = tokio::time::Instant::now().saturating_duration_since(due);
+            let id = id.filter(|_| first_pass);
+            let incoming = Incoming { stats: Arc::clone(&stats), stock_id, price, ts: SystemTime::now(), id, lag };
+            if tx.send(incoming).await.is_err() {
+                return;
+            }
+        }
+        if !repeat {
+            info!("Feed {} finished replaying {}", stats.name, path);
+            return;
+        }
+        first_pass = false;
+    }
+}
+
+/// Joins the A side and, if configured, the B side of an SBE multicast feed
+/// and forwards the ticks of whichever copy of each packet arrives first.
+async fn run_multicast(
+    groups: Vec<String>,
+    interface: Option<String>,
+    mut arbiter: Arbiter,
+    n_stocks: usize,
+    tx: mpsc::Sender<Incoming>,
+    stats: Arc<FeedStats>,
+    health: Vec<HealthHandle>,
+) {
+    let sockets = match groups.iter().map(|g| join_multicast(g, interface.as_deref())).collect::<io::Result<Vec<_>>>() {
+        Ok(sockets) => sockets,
+        Err(e) => {
+            error!("Feed {} disabled: {}", stats.name, e);
+            for side in &health {
+                side.down(&e);
+            }
+            return;
+        }
+    };
+    info!("Feed {} joined {}", stats.name, groups.join(" and "));
+
+    // A packet filled in late may be older than one the other side already
+    // delivered for the same symbol.
+    let mut latest = vec![UNIX_EPOCH; n_stocks];
+    let (mut buf_a, mut buf_b) = (vec![0u8; 64 * 1024], vec![0u8; 64 * 1024]);
+    let mut check = tokio::time::interval(Duration::from_millis(50));
+    loop {
+        let (side, received) = tokio::select! {
+            received = sockets[0].recv(&mut buf_a) => (0, received),
+            received = recv_side(sockets.get(1), &mut buf_b) => (1, received),
+            _ = check.tick() => {
+                if let Some(active) = arbiter.check(Instant::now()) {
+                    let other = SIDES[1 - active];
+                    warn!("Feed {}: side {} stalled, failing over to {}", stats.name, other, SIDES[active]);
+                }
+                for (side, health) in health.iter().enumerate() {
+                    match arbiter.stalled(side) {
+                        Some(true) => health.down("stalled"),
+                        Some(false) => health.up(None),
+                        None => {}
+                    }
+                }
+                continue;
+            }
+        };
+        let len = match received {
+            Ok(len) => len,
+            Err(e) => {
+                warn!("Feed {} side {} receive failed: {}", stats.name, SIDES[side], e);
+                health[side].down(&e);
+                continue;
+            }
+        };
+        let buf = if side == 0 { &buf_a } else { &buf_b };
+        let Some((header, ticks)) = sbe::decode_packet(&buf[..len]) else {
+            stats.drop_one();
+            continue;
+        };
+        if !arbiter.offer(side, header.seq, Instant::now()) {
+            continue;
+        }
+        let now = SystemTime::now();
+        for tick in ticks {
+            let Some(stock_id) = usize::try_from(tick.stock_id).ok().filter(|&id| id < n_stocks) else {
+                stats.drop_one();
+                continue;
+            };
+            if tick.ts <= latest[stock_id] {
+                continue;
+            }
+            latest[stock_id] = tick.ts;
+            let lag = now.duration_since(tick.ts).unwrap_or_default();
+            let incoming =
+                Incoming { stats: Arc::clone(&stats), stock_id, price: tick.price, ts: tick.ts, id: None, lag };
+            if tx.send(incoming).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Never completes for a side that is not configured.
+async fn recv_side(socket: Option<&tokio::net::UdpSocket>, buf: &mut [u8]) -> io::Result<usize> {
+    match socket {
+        Some(socket) => socket.recv(buf).await,
+        None => std::future::pending().await,
+    }
+}
@@ -0,0 +1,160 @@
+//! A/B arbitration of a sequence-numbered feed. Both sides carry the same
+//! packets and the first copy of each sequence number wins, so a packet lost
+//! on one side, or a whole side going quiet, costs nothing while the other
+//! side has it. A side silent for the stall timeout while the other keeps
+//! delivering is failed over from; sequence numbers neither side delivers
+//! within the same timeout are counted as gaps.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const SIDES: [&str; 2] = ["A", "B"];
+/// Skipped sequence numbers held while waiting for the other side; the rest
+/// of a larger jump counts as gaps straight away.
+const MAX_PENDING: u64 = 4096;
+
+pub struct ArbStats {
+    sides: usize,
+    /// Packets each side delivered first.
+    wins: [AtomicU64; 2],
+    /// Packets one side skipped and the other delivered later.
+    recovered: AtomicU64,
+    /// Packets neither side delivered.
+    gaps: AtomicU64,
+    failovers: AtomicU64,
+    active: AtomicUsize,
+}
+
+impl ArbStats {
+    pub fn new(sides: usize) -> Self {
+        ArbStats {
+            sides,
+            wins: Default::default(),
+            recovered: AtomicU64::new(0),
+            gaps: AtomicU64::new(0),
+            failovers: AtomicU64::new(0),
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    /// `A 71% B 29%, 3 recovered, 2 gaps, 1 failovers, on B`, or just the
+    /// gaps for a single side.
+    pub fn describe(&self) -> String {
+        let gaps = self.gaps.load(Ordering::Relaxed);
+        if self.sides < 2 {
+            return format!("{} gaps", gaps);
+        }
+        let wins = self.wins.each_ref().map(|w| w.load(Ordering::Relaxed));
+        let total = (wins[0] + wins[1]).max(1) as f64;
+        format!(
+            "A {:.0}% B {:.0}%, {} recovered, {} gaps, {} failovers, on {}",
+            wins[0] as f64 * 100.0 / total,
+            wins[1] as f64 * 100.0 / total,
+            self.recovered.load(Ordering::Relaxed),
+            gaps,
+            self.failovers.load(Ordering::Relaxed),
+            SIDES[self.active.load(Ordering::Relaxed)]
+        )
+    }
+}
+
+pub struct Arbiter {
+    sides: usize,
+    timeout: Duration,
+    stats: Arc<ArbStats>,
+    /// Highest sequence number taken so far.
+    highest: Option<u64>,
+    /// Sequence numbers skipped, and when they were.
+    pending: BTreeMap<u64, Instant>,
+    last_seen: [Option<Instant>; 2],
+    stalled: [bool; 2],
+    /// A unless it has stalled and B has not.
+    active: usize,
+}
+
+impl Arbiter {
+    pub fn new(timeout: Duration, stats: Arc<ArbStats>) -> Self {
+        Arbiter {
+            sides: stats.sides,
+            timeout,
+            stats,
+            highest: None,
+            pending: BTreeMap::new(),
+            last_seen: [None; 2],
+            stalled: [false; 2],
+            active: 0,
+        }
+    }
+
+    /// Whether packet `seq` from `side` is the first copy and should be used.
+    pub fn offer(&mut self, side: usize, seq: u64, now: Instant) -> bool {
+        self.last_seen[side] = Some(now);
+        let take = match self.highest {
+            // The publisher restarted and counts from 1 again.
+            Some(highest) if seq == 1 && highest > 1 => {
+                self.pending.clear();
+                self.highest = Some(seq);
+                true
+            }
+            Some(highest) if seq <= highest => {
+                let recovered = self.pending.remove(&seq).is_some();
+                if recovered {
+                    self.stats.recovered.fetch_add(1, Ordering::Relaxed);
+                }
+                recovered
+            }
+            Some(highest) => {
+                let skipped = seq - highest - 1;
+                let held = skipped.min(MAX_PENDING);
+                self.stats.gaps.fetch_add(skipped - held, Ordering::Relaxed);
+                self.pending.extend((seq - held..seq).map(|s| (s, now)));
+                self.highest = Some(seq);
+                true
+            }
+            None => {
+                self.highest = Some(seq);
+                true
+            }
+        };
+        if take {
+            self.stats.wins[side].fetch_add(1, Ordering::Relaxed);
+        }
+        take
+    }
+
+    /// Counts skipped packets the other side did not fill in time as gaps
+    /// and re-evaluates which sides have stalled. Returns the new active
+    /// side if it changed.
+    pub fn check(&mut self, now: Instant) -> Option<usize> {
+        let expired: Vec<u64> =
+            self.pending.iter().filter(|(_, &at)| now - at >= self.timeout).map(|(&seq, _)| seq).collect();
+        self.stats.gaps.fetch_add(expired.len() as u64, Ordering::Relaxed);
+        for seq in expired {
+            self.pending.remove(&seq);
+        }
+
+        if self.sides < 2 {
+            return None;
+        }
+        let silent = self.last_seen.map(|seen| seen.is_none_or(|at| now - at >= self.timeout));
+        // Both quiet is an idle publisher, not a stalled side.
+        if !(silent[0] && silent[1]) {
+            self.stalled = silent;
+        }
+        let active = if self.stalled[0] && !self.stalled[1] { 1 } else { 0 };
+        if active == self.active {
+            return None;
+        }
+        self.active = active;
+        self.stats.active.store(active, Ordering::Relaxed);
+        self.stats.failovers.fetch_add(1, Ordering::Relaxed);
+        Some(active)
+    }
+
+    /// `None` until the side has delivered a packet.
+    pub fn stalled(&self, side: usize) -> Option<bool> {
+        self.last_seen[side].map(|_| self.stalled[side])
+    }
+}
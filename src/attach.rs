@@ -0,0 +1,402 @@
+//! `attach`: the TUI of a pipeline running elsewhere, typically started with
+//! `--headless` on a remote box. Symbols and prices are read once from its
+//! HTTP API, ticks and latency samples streamed from `/ws`, and the
+//! Diagnostics and Health panels polled from `/status`. Quitting detaches;
+//! the pipeline keeps running, and viewers can come and go.
+
+use std::collections::BTreeMap;
+use std::io::{self, stdout};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use crossterm::event::{self, Event};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::ExecutableCommand;
+use futures::StreamExt;
+use ratatui::backend::CrosstermBackend;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Chart, Dataset, Paragraph};
+use ratatui::Terminal;
+use serde::de::DeserializeOwned;
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{info, warn};
+
+use crate::auction;
+use crate::bus::BUS_CAPACITY;
+use crate::candles::Candles;
+use crate::cli::AttachArgs;
+use crate::command::{self, Command};
+use crate::config::Config;
+use crate::halt;
+use crate::heatmap::LatencyHeatmap;
+use crate::http::{Event as WireEvent, PriceEntry, SymbolEntry};
+use crate::keymap::{Action, Keymap};
+use crate::latency::{LatencySample, Stage};
+use crate::market::{MarketData, Ring};
+use crate::refresh::RefreshScheduler;
+use crate::risk;
+use crate::screen;
+use crate::sequence::{GapRegistry, SeqCheck};
+use crate::shock;
+use crate::spark::LatencySparks;
+use crate::spool;
+use crate::stats::{self, RunningStats};
+use crate::status::StatusReport;
+use crate::symbols::{self, Symbol};
+use crate::tick::Tick;
+use crate::ulid::Ulid;
+use crate::view::{self, Mode, View};
+use crate::vol::RealizedVol;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const STATUS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What the `/ws` reader shares with the frame loop.
+struct Remote {
+    market: RwLock<Vec<MarketData>>,
+    running: Mutex<BTreeMap<(Stage, Option<i32>), RunningStats>>,
+    /// Ticks since the last frame, for the refresh rate.
+    ticks: AtomicU64,
+    /// `connected` or why not.
+    link: RwLock<String>,
+    status: RwLock<Option<StatusReport>>,
+}
+
+pub async fn run(args: AttachArgs, config: &Config) -> io::Result<()> {
+    let base = args.url.trim_end_matches('/').to_string();
+    let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().map_err(io::Error::other)?;
+    let listed: Vec<SymbolEntry> = get_json(&client, &format!("{}/symbols", base)).await?;
+    let prices: Vec<PriceEntry> = get_json(&client, &format!("{}/prices", base)).await?;
+    info!("Attached to {} with {} symbols", base, listed.len());
+
+    let symbols: Arc<Vec<Symbol>> = Arc::new(
+        listed
+            .into_iter()
+            .map(|entry| {
+                let price = prices.iter().find(|p| p.stock_id == entry.stock_id).map_or(entry.tick_size, |p| p.price);
+                Symbol {
+                    ticker: entry.ticker,
+                    name: entry.name,
+                    tick_size: entry.tick_size,
+                    initial_price: price,
+                    color: symbols::color(config.ui.palette, entry.stock_id),
+                    volatility: 0.0,
+                    interval: Duration::ZERO,
+                    vol_window: Duration::from_secs(config.realized_vol.window_s.max(1)),
+                }
+            })
+            .collect(),
+    );
+    let n_stocks = symbols.len();
+    let history_len = config.ui.history_len.max(1);
+    let remote = Arc::new(Remote {
+        market: RwLock::new(
+            symbols
+                .iter()
+                .enumerate()
+                .map(|(i, s)| MarketData {
+                    count: i,
+                    price: Arc::new(RwLock::new(s.initial_price)),
+                    last_update: Instant::now(),
+                    history: Ring::new(history_len, vec![s.initial_price.to_f64(); history_len]),
+                    times: Ring::new(history_len, []),
+                    seq: 0,
+                })
+                .collect(),
+        ),
+        running: Mutex::new(BTreeMap::new()),
+        ticks: AtomicU64::new(0),
+        link: RwLock::new("connecting".to_string()),
+        status: RwLock::new(None),
+    });
+
+    let (tick_tx, _) = broadcast::channel(BUS_CAPACITY);
+    let (latency_tx, _) = broadcast::channel(BUS_CAPACITY);
+    let sparks = LatencySparks::new(n_stocks);
+    tokio::spawn(sparks.clone().run(tick_tx.subscribe()));
+    let realized_vol = RealizedVol::new(&symbols);
+    tokio::spawn(realized_vol.clone().run(tick_tx.subscribe()));
+    let candles = Candles::new(&config.candles, n_stocks)?;
+    tokio::spawn(candles.clone().run(tick_tx.subscribe()));
+    let heatmap = LatencyHeatmap::new();
+    tokio::spawn(heatmap.clone().run(latency_tx.subscribe()));
+    let gaps = GapRegistry::default();
+    let seq = gaps.register("link", n_stocks);
+    tokio::spawn(stream(ws_url(&base), Arc::clone(&remote), tick_tx, latency_tx, seq));
+    tokio::spawn(poll_status(client, format!("{}/status", base), Arc::clone(&remote)));
+
+    enable_raw_mode()?;
+    let mut stdout = stdout();
+    stdout.execute(EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+    // Shocks fire only in the pipeline, so their keys are free here.
+    let keymap = Keymap::new(&config.keymap, &[])?;
+    let mut view = View::new(Arc::clone(&symbols), &config.ui, keymap.clone())?;
+    view.about = vec![
+        ("Symbols", n_stocks.to_string()),
+        ("Chart points", history_len.to_string()),
+        ("Refresh", format!("{} to {}ms", config.ui.min_frame_ms, config.ui.max_frame_ms)),
+        ("Attached to", base.clone()),
+        ("Ticks from", ws_url(&base)),
+    ];
+    let mut refresh = RefreshScheduler::new(&config.ui);
+    let mut next_frame = Instant::now();
+    let mut last_frame = Instant::now();
+
+    loop {
+        if event::poll(next_frame.saturating_duration_since(Instant::now()))? {
+            let mut action = None;
+            match event::read()? {
+                Event::Key(key) if !view.handle_key(key.code) => action = keymap.action(key.code),
+                Event::Resize(..) => {
+                    terminal.autoresize()?;
+                    terminal.clear()?;
+                }
+                _ => {}
+            }
+            // The pipeline's own commands have no effect from here.
+            match view.take_command().map(|line| command::parse(&line)) {
+                Some(Ok(Command::Action(bound))) if !view.apply(bound) => action = Some(bound),
+                Some(Ok(Command::Action(_))) | None => {}
+                Some(Ok(Command::Symbol(ticker))) => {
+                    if let Err(e) = view.show_symbol(&ticker) {
+                        view.report(e);
+                    }
+                }
+                Some(Ok(_)) => view.report("Only the pipeline itself runs that command, not attach"),
+                Some(Err(e)) => view.report(e),
+            }
+            match action {
+                Some(Action::Quit) => break,
+                Some(Action::ResetStats) => remote.running.lock().unwrap().clear(),
+                _ => {}
+            }
+        }
+        sparks.end_frame();
+        let ticks = remote.ticks.swap(0, Ordering::Relaxed);
+        next_frame = Instant::now() + refresh.next(ticks, std::mem::replace(&mut last_frame, Instant::now()).elapsed());
+
+        let md_vec = remote.market.read().unwrap().clone();
+        let page = view.chart_ids();
+        let page_label = view.chart_page_label();
+        let stats_rows =
+            if view.mode == Mode::Stats { stats::rows(&remote.running.lock().unwrap(), &page) } else { Vec::new() };
+        let vol_rows: Vec<(usize, Option<(f64, usize)>)> = match view.mode {
+            Mode::Stats => page.iter().map(|&id| (id, realized_vol.current(id))).collect(),
+            _ => Vec::new(),
+        };
+        let status = remote.status.read().unwrap().clone().unwrap_or_default();
+        let mut diagnostics = vec![Line::from(format!(
+            "Attached: {} ({}), {}, refresh {}",
+            base,
+            remote.link.read().unwrap(),
+            gaps.describe(),
+            refresh.describe()
+        ))];
+        diagnostics.extend(
+            status
+                .diagnostics
+                .iter()
+                .map(|l| {
+                    let style = risk::line_style(&l.label, &l.value)
+                        .patch(auction::line_style(&l.label, &l.value))
+                        .patch(halt::line_style(&l.label, &l.value))
+                        .patch(shock::line_style(&l.label, &l.value))
+                        .patch(spool::line_style(&l.label, &l.value));
+                    Line::styled(format!("{}: {}", l.label, l.value), style)
+                }),
+        );
+        let health_lines: Vec<Line> = status.health.iter().map(|conn| conn.line()).collect();
+        let diagnostics_height = diagnostics.len() as u16 + 2;
+        let health_height = health_lines.len() as u16 + 2;
+
+        screen::draw(&mut terminal, view.ascii, view.help(), view.prompt(), |f| {
+            if screen::too_small(f) {
+                return;
+            }
+            let (chunks, cut) = screen::panels(f.area(), [8, diagnostics_height, health_height]);
+
+            let pointers: Vec<Line> = page
+                .iter()
+                .map(|&id| {
+                    let md = &md_vec[id];
+                    Line::from(format!(
+                        "{} ({}) {} -> price: {}, seq: {}",
+                        symbols[id].ticker,
+                        symbols[id].name,
+                        sparks.describe(id),
+                        *md.price.read().unwrap(),
+                        md.seq
+                    ))
+                })
+                .collect();
+            f.render_widget(
+                Paragraph::new(pointers).block(Block::default().borders(Borders::ALL).title("Pointers")),
+                chunks[0],
+            );
+            let title = screen::cut_title("Diagnostics (remote) - q detaches", cut);
+            f.render_widget(
+                Paragraph::new(diagnostics).block(Block::default().borders(Borders::ALL).title(title)),
+                chunks[1],
+            );
+            f.render_widget(
+                Paragraph::new(health_lines).block(Block::default().borders(Borders::ALL).title("Health")),
+                chunks[2],
+            );
+
+            match view.mode {
+                // Without a moving average the combined view is the chart alone.
+                Mode::Charts | Mode::Combined => {}
+                Mode::Grid => return view.render_grid(f, chunks[3], &md_vec),
+                Mode::Stats => {
+                    return stats::render(f, chunks[3], &stats_rows, &vol_rows, &symbols, &page_label, &keymap)
+                }
+                Mode::Heatmap => return heatmap.render(f, chunks[3]),
+                Mode::Candles => return candles.render(f, chunks[3], &page, &symbols, view.candle_interval),
+                Mode::Query => {
+                    let text = "attach has no Postgres connection; run SQL in the pipeline's own TUI.";
+                    let block = Block::default().borders(Borders::ALL).title("SQL - x charts");
+                    return f.render_widget(Paragraph::new(text).block(block), chunks[3]);
+                }
+                Mode::Keys => {
+                    let text = "attach has no Redis connection; inspect the keys in the pipeline's own TUI.";
+                    let block = Block::default().borders(Borders::ALL).title("Redis - k charts");
+                    return f.render_widget(Paragraph::new(text).block(block), chunks[3]);
+                }
+                Mode::Portfolio => {
+                    let text = "Positions are not in /status; the Portfolio line above has the totals.";
+                    let block = Block::default().borders(Borders::ALL).title("Positions - o charts");
+                    return f.render_widget(Paragraph::new(text).block(block), chunks[3]);
+                }
+            }
+            let points: Vec<Vec<(f64, f64)>> =
+                page.iter().map(|&id| view::plot(md_vec[id].points(), view.log_backend, chunks[3].width)).collect();
+            let datasets: Vec<Dataset> = page
+                .iter()
+                .zip(&points)
+                .map(|(&id, pts)| {
+                    Dataset::default()
+                        .name(symbols[id].ticker.clone())
+                        .marker(view.marker_of(id))
+                        .style(Style::default().fg(symbols[id].color))
+                        .data(pts)
+                })
+                .collect();
+            let title = keymap.title(&format!("Remote Stocks ({})", page_label), &[
+                (&[Action::NextPage, Action::PreviousPage], "page"),
+                (&[Action::Grid], "grid"),
+                (&[Action::Stats], "statistics"),
+                (&[Action::Heatmap], "heatmap"),
+                (&[Action::LogBackend], "log"),
+                (&[Action::Marker], "marker"),
+                (&[Action::Search], "search"),
+                (&[Action::Watchlist], "watchlist"),
+            ]);
+            let chart = Chart::new(datasets)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .x_axis(view::time_axis(points.iter().map(Vec::as_slice)))
+                .y_axis(view::price_axis(points.iter().map(Vec::as_slice), view.log_backend));
+            f.render_widget(chart, chunks[3]);
+        })?;
+    }
+
+    disable_raw_mode()?;
+    terminal.backend_mut().execute(LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    info!("Detached from {}", base);
+    Ok(())
+}
+
+pub async fn get_json<T: DeserializeOwned>(client: &reqwest::Client, url: &str) -> io::Result<T> {
+    let body = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| io::Error::other(format!("GET {}: {}", url, e)))?
+        .text()
+        .await
+        .map_err(|e| io::Error::other(format!("GET {}: {}", url, e)))?;
+    serde_json::from_str(&body).map_err(|e| io::Error::other(format!("GET {}: {}", url, e)))
+}
+
+/// `http://host:8080` -> `ws://host:8080/ws`
+fn ws_url(base: &str) -> String {
+    let rest = base.strip_prefix("https://").map(|r| format!("wss://{}", r));
+    let url = rest.unwrap_or_else(|| format!("ws://{}", base.strip_prefix("http://").unwrap_or(base)));
+    format!("{}/ws", url)
+}
+
+/// Applies `/ws` events to `remote` and republishes them on the local buses,
+/// reconnecting whenever the stream drops.
+async fn stream(
+    url: String,
+    remote: Arc<Remote>,
+    ticks: broadcast::Sender<Tick>,
+    latency: broadcast::Sender<LatencySample>,
+    mut seq: SeqCheck,
+) {
+    let at = |ts_us: i64| UNIX_EPOCH + Duration::from_micros(ts_us.max(0) as u64);
+    loop {
+        match tokio_tungstenite::connect_async(url.as_str()).await {
+            Ok((mut ws, _)) => {
+                *remote.link.write().unwrap() = "connected".to_string();
+                while let Some(msg) = ws.next().await {
+                    let text = match msg {
+                        Ok(Message::Text(text)) => text,
+                        Ok(Message::Close(_)) => break,
+                        Ok(_) => continue,
+                        Err(e) => {
+                            warn!("Attach stream {} failed: {}", url, e);
+                            break;
+                        }
+                    };
+                    match serde_json::from_str(&text) {
+                        Ok(WireEvent::Tick { stock_id, price, ts_us, seq: tick_seq, id }) => {
+                            {
+                                let mut market = remote.market.write().unwrap();
+                                let Some(md) = usize::try_from(stock_id).ok().and_then(|id| market.get_mut(id)) else {
+                                    continue;
+                                };
+                                md.update(price, at(ts_us));
+                                // Numbered by the remote pipeline, not by this copy.
+                                md.seq = tick_seq;
+                            }
+                            seq.observe(stock_id, tick_seq);
+                            remote.ticks.fetch_add(1, Ordering::Relaxed);
+                            let id = id.unwrap_or_else(|| Ulid::new(at(ts_us)));
+                            let _ = ticks.send(Tick { stock_id, price, ts: at(ts_us), seq: tick_seq, id });
+                        }
+                        Ok(WireEvent::Latency { stage, stock_id, nanos, ts_us }) => {
+                            let mut running = remote.running.lock().unwrap();
+                            for key in [Some((stage, None)), stock_id.map(|id| (stage, Some(id)))].into_iter().flatten()
+                            {
+                                running.entry(key).or_insert_with(RunningStats::new).record(nanos);
+                            }
+                            let _ = latency.send(LatencySample { stage, stock_id, at: at(ts_us), nanos });
+                        }
+                        Err(e) => warn!("Attach stream {}: unreadable event: {}", url, e),
+                    }
+                }
+                *remote.link.write().unwrap() = "disconnected, reconnecting".to_string();
+            }
+            Err(e) => *remote.link.write().unwrap() = format!("cannot connect: {}", e),
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn poll_status(client: reqwest::Client, url: String, remote: Arc<Remote>) {
+    let mut interval = tokio::time::interval(STATUS_INTERVAL);
+    loop {
+        interval.tick().await;
+        match get_json::<StatusReport>(&client, &url).await {
+            Ok(status) => *remote.status.write().unwrap() = Some(status),
+            Err(e) => warn!("{}", e),
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! Opening and closing call auctions on the simulated symbols, from
+//! `[auction]`. During a call the simulator stops moving prices and instead
+//! sends random limit orders into each symbol's call book, whose indicative
+//! price is the one that would trade the most. When the call ends the book
+//! uncrosses at that price, which is published as the symbol's auction
+//! print; continuous trading after the opening call starts from it, and the
+//! simulator stops after the closing print. External feeds are not called.
+//! The phase heads the Pointers panel and the Auction diagnostics line,
+//! which is yellow while a call is on.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use ratatui::style::{Color, Style};
+use tracing::info;
+
+use crate::config::AuctionConfig;
+use crate::price::Price;
+use crate::symbols::Symbol;
+
+/// Largest call order, in shares.
+const ORDER_QTY: u64 = 100;
+/// How far from the reference price call orders are limited, in the
+/// symbol's largest moves.
+const ORDER_REACH: i64 = 2;
+/// Symbols named on the Auction line.
+const SHOWN: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    OpeningCall,
+    Continuous,
+    ClosingCall,
+    Closed,
+}
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::OpeningCall => "opening call",
+            Phase::Continuous => "continuous",
+            Phase::ClosingCall => "closing call",
+            Phase::Closed => "closed",
+        }
+    }
+
+    fn calling(self) -> bool {
+        matches!(self, Phase::OpeningCall | Phase::ClosingCall)
+    }
+}
+
+/// Where a call book would uncross.
+#[derive(Clone, Copy)]
+struct Indicative {
+    price: Price,
+    volume: u64,
+    /// Left unmatched at that price; positive for buys.
+    imbalance: i64,
+}
+
+/// One symbol's call orders, as shares bid and offered by limit price.
+#[derive(Default)]
+struct CallBook {
+    /// The price before the call, to break ties by.
+    reference: Price,
+    levels: BTreeMap<Price, (u64, u64)>,
+}
+
+impl CallBook {
+    /// The price that trades the most, then leaves the least imbalance, then
+    /// is nearest the reference; none if nothing crosses.
+    fn indicative(&self) -> Option<Indicative> {
+        let mut sold: u64 = 0;
+        let mut bought: u64 = self.levels.values().map(|&(buys, _)| buys).sum();
+        let mut best: Option<Indicative> = None;
+        for (&price, &(buys, sells)) in &self.levels {
+            // Buys limited at `price` or above, sells at `price` or below.
+            sold += sells;
+            let volume = bought.min(sold);
+            let candidate = Indicative { price, volume, imbalance: bought as i64 - sold as i64 };
+            bought -= buys;
+            let distance = |i: &Indicative| (i.price.units() - self.reference.units()).abs();
+            let better = best.is_none_or(|b| {
+                (volume, -candidate.imbalance.abs(), -distance(&candidate))
+                    > (b.volume, -b.imbalance.abs(), -distance(&b))
+            });
+            if volume > 0 && better {
+                best = Some(candidate);
+            }
+        }
+        best
+    }
+}
+
+struct Inner {
+    phase: Phase,
+    books: Vec<CallBook>,
+    /// Each symbol's last auction print and its volume.
+    prints: Vec<Option<(Price, u64)>>,
+}
+
+#[derive(Clone)]
+pub struct Auction {
+    cfg: Option<AuctionConfig>,
+    started: Instant,
+    tickers: Arc<Vec<String>>,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Auction {
+    pub fn new(cfg: Option<AuctionConfig>, symbols: &[Symbol]) -> Self {
+        let inner = Inner {
+            phase: Phase::Continuous,
+            books: symbols.iter().map(|_| CallBook::default()).collect(),
+            prints: vec![None; symbols.len()],
+        };
+        let auction = Auction {
+            cfg,
+            started: Instant::now(),
+            tickers: Arc::new(symbols.iter().map(|s| s.ticker.clone()).collect()),
+            inner: Arc::new(Mutex::new(inner)),
+        };
+        auction.inner.lock().unwrap().phase = auction.scheduled(Duration::ZERO);
+        auction
+    }
+
+    /// The phase `elapsed` after startup.
+    fn scheduled(&self, elapsed: Duration) -> Phase {
+        let Some(cfg) = &self.cfg else { return Phase::Continuous };
+        let open = Duration::from_secs(cfg.open_call_s);
+        if elapsed < open {
+            return Phase::OpeningCall;
+        }
+        let Some(close_at) = cfg.close_at_s.map(|s| Duration::from_secs(s).max(open)) else {
+            return Phase::Continuous;
+        };
+        if elapsed < close_at {
+            Phase::Continuous
+        } else if elapsed < close_at + Duration::from_secs(cfg.close_call_s) {
+            Phase::ClosingCall
+        } else {
+            Phase::Closed
+        }
+    }
+
+    /// Moves to the phase due now. Returns the prints of a call that has
+    /// just ended, by stock id.
+    pub fn advance(&self) -> Vec<(usize, Price)> {
+        let due = self.scheduled(self.started.elapsed());
+        let mut inner = self.inner.lock().unwrap();
+        if due == inner.phase {
+            return Vec::new();
+        }
+        info!("Auction phase {} -> {}", inner.phase.name(), due.name());
+        let mut uncrossed = Vec::new();
+        if inner.phase.calling() {
+            for id in 0..inner.books.len() {
+                let book = std::mem::take(&mut inner.books[id]);
+                let Some(print) = book.indicative() else { continue };
+                info!(
+                    "{} {} uncrossed at {} for {} shares, {} unmatched",
+                    self.tickers[id],
+                    inner.phase.name(),
+                    print.price,
+                    print.volume,
+                    print.imbalance
+                );
+                inner.prints[id] = Some((print.price, print.volume));
+                uncrossed.push((id, print.price));
+            }
+        }
+        inner.phase = due;
+        uncrossed
+    }
+
+    /// Whether the simulator moves prices, rather than calling or being
+    /// closed.
+    pub fn trading(&self) -> bool {
+        self.inner.lock().unwrap().phase == Phase::Continuous
+    }
+
+    /// During a call, sends a random limit order for `stock_id` around
+    /// `reference`, its price before the call.
+    pub fn call_order(&self, stock_id: usize, reference: Price, symbol: &Symbol, rng: &mut impl Rng) {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.phase.calling() {
+            return;
+        }
+        let reach = symbol.max_steps() * ORDER_REACH;
+        let limit = reference + symbol.tick_size * rng.gen_range(-reach..=reach);
+        let qty = rng.gen_range(1..=ORDER_QTY);
+        let book = &mut inner.books[stock_id];
+        if book.levels.is_empty() {
+            book.reference = reference;
+        }
+        let level = book.levels.entry(limit).or_default();
+        if rng.gen_bool(0.5) {
+            level.0 += qty;
+        } else {
+            level.1 += qty;
+        }
+    }
+
+    /// For the Pointers panel's title, while a call is on.
+    pub fn title(&self) -> Option<&'static str> {
+        let phase = self.inner.lock().unwrap().phase;
+        phase.calling().then_some(phase.name())
+    }
+
+    /// `opening call, uncross in 6s: AAPL 187.2 x 1200 (+300), MSFT no cross, GOOG 141.05 x 800 (-20)` or
+    /// `continuous, closing call in 580s, opening prints AAPL 187.2 x 1200, ...`
+    pub fn describe(&self) -> String {
+        let Some(cfg) = &self.cfg else { return "off (add an [auction] section)".to_string() };
+        let inner = self.inner.lock().unwrap();
+        let elapsed = self.started.elapsed().as_secs();
+        let symbols = |each: &dyn Fn(usize) -> String| {
+            let mut named: Vec<String> = (0..self.tickers.len().min(SHOWN)).map(each).collect();
+            if self.tickers.len() > SHOWN {
+                named.push(format!("{} more", self.tickers.len() - SHOWN));
+            }
+            named.join(", ")
+        };
+        let prints = || {
+            symbols(&|id| match inner.prints[id] {
+                Some((price, volume)) => format!("{} {} x {}", self.tickers[id], price, volume),
+                None => format!("{} no print", self.tickers[id]),
+            })
+        };
+        let indicative = || {
+            symbols(&|id| match inner.books[id].indicative() {
+                Some(i) => format!("{} {} x {} ({:+})", self.tickers[id], i.price, i.volume, i.imbalance),
+                None => format!("{} no cross", self.tickers[id]),
+            })
+        };
+        let close_at = cfg.close_at_s.map(|s| s.max(cfg.open_call_s));
+        match inner.phase {
+            Phase::OpeningCall => {
+                format!("opening call, uncross in {}s: {}", cfg.open_call_s.saturating_sub(elapsed), indicative())
+            }
+            Phase::ClosingCall => {
+                let ends = close_at.unwrap_or_default() + cfg.close_call_s;
+                format!("closing call, uncross in {}s: {}", ends.saturating_sub(elapsed), indicative())
+            }
+            Phase::Continuous => {
+                let close = close_at.map_or("no closing call".to_string(), |at| {
+                    format!("closing call in {}s", at.saturating_sub(elapsed))
+                });
+                if cfg.open_call_s == 0 {
+                    return format!("continuous, {}", close);
+                }
+                format!("continuous, {}, opening prints {}", close, prints())
+            }
+            Phase::Closed => format!("closed, closing prints {}", prints()),
+        }
+    }
+}
+
+/// For a diagnostics line, here or read back from `/status`: yellow while a
+/// call is on.
+pub fn line_style(label: &str, value: &str) -> Style {
+    if label == "Auction" && value.contains(" call, uncross") {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    }
+}
@@ -0,0 +1,50 @@
+//! Seeds the chart histories from Postgres on startup, so they start from the
+//! last stored prices instead of a flat line.
+
+use std::io;
+
+use std::time::SystemTime;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+
+use crate::price::Price;
+
+/// The last `len` prices of each of the first `n_stocks` symbols with their
+/// tick times, oldest first. Symbols without stored ticks get an empty
+/// history.
+pub async fn load(pool: &PgPool, n_stocks: usize, len: usize) -> io::Result<Vec<Vec<(Price, SystemTime)>>> {
+    let ids: Vec<i32> = (0..n_stocks as i32).collect();
+    let rows: Vec<(i32, Decimal, NaiveDateTime)> = sqlx::query_as(
+        "SELECT stock_id, price::numeric, ts FROM ( \
+             SELECT stock_id, price, ts, \
+                    ROW_NUMBER() OVER (PARTITION BY stock_id ORDER BY ts DESC) AS rn \
+             FROM stock_data WHERE stock_id = ANY($1) \
+         ) recent WHERE rn <= $2 ORDER BY stock_id, ts",
+    )
+    .bind(&ids)
+    .bind(len as i64)
+    .fetch_all(pool)
+    .await
+    .map_err(io::Error::other)?;
+
+    let mut histories = vec![Vec::with_capacity(len); n_stocks];
+    for (stock_id, price, ts) in rows {
+        if let Some(price) = Price::from_decimal(price) {
+            histories[stock_id as usize].push((price, ts.and_utc().into()));
+        }
+    }
+    Ok(histories)
+}
+
+/// Trailing averages over `window` points, one per point of `history`, as
+/// the frontend thread computes them live.
+pub fn moving_averages(history: &[f64], window: usize) -> Vec<f64> {
+    (0..history.len())
+        .map(|i| {
+            let slice = &history[(i + 1).saturating_sub(window)..=i];
+            slice.iter().sum::<f64>() / slice.len() as f64
+        })
+        .collect()
+}
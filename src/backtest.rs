@@ -0,0 +1,85 @@
+//! `backtest`: a recording replayed through a strategy and the paper venue
+//! as fast as they can take it, then its PnL and decision latency. Time
+//! inside the run is the recording's, so the strategy's timer and order
+//! expiries behave as they would have live however fast the replay goes.
+
+use std::fs;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::cli::BacktestArgs;
+use crate::config::Config;
+use crate::export::ParquetExporter;
+use crate::latency::{LatencyRecorder, Stage};
+use crate::paper;
+use crate::report;
+use crate::spool;
+use crate::symbols;
+use crate::timing;
+
+pub fn run(args: BacktestArgs, config: &Config) -> io::Result<()> {
+    let symbols = symbols::load(config)?;
+    let mut cfg = config.paper.clone().unwrap_or_default();
+    if let Some(strategy) = args.strategy {
+        cfg.strategy = strategy;
+    }
+    if args.script.is_some() {
+        cfg.script = args.script;
+    }
+    let content = fs::read_to_string(&args.recording)
+        .map_err(|e| io::Error::other(format!("reading {}: {}", args.recording.display(), e)))?;
+    let recorded = spool::recorded_ticks(&content);
+    let Some(&(_, _, first, _)) = recorded.first() else {
+        return Err(io::Error::other(format!("no timestamped ticks in {}", args.recording.display())));
+    };
+    let last = recorded.last().map_or(first, |&(_, _, ts, _)| ts);
+    let ticks: Vec<_> = recorded
+        .iter()
+        .filter(|&&(stock_id, _, _, _)| usize::try_from(stock_id).is_ok_and(|id| id < symbols.len()))
+        .map(|&(stock_id, price, ts, _)| (stock_id, price, (ts - first).to_std().unwrap_or_default()))
+        .collect();
+    let skipped = recorded.len() - ticks.len();
+
+    // Nothing here is exported; the recorder only feeds the summary.
+    let latency = LatencyRecorder::new(Arc::new(Mutex::new(ParquetExporter::new("export"))));
+    let timer = timing::from_config(&config.timing);
+    let started = Instant::now();
+    let replayed = ticks.len();
+    let orders = paper::backtest(&cfg, &symbols, ticks, latency.clone(), timer)?;
+    let run_time = started.elapsed();
+
+    let recorded_secs = (last - first).to_std().unwrap_or_default().as_secs_f64();
+    let mut counters = vec![
+        (
+            "Recording",
+            format!(
+                "{} ticks over {:.1}s, {} for unknown symbols skipped, replayed at {:.0} ticks/s",
+                replayed,
+                recorded_secs,
+                skipped,
+                replayed as f64 / run_time.as_secs_f64().max(f64::MIN_POSITIVE)
+            ),
+        ),
+        ("Orders", orders.describe()),
+        ("Venues", orders.describe_venues()),
+        ("Router", orders.router().describe()),
+        ("Order flow", orders.flow().describe(&symbols)),
+        ("Microprice", orders.marks().describe(&symbols)),
+    ];
+    let portfolio = orders.portfolio();
+    let by_symbol: Vec<String> = portfolio
+        .positions()
+        .iter()
+        .map(|(&id, p)| format!("{} {:+.2} (holding {})", symbols[id as usize].ticker, p.pnl(), p.qty))
+        .collect();
+    counters.push(("PnL", format!("{} | {}", portfolio.describe(), by_symbol.join(", "))));
+    counters.push(("Risk", orders.risk().describe()));
+    if let Some(script) = orders.script_stats() {
+        counters.push(("Script", script.describe()));
+    }
+    // Ack and fill times would be the replay's wall time, not the recording's.
+    let stages: Vec<_> = latency.summary().into_iter().filter(|s| s.stage == Stage::Decision).collect();
+    print!("{}", report::render(run_time, &stages, &counters));
+    Ok(())
+}
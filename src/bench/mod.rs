@@ -0,0 +1,49 @@
+//! `bench` subcommands: synthetic load with HDR latency tables.
+
+mod pipeline;
+mod tick_to_trade;
+
+use std::io;
+
+use hdrhistogram::Histogram;
+
+use crate::cli::{BenchArgs, BenchCommand};
+use crate::config::Config;
+
+/// Samples above this are clamped into the top histogram bucket.
+const MAX_TRACKED_NANOS: u64 = 60_000_000_000;
+
+pub async fn run(args: BenchArgs, config: &Config) -> io::Result<()> {
+    match args.command {
+        BenchCommand::TickToTrade(args) => tick_to_trade::run(args, config),
+        BenchCommand::Pipeline(args) => pipeline::run(args, config).await,
+    }
+}
+
+fn histogram() -> Histogram<u64> {
+    Histogram::new_with_bounds(1, MAX_TRACKED_NANOS, 3).unwrap()
+}
+
+/// Prints one row per histogram, in microseconds.
+fn print_table(rows: &[(&str, &Histogram<u64>)]) {
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0).max(5);
+    println!(
+        "{:<width$} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+        "stage", "count", "min", "p50", "p90", "p99", "p99.9", "max"
+    );
+    let us = |nanos: u64| format!("{:.2}", nanos as f64 / 1000.0);
+    for (name, h) in rows {
+        println!(
+            "{:<width$} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+            name,
+            h.len(),
+            us(h.min()),
+            us(h.value_at_quantile(0.5)),
+            us(h.value_at_quantile(0.9)),
+            us(h.value_at_quantile(0.99)),
+            us(h.value_at_quantile(0.999)),
+            us(h.max())
+        );
+    }
+    println!("(latencies in µs)");
+}
@@ -0,0 +1,140 @@
+//! Times each pipeline stage on its own, so infrastructure changes can be
+//! compared one stage at a time. Stages whose service is unreachable are
+//! skipped.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use hdrhistogram::Histogram;
+use sqlx::postgres::PgPoolOptions;
+use tokio::sync::{broadcast, mpsc};
+
+use super::{histogram, print_table};
+use crate::bus::BUS_CAPACITY;
+use crate::cli::{PipelineArgs, PipelineStage};
+use crate::cache::RedisCache;
+use crate::config::Config;
+use crate::config::PostgresTlsConfig;
+use crate::price::Price;
+use crate::secrets::{Credentials, Dsn};
+use crate::tls;
+use crate::timing::{self, SharedClock};
+
+pub async fn run(args: PipelineArgs, config: &Config) -> io::Result<()> {
+    let timer = timing::from_config(&config.timing);
+    let credentials = Credentials::load(&config.secrets)?;
+    let redis_url = match &args.redis_url {
+        Some(raw) => Dsn::redis(raw, "--redis-url")?,
+        None => credentials.redis.clone(),
+    };
+    let redis_cache = RedisCache::new(&redis_url, &config.redis, &config.tls.redis)?;
+    let stages = if args.stages.is_empty() {
+        vec![PipelineStage::File, PipelineStage::Redis, PipelineStage::Postgres, PipelineStage::Channel]
+    } else {
+        args.stages.clone()
+    };
+    println!("pipeline: {} iterations per stage, {} clock", args.iterations, timer.name());
+
+    let mut results = Vec::new();
+    for stage in stages {
+        let (name, result) = match stage {
+            PipelineStage::File => ("file append", file_append(&timer, args.iterations)),
+            PipelineStage::Redis => ("redis set", redis_set(&timer, args.iterations, &redis_cache).await),
+            PipelineStage::Postgres => ("postgres insert", postgres_insert(&timer, args.iterations, credentials.postgres(), &config.tls.postgres).await),
+            PipelineStage::Channel => ("channel hop", channel_hop(&timer, args.iterations).await),
+        };
+        match result {
+            Ok(h) => results.push((name, h)),
+            Err(e) => println!("skipping {}: {}", name, e),
+        }
+    }
+
+    let rows: Vec<(&str, &Histogram<u64>)> = results.iter().map(|(name, h)| (*name, h)).collect();
+    print_table(&rows);
+    Ok(())
+}
+
+/// One unbuffered append per tick, as the spool did before buffering.
+fn file_append(timer: &SharedClock, iterations: u64) -> io::Result<Histogram<u64>> {
+    let path = std::env::temp_dir().join(format!("hft-bench-{}.txt", std::process::id()));
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let mut h = histogram();
+    for i in 0..iterations {
+        let started = timer.now_nanos();
+        writeln!(file, "{},{},{}", i % 3, 100.0 + i as f64 * 0.01, i)?;
+        h.saturating_record(timer.elapsed(started).as_nanos().max(1) as u64);
+    }
+    drop(file);
+    std::fs::remove_file(&path)?;
+    Ok(h)
+}
+
+async fn redis_set(timer: &SharedClock, iterations: u64, cache: &RedisCache) -> io::Result<Histogram<u64>> {
+    cache.ping().await.map_err(io::Error::other)?;
+    let mut h = histogram();
+    for i in 0..iterations {
+        let started = timer.now_nanos();
+        cache.set(&format!("bench:{}", i % 3), 100.0 + i as f32 * 0.01).await.map_err(io::Error::other)?;
+        h.saturating_record(timer.elapsed(started).as_nanos().max(1) as u64);
+    }
+    Ok(h)
+}
+
+/// Inserts into a temporary copy of `stock_data`, leaving the real table alone.
+async fn postgres_insert(
+    timer: &SharedClock,
+    iterations: u64,
+    url: io::Result<&Dsn>,
+    tls: &PostgresTlsConfig,
+) -> io::Result<Histogram<u64>> {
+    // A single connection, because temporary tables are per connection.
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .acquire_timeout(Duration::from_secs(3))
+        .connect_with(tls::pg_connect_options(url?, tls)?)
+        .await
+        .map_err(io::Error::other)?;
+    sqlx::query("CREATE TEMP TABLE bench_stock_data (LIKE stock_data INCLUDING DEFAULTS)")
+        .execute(&pool)
+        .await
+        .map_err(io::Error::other)?;
+    let mut h = histogram();
+    for i in 0..iterations {
+        let started = timer.now_nanos();
+        sqlx::query("INSERT INTO bench_stock_data (stock_id, price, ts) VALUES ($1, $2, NOW())")
+            .bind((i % 3) as i32)
+            .bind((Price::from_f64(100.0) + Price::from_f64(0.01) * i as i64).to_decimal())
+            .execute(&pool)
+            .await
+            .map_err(io::Error::other)?;
+        h.saturating_record(timer.elapsed(started).as_nanos().max(1) as u64);
+    }
+    Ok(h)
+}
+
+/// Send-to-receive latency across a broadcast channel like the tick bus, with
+/// one message in flight at a time so queueing does not skew the result.
+async fn channel_hop(timer: &SharedClock, iterations: u64) -> io::Result<Histogram<u64>> {
+    let (tx, mut rx) = broadcast::channel::<u64>(BUS_CAPACITY);
+    let (done_tx, mut done_rx) = mpsc::channel::<u64>(1);
+    let receiver = {
+        let timer = SharedClock::clone(timer);
+        tokio::spawn(async move {
+            while let Ok(sent) = rx.recv().await {
+                if done_tx.send(timer.now_nanos().saturating_sub(sent)).await.is_err() {
+                    break;
+                }
+            }
+        })
+    };
+    let mut h = histogram();
+    for _ in 0..iterations {
+        tx.send(timer.now_nanos()).map_err(io::Error::other)?;
+        let nanos = done_rx.recv().await.ok_or_else(|| io::Error::other("receiver stopped"))?;
+        h.saturating_record(nanos.max(1));
+    }
+    drop(tx);
+    receiver.await.map_err(io::Error::other)?;
+    Ok(h)
+}
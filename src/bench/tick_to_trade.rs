@@ -0,0 +1,208 @@
+//! Tick-to-trade round trip: a feed thread generates ticks and refreshes a
+//! market maker's quotes, a strategy thread turns every tick into an IOC
+//! order that crosses the spread, an engine thread matches it, and the
+//! execution report is timed back on the calling thread.
+
+use std::io;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use rand::Rng;
+
+use super::{histogram, print_table};
+use crate::cli::TickToTradeArgs;
+use crate::config::Config;
+use crate::engine::{MatchingEngine, Order, Side, TimeInForce, Visibility};
+use crate::price::Price;
+use crate::timing::{self, SharedClock};
+
+/// Quoted half-spread, in price ticks.
+const HALF_SPREAD: i64 = 1;
+const QUOTE_QTY: u32 = 100;
+/// Market maker order ids count up from here so they never clash with
+/// strategy order ids.
+const MAKER_ID_BASE: u64 = u64::MAX / 2;
+
+struct BenchTick {
+    seq: u64,
+    stock_id: i32,
+    price: i64,
+    created: u64,
+}
+
+enum EngineMsg {
+    Quote { stock_id: i32, mid: i64 },
+    Order { order: Order, created: u64, decided: u64 },
+}
+
+struct Report {
+    seq: u64,
+    filled: u32,
+    notional: f64,
+    created: u64,
+    decided: u64,
+    matched: u64,
+}
+
+pub fn run(args: TickToTradeArgs, config: &Config) -> io::Result<()> {
+    let timer = timing::from_config(&config.timing);
+    // Prices are ticks of `[prices] tick_size`.
+    let tick = config.prices.tick_size;
+    let total = args.warmup + args.iterations;
+    println!(
+        "tick-to-trade: {} iterations after {} warm-up, {} symbols, one tick every {} µs, {} clock, {} hand-off",
+        args.iterations,
+        args.warmup,
+        args.symbols,
+        args.interval_us,
+        timer.name(),
+        if args.spin { "spinning" } else { "blocking" }
+    );
+
+    let (tick_tx, tick_rx) = mpsc::channel::<BenchTick>();
+    let (engine_tx, engine_rx) = mpsc::channel::<EngineMsg>();
+    let (report_tx, report_rx) = mpsc::channel::<Report>();
+
+    let feed = {
+        let timer = SharedClock::clone(&timer);
+        let engine_tx = engine_tx.clone();
+        let (symbols, interval) = (args.symbols.max(1), Duration::from_micros(args.interval_us));
+        thread::spawn(move || {
+            let mut rng = rand::thread_rng();
+            let mut prices = vec![Price::from_f64(100.0).ticks(tick); symbols];
+            let mut next = timer.now_nanos();
+            for seq in 0..total {
+                let stock_id = (seq % symbols as u64) as i32;
+                let price = &mut prices[stock_id as usize];
+                *price = (*price + rng.gen_range(-2..=2)).max(HALF_SPREAD + 1);
+                // Queued ahead of the strategy's order, so the order sees the new quote.
+                let _ = engine_tx.send(EngineMsg::Quote { stock_id, mid: *price });
+                let _ = tick_tx.send(BenchTick { seq, stock_id, price: *price, created: timer.now_nanos() });
+
+                next += interval.as_nanos() as u64;
+                while timer.now_nanos() < next {
+                    std::hint::spin_loop();
+                }
+            }
+        })
+    };
+
+    let strategy = {
+        let timer = SharedClock::clone(&timer);
+        let (symbols, spin) = (args.symbols.max(1), args.spin);
+        thread::spawn(move || {
+            let mut last = vec![None; symbols];
+            while let Some(tick) = recv(&tick_rx, spin) {
+                // Momentum: follow the last move, crossing the spread.
+                let up = last[tick.stock_id as usize].is_none_or(|prev| tick.price >= prev);
+                last[tick.stock_id as usize] = Some(tick.price);
+                let (side, price) = if up {
+                    (Side::Buy, tick.price + HALF_SPREAD)
+                } else {
+                    (Side::Sell, tick.price - HALF_SPREAD)
+                };
+                let (id, stock_id, tif, visibility) = (tick.seq, tick.stock_id, TimeInForce::Ioc, Visibility::Lit);
+                let order = Order { id, stock_id, side, price, qty: 1, tif, visibility };
+                let msg = EngineMsg::Order { order, created: tick.created, decided: timer.now_nanos() };
+                if engine_tx.send(msg).is_err() {
+                    break;
+                }
+            }
+        })
+    };
+
+    let engine = {
+        let timer = SharedClock::clone(&timer);
+        let (symbols, spin) = (args.symbols.max(1), args.spin);
+        thread::spawn(move || {
+            let mut engine = MatchingEngine::default();
+            let mut quotes: Vec<Option<(u64, u64)>> = vec![None; symbols];
+            let mut next_maker_id = MAKER_ID_BASE;
+            while let Some(msg) = recv(&engine_rx, spin) {
+                match msg {
+                    EngineMsg::Quote { stock_id, mid } => {
+                        if let Some((bid, ask)) = quotes[stock_id as usize].take() {
+                            engine.cancel(stock_id, bid);
+                            engine.cancel(stock_id, ask);
+                        }
+                        let (bid, ask) = (next_maker_id, next_maker_id + 1);
+                        next_maker_id += 2;
+                        for (id, side, price) in [(bid, Side::Buy, mid - HALF_SPREAD), (ask, Side::Sell, mid + HALF_SPREAD)] {
+                            let (qty, tif, visibility) = (QUOTE_QTY, TimeInForce::Gtc, Visibility::Lit);
+                            engine.submit(&Order { id, stock_id, side, price, qty, tif, visibility });
+                        }
+                        quotes[stock_id as usize] = Some((bid, ask));
+                    }
+                    EngineMsg::Order { order, created, decided } => {
+                        let fills = engine.submit(&order);
+                        debug_assert!(fills.iter().all(|f| f.taker_id == order.id && f.maker_id >= MAKER_ID_BASE));
+                        let report = Report {
+                            seq: order.id,
+                            filled: fills.iter().map(|f| f.qty).sum(),
+                            notional: fills.iter().map(|f| (tick * f.price).to_f64() * f.qty as f64).sum(),
+                            created,
+                            decided,
+                            matched: timer.now_nanos(),
+                        };
+                        if report_tx.send(report).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        })
+    };
+
+    let (mut decide, mut matching, mut report_hop, mut round_trip) = (histogram(), histogram(), histogram(), histogram());
+    let (mut filled, mut notional) = (0u64, 0.0);
+    for _ in 0..total {
+        let Some(report) = recv(&report_rx, args.spin) else {
+            break;
+        };
+        let done = timer.now_nanos();
+        if report.seq < args.warmup {
+            continue;
+        }
+        decide.saturating_record(report.decided.saturating_sub(report.created).max(1));
+        matching.saturating_record(report.matched.saturating_sub(report.decided).max(1));
+        report_hop.saturating_record(done.saturating_sub(report.matched).max(1));
+        round_trip.saturating_record(done.saturating_sub(report.created).max(1));
+        filled += report.filled as u64;
+        notional += report.notional;
+    }
+    drop(report_rx);
+
+    for handle in [feed, strategy, engine] {
+        handle.join().map_err(|_| io::Error::other("benchmark thread panicked"))?;
+    }
+
+    print_table(&[
+        ("tick -> order", &decide),
+        ("order -> match", &matching),
+        ("match -> report", &report_hop),
+        ("tick -> trade", &round_trip),
+    ]);
+    println!(
+        "{} of {} orders filled, {:.2} notional",
+        filled,
+        round_trip.len(),
+        notional
+    );
+    Ok(())
+}
+
+/// Blocks for the next message, or busy-polls when `spin` is set. `None`
+/// once every sender is gone.
+fn recv<T>(rx: &Receiver<T>, spin: bool) -> Option<T> {
+    if !spin {
+        return rx.recv().ok();
+    }
+    loop {
+        match rx.try_recv() {
+            Ok(msg) => return Some(msg),
+            Err(TryRecvError::Empty) => std::hint::spin_loop(),
+            Err(TryRecvError::Disconnected) => return None,
+        }
+    }
+}
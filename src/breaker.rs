@@ -0,0 +1,100 @@
+//! Circuit breakers that stop calling Postgres or Redis after repeated
+//! failures, then let a single probe through once the cooldown has passed.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+use crate::config::BreakerConfig;
+
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    /// A probe is in flight; everything else is still held back.
+    HalfOpen,
+}
+
+struct Inner {
+    state: State,
+    trips: u64,
+    skipped: u64,
+}
+
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    name: &'static str,
+    threshold: u32,
+    cooldown: Duration,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(name: &'static str, cfg: &BreakerConfig) -> Self {
+        CircuitBreaker {
+            name,
+            threshold: cfg.failure_threshold.max(1),
+            cooldown: Duration::from_millis(cfg.cooldown_ms),
+            inner: Arc::new(Mutex::new(Inner { state: State::Closed { failures: 0 }, trips: 0, skipped: 0 })),
+        }
+    }
+
+    /// Whether the next call should go through. Once an open breaker's
+    /// cooldown is over, the first caller becomes the probe.
+    pub fn allow(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            State::Closed { .. } => true,
+            State::Open { until } if Instant::now() >= until => {
+                info!("{} breaker half-open, probing", self.name);
+                inner.state = State::HalfOpen;
+                true
+            }
+            State::Open { .. } | State::HalfOpen => {
+                inner.skipped += 1;
+                false
+            }
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if matches!(inner.state, State::HalfOpen) {
+            info!("{} breaker closed, backend recovered", self.name);
+        }
+        inner.state = State::Closed { failures: 0 };
+    }
+
+    pub fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        let trip = match inner.state {
+            State::Closed { failures } if failures + 1 >= self.threshold => true,
+            State::Closed { failures } => {
+                inner.state = State::Closed { failures: failures + 1 };
+                false
+            }
+            State::HalfOpen => true,
+            // A call let through before the breaker tripped.
+            State::Open { .. } => false,
+        };
+        if trip {
+            warn!("{} breaker open for {:?} after repeated failures", self.name, self.cooldown);
+            inner.state = State::Open { until: Instant::now() + self.cooldown };
+            inner.trips += 1;
+        }
+    }
+
+    /// One-line summary for the TUI.
+    pub fn describe(&self) -> String {
+        let inner = self.inner.lock().unwrap();
+        let state = match inner.state {
+            State::Closed { failures: 0 } => "closed".to_string(),
+            State::Closed { failures } => format!("closed ({}/{} failures)", failures, self.threshold),
+            State::Open { until } => {
+                format!("OPEN, probe in {}s", until.saturating_duration_since(Instant::now()).as_secs())
+            }
+            State::HalfOpen => "half-open".to_string(),
+        };
+        format!("{} {}, {} trips, {} skipped", self.name, state, inner.trips, inner.skipped)
+    }
+}
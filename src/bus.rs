@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+/// Messages buffered per subscriber before slow consumers start lagging.
+pub const BUS_CAPACITY: usize = 4096;
+
+/// Waits for the next message accepted by `keep`, then keeps collecting until
+/// `window` has passed or `max` messages are buffered. Returns `None` once the
+/// bus is closed. `name` identifies the consumer when it lags.
+pub async fn recv_batch<T: Clone>(
+    rx: &mut broadcast::Receiver<T>,
+    name: &str,
+    window: Duration,
+    max: usize,
+    keep: impl Fn(&T) -> bool,
+) -> Option<Vec<T>> {
+    let mut batch = Vec::new();
+    let deadline = tokio::time::sleep(Duration::MAX);
+    tokio::pin!(deadline);
+    while batch.len() < max {
+        tokio::select! {
+            _ = &mut deadline, if !batch.is_empty() => break,
+            msg = rx.recv() => match msg {
+                Ok(msg) if keep(&msg) => {
+                    if batch.is_empty() {
+                        deadline.as_mut().reset(tokio::time::Instant::now() + window);
+                    }
+                    batch.push(msg);
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(n)) => warn!("{} lagged, skipped {} messages", name, n),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+    Some(batch)
+}
@@ -0,0 +1,159 @@
+//! The Redis price cache, on a single server, a Redis Cluster or a
+//! Sentinel-managed master. One multiplexed connection is shared and
+//! re-established after an error; against a Cluster, `stock:<ticker>` keys are
+//! routed to the node owning their hash slot. How long each key's last
+//! `SET` took is kept for the Redis pane.
+
+use std::collections::HashMap;
+use std::io;
+use std::time::{Duration, Instant, SystemTime};
+
+use redis::aio::MultiplexedConnection;
+use redis::cluster::ClusterClient;
+use redis::cluster_async::ClusterConnection;
+use redis::sentinel::{Sentinel, SentinelNodeConnectionInfo};
+use redis::{Cmd, ConnectionAddr, FromRedisValue, IntoConnectionInfo, RedisResult, TlsMode, ToRedisArgs};
+use tokio::sync::Mutex;
+use url::Url;
+
+use crate::config::{RedisConfig, RedisMode, RedisTlsConfig};
+use crate::secrets::Dsn;
+use crate::tls;
+
+enum Backend {
+    Standalone(redis::Client),
+    Cluster(ClusterClient),
+    /// The master is looked up again on every reconnect, following failovers.
+    Sentinel { sentinel: Mutex<Sentinel>, master: String, node: SentinelNodeConnectionInfo },
+}
+
+#[derive(Clone)]
+enum Connection {
+    Single(MultiplexedConnection),
+    Cluster(ClusterConnection),
+}
+
+/// A key's last successful `SET`.
+#[derive(Clone, Copy)]
+pub struct LastWrite {
+    pub took: Duration,
+    pub at: SystemTime,
+}
+
+pub struct RedisCache {
+    backend: Backend,
+    conn: Mutex<Option<Connection>>,
+    writes: std::sync::Mutex<HashMap<String, LastWrite>>,
+}
+
+impl RedisCache {
+    /// `url` supplies scheme, credentials and database; in cluster and
+    /// sentinel mode its host is replaced by each of `[redis] nodes`.
+    pub fn new(url: &Dsn, cfg: &RedisConfig, tls_cfg: &RedisTlsConfig) -> io::Result<Self> {
+        let base = tls::redis_url(url, tls_cfg)?;
+        let backend = match cfg.mode {
+            RedisMode::Standalone => Backend::Standalone(redis::Client::open(base.as_str()).map_err(io::Error::other)?),
+            RedisMode::Cluster => {
+                let nodes = node_urls(&base, &cfg.nodes, true)?;
+                Backend::Cluster(ClusterClient::new(nodes).map_err(io::Error::other)?)
+            }
+            RedisMode::Sentinel => {
+                let master = cfg.master_name.clone().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "[redis] mode = \"sentinel\" needs master_name")
+                })?;
+                // Sentinels are reached without the master's credentials.
+                let sentinels = node_urls(&base, &cfg.nodes, false)?;
+                let info = base.as_str().into_connection_info().map_err(io::Error::other)?;
+                let tls_mode = match info.addr {
+                    ConnectionAddr::TcpTls { insecure: true, .. } => Some(TlsMode::Insecure),
+                    ConnectionAddr::TcpTls { insecure: false, .. } => Some(TlsMode::Secure),
+                    _ => None,
+                };
+                let node = SentinelNodeConnectionInfo { tls_mode, redis_connection_info: Some(info.redis) };
+                let sentinel = Sentinel::build(sentinels).map_err(io::Error::other)?;
+                Backend::Sentinel { sentinel: Mutex::new(sentinel), master, node }
+            }
+        };
+        Ok(RedisCache { backend, conn: Mutex::new(None), writes: std::sync::Mutex::new(HashMap::new()) })
+    }
+
+    async fn connect(&self) -> RedisResult<Connection> {
+        match &self.backend {
+            Backend::Standalone(client) => client.get_multiplexed_tokio_connection().await.map(Connection::Single),
+            Backend::Cluster(client) => client.get_async_connection().await.map(Connection::Cluster),
+            Backend::Sentinel { sentinel, master, node } => {
+                let client = sentinel.lock().await.async_master_for(master, Some(node)).await?;
+                client.get_multiplexed_tokio_connection().await.map(Connection::Single)
+            }
+        }
+    }
+
+    async fn query<T: FromRedisValue>(&self, cmd: &Cmd) -> RedisResult<T> {
+        let mut conn = {
+            let mut cached = self.conn.lock().await;
+            match &*cached {
+                Some(conn) => conn.clone(),
+                None => cached.insert(self.connect().await?).clone(),
+            }
+        };
+        let result = match &mut conn {
+            Connection::Single(c) => cmd.query_async(c).await,
+            Connection::Cluster(c) => cmd.query_async(c).await,
+        };
+        if result.is_err() {
+            *self.conn.lock().await = None;
+        }
+        result
+    }
+
+    pub async fn set(&self, key: &str, value: impl ToRedisArgs) -> RedisResult<()> {
+        let started = Instant::now();
+        self.query::<()>(redis::cmd("SET").arg(key).arg(value)).await?;
+        let write = LastWrite { took: started.elapsed(), at: SystemTime::now() };
+        self.writes.lock().unwrap().insert(key.to_string(), write);
+        Ok(())
+    }
+
+    pub async fn get(&self, key: &str) -> RedisResult<Option<String>> {
+        self.query(redis::cmd("GET").arg(key)).await
+    }
+
+    /// Milliseconds `key` has left; -1 if it never expires, -2 if it is
+    /// missing.
+    pub async fn pttl(&self, key: &str) -> RedisResult<i64> {
+        self.query(redis::cmd("PTTL").arg(key)).await
+    }
+
+    /// Of this process, since it started.
+    pub fn last_write(&self, key: &str) -> Option<LastWrite> {
+        self.writes.lock().unwrap().get(key).copied()
+    }
+
+    pub async fn ping(&self) -> RedisResult<()> {
+        self.query::<String>(&redis::cmd("PING")).await.map(drop)
+    }
+}
+
+/// `base` pointed at each `host:port` in `nodes`.
+fn node_urls(base: &Url, nodes: &[String], keep_auth: bool) -> io::Result<Vec<String>> {
+    if nodes.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "[redis] nodes must list at least one host:port"));
+    }
+    nodes
+        .iter()
+        .map(|node| {
+            let invalid = || io::Error::new(io::ErrorKind::InvalidInput, format!("[redis] invalid node {}", node));
+            let (host, port) = node.rsplit_once(':').ok_or_else(invalid)?;
+            let port: u16 = port.parse().map_err(|_| invalid())?;
+            let mut url = base.clone();
+            url.set_host(Some(host)).map_err(|_| invalid())?;
+            url.set_port(Some(port)).map_err(|_| invalid())?;
+            if !keep_auth {
+                let _ = url.set_username("");
+                let _ = url.set_password(None);
+                url.set_path("");
+            }
+            Ok(url.to_string())
+        })
+        .collect()
+}
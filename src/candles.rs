@@ -0,0 +1,233 @@
+//! OHLCV bars built from the ticks, from `[candles]`: for each interval every
+//! symbol's ticks are bucketed by wall-clock time into bars of open, high,
+//! low, close and volume. Ticks carry no size, so the volume is the number
+//! of ticks. A bar closes when its symbol's first tick of a later bucket
+//! arrives; closed bars are upserted into the `candles` table once a second,
+//! and the last `bars` of them stay in memory for the candlestick view, which
+//! `c` toggles with the line charts and `i` moves through the intervals.
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+use chrono::{DateTime, Utc};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::Color;
+use ratatui::symbols::Marker;
+use ratatui::widgets::canvas::{Canvas, Line};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+use sqlx::PgPool;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+use crate::config::CandlesConfig;
+use crate::price::Price;
+use crate::symbols::Symbol;
+use crate::tick::Tick;
+
+const PERSIST_EVERY: Duration = Duration::from_secs(1);
+/// Candlestick panels side by side.
+const PANELS_PER_ROW: usize = 3;
+
+#[derive(Clone, Copy)]
+struct Bar {
+    /// Unix seconds, a multiple of the interval.
+    start_s: i64,
+    open: Price,
+    high: Price,
+    low: Price,
+    close: Price,
+    volume: u64,
+}
+
+impl Bar {
+    fn new(start_s: i64, price: Price) -> Self {
+        Bar { start_s, open: price, high: price, low: price, close: price, volume: 1 }
+    }
+
+    fn add(&mut self, price: Price) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume += 1;
+    }
+}
+
+struct Inner {
+    /// By interval, then stock id: closed bars oldest first, then the open
+    /// one.
+    bars: Vec<Vec<VecDeque<Bar>>>,
+    /// Closed since the last write, with their interval and stock id.
+    closed: Vec<(u64, i32, Bar)>,
+}
+
+#[derive(Clone)]
+pub struct Candles {
+    intervals_s: Arc<Vec<u64>>,
+    /// Closed bars kept per symbol and interval.
+    keep: usize,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Candles {
+    pub fn new(cfg: &CandlesConfig, n_stocks: usize) -> io::Result<Self> {
+        if cfg.intervals_s.is_empty() || cfg.intervals_s.contains(&0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "candles.intervals_s: needs at least one interval, each at least 1s",
+            ));
+        }
+        let bars = cfg.intervals_s.iter().map(|_| vec![VecDeque::new(); n_stocks]).collect();
+        Ok(Candles {
+            intervals_s: Arc::new(cfg.intervals_s.clone()),
+            keep: cfg.bars.max(1),
+            inner: Arc::new(Mutex::new(Inner { bars, closed: Vec::new() })),
+        })
+    }
+
+    /// Adds `tick` to its symbol's open bar of every interval. A tick older
+    /// than the open bar, from a lagging feed, still goes into it.
+    pub fn record(&self, tick: &Tick) {
+        let Ok(id) = usize::try_from(tick.stock_id) else { return };
+        let now_s = tick.ts.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+        let mut inner = self.inner.lock().unwrap();
+        let Inner { bars, closed } = &mut *inner;
+        for (&interval_s, by_symbol) in self.intervals_s.iter().zip(bars.iter_mut()) {
+            let Some(bars) = by_symbol.get_mut(id) else { return };
+            let start_s = now_s - now_s.rem_euclid(interval_s as i64);
+            match bars.back_mut() {
+                Some(open) if open.start_s >= start_s => open.add(tick.price),
+                open => {
+                    if let Some(open) = open {
+                        closed.push((interval_s, tick.stock_id, *open));
+                    }
+                    bars.push_back(Bar::new(start_s, tick.price));
+                    if bars.len() > self.keep + 1 {
+                        bars.pop_front();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Records the ticks of a bus.
+    pub async fn run(self, mut rx: broadcast::Receiver<Tick>) {
+        loop {
+            match rx.recv().await {
+                Ok(tick) => self.record(&tick),
+                Err(RecvError::Lagged(n)) => warn!("Candles lagged, skipped {} ticks", n),
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// Upserts the bars closed since the last write into `candles` once a
+    /// second; the open bars are not written.
+    pub async fn persist(self, pool: Arc<PgPool>) {
+        let mut interval = tokio::time::interval(PERSIST_EVERY);
+        loop {
+            interval.tick().await;
+            let closed = std::mem::take(&mut self.inner.lock().unwrap().closed);
+            if closed.is_empty() {
+                continue;
+            }
+            if let Err(e) = store(&pool, &closed).await {
+                warn!("Storing {} candles failed (run with --migrate): {}", closed.len(), e);
+            }
+        }
+    }
+
+    /// One candlestick panel per symbol on the page, for the interval at
+    /// `interval` (wrapping), green where the bar closed at or above its open.
+    pub fn render(&self, f: &mut Frame, area: Rect, page: &[usize], symbols: &[Symbol], interval: usize) {
+        let interval_s = self.intervals_s[interval % self.intervals_s.len()];
+        let title = format!("Candles, {}s bars, volume in ticks - c line charts, i interval", interval_s);
+        let outer = Block::default().borders(Borders::ALL).title(title);
+        let inner_area = outer.inner(area);
+        f.render_widget(outer, area);
+        if page.is_empty() {
+            f.render_widget(Paragraph::new("no symbols on this page"), inner_area);
+            return;
+        }
+        let inner = self.inner.lock().unwrap();
+        let by_symbol = &inner.bars[interval % self.intervals_s.len()];
+        let rows = page.len().div_ceil(PANELS_PER_ROW);
+        let row_areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Ratio(1, rows as u32); rows])
+            .split(inner_area);
+        for (row, ids) in row_areas.iter().zip(page.chunks(PANELS_PER_ROW)) {
+            let panels = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Ratio(1, PANELS_PER_ROW as u32); PANELS_PER_ROW])
+                .split(*row);
+            for (&panel, &id) in panels.iter().zip(ids) {
+                let (Some(symbol), Some(bars)) = (symbols.get(id), by_symbol.get(id)) else { continue };
+                render_panel(f, panel, symbol, bars);
+            }
+        }
+    }
+}
+
+/// `AAPL 14:32:05 O 187.20 H 187.31 L 187.12 C 187.25 V 42` for the last bar,
+/// above as many bars as fit, two columns each.
+fn render_panel(f: &mut Frame, area: Rect, symbol: &Symbol, bars: &VecDeque<Bar>) {
+    let title = match bars.back() {
+        Some(bar) => {
+            let at = DateTime::<Utc>::from_timestamp(bar.start_s, 0).unwrap_or_default().format("%H:%M:%S");
+            let (o, h, l, c) = (bar.open, bar.high, bar.low, bar.close);
+            format!("{} {} O {} H {} L {} C {} V {}", symbol.ticker, at, o, h, l, c, bar.volume)
+        }
+        None => format!("{} no ticks", symbol.ticker),
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let fit = (block.inner(area).width as usize / 2).max(1);
+    let shown: Vec<Bar> = bars.iter().skip(bars.len().saturating_sub(fit)).copied().collect();
+    let low = shown.iter().map(|b| b.low.to_f64()).fold(f64::INFINITY, f64::min);
+    let high = shown.iter().map(|b| b.high.to_f64()).fold(f64::NEG_INFINITY, f64::max);
+    // A flat range still gets some height.
+    let pad = ((high - low) * 0.05).max(symbol.tick_size.to_f64());
+    let canvas = Canvas::default()
+        .block(block)
+        .marker(Marker::Braille)
+        .x_bounds([0.0, (fit * 2) as f64])
+        .y_bounds([low - pad, high + pad])
+        .paint(move |ctx| {
+            for (i, bar) in shown.iter().enumerate() {
+                let color = if bar.close >= bar.open { Color::Green } else { Color::Red };
+                let x = (i * 2) as f64;
+                let (open, close) = (bar.open.to_f64(), bar.close.to_f64());
+                ctx.draw(&Line::new(x + 0.5, bar.low.to_f64(), x + 0.5, bar.high.to_f64(), color));
+                for dx in [0.1, 0.5, 0.9] {
+                    ctx.draw(&Line::new(x + dx, open.min(close), x + dx, open.max(close), color));
+                }
+            }
+        });
+    f.render_widget(canvas, area);
+}
+
+async fn store(pool: &PgPool, closed: &[(u64, i32, Bar)]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for &(interval_s, stock_id, bar) in closed {
+        sqlx::query(
+            "INSERT INTO candles (stock_id, interval_s, start_at, open, high, low, close, volume) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+             ON CONFLICT (stock_id, interval_s, start_at) DO UPDATE SET \
+             high = GREATEST(candles.high, EXCLUDED.high), low = LEAST(candles.low, EXCLUDED.low), \
+             close = EXCLUDED.close, volume = candles.volume + EXCLUDED.volume",
+        )
+        .bind(stock_id)
+        .bind(interval_s as i32)
+        .bind(DateTime::<Utc>::from_timestamp(bar.start_s, 0).unwrap_or_default())
+        .bind(bar.open.to_decimal())
+        .bind(bar.high.to_decimal())
+        .bind(bar.low.to_decimal())
+        .bind(bar.close.to_decimal())
+        .bind(bar.volume as i64)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
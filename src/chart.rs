@@ -0,0 +1,236 @@
+//! The price charts drawn to SVG from their points rather than the terminal
+//! cells, for reports: `e` in the TUI writes the backend chart of the page
+//! with the moving averages, zoomed and log-scaled as shown, and
+//! `hft-latency chart` draws the same from a running pipeline's `/history`.
+//! Series longer than the drawing is wide are downsampled with LTTB.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use ratatui::style::Color;
+
+use crate::attach::get_json;
+use crate::cli::ChartArgs;
+use crate::config::Config;
+use crate::http::{HistoryEntry, SymbolEntry};
+use crate::lttb;
+use crate::symbols;
+use crate::view::{self, CHART_PAGE_SIZE};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const WIDTH: f64 = 1000.0;
+const HEIGHT: f64 = 500.0;
+/// Around the plot area: left for the price labels, bottom for the times.
+const LEFT: f64 = 80.0;
+const RIGHT: f64 = 20.0;
+const TOP: f64 = 40.0;
+const BOTTOM: f64 = 40.0;
+/// Intervals between axis labels.
+const TICKS: usize = 4;
+/// Points drawn per series.
+const MAX_POINTS: usize = 2000;
+const BACKGROUND: &str = "#1e1e1e";
+const FOREGROUND: &str = "#d0d0d0";
+const FONT: &str = r#"font-family="monospace" font-size="12""#;
+const AVERAGE: &str = r#" stroke-dasharray="4 3" opacity="0.6""#;
+/// Price lines by [`symbols::line_style`].
+const DASHES: [&str; 3] = ["", r#" stroke-dasharray="8 3""#, r#" stroke-dasharray="2 2""#];
+
+pub struct Series {
+    pub name: String,
+    pub color: Color,
+    /// Drawn dashed and dimmed, like the moving averages on the combined chart.
+    pub average: bool,
+    /// From [`symbols::line_style`], for a price line.
+    pub line_style: usize,
+    /// At their chart x, Unix seconds.
+    pub points: Vec<(f64, f64)>,
+}
+
+/// The series on one chart with time across and price up, a log axis if
+/// `log`, and a legend naming each.
+pub fn svg(title: &str, series: &[Series], log: bool) -> String {
+    let scaled: Vec<Vec<(f64, f64)>> =
+        series.iter().map(|s| lttb::downsample(&view::scale(s.points.clone(), log), MAX_POINTS)).collect();
+    let bounds = |pick: fn(&(f64, f64)) -> f64| {
+        let (low, high) = scaled
+            .iter()
+            .flatten()
+            .map(pick)
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+        match (low, high) {
+            (low, high) if low < high => (low, high),
+            (low, _) if low.is_finite() => (low - 1.0, low + 1.0),
+            _ => (0.0, 1.0),
+        }
+    };
+    let ((x_low, x_high), (y_low, y_high)) = (bounds(|p| p.0), bounds(|p| p.1));
+    let (plot_w, plot_h) = (WIDTH - LEFT - RIGHT, HEIGHT - TOP - BOTTOM);
+    let x_of = |x: f64| LEFT + (x - x_low) / (x_high - x_low) * plot_w;
+    let y_of = |y: f64| TOP + (y_high - y) / (y_high - y_low) * plot_h;
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" {font}>"#,
+        w = WIDTH,
+        h = HEIGHT,
+        font = FONT
+    );
+    let _ = writeln!(out, r#"<rect width="100%" height="100%" fill="{}"/>"#, BACKGROUND);
+    let _ = writeln!(out, r#"<text x="{}" y="24" fill="{}" font-size="14">{}</text>"#, LEFT, FOREGROUND, escape(title));
+    let _ = writeln!(
+        out,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="{}"/>"#,
+        LEFT, TOP, plot_w, plot_h, FOREGROUND
+    );
+    for i in 0..=TICKS {
+        let f = i as f64 / TICKS as f64;
+        let (x, y) = (x_low + (x_high - x_low) * f, y_low + (y_high - y_low) * f);
+        let at = DateTime::<Utc>::from_timestamp_micros((x * 1e6) as i64).unwrap_or_default().format("%H:%M:%S");
+        let price = if log { y.exp() } else { y };
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" fill="{}" text-anchor="middle">{}</text>"#,
+            x_of(x),
+            HEIGHT - BOTTOM + 18.0,
+            FOREGROUND,
+            at
+        );
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" fill="{}" text-anchor="end">{:.2}</text>"#,
+            LEFT - 6.0,
+            y_of(y) + 4.0,
+            FOREGROUND,
+            price
+        );
+    }
+    if log {
+        let _ = writeln!(out, r#"<text x="8" y="{}" fill="{}">log</text>"#, TOP + 12.0, FOREGROUND);
+    }
+    for (s, points) in series.iter().zip(&scaled) {
+        let path: Vec<String> = points.iter().map(|&(x, y)| format!("{:.1},{:.1}", x_of(x), y_of(y))).collect();
+        let _ = writeln!(
+            out,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"{}/>"#,
+            path.join(" "),
+            hex(s.color),
+            stroke(s)
+        );
+    }
+    for (i, s) in series.iter().enumerate() {
+        let (x, y) = (WIDTH - RIGHT - 150.0, TOP + 16.0 + i as f64 * 16.0);
+        let _ = writeln!(
+            out,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="2"{}/>"#,
+            x,
+            y - 4.0,
+            x + 20.0,
+            y - 4.0,
+            hex(s.color),
+            stroke(s)
+        );
+        let _ = writeln!(out, r#"<text x="{}" y="{}" fill="{}">{}</text>"#, x + 26.0, y, FOREGROUND, escape(&s.name));
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+fn stroke(series: &Series) -> &'static str {
+    if series.average {
+        AVERAGE
+    } else {
+        DASHES[series.line_style % DASHES.len()]
+    }
+}
+
+/// Writes [`svg`] to `path`, creating its directory.
+pub fn save(path: &Path, title: &str, series: &[Series], log: bool) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, svg(title, series, log))
+}
+
+/// `hft-latency chart`: the price and moving average of each symbol from
+/// `/history`, in the chart colors `attach` gives them.
+pub async fn run(args: ChartArgs, config: &Config) -> io::Result<()> {
+    let base = args.url.trim_end_matches('/').to_string();
+    let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().map_err(io::Error::other)?;
+    let listed: Vec<SymbolEntry> = get_json(&client, &format!("{}/symbols", base)).await?;
+    let chosen: Vec<&SymbolEntry> = if args.symbols.is_empty() {
+        listed.iter().take(CHART_PAGE_SIZE).collect()
+    } else {
+        args.symbols
+            .iter()
+            .map(|ticker| {
+                listed.iter().find(|s| &s.ticker == ticker).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, format!("--symbols: unknown ticker {}", ticker))
+                })
+            })
+            .collect::<io::Result<_>>()?
+    };
+    let mut series = Vec::new();
+    for symbol in chosen {
+        let history: HistoryEntry = get_json(&client, &format!("{}/history/{}", base, symbol.stock_id)).await?;
+        let color = symbols::color(config.ui.palette, symbol.stock_id);
+        series.push(Series {
+            name: history.ticker.clone(),
+            color,
+            average: false,
+            line_style: symbols::line_style(config.ui.palette, symbol.stock_id),
+            points: timed(&history.prices, &history.times_unix_us),
+        });
+        series.push(Series {
+            name: format!("{} avg", history.ticker),
+            color,
+            average: true,
+            line_style: 0,
+            points: timed(&history.moving_avg, &history.moving_avg_times_unix_us),
+        });
+    }
+    save(&args.out, &format!("Stocks from {}", base), &series, args.log)?;
+    println!("Wrote {} series to {}", series.len(), args.out.display());
+    Ok(())
+}
+
+/// The values that have a time, at it; both line up at the end.
+fn timed(values: &[f64], times_us: &[u64]) -> Vec<(f64, f64)> {
+    let values = &values[values.len().saturating_sub(times_us.len())..];
+    let times_us = &times_us[times_us.len().saturating_sub(values.len())..];
+    values.iter().zip(times_us).map(|(&y, &us)| (us as f64 / 1e6, y)).collect()
+}
+
+/// As xterm draws the named colors.
+fn hex(color: Color) -> String {
+    let rgb = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Black => (0, 0, 0),
+        Color::Red => (205, 0, 0),
+        Color::Green => (0, 205, 0),
+        Color::Yellow => (205, 205, 0),
+        Color::Blue => (0, 0, 238),
+        Color::Magenta => (205, 0, 205),
+        Color::Cyan => (0, 205, 205),
+        Color::Gray => (229, 229, 229),
+        Color::DarkGray => (127, 127, 127),
+        Color::LightRed => (255, 0, 0),
+        Color::LightGreen => (0, 255, 0),
+        Color::LightYellow => (255, 255, 0),
+        Color::LightBlue => (92, 92, 255),
+        Color::LightMagenta => (255, 0, 255),
+        Color::LightCyan => (0, 255, 255),
+        Color::White => (255, 255, 255),
+        Color::Reset | Color::Indexed(_) => (208, 208, 208),
+    };
+    format!("#{:02x}{:02x}{:02x}", rgb.0, rgb.1, rgb.2)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
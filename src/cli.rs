@@ -0,0 +1,208 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::config::{StrategyKind, DEFAULT_MULTICAST_GROUP};
+
+/// Simulated market data pipeline with a latency-focused TUI.
+///
+/// Runs the TUI when no subcommand is given.
+#[derive(Parser)]
+#[command(name = "hft-latency", version)]
+pub struct Cli {
+    /// Config file [default: hft.toml if it exists]
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    #[command(flatten)]
+    pub run: RunArgs,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Options of the pipeline run when no subcommand is given.
+#[derive(Args)]
+pub struct RunArgs {
+    /// Apply the embedded schema migrations to Postgres before starting
+    #[arg(long)]
+    pub migrate: bool,
+    /// Also write the latency summary printed on exit to this file
+    #[arg(long, value_name = "FILE")]
+    pub summary: Option<PathBuf>,
+    /// Write the latency histograms on exit as a standalone HTML report, or
+    /// as JSON if FILE ends in .json
+    #[arg(long, value_name = "FILE")]
+    pub report: Option<PathBuf>,
+    /// Run without the TUI until Ctrl-C; viewers attach over [http]
+    #[arg(long)]
+    pub headless: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Export stored ticks to per-symbol files
+    Export(ExportArgs),
+    /// Join the multicast feed and report gaps and one-way latency
+    MulticastRecv(MulticastRecvArgs),
+    /// Run latency benchmarks against synthetic load
+    Bench(BenchArgs),
+    /// Open the TUI on a pipeline running elsewhere, e.g. with --headless
+    Attach(AttachArgs),
+    /// Replay a recording through a strategy as fast as possible
+    Backtest(BacktestArgs),
+    /// Draw the price charts of a running pipeline to an SVG file
+    Chart(ChartArgs),
+}
+
+#[derive(Args)]
+pub struct BacktestArgs {
+    /// Tick file in the spool format, as read by `replay` feeds
+    pub recording: PathBuf,
+    /// [default: [paper] strategy]
+    #[arg(long, value_enum)]
+    pub strategy: Option<StrategyKind>,
+    /// Rhai file of --strategy script [default: [paper] script]
+    #[arg(long)]
+    pub script: Option<String>,
+}
+
+#[derive(Args)]
+pub struct AttachArgs {
+    /// Base URL of its [http] server
+    #[arg(default_value = "http://127.0.0.1:8080")]
+    pub url: String,
+}
+
+#[derive(Args)]
+pub struct ChartArgs {
+    /// Base URL of its [http] server
+    #[arg(default_value = "http://127.0.0.1:8080")]
+    pub url: String,
+    /// Tickers to draw, comma-separated [default: the first six]
+    #[arg(long, value_delimiter = ',')]
+    pub symbols: Vec<String>,
+    /// Log-scale y axis
+    #[arg(long)]
+    pub log: bool,
+    #[arg(long, default_value = "chart.svg")]
+    pub out: PathBuf,
+}
+
+#[derive(Args)]
+pub struct BenchArgs {
+    #[command(subcommand)]
+    pub command: BenchCommand,
+}
+
+#[derive(Subcommand)]
+pub enum BenchCommand {
+    /// Tick -> strategy -> matching engine -> execution report round trip
+    TickToTrade(TickToTradeArgs),
+    /// Time each pipeline stage in isolation
+    Pipeline(PipelineArgs),
+}
+
+#[derive(Args)]
+pub struct PipelineArgs {
+    /// Operations per stage
+    #[arg(long, default_value_t = 10_000)]
+    pub iterations: u64,
+    /// Stages to run, comma-separated [default: all]
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub stages: Vec<PipelineStage>,
+    /// Overrides HFT_REDIS_URL and the secrets file
+    #[arg(long)]
+    pub redis_url: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PipelineStage {
+    /// Unbuffered append to a temporary file
+    File,
+    /// SET over one Redis connection
+    Redis,
+    /// INSERT into a temporary copy of `stock_data`
+    Postgres,
+    /// Hop across a broadcast channel like the tick bus
+    Channel,
+}
+
+#[derive(Args)]
+pub struct TickToTradeArgs {
+    /// Measured round trips
+    #[arg(long, default_value_t = 100_000)]
+    pub iterations: u64,
+    /// Round trips run before measuring
+    #[arg(long, default_value_t = 10_000)]
+    pub warmup: u64,
+    #[arg(long, default_value_t = 3)]
+    pub symbols: usize,
+    /// Microseconds between generated ticks
+    #[arg(long, default_value_t = 10)]
+    pub interval_us: u64,
+    /// Busy-poll the hand-off queues instead of blocking on them
+    #[arg(long)]
+    pub spin: bool,
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+    pub format: ExportFormat,
+    /// Where to read ticks from
+    #[arg(long, value_enum, default_value_t = ExportSource::Postgres)]
+    pub source: ExportSource,
+    /// Inclusive lower bound, e.g. `2026-10-14` or `2026-10-14 09:30:00`
+    #[arg(long, value_parser = parse_time)]
+    pub from: Option<NaiveDateTime>,
+    /// Exclusive upper bound, same formats as `--from`
+    #[arg(long, value_parser = parse_time)]
+    pub to: Option<NaiveDateTime>,
+    /// Output directory
+    #[arg(long, default_value = "export")]
+    pub out: PathBuf,
+}
+
+#[derive(Args)]
+pub struct MulticastRecvArgs {
+    /// Group and port to join
+    #[arg(long, default_value = DEFAULT_MULTICAST_GROUP)]
+    pub group: String,
+    /// Local address of the interface to join on [default: chosen by the OS]
+    #[arg(long)]
+    pub interface: Option<String>,
+    /// Milliseconds between interval reports
+    #[arg(long, default_value_t = 1000)]
+    pub report_ms: u64,
+    /// Split latency at the kernel RX timestamp (`SO_TIMESTAMPING`, Linux only)
+    #[arg(long)]
+    pub kernel_timestamps: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportSource {
+    /// The `stock_data` table
+    Postgres,
+    /// Ticks still waiting in the local spool file
+    Spool,
+}
+
+fn parse_time(s: &str) -> Result<NaiveDateTime, String> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(s) {
+        return Ok(ts.naive_utc());
+    }
+    for fmt in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+        if let Ok(ts) = NaiveDateTime::parse_from_str(s, fmt) {
+            return Ok(ts);
+        }
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|d| d.and_hms_opt(0, 0, 0).unwrap())
+        .map_err(|_| format!("invalid timestamp `{}`", s))
+}
@@ -0,0 +1,161 @@
+//! Periodically measures the local clock's offset from an NTP server or the
+//! PTP grandmaster, so one-way latencies between hosts can be judged against
+//! the skew between their clocks.
+
+use std::io;
+use std::process::Stdio;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tokio::net::UdpSocket;
+use tokio::process::Command;
+use tracing::warn;
+
+use crate::config::{ClockConfig, ClockSource};
+
+const NTP_TIMEOUT: Duration = Duration::from_secs(2);
+/// Seconds from the NTP epoch (1900) to the Unix epoch.
+const NTP_UNIX_OFFSET_SECS: u64 = 2_208_988_800;
+/// LI = 0, version 4, mode 3 (client).
+const NTP_CLIENT_HEADER: u8 = 0x23;
+const NTP_MODE_SERVER: u8 = 4;
+
+#[derive(Clone, Debug)]
+pub struct ClockOffset {
+    /// Reference minus local clock: positive means the local clock is behind.
+    pub offset_ns: i64,
+    /// Round-trip delay, when the source reports one.
+    pub delay_ns: Option<i64>,
+    pub measured_at: Instant,
+}
+
+#[derive(Clone, Debug)]
+pub enum ClockStatus {
+    Disabled,
+    Pending,
+    Synced(ClockOffset),
+    Failed(String),
+}
+
+pub type SharedClockStatus = Arc<RwLock<ClockStatus>>;
+
+impl ClockStatus {
+    /// One-line summary for the TUI.
+    pub fn describe(&self, cfg: Option<&ClockConfig>) -> String {
+        let source = match cfg {
+            Some(ClockConfig { source: ClockSource::Ntp, server, .. }) => format!("ntp {}", server),
+            Some(ClockConfig { source: ClockSource::Ptp, .. }) => "ptp".to_string(),
+            None => String::new(),
+        };
+        match self {
+            ClockStatus::Disabled => "not monitored (add a [clock] section)".to_string(),
+            ClockStatus::Pending => format!("{}: waiting for first measurement", source),
+            ClockStatus::Synced(c) => format!(
+                "{}: offset {:+.3} ms{}, measured {}s ago",
+                source,
+                c.offset_ns as f64 / 1e6,
+                c.delay_ns.map(|d| format!(", rtt {:.3} ms", d as f64 / 1e6)).unwrap_or_default(),
+                c.measured_at.elapsed().as_secs()
+            ),
+            ClockStatus::Failed(e) => format!("{}: {}", source, e),
+        }
+    }
+}
+
+pub async fn run(cfg: ClockConfig, status: SharedClockStatus) {
+    let mut interval = tokio::time::interval(Duration::from_millis(cfg.interval_ms.max(1000)));
+    loop {
+        interval.tick().await;
+        let result = match cfg.source {
+            ClockSource::Ntp => query_ntp(&cfg.server).await,
+            ClockSource::Ptp => query_ptp().await,
+        };
+        *status.write().unwrap() = match result {
+            Ok(offset) => ClockStatus::Synced(offset),
+            Err(e) => {
+                warn!("Clock offset measurement failed: {}", e);
+                ClockStatus::Failed(e.to_string())
+            }
+        };
+    }
+}
+
+/// One SNTP exchange (RFC 4330).
+async fn query_ntp(server: &str) -> io::Result<ClockOffset> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(server).await?;
+
+    let mut request = [0u8; 48];
+    request[0] = NTP_CLIENT_HEADER;
+    let t1 = SystemTime::now();
+    let t1_ntp = to_ntp(t1);
+    request[40..48].copy_from_slice(&t1_ntp.to_be_bytes());
+    socket.send(&request).await?;
+
+    let mut response = [0u8; 48];
+    let len = tokio::time::timeout(NTP_TIMEOUT, socket.recv(&mut response))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "no NTP response"))??;
+    let t4 = SystemTime::now();
+
+    if len < 48 || response[0] & 0x7 != NTP_MODE_SERVER {
+        return Err(invalid("malformed NTP response"));
+    }
+    if response[1] == 0 {
+        return Err(invalid("NTP server sent kiss-of-death"));
+    }
+    if response[24..32] != t1_ntp.to_be_bytes() {
+        return Err(invalid("NTP response does not match request"));
+    }
+    let t2 = from_ntp(u64::from_be_bytes(response[32..40].try_into().unwrap()));
+    let t3 = from_ntp(u64::from_be_bytes(response[40..48].try_into().unwrap()));
+    let (t1, t4) = (unix_nanos(t1), unix_nanos(t4));
+
+    Ok(ClockOffset {
+        offset_ns: (((t2 - t1) + (t3 - t4)) / 2) as i64,
+        delay_ns: Some(((t4 - t1) - (t3 - t2)) as i64),
+        measured_at: Instant::now(),
+    })
+}
+
+/// Asks the local `ptp4l` for its offset from the grandmaster.
+async fn query_ptp() -> io::Result<ClockOffset> {
+    let output = Command::new("pmc")
+        .args(["-u", "-b", "0", "GET TIME_STATUS_NP"])
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("pmc exited with {}", output.status)));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let offset = stdout
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("master_offset"))
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .ok_or_else(|| invalid("no master_offset in pmc output (is ptp4l running?)"))?;
+    // ptp4l reports local minus master.
+    Ok(ClockOffset { offset_ns: -offset, delay_ns: None, measured_at: Instant::now() })
+}
+
+fn to_ntp(ts: SystemTime) -> u64 {
+    let d = ts.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO);
+    let secs = d.as_secs() + NTP_UNIX_OFFSET_SECS;
+    let frac = ((d.subsec_nanos() as u64) << 32) / 1_000_000_000;
+    (secs << 32) | frac
+}
+
+/// NTP timestamp to Unix nanoseconds.
+fn from_ntp(ts: u64) -> i128 {
+    let secs = (ts >> 32) as i128 - NTP_UNIX_OFFSET_SECS as i128;
+    let nanos = ((ts & 0xffff_ffff) as i128 * 1_000_000_000) >> 32;
+    secs * 1_000_000_000 + nanos
+}
+
+fn unix_nanos(ts: SystemTime) -> i128 {
+    ts.duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as i128).unwrap_or(0)
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
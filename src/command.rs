@@ -0,0 +1,134 @@
+//! The `:` command line of the TUI and `attach`: every `[keymap]` action by
+//! name, such as `:grid` or `:quit`, and the commands no key has.
+
+use crate::keymap::{self, Action};
+
+/// Fastest the simulator can be sped up to with `:speed`.
+const MAX_SPEED: f64 = 1000.0;
+
+pub enum Command {
+    Action(Action),
+    /// Shows the chart page with this ticker on it.
+    Symbol(String),
+    /// Writes the spool to Postgres now instead of at the next flush.
+    Flush,
+    /// Milliseconds between Postgres flushes.
+    FlushMs(u64),
+    /// Most lines a Postgres flush inserts; `0` for all of them.
+    BatchRows(usize),
+    /// Multiplies the simulator's tick rate.
+    Speed(f64),
+    /// Restarts the simulator's random walk from this seed.
+    Seed(u64),
+    Export(Export),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Export {
+    /// The spool as one CSV file per symbol.
+    Csv,
+    Parquet,
+    /// The chart page, as `e` writes it.
+    Svg,
+}
+
+/// `line` without its `:`, or why it is not a command.
+pub fn parse(line: &str) -> Result<Command, String> {
+    let mut words = line.split_whitespace();
+    let Some(name) = words.next() else { return Err("empty command".to_string()) };
+    let arg = words.next();
+    if words.next().is_some() {
+        return Err(format!(":{} takes at most one argument", name));
+    }
+    let command = match (name, arg) {
+        ("symbol", Some(ticker)) => Command::Symbol(ticker.to_string()),
+        ("flush", None) => Command::Flush,
+        ("flush_ms", Some(ms)) => {
+            Command::FlushMs(ms.trim_end_matches("ms").parse().map_err(|_| ":flush_ms takes milliseconds, like 500")?)
+        }
+        ("batch_rows", Some(rows)) => {
+            Command::BatchRows(rows.parse().map_err(|_| ":batch_rows takes a number of rows, 0 for no limit")?)
+        }
+        ("speed", Some(speed)) => match speed.trim_end_matches('x').parse::<f64>() {
+            Ok(speed) if speed > 0.0 && speed <= MAX_SPEED => Command::Speed(speed),
+            _ => return Err(format!(":speed takes a multiplier above 0 and up to {}, like 2x", MAX_SPEED)),
+        },
+        ("seed", Some(seed)) => Command::Seed(seed.parse().map_err(|_| ":seed takes a whole number".to_string())?),
+        ("export", Some("csv")) => Command::Export(Export::Csv),
+        ("export", Some("parquet")) => Command::Export(Export::Parquet),
+        ("export", Some("svg")) => Command::Export(Export::Svg),
+        ("export", _) => return Err(":export takes csv, parquet or svg".to_string()),
+        ("symbol" | "speed" | "seed" | "flush_ms" | "batch_rows", None) => {
+            return Err(format!(":{} needs an argument", name))
+        }
+        (name, None) => match keymap::named(name) {
+            Some(action) => Command::Action(action),
+            None => return Err(format!("unknown command :{}", name)),
+        },
+        (name, Some(_)) => return Err(format!(":{} takes no argument", name)),
+    };
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refused(line: &str) -> String {
+        match parse(line) {
+            Ok(_) => panic!(":{} accepted", line),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn keymap_actions_are_commands_by_name() {
+        assert!(matches!(parse("grid"), Ok(Command::Action(Action::Grid))));
+        assert!(matches!(parse("  reset_stats "), Ok(Command::Action(Action::ResetStats))));
+        assert_eq!(refused("grud"), "unknown command :grud");
+        assert_eq!(refused("Grid"), "unknown command :Grid");
+        assert_eq!(refused(""), "empty command");
+    }
+
+    #[test]
+    fn arguments_are_counted() {
+        assert_eq!(refused("symbol AAPL MSFT"), ":symbol takes at most one argument");
+        assert_eq!(refused("symbol"), ":symbol needs an argument");
+        assert_eq!(refused("speed"), ":speed needs an argument");
+        assert_eq!(refused("grid now"), ":grid takes no argument");
+        assert_eq!(refused("flush now"), ":flush takes no argument");
+        assert!(matches!(parse("flush"), Ok(Command::Flush)));
+        assert!(matches!(parse("symbol AAPL"), Ok(Command::Symbol(t)) if t == "AAPL"));
+    }
+
+    #[test]
+    fn units_may_follow_the_number() {
+        assert!(matches!(parse("speed 2x"), Ok(Command::Speed(s)) if s == 2.0));
+        assert!(matches!(parse("speed 0.5"), Ok(Command::Speed(s)) if s == 0.5));
+        assert!(matches!(parse("flush_ms 500ms"), Ok(Command::FlushMs(500))));
+        assert!(matches!(parse("flush_ms 250"), Ok(Command::FlushMs(250))));
+        assert!(matches!(parse("batch_rows 0"), Ok(Command::BatchRows(0))));
+        assert!(matches!(parse("seed 42"), Ok(Command::Seed(42))));
+    }
+
+    #[test]
+    fn values_out_of_range_are_refused() {
+        let speed = ":speed takes a multiplier above 0 and up to 1000, like 2x";
+        for line in ["speed 0x", "speed -2", "speed 1001x", "speed fast", "speed NaN"] {
+            assert_eq!(refused(line), speed, "{}", line);
+        }
+        assert!(matches!(parse("speed 1000x"), Ok(Command::Speed(s)) if s == MAX_SPEED));
+        assert_eq!(refused("flush_ms -5"), ":flush_ms takes milliseconds, like 500");
+        assert_eq!(refused("flush_ms 2s"), ":flush_ms takes milliseconds, like 500");
+        assert_eq!(refused("batch_rows -1"), ":batch_rows takes a number of rows, 0 for no limit");
+        assert_eq!(refused("seed 1.5"), ":seed takes a whole number");
+    }
+
+    #[test]
+    fn exports_name_their_format() {
+        assert!(matches!(parse("export csv"), Ok(Command::Export(Export::Csv))));
+        assert!(matches!(parse("export svg"), Ok(Command::Export(Export::Svg))));
+        assert_eq!(refused("export"), ":export takes csv, parquet or svg");
+        assert_eq!(refused("export json"), ":export takes csv, parquet or svg");
+    }
+}
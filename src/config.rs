@@ -0,0 +1,1291 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ratatui::symbols::Marker;
+use serde::Deserialize;
+
+use crate::keymap::Action;
+use crate::latency::Stage;
+use crate::price::Price;
+use crate::ratelimit::{RateLimitConfig, SinkKind};
+
+/// Config file read when `--config` is not given. Missing is fine; every
+/// section has defaults.
+pub const DEFAULT_CONFIG_PATH: &str = "hft.toml";
+
+pub const DEFAULT_MULTICAST_GROUP: &str = "239.255.0.1:5007";
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub sinks: SinksConfig,
+    /// Embedded HTTP server; disabled unless the section is present.
+    pub http: Option<HttpConfig>,
+    /// Clock-offset monitoring; disabled unless the section is present.
+    pub clock: Option<ClockConfig>,
+    pub timing: TimingConfig,
+    /// CPU cores to pin threads to; unpinned unless set.
+    pub affinity: AffinityConfig,
+    pub producer: ProducerConfig,
+    /// Opening and closing call auctions in the simulator; continuous throughout unless the section is present.
+    pub auction: Option<AuctionConfig>,
+    /// Trading halts in the simulator; none unless the section is present.
+    pub halts: Option<HaltConfig>,
+    /// News shocks injected into the simulator, on a timer or a key.
+    pub shocks: Vec<ShockConfig>,
+    pub prices: PricesConfig,
+    pub realized_vol: RealizedVolConfig,
+    /// OHLCV bars built from the ticks.
+    pub candles: CandlesConfig,
+    /// Simulated instruments, in stock id order; three defaults if empty.
+    pub symbols: Vec<SymbolConfig>,
+    pub universe: UniverseConfig,
+    pub ui: UiConfig,
+    /// Keys of the TUI actions, replacing the defaults of those listed.
+    pub keymap: BTreeMap<Action, Vec<String>>,
+    pub logging: LoggingConfig,
+    /// External tick sources merged with the simulator.
+    pub feeds: Vec<FeedConfig>,
+    pub spool: SpoolConfig,
+    /// Hand-off between the producer and the spool writer.
+    pub queue: QueueConfig,
+    /// Backoff for Postgres and Redis connects and writes.
+    pub retry: RetryConfig,
+    /// When to stop calling Postgres or Redis after repeated failures.
+    pub breaker: BreakerConfig,
+    pub health: HealthConfig,
+    /// Where connection URLs come from when not in the environment.
+    pub secrets: SecretsConfig,
+    pub tls: TlsConfig,
+    /// Topology of the Redis price cache.
+    pub redis: RedisConfig,
+    /// Periodic state snapshots; disabled unless the section is present.
+    pub snapshot: Option<SnapshotConfig>,
+    /// Every tick on disk for zooming the charts out; disabled unless the section is present.
+    pub history: Option<HistoryConfig>,
+    /// Artificial delays added to pipeline stages, keyed by stage name.
+    pub inject: BTreeMap<Stage, DelayDistribution>,
+    /// Token-bucket limits, keyed by sink name.
+    pub rate_limits: BTreeMap<SinkKind, RateLimitConfig>,
+    /// Simulated orders against the live ticks; disabled unless the section is present.
+    pub paper: Option<PaperConfig>,
+    /// Rhai tick filter in front of the publisher; disabled unless the section is present.
+    pub filter: Option<FilterConfig>,
+    /// WASM transforms of every new price, in order.
+    pub plugins: Vec<PluginConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuctionConfig {
+    /// Length of the opening call from startup; the run opens continuous if 0.
+    #[serde(default)]
+    pub open_call_s: u64,
+    /// When the closing call starts, from startup; no close if unset.
+    pub close_at_s: Option<u64>,
+    /// After this the simulator stops.
+    #[serde(default = "default_auction_close_call_s")]
+    pub close_call_s: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HaltConfig {
+    /// Limit-up/limit-down band around the reference price, in percent; no band if unset.
+    pub band_pct: Option<f64>,
+    /// How long a band breach halts the symbol.
+    #[serde(default = "default_halt_s")]
+    pub halt_s: u64,
+    #[serde(default)]
+    pub scheduled: Vec<ScheduledHalt>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduledHalt {
+    pub ticker: String,
+    /// From startup.
+    pub at_s: u64,
+    pub for_s: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShockConfig {
+    pub ticker: String,
+    /// From startup; only on `key` if unset.
+    pub at_s: Option<u64>,
+    /// Fires the shock in the TUI; one the TUI does not already use, such as a digit.
+    pub key: Option<char>,
+    /// Instant price move, in percent.
+    #[serde(default)]
+    pub move_pct: f64,
+    /// Volatility multiplier after the move.
+    #[serde(default = "default_shock_vol_mult")]
+    pub vol_mult: f64,
+    /// How long the volatility stays raised.
+    #[serde(default = "default_shock_for_s")]
+    pub for_s: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpConfig {
+    #[serde(default = "default_http_addr")]
+    pub addr: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockSource {
+    /// SNTP query against `server`.
+    Ntp,
+    /// `master_offset` reported by a local linuxptp `ptp4l`, read through `pmc`.
+    Ptp,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClockConfig {
+    #[serde(default = "default_clock_source")]
+    pub source: ClockSource,
+    /// NTP server `host:port`.
+    #[serde(default = "default_ntp_server")]
+    pub server: String,
+    #[serde(default = "default_clock_interval_ms")]
+    pub interval_ms: u64,
+}
+
+/// Time source for hot-path latency measurements.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimerKind {
+    /// `std::time::Instant`.
+    #[default]
+    Monotonic,
+    /// Raw `rdtsc`, calibrated against `Instant` at startup (x86_64 only).
+    Tsc,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimingConfig {
+    pub clock: TimerKind,
+    /// How often the TSC calibration is checked against `Instant`.
+    pub drift_check_ms: u64,
+    /// Recalibrate when the TSC drifts further than this, in parts per million.
+    pub max_drift_ppm: f64,
+}
+
+impl Default for TimingConfig {
+    fn default() -> Self {
+        TimingConfig { clock: TimerKind::Monotonic, drift_check_ms: 10_000, max_drift_ppm: 50.0 }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AffinityConfig {
+    /// Backend thread generating ticks.
+    pub producer: Option<usize>,
+    /// Frontend thread computing moving averages.
+    pub aggregator: Option<usize>,
+    /// One core per Tokio worker; also sets the worker count.
+    pub tokio_workers: Vec<usize>,
+}
+
+/// How the producer waits between rounds of ticks.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PacingMode {
+    /// `thread::sleep`; cheap, but overshoots by the scheduler's wake-up latency.
+    #[default]
+    Sleep,
+    /// Sleep until `spin_threshold_us` before the deadline, then spin.
+    Hybrid,
+    /// Spin the whole interval; burns a core but hits microsecond intervals.
+    Spin,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProducerConfig {
+    pub pacing: PacingMode,
+    /// Time between ticks of symbols that do not set their own.
+    pub interval_us: u64,
+    pub spin_threshold_us: u64,
+    /// Generate ticks; turn off to run on `[[feeds]]` alone.
+    pub simulate: bool,
+}
+
+impl Default for ProducerConfig {
+    fn default() -> Self {
+        ProducerConfig { pacing: PacingMode::Sleep, interval_us: 100_000, spin_threshold_us: 200, simulate: true }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PricesConfig {
+    /// Smallest price increment of symbols that do not set their own.
+    pub tick_size: Price,
+}
+
+impl Default for PricesConfig {
+    fn default() -> Self {
+        PricesConfig { tick_size: Price::from_f64(0.01) }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RealizedVolConfig {
+    /// Returns the estimate covers, for symbols that do not set their own.
+    pub window_s: u64,
+}
+
+impl Default for RealizedVolConfig {
+    fn default() -> Self {
+        RealizedVolConfig { window_s: 60 }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CandlesConfig {
+    /// Bar lengths, each aligned to the wall clock; `i` cycles through them
+    /// in the candlestick view.
+    pub intervals_s: Vec<u64>,
+    /// Closed bars kept in memory per symbol and interval for the chart.
+    pub bars: usize,
+}
+
+impl Default for CandlesConfig {
+    fn default() -> Self {
+        CandlesConfig { intervals_s: vec![1, 5, 60], bars: 120 }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SymbolConfig {
+    pub ticker: String,
+    /// Display name; the ticker if unset.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Overrides `[prices] tick_size`.
+    #[serde(default)]
+    pub tick_size: Option<Price>,
+    #[serde(default = "default_initial_price")]
+    pub initial_price: Price,
+    /// Chart color, by name (`"cyan"`) or as `"#rrggbb"`; picked from a
+    /// palette if unset.
+    #[serde(default)]
+    pub color: Option<String>,
+    /// Largest price move per producer round.
+    #[serde(default = "default_volatility")]
+    pub volatility: f64,
+    /// Overrides `[producer] interval_us`, e.g. 1000 for a liquid name and
+    /// 500000 for an illiquid one.
+    #[serde(default)]
+    pub interval_us: Option<u64>,
+    /// Overrides `[realized_vol] window_s`.
+    #[serde(default)]
+    pub realized_vol_window_s: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UniverseConfig {
+    /// Pads the symbol list with generated `SYM0003`-style tickers up to this
+    /// many symbols.
+    pub size: usize,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiConfig {
+    /// Tickers on the watchlist at startup; `w` shows only these.
+    pub watchlist: Vec<String>,
+    /// Draws taking longer are logged as warnings.
+    pub frame_budget_ms: u64,
+    /// Shortest wait between frames, while ticks arrive faster than this.
+    pub min_frame_ms: u64,
+    /// Longest wait between frames, reached when no ticks arrive.
+    pub max_frame_ms: u64,
+    /// Points each chart keeps per symbol; histories longer than a chart is
+    /// wide are downsampled to draw them.
+    pub history_len: usize,
+    /// How the line charts are arranged at startup; `l` cycles through them.
+    pub layout: ChartLayout,
+    /// What the price lines are drawn with at startup; `b` cycles through them.
+    pub marker: ChartMarker,
+    pub glyphs: Glyphs,
+    /// Colors of the symbols that do not set one.
+    pub palette: Palette,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        UiConfig {
+            watchlist: Vec::new(),
+            frame_budget_ms: 16,
+            min_frame_ms: 16,
+            max_frame_ms: 500,
+            history_len: crate::HISTORY_LEN,
+            layout: ChartLayout::default(),
+            marker: ChartMarker::default(),
+            glyphs: Glyphs::default(),
+            palette: Palette::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartLayout {
+    /// The backend and frontend charts side by side.
+    #[default]
+    SideBySide,
+    /// The backend chart above the frontend chart.
+    Stacked,
+    /// A chart of price and moving average for each symbol on the page.
+    PerSymbol,
+    /// The backend chart above the page's latency statistics and the heatmap.
+    Latency,
+    /// The backend chart beside the paper strategy's positions.
+    Trading,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartMarker {
+    /// Eight dots per cell, the finest.
+    #[default]
+    Braille,
+    Dot,
+    /// Full cells, for terminals whose fonts draw Braille poorly.
+    Block,
+}
+
+impl ChartMarker {
+    /// The one after, wrapping.
+    pub fn next(self) -> Self {
+        match self {
+            ChartMarker::Braille => ChartMarker::Dot,
+            ChartMarker::Dot => ChartMarker::Block,
+            ChartMarker::Block => ChartMarker::Braille,
+        }
+    }
+
+    pub fn marker(self) -> Marker {
+        match self {
+            ChartMarker::Braille => Marker::Braille,
+            ChartMarker::Dot => Marker::Dot,
+            ChartMarker::Block => Marker::Block,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Palette {
+    /// The six basic terminal colors.
+    #[default]
+    Default,
+    /// Okabe and Ito's colors, told apart with red-green color blindness.
+    Deuteranopia,
+    /// The bright colors, for dim screens and projectors.
+    HighContrast,
+    /// Two shades, and lines told apart by marker in the TUI and by dashes
+    /// in SVG.
+    Monochrome,
+}
+
+/// Characters the TUI may draw with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Glyphs {
+    /// ASCII unless the locale is UTF-8 and the terminal is not the Linux
+    /// console, whose font has no Braille.
+    #[default]
+    Auto,
+    Unicode,
+    /// Borders, markers and sparklines replaced by ASCII.
+    Ascii,
+}
+
+impl Glyphs {
+    /// Whether to draw in ASCII, going by `LC_ALL`, `LC_CTYPE`, `LANG` and
+    /// `TERM` for `Auto`.
+    pub fn ascii(self) -> bool {
+        match self {
+            Glyphs::Unicode => false,
+            Glyphs::Ascii => true,
+            Glyphs::Auto => {
+                let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+                    .iter()
+                    .filter_map(|name| std::env::var(name).ok())
+                    .find(|value| !value.is_empty())
+                    .unwrap_or_default()
+                    .to_lowercase();
+                let term = std::env::var("TERM").unwrap_or_default();
+                !(locale.contains("utf-8") || locale.contains("utf8")) || term == "linux" || term == "dumb"
+            }
+        }
+    }
+}
+
+impl ChartLayout {
+    /// The one after, wrapping.
+    pub fn next(self) -> Self {
+        match self {
+            ChartLayout::SideBySide => ChartLayout::Stacked,
+            ChartLayout::Stacked => ChartLayout::PerSymbol,
+            ChartLayout::PerSymbol => ChartLayout::Latency,
+            ChartLayout::Latency => ChartLayout::Trading,
+            ChartLayout::Trading => ChartLayout::SideBySide,
+        }
+    }
+}
+
+/// The durable backends keep every tick in the spool file until it is in
+/// Postgres; `memory` trades that for no file writes at all.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpoolBackend {
+    /// Buffered appends to the spool file.
+    #[default]
+    File,
+    /// Writes into a memory-mapped, pre-allocated spool file.
+    Mmap,
+    /// Ticks held in memory until the Postgres flush; lost on a crash.
+    Memory,
+}
+
+/// When the `file` backend has the kernel put what it wrote on disk.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FsyncPolicy {
+    /// Left to the kernel's writeback, which a power loss can beat.
+    #[default]
+    Never,
+    /// After every write-out of the buffer.
+    Flush,
+    /// After a write-out at least `fsync_ms` since the last one.
+    Interval,
+}
+
+/// How often a new log file is started.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    Daily,
+    Never,
+}
+
+/// JSON-lines log files; the TUI owns the terminal, so nothing is logged there.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub dir: String,
+    /// File name; rotated files get the date appended.
+    pub file: String,
+    pub rotation: LogRotation,
+    /// Rotated files kept, the oldest deleted first; all of them by default.
+    pub max_files: Option<usize>,
+    /// Used when `RUST_LOG` is unset, e.g. `info,hft_latency::spool=debug`.
+    pub filter: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            dir: "logs".to_string(),
+            file: "hft-latency.log".to_string(),
+            rotation: LogRotation::Daily,
+            max_files: None,
+            filter: "info".to_string(),
+        }
+    }
+}
+
+/// Storage of the local spool file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpoolConfig {
+    pub backend: SpoolBackend,
+    /// Longest a tick waits before being written (`file`) or msynced
+    /// (`mmap`); `0` does so after every producer round.
+    pub flush_ms: u64,
+    /// Write buffer of the `file` backend.
+    pub buffer_bytes: usize,
+    pub fsync: FsyncPolicy,
+    /// Between fsyncs with `fsync = "interval"`.
+    pub fsync_ms: u64,
+    /// Initial size of the `mmap` region; it doubles when full.
+    pub mmap_bytes: usize,
+    /// Between flushes to Postgres; `:flush_ms` changes it live.
+    pub pg_flush_ms: u64,
+    /// Most lines one Postgres flush inserts, the rest waiting for the
+    /// next; `0` inserts everything pending. `:batch_rows` changes it live.
+    pub batch_rows: usize,
+    /// JSON lines file that spool lines Postgres rejects, or that do not
+    /// parse, are moved to with the error.
+    pub dead_letter: String,
+}
+
+impl Default for SpoolConfig {
+    fn default() -> Self {
+        SpoolConfig {
+            backend: SpoolBackend::File,
+            flush_ms: 50,
+            buffer_bytes: 64 * 1024,
+            fsync: FsyncPolicy::Never,
+            fsync_ms: 1000,
+            mmap_bytes: 64 * 1024 * 1024,
+            pg_flush_ms: 1000,
+            batch_rows: 0,
+            dead_letter: "dead_letter.jsonl".to_string(),
+        }
+    }
+}
+
+/// What the producer does when the spool writer's queue is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Wait for room, slowing the producer down to the writer's pace.
+    #[default]
+    Block,
+    /// Evict the oldest queued tick to make room.
+    DropOldest,
+    /// Discard the incoming tick.
+    DropNewest,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueueConfig {
+    /// Ticks held while the writer is busy flushing to Postgres.
+    pub capacity: usize,
+    pub on_full: OverflowPolicy,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        QueueConfig { capacity: 10_000, on_full: OverflowPolicy::Block }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub multiplier: f64,
+    /// Each delay is scaled by a random factor in `1 ± jitter`.
+    pub jitter: f64,
+    /// Including the first try.
+    pub max_attempts: u32,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig { initial_backoff_ms: 100, max_backoff_ms: 5_000, multiplier: 2.0, jitter: 0.2, max_attempts: 5 }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BreakerConfig {
+    /// Consecutive failed flushes (Postgres) or sets (Redis) that trip the breaker.
+    pub failure_threshold: u32,
+    /// How long a tripped breaker waits before probing again.
+    pub cooldown_ms: u64,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        BreakerConfig { failure_threshold: 5, cooldown_ms: 10_000 }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthConfig {
+    /// How often Postgres and Redis are pinged.
+    pub interval_ms: u64,
+    /// Round trips slower than this show as degraded.
+    pub degraded_ms: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        HealthConfig { interval_ms: 1000, degraded_ms: 50 }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecretsConfig {
+    /// TOML file with `postgres_url` and `redis_url`; keep it out of version control.
+    pub file: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedisMode {
+    /// The server in the Redis URL.
+    #[default]
+    Standalone,
+    /// A Redis Cluster, discovered from `nodes`.
+    Cluster,
+    /// The master named `master_name`, as reported by the sentinels in `nodes`.
+    Sentinel,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedisConfig {
+    pub mode: RedisMode,
+    /// `host:port` of cluster seed nodes or sentinels. Scheme, credentials
+    /// and TLS are taken from the Redis URL.
+    pub nodes: Vec<String>,
+    pub master_name: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    pub postgres: PostgresTlsConfig,
+    pub redis: RedisTlsConfig,
+}
+
+/// libpq's `sslmode` values.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PgSslModeConfig {
+    Disable,
+    Allow,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+/// Unset fields fall back to the URL's query parameters, then to libpq's defaults.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PostgresTlsConfig {
+    pub ssl_mode: Option<PgSslModeConfig>,
+    /// PEM file of the CA to verify the server against.
+    pub ca_cert: Option<String>,
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedisTlsConfig {
+    /// Skip certificate verification for `rediss://` URLs.
+    pub insecure: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SnapshotConfig {
+    #[serde(default = "default_snapshot_path")]
+    pub path: String,
+    #[serde(default = "default_snapshot_interval_ms")]
+    pub interval_ms: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HistoryConfig {
+    #[serde(default = "default_history_dir")]
+    pub dir: String,
+    /// Hours of ticks kept on disk.
+    #[serde(default = "default_history_retain_h")]
+    pub retain_h: u64,
+}
+
+/// Built-in strategies; see `src/strategy`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyKind {
+    /// Rests limit orders behind the quote.
+    #[default]
+    Passive,
+    /// Crosses the spread after each move.
+    Momentum,
+    /// The Rhai script at `script`.
+    Script,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilterConfig {
+    /// Rhai file defining `filter(tick)`.
+    pub script: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginConfig {
+    /// A `.wasm` module or its `.wat` text.
+    pub path: String,
+    /// Fuel per call, about one unit per instruction.
+    #[serde(default = "default_plugin_fuel")]
+    pub fuel: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PaperConfig {
+    #[serde(default)]
+    pub strategy: StrategyKind,
+    /// How often the strategy's timer fires.
+    #[serde(default = "default_paper_timer_ms")]
+    pub timer_ms: u64,
+    /// Rhai file of `strategy = "script"`.
+    pub script: Option<String>,
+    /// One order per this many ticks of a symbol.
+    #[serde(default = "default_paper_order_every")]
+    pub order_every: u64,
+    #[serde(default = "default_paper_qty")]
+    pub qty: u32,
+    /// How far behind the market maker's quote a passive order rests, in engine price ticks.
+    #[serde(default = "default_paper_offset_ticks")]
+    pub offset_ticks: i64,
+    /// Passive orders still open after this long are cancelled.
+    #[serde(default = "default_paper_ttl_ms")]
+    pub ttl_ms: u64,
+    /// Price levels the market maker keeps quoted on each side.
+    #[serde(default = "default_paper_depth")]
+    pub depth: u32,
+    /// The maker's quotes are icebergs showing this much of each level; shown in full if unset.
+    pub maker_display: Option<u32>,
+    /// Hidden size the maker also rests at each quoted level, showing nothing.
+    #[serde(default)]
+    pub maker_hidden: u32,
+    /// Time the engine takes per order or cancel, one at a time; none if unset.
+    pub processing: Option<DelayDistribution>,
+    /// Limits that trip the kill switch; none if unset.
+    pub risk: Option<RiskConfig>,
+    /// Charged on every fill; free if unset.
+    pub fees: Option<FeeConfig>,
+    /// Applied to fills that took liquidity; none if unset.
+    pub slippage: Option<SlippageModel>,
+    /// How far the strategy's own fills move the book; none if unset.
+    pub impact: Option<ImpactConfig>,
+    /// Exchanges quoting the same symbols; one with no latency if empty.
+    #[serde(default)]
+    pub venues: Vec<VenueConfig>,
+    /// Order-flow imbalance is summed over this much of each symbol's book updates.
+    #[serde(default = "default_paper_flow_window_ms")]
+    pub flow_window_ms: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VenueConfig {
+    pub name: String,
+    /// Between the strategy and the venue, each way: orders there, acks, fills and quotes back.
+    #[serde(default)]
+    pub latency_us: u64,
+    /// How long after a tick the venue's market maker re-quotes, so its book can trail the price.
+    #[serde(default)]
+    pub quote_lag_us: u64,
+    /// Replaces `[paper] fees` on this venue.
+    pub fees: Option<FeeConfig>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImpactFunction {
+    /// `ticks` per share filled.
+    Linear,
+    /// `ticks` times the square root of the shares filled.
+    SquareRoot,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImpactConfig {
+    pub function: ImpactFunction,
+    /// The function's coefficient, in engine price ticks.
+    pub ticks: f64,
+    /// Time for half of a fill's impact to fade.
+    #[serde(default = "default_impact_half_life_ms")]
+    pub half_life_ms: u64,
+}
+
+/// Per share, in price units; a negative fee is a rebate.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeeConfig {
+    /// For fills of resting orders.
+    #[serde(default)]
+    pub maker_per_share: f64,
+    /// For fills of orders that crossed the spread.
+    #[serde(default)]
+    pub taker_per_share: f64,
+}
+
+/// How much worse than the book a taker fill comes out, in engine price ticks.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SlippageModel {
+    /// The same on every fill.
+    Fixed { ticks: i64 },
+    /// Growing with the fill's size, rounded to whole ticks.
+    Linear { ticks_per_share: f64 },
+}
+
+/// Each limit is off unless set.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RiskConfig {
+    /// Shares held long or short in any one symbol.
+    pub max_position: Option<u64>,
+    /// Of all positions at the last mid, in price units.
+    pub max_notional: Option<f64>,
+    /// Fall of total PnL from its high, in price units.
+    pub max_drawdown: Option<f64>,
+}
+
+/// The section's defaults, for a backtest without one.
+impl Default for PaperConfig {
+    fn default() -> Self {
+        PaperConfig {
+            strategy: StrategyKind::default(),
+            timer_ms: default_paper_timer_ms(),
+            script: None,
+            order_every: default_paper_order_every(),
+            qty: default_paper_qty(),
+            offset_ticks: default_paper_offset_ticks(),
+            ttl_ms: default_paper_ttl_ms(),
+            depth: default_paper_depth(),
+            maker_display: None,
+            maker_hidden: 0,
+            processing: None,
+            risk: None,
+            fees: None,
+            slippage: None,
+            impact: None,
+            venues: Vec::new(),
+            flow_window_ms: default_paper_flow_window_ms(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum FeedConfig {
+    /// A WebSocket sending JSON ticks, such as another instance's `/ws`.
+    Websocket { name: String, url: String },
+    /// A spool-format file replayed with its original timing.
+    Replay {
+        name: String,
+        path: String,
+        /// Replay rate relative to the recorded one.
+        #[serde(default = "default_replay_speed")]
+        speed: f64,
+        /// Start over at the end of the file.
+        #[serde(default)]
+        repeat: bool,
+    },
+    /// The SBE multicast feed of another instance, optionally as an A/B pair
+    /// arbitrated by sequence number.
+    Multicast {
+        name: String,
+        /// A side, e.g. `239.255.0.1:5007`.
+        group: String,
+        /// B side carrying the same packets, on another port.
+        #[serde(default)]
+        secondary_group: Option<String>,
+        /// Local address of the interface to join on.
+        #[serde(default)]
+        interface: Option<String>,
+        /// Silence after which a side counts as stalled, and how long a
+        /// sequence number missing from one side waits for the other.
+        #[serde(default = "default_stall_ms")]
+        stall_ms: u64,
+    },
+}
+
+impl FeedConfig {
+    /// `name: ` and where it reads from.
+    pub fn endpoint(&self) -> String {
+        match self {
+            FeedConfig::Websocket { name, url } => format!("{}: {}", name, url),
+            FeedConfig::Replay { name, path, .. } => format!("{}: {}", name, path),
+            FeedConfig::Multicast { name, group, secondary_group: Some(b), .. } => {
+                format!("{}: {} and {}", name, group, b)
+            }
+            FeedConfig::Multicast { name, group, .. } => format!("{}: {}", name, group),
+        }
+    }
+}
+
+/// Distribution an injected delay is drawn from, in microseconds.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "distribution", rename_all = "snake_case", deny_unknown_fields)]
+pub enum DelayDistribution {
+    Fixed { delay_us: f64 },
+    /// Negative draws are clamped to zero.
+    Normal { mean_us: f64, std_dev_us: f64 },
+    /// Heavy-tailed: at least `scale_us`, with a lower `shape` giving a fatter tail.
+    Pareto { scale_us: f64, shape: f64 },
+}
+
+/// Optional downstream sinks. A sink runs when its section is present.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SinksConfig {
+    pub kdb: Option<KdbConfig>,
+    pub clickhouse: Option<ClickHouseConfig>,
+    pub questdb: Option<QuestDbConfig>,
+    pub influxdb: Option<InfluxDbConfig>,
+    pub kafka: Option<KafkaConfig>,
+    pub nats: Option<NatsConfig>,
+    pub zmq: Option<ZmqConfig>,
+    pub multicast: Option<MulticastConfig>,
+    pub shm: Option<ShmConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KdbConfig {
+    /// `host:port` of the q process.
+    pub addr: String,
+    #[serde(default = "default_table")]
+    pub table: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default = "default_batch_ms")]
+    pub batch_ms: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClickHouseConfig {
+    /// HTTP interface, e.g. `http://127.0.0.1:8123`.
+    pub url: String,
+    #[serde(default = "default_clickhouse_database")]
+    pub database: String,
+    #[serde(default = "default_table")]
+    pub table: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default = "default_http_batch_ms")]
+    pub batch_ms: u64,
+    #[serde(default = "default_clickhouse_max_batch")]
+    pub max_batch: usize,
+    /// Let the server buffer inserts (`async_insert=1`) instead of creating a
+    /// part per request.
+    #[serde(default = "default_true")]
+    pub async_insert: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuestDbConfig {
+    /// ILP TCP endpoint, usually port 9009.
+    pub addr: String,
+    #[serde(default = "default_table")]
+    pub table: String,
+    #[serde(default = "default_batch_ms")]
+    pub batch_ms: u64,
+    /// Unsent lines kept while QuestDB is slow or down; oldest are dropped beyond this.
+    #[serde(default = "default_questdb_max_buffer_bytes")]
+    pub max_buffer_bytes: usize,
+    #[serde(default = "default_questdb_write_timeout_ms")]
+    pub write_timeout_ms: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InfluxDbConfig {
+    /// Base URL, e.g. `http://127.0.0.1:8086`.
+    pub url: String,
+    pub org: String,
+    pub bucket: String,
+    pub token: String,
+    #[serde(default = "default_http_batch_ms")]
+    pub batch_ms: u64,
+}
+
+/// Requires building with `--features kafka`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(not(feature = "kafka"), allow(dead_code))]
+pub struct KafkaConfig {
+    /// `bootstrap.servers`, e.g. `localhost:9092`.
+    pub brokers: String,
+    #[serde(default = "default_table")]
+    pub topic: String,
+    /// `0`, `1` or `all`.
+    #[serde(default = "default_kafka_acks")]
+    pub acks: String,
+    #[serde(default = "default_kafka_linger_ms")]
+    pub linger_ms: u64,
+    /// Extra librdkafka producer properties, e.g. `"compression.type" = "lz4"`.
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NatsConfig {
+    #[serde(default = "default_nats_url")]
+    pub url: String,
+    /// Ticks go to `<subject_prefix>.<stock_id>`.
+    #[serde(default = "default_table")]
+    pub subject_prefix: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Publish through JetStream, creating `stream` if it does not exist.
+    #[serde(default)]
+    pub jetstream: bool,
+    #[serde(default = "default_nats_stream")]
+    pub stream: String,
+}
+
+/// Requires building with `--features zmq`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(not(feature = "zmq"), allow(dead_code))]
+pub struct ZmqConfig {
+    /// Endpoint the PUB socket binds, e.g. `tcp://127.0.0.1:5556`.
+    pub endpoint: String,
+}
+
+/// SBE-encoded ticks over UDP multicast, see `src/sbe.rs`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MulticastConfig {
+    /// Group and port, e.g. `239.255.0.1:5007`.
+    #[serde(default = "default_multicast_group")]
+    pub group: String,
+    /// B side of an A/B pair: every packet is sent here too. Use another
+    /// port, as receivers bind the port rather than the group.
+    #[serde(default)]
+    pub secondary_group: Option<String>,
+    /// Local address of the outgoing interface; the OS picks one by default.
+    #[serde(default)]
+    pub interface: Option<String>,
+    #[serde(default = "default_multicast_ttl")]
+    pub ttl: u32,
+    /// Deliver packets to receivers on this host as well.
+    #[serde(default = "default_true")]
+    pub loopback: bool,
+}
+
+/// Memory-mapped tick ring for readers on the same host, see
+/// `examples/shm_reader.rs`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShmConfig {
+    #[serde(default = "default_shm_path")]
+    pub path: String,
+    /// Slots in the ring; must be a power of two.
+    #[serde(default = "default_shm_capacity")]
+    pub capacity: usize,
+}
+
+fn default_http_addr() -> String {
+    "127.0.0.1:8080".to_string()
+}
+
+fn default_clock_source() -> ClockSource {
+    ClockSource::Ntp
+}
+
+fn default_ntp_server() -> String {
+    "pool.ntp.org:123".to_string()
+}
+
+fn default_clock_interval_ms() -> u64 {
+    16_000
+}
+
+fn default_table() -> String {
+    "ticks".to_string()
+}
+
+fn default_batch_ms() -> u64 {
+    100
+}
+
+fn default_clickhouse_database() -> String {
+    "default".to_string()
+}
+
+fn default_http_batch_ms() -> u64 {
+    1000
+}
+
+fn default_clickhouse_max_batch() -> usize {
+    50_000
+}
+
+fn default_questdb_max_buffer_bytes() -> usize {
+    8 * 1024 * 1024
+}
+
+fn default_questdb_write_timeout_ms() -> u64 {
+    1000
+}
+
+fn default_kafka_acks() -> String {
+    "1".to_string()
+}
+
+fn default_kafka_linger_ms() -> u64 {
+    5
+}
+
+fn default_nats_url() -> String {
+    "nats://127.0.0.1:4222".to_string()
+}
+
+fn default_nats_stream() -> String {
+    "TICKS".to_string()
+}
+
+fn default_multicast_group() -> String {
+    DEFAULT_MULTICAST_GROUP.to_string()
+}
+
+fn default_multicast_ttl() -> u32 {
+    1
+}
+
+fn default_snapshot_path() -> String {
+    "hft-snapshot.json".to_string()
+}
+
+fn default_snapshot_interval_ms() -> u64 {
+    5000
+}
+
+fn default_history_dir() -> String {
+    "hft-history".to_string()
+}
+
+fn default_history_retain_h() -> u64 {
+    24
+}
+
+fn default_plugin_fuel() -> u64 {
+    1_000_000
+}
+
+fn default_paper_timer_ms() -> u64 {
+    100
+}
+
+fn default_paper_order_every() -> u64 {
+    10
+}
+
+fn default_paper_qty() -> u32 {
+    1
+}
+
+fn default_paper_offset_ticks() -> i64 {
+    1
+}
+
+fn default_paper_ttl_ms() -> u64 {
+    5000
+}
+
+fn default_auction_close_call_s() -> u64 {
+    10
+}
+
+fn default_halt_s() -> u64 {
+    5
+}
+
+fn default_shock_vol_mult() -> f64 {
+    1.0
+}
+
+fn default_shock_for_s() -> u64 {
+    10
+}
+
+fn default_paper_depth() -> u32 {
+    5
+}
+
+fn default_paper_flow_window_ms() -> u64 {
+    1000
+}
+
+fn default_impact_half_life_ms() -> u64 {
+    1000
+}
+
+fn default_shm_path() -> String {
+    "/dev/shm/hft-ticks".to_string()
+}
+
+fn default_shm_capacity() -> usize {
+    65_536
+}
+
+fn default_initial_price() -> Price {
+    Price::from_f64(100.0)
+}
+
+fn default_volatility() -> f64 {
+    2.0
+}
+
+fn default_replay_speed() -> f64 {
+    1.0
+}
+
+fn default_stall_ms() -> u64 {
+    500
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Config {
+    /// Loads `path`, or the default config file if present.
+    pub fn load(path: Option<&Path>) -> io::Result<Config> {
+        let (path, content) = match path {
+            Some(path) => (path, fs::read_to_string(path)?),
+            None => match fs::read_to_string(DEFAULT_CONFIG_PATH) {
+                Ok(content) => (Path::new(DEFAULT_CONFIG_PATH), content),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+                Err(e) => return Err(e),
+            },
+        };
+        // Only the message: the error's Debug form embeds the whole file.
+        toml::from_str(&content).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e.message()))
+        })
+    }
+}
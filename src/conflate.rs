@@ -0,0 +1,85 @@
+//! Coalesces ticks per symbol between TUI frames. The publisher records
+//! every tick; each frame takes what accumulated since the last one, so the
+//! panels redraw each symbol once per frame however fast it ticks, while the
+//! counts and ranges still cover every tick.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::price::Price;
+use crate::tick::Tick;
+
+/// One symbol's ticks since the last frame.
+#[derive(Clone, Copy, Debug)]
+pub struct Bar {
+    pub count: u64,
+    pub last: Price,
+    pub min: Price,
+    pub max: Price,
+}
+
+/// Everything recorded between two frames.
+pub struct Frame {
+    /// By stock id; `None` for symbols that did not tick.
+    pub bars: Vec<Option<Bar>>,
+    pub ticks: u64,
+    pub elapsed: Duration,
+}
+
+impl Frame {
+    /// `4521 ticks into 3 symbols (1507 per symbol), 75341/s`
+    pub fn describe(&self) -> String {
+        let updated = self.bars.iter().flatten().count();
+        format!(
+            "{} ticks into {} symbols ({} per symbol), {:.0}/s",
+            self.ticks,
+            updated,
+            self.ticks / updated.max(1) as u64,
+            self.ticks as f64 / self.elapsed.as_secs_f64().max(f64::MIN_POSITIVE)
+        )
+    }
+}
+
+struct Pending {
+    bars: Vec<Option<Bar>>,
+    ticks: u64,
+    since: Instant,
+}
+
+#[derive(Clone)]
+pub struct Conflator {
+    pending: Arc<Mutex<Pending>>,
+}
+
+impl Conflator {
+    pub fn new(n_stocks: usize) -> Self {
+        Conflator { pending: Arc::new(Mutex::new(Pending { bars: vec![None; n_stocks], ticks: 0, since: Instant::now() })) }
+    }
+
+    pub fn record(&self, tick: Tick) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.ticks += 1;
+        let Some(slot) = usize::try_from(tick.stock_id).ok().and_then(|id| pending.bars.get_mut(id)) else {
+            return;
+        };
+        match slot {
+            Some(bar) => {
+                bar.count += 1;
+                bar.last = tick.price;
+                bar.min = bar.min.min(tick.price);
+                bar.max = bar.max.max(tick.price);
+            }
+            None => *slot = Some(Bar { count: 1, last: tick.price, min: tick.price, max: tick.price }),
+        }
+    }
+
+    /// What accumulated since the previous call.
+    pub fn take(&self) -> Frame {
+        let mut pending = self.pending.lock().unwrap();
+        let n_stocks = pending.bars.len();
+        let bars = std::mem::replace(&mut pending.bars, vec![None; n_stocks]);
+        let ticks = std::mem::take(&mut pending.ticks);
+        let elapsed = std::mem::replace(&mut pending.since, Instant::now()).elapsed();
+        Frame { bars, ticks, elapsed }
+    }
+}
@@ -0,0 +1,65 @@
+//! What a paper fill really costs: the fee schedule, per share and
+//! different for making and taking liquidity, from `[paper] fees` or the
+//! venue's own `fees`, and the `slippage` model,
+//! which fills orders that cross the spread at a worse price than the book
+//! showed. Without them PnL is what a free, frictionless venue would pay.
+
+use crate::config::{FeeConfig, PaperConfig, SlippageModel, VenueConfig};
+use crate::engine::Side;
+
+#[derive(Clone)]
+pub struct Costs {
+    fees: Option<FeeConfig>,
+    /// By venue, where it has its own schedule.
+    venue_fees: Vec<Option<FeeConfig>>,
+    slippage: Option<SlippageModel>,
+}
+
+impl Costs {
+    pub fn new(cfg: &PaperConfig, venues: &[VenueConfig]) -> Self {
+        let venue_fees = venues.iter().map(|v| v.fees.clone()).collect();
+        Costs { fees: cfg.fees.clone(), venue_fees, slippage: cfg.slippage.clone() }
+    }
+
+    fn schedule(&self, venue: usize) -> Option<&FeeConfig> {
+        self.venue_fees.get(venue).and_then(Option::as_ref).or(self.fees.as_ref())
+    }
+
+    /// The price an order on `side` is filled at once slipped, and the fee
+    /// for the fill on `venue` in price units. Only taker fills slip.
+    pub fn fill(&self, venue: usize, side: Side, price: i64, qty: u32, taker: bool) -> (i64, f64) {
+        let slipped = match (&self.slippage, taker) {
+            (Some(SlippageModel::Fixed { ticks }), true) => *ticks,
+            (Some(SlippageModel::Linear { ticks_per_share }), true) => (ticks_per_share * qty as f64).round() as i64,
+            _ => 0,
+        };
+        let price = match side {
+            Side::Buy => price + slipped,
+            Side::Sell => price - slipped,
+        };
+        let per_share = self.schedule(venue).map_or(0.0, |f| if taker { f.taker_per_share } else { f.maker_per_share });
+        (price, per_share * qty as f64)
+    }
+
+    /// `maker -0.0020/share, taker 0.0030/share, slippage 0.01 ticks/share`
+    pub fn describe(&self) -> String {
+        let fees = describe_fees(self.fees.as_ref());
+        let slippage = match &self.slippage {
+            None => "no slippage".to_string(),
+            Some(SlippageModel::Fixed { ticks }) => format!("slippage {} ticks", ticks),
+            Some(SlippageModel::Linear { ticks_per_share }) => format!("slippage {} ticks/share", ticks_per_share),
+        };
+        format!("{}, {}", fees, slippage)
+    }
+
+    /// The schedule in use on `venue`.
+    pub fn fees_on(&self, venue: usize) -> String {
+        describe_fees(self.schedule(venue))
+    }
+}
+
+fn describe_fees(fees: Option<&FeeConfig>) -> String {
+    fees.map_or("no fees".to_string(), |f| {
+        format!("maker {:.4}/share, taker {:.4}/share", f.maker_per_share, f.taker_per_share)
+    })
+}
@@ -0,0 +1,292 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use super::{Fill, Order, Side, TimeInForce, Visibility};
+
+struct Resting {
+    id: u64,
+    /// Left to trade, shown or not.
+    qty: u32,
+    /// Of `qty`, what the book displays: all of a lit order, the current
+    /// slice of an iceberg.
+    shown: u32,
+    /// An iceberg's slice size.
+    display: Option<u32>,
+}
+
+/// One price: orders with something displayed trade first, in time
+/// priority, then hidden orders in theirs.
+#[derive(Default)]
+struct Level {
+    lit: VecDeque<Resting>,
+    hidden: VecDeque<Resting>,
+}
+
+impl Level {
+    fn is_empty(&self) -> bool {
+        self.lit.is_empty() && self.hidden.is_empty()
+    }
+
+    fn shown(&self) -> u32 {
+        self.lit.iter().map(|r| r.shown).sum()
+    }
+
+    fn total(&self) -> u32 {
+        self.lit.iter().chain(&self.hidden).map(|r| r.qty).sum()
+    }
+}
+
+/// One symbol's limit order book with price-time priority. An iceberg
+/// shows a slice at a time; once that trades, the next slice joins the back
+/// of the queue. Hidden orders show nothing and trade after everything
+/// displayed at their price.
+#[derive(Default)]
+pub struct OrderBook {
+    bids: BTreeMap<i64, Level>,
+    asks: BTreeMap<i64, Level>,
+    /// Resting order id to its side and price level.
+    index: HashMap<u64, (Side, i64)>,
+}
+
+impl OrderBook {
+    /// Matches `order` against the opposite side, then rests any remainder
+    /// unless it is IOC.
+    pub fn submit(&mut self, order: &Order) -> Vec<Fill> {
+        let mut remaining = order.qty;
+        let mut fills = Vec::new();
+        let opposite = match order.side {
+            Side::Buy => &mut self.asks,
+            Side::Sell => &mut self.bids,
+        };
+
+        while remaining > 0 {
+            let best = match order.side {
+                Side::Buy => opposite.keys().next().copied().filter(|&p| p <= order.price),
+                Side::Sell => opposite.keys().next_back().copied().filter(|&p| p >= order.price),
+            };
+            let Some(price) = best else {
+                break;
+            };
+            let level = opposite.get_mut(&price).unwrap();
+            while remaining > 0 {
+                let (queue, lit) =
+                    if level.lit.is_empty() { (&mut level.hidden, false) } else { (&mut level.lit, true) };
+                let Some(resting) = queue.front_mut() else {
+                    break;
+                };
+                let qty = remaining.min(if lit { resting.shown } else { resting.qty });
+                fills.push(Fill { taker_id: order.id, maker_id: resting.id, price, qty });
+                remaining -= qty;
+                resting.qty -= qty;
+                if lit {
+                    resting.shown -= qty;
+                }
+                if resting.qty == 0 {
+                    self.index.remove(&resting.id);
+                    queue.pop_front();
+                } else if lit && resting.shown == 0 {
+                    resting.shown = resting.display.unwrap_or(resting.qty).min(resting.qty);
+                    let refilled = queue.pop_front().unwrap();
+                    queue.push_back(refilled);
+                }
+            }
+            if level.is_empty() {
+                opposite.remove(&price);
+            }
+        }
+
+        if remaining > 0 && order.tif == TimeInForce::Gtc {
+            let own = match order.side {
+                Side::Buy => &mut self.bids,
+                Side::Sell => &mut self.asks,
+            };
+            let level = own.entry(order.price).or_default();
+            let (queue, shown, display) = match order.visibility {
+                Visibility::Lit => (&mut level.lit, remaining, None),
+                Visibility::Iceberg { display } => (&mut level.lit, display.clamp(1, remaining), Some(display.max(1))),
+                Visibility::Hidden => (&mut level.hidden, 0, None),
+            };
+            queue.push_back(Resting { id: order.id, qty: remaining, shown, display });
+            self.index.insert(order.id, (order.side, order.price));
+        }
+        fills
+    }
+
+    /// Quantity that trades before order `id` at its price level, or `None`
+    /// when it is not resting: the displayed quantity ahead of a lit order or
+    /// iceberg, and for a hidden order everything displayed at the level and
+    /// the hidden quantity ahead.
+    pub fn queue_ahead(&self, id: u64) -> Option<u32> {
+        let &(side, price) = self.index.get(&id)?;
+        let levels = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+        let level = &levels[&price];
+        if level.lit.iter().any(|r| r.id == id) {
+            return Some(level.lit.iter().take_while(|r| r.id != id).map(|r| r.shown).sum());
+        }
+        Some(level.shown() + level.hidden.iter().take_while(|r| r.id != id).map(|r| r.qty).sum::<u32>())
+    }
+
+    /// Up to `levels` price levels of `side` with something displayed, best
+    /// first, with the quantity displayed at each: what a market data feed
+    /// would show.
+    pub fn depth(&self, side: Side, levels: usize) -> Vec<(i64, u32)> {
+        let shown = |(&price, level): (&i64, &Level)| Some((price, level.shown())).filter(|&(_, qty)| qty > 0);
+        match side {
+            Side::Buy => self.bids.iter().rev().filter_map(shown).take(levels).collect(),
+            Side::Sell => self.asks.iter().filter_map(shown).take(levels).collect(),
+        }
+    }
+
+    /// Everything resting on both sides, shown or not, and what of it is
+    /// displayed.
+    pub fn liquidity(&self) -> (u32, u32) {
+        let levels = self.bids.values().chain(self.asks.values());
+        levels.fold((0, 0), |(total, shown), level| (total + level.total(), shown + level.shown()))
+    }
+
+    /// Removes a resting order; returns whether it was found.
+    pub fn cancel(&mut self, id: u64) -> bool {
+        let Some((side, price)) = self.index.remove(&id) else {
+            return false;
+        };
+        let levels = match side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        };
+        if let Some(level) = levels.get_mut(&price) {
+            level.lit.retain(|r| r.id != id);
+            level.hidden.retain(|r| r.id != id);
+            if level.is_empty() {
+                levels.remove(&price);
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(id: u64, side: Side, price: i64, qty: u32, visibility: Visibility) -> Order {
+        Order { id, stock_id: 0, side, price, qty, tif: TimeInForce::Gtc, visibility }
+    }
+
+    /// Maker, price and quantity of each fill.
+    fn fills(fills: Vec<Fill>) -> Vec<(u64, i64, u32)> {
+        fills.into_iter().map(|f| (f.maker_id, f.price, f.qty)).collect()
+    }
+
+    #[test]
+    fn best_price_then_time_priority() {
+        let mut book = OrderBook::default();
+        book.submit(&order(1, Side::Sell, 100, 10, Visibility::Lit));
+        book.submit(&order(2, Side::Sell, 100, 10, Visibility::Lit));
+        book.submit(&order(3, Side::Sell, 99, 10, Visibility::Lit));
+
+        let traded = book.submit(&order(4, Side::Buy, 100, 25, Visibility::Lit));
+        assert_eq!(fills(traded), [(3, 99, 10), (1, 100, 10), (2, 100, 5)]);
+        assert_eq!(book.depth(Side::Sell, 5), [(100, 5)]);
+        assert!(book.depth(Side::Buy, 5).is_empty());
+    }
+
+    #[test]
+    fn limit_price_stops_the_sweep_and_the_rest_rests() {
+        let mut book = OrderBook::default();
+        book.submit(&order(1, Side::Sell, 101, 10, Visibility::Lit));
+
+        assert!(book.submit(&order(2, Side::Buy, 100, 10, Visibility::Lit)).is_empty());
+        assert_eq!(book.depth(Side::Buy, 5), [(100, 10)]);
+        assert_eq!(book.depth(Side::Sell, 5), [(101, 10)]);
+    }
+
+    #[test]
+    fn ioc_remainder_is_cancelled() {
+        let mut book = OrderBook::default();
+        book.submit(&order(1, Side::Sell, 100, 10, Visibility::Lit));
+
+        let ioc = Order { tif: TimeInForce::Ioc, ..order(2, Side::Buy, 100, 25, Visibility::Lit) };
+        assert_eq!(fills(book.submit(&ioc)), [(1, 100, 10)]);
+        assert!(book.depth(Side::Buy, 5).is_empty());
+        assert_eq!(book.queue_ahead(2), None);
+        assert_eq!(book.liquidity(), (0, 0));
+    }
+
+    #[test]
+    fn iceberg_shows_a_slice_and_requeues_the_next() {
+        let mut book = OrderBook::default();
+        book.submit(&order(1, Side::Sell, 100, 30, Visibility::Iceberg { display: 10 }));
+        book.submit(&order(2, Side::Sell, 100, 10, Visibility::Lit));
+        assert_eq!(book.depth(Side::Sell, 5), [(100, 20)]);
+        assert_eq!(book.liquidity(), (40, 20));
+
+        // The first slice trades, the next one goes behind order 2.
+        let traded = book.submit(&order(3, Side::Buy, 100, 15, Visibility::Lit));
+        assert_eq!(fills(traded), [(1, 100, 10), (2, 100, 5)]);
+        assert_eq!(book.queue_ahead(1), Some(5));
+        assert_eq!(book.queue_ahead(2), Some(0));
+        assert_eq!(book.liquidity(), (25, 15));
+
+        let traded = book.submit(&order(4, Side::Buy, 100, 25, Visibility::Lit));
+        assert_eq!(fills(traded), [(2, 100, 5), (1, 100, 10), (1, 100, 10)]);
+        assert!(book.depth(Side::Sell, 5).is_empty());
+    }
+
+    #[test]
+    fn iceberg_last_slice_is_what_is_left() {
+        let mut book = OrderBook::default();
+        book.submit(&order(1, Side::Sell, 100, 25, Visibility::Iceberg { display: 10 }));
+        book.submit(&order(2, Side::Buy, 100, 20, Visibility::Lit));
+
+        assert_eq!(book.depth(Side::Sell, 5), [(100, 5)]);
+        assert_eq!(book.liquidity(), (5, 5));
+    }
+
+    #[test]
+    fn hidden_trades_after_lit_even_when_older() {
+        let mut book = OrderBook::default();
+        book.submit(&order(1, Side::Sell, 100, 10, Visibility::Hidden));
+        book.submit(&order(2, Side::Sell, 100, 10, Visibility::Lit));
+        assert_eq!(book.depth(Side::Sell, 5), [(100, 10)]);
+        assert_eq!(book.liquidity(), (20, 10));
+        assert_eq!(book.queue_ahead(1), Some(10));
+
+        let traded = book.submit(&order(3, Side::Buy, 100, 15, Visibility::Lit));
+        assert_eq!(fills(traded), [(2, 100, 10), (1, 100, 5)]);
+        assert!(book.depth(Side::Sell, 5).is_empty());
+        assert_eq!(book.queue_ahead(1), Some(0));
+    }
+
+    #[test]
+    fn hidden_queue_counts_displayed_and_older_hidden() {
+        let mut book = OrderBook::default();
+        book.submit(&order(1, Side::Buy, 100, 7, Visibility::Hidden));
+        book.submit(&order(2, Side::Buy, 100, 3, Visibility::Hidden));
+        book.submit(&order(3, Side::Buy, 100, 10, Visibility::Iceberg { display: 4 }));
+
+        assert_eq!(book.queue_ahead(3), Some(0));
+        assert_eq!(book.queue_ahead(1), Some(4));
+        assert_eq!(book.queue_ahead(2), Some(11));
+    }
+
+    #[test]
+    fn cancel_removes_the_order_and_moves_the_queue_up() {
+        let mut book = OrderBook::default();
+        book.submit(&order(1, Side::Buy, 100, 10, Visibility::Lit));
+        book.submit(&order(2, Side::Buy, 100, 6, Visibility::Lit));
+        assert_eq!(book.queue_ahead(2), Some(10));
+
+        assert!(book.cancel(1));
+        assert!(!book.cancel(1));
+        assert_eq!(book.queue_ahead(1), None);
+        assert_eq!(book.queue_ahead(2), Some(0));
+
+        assert!(book.cancel(2));
+        assert!(book.depth(Side::Buy, 5).is_empty());
+        assert_eq!(book.liquidity(), (0, 0));
+        let traded = book.submit(&order(3, Side::Sell, 100, 5, Visibility::Lit));
+        assert!(traded.is_empty());
+    }
+}
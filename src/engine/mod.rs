@@ -0,0 +1,87 @@
+//! In-process matching engine for simulated order flow.
+//!
+//! Prices are integer ticks of each symbol's `tick_size`, from
+//! [`Price::ticks`](crate::price::Price::ticks), so levels can be ordered and
+//! compared exactly.
+
+mod book;
+
+use std::collections::HashMap;
+
+use book::OrderBook;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeInForce {
+    /// Rests until filled or cancelled.
+    Gtc,
+    /// Fills what it can immediately; the rest is cancelled.
+    Ioc,
+}
+
+/// How much of a resting order the book displays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Visibility {
+    Lit,
+    /// Shows `display` at a time and keeps the rest in reserve.
+    Iceberg { display: u32 },
+    /// Shows nothing.
+    Hidden,
+}
+
+#[derive(Clone, Debug)]
+pub struct Order {
+    pub id: u64,
+    pub stock_id: i32,
+    pub side: Side,
+    /// Limit price in ticks.
+    pub price: i64,
+    pub qty: u32,
+    pub tif: TimeInForce,
+    /// Once it rests.
+    pub visibility: Visibility,
+}
+
+#[derive(Clone, Debug)]
+pub struct Fill {
+    pub taker_id: u64,
+    pub maker_id: u64,
+    pub price: i64,
+    pub qty: u32,
+}
+
+/// Books for every symbol seen so far.
+#[derive(Default)]
+pub struct MatchingEngine {
+    books: HashMap<i32, OrderBook>,
+}
+
+impl MatchingEngine {
+    pub fn submit(&mut self, order: &Order) -> Vec<Fill> {
+        self.books.entry(order.stock_id).or_default().submit(order)
+    }
+
+    pub fn cancel(&mut self, stock_id: i32, id: u64) -> bool {
+        self.books.get_mut(&stock_id).is_some_and(|book| book.cancel(id))
+    }
+
+    /// See [`OrderBook::queue_ahead`].
+    pub fn queue_ahead(&self, stock_id: i32, id: u64) -> Option<u32> {
+        self.books.get(&stock_id).and_then(|book| book.queue_ahead(id))
+    }
+
+    /// See [`OrderBook::depth`]; empty for a symbol not traded yet.
+    pub fn depth(&self, stock_id: i32, side: Side, levels: usize) -> Vec<(i64, u32)> {
+        self.books.get(&stock_id).map_or_else(Vec::new, |book| book.depth(side, levels))
+    }
+
+    /// See [`OrderBook::liquidity`].
+    pub fn liquidity(&self, stock_id: i32) -> (u32, u32) {
+        self.books.get(&stock_id).map_or((0, 0), OrderBook::liquidity)
+    }
+}
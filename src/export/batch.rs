@@ -0,0 +1,79 @@
+use std::io;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use arrow_array::{
+    Decimal128Array, Float64Array, Int32Array, RecordBatch, StringArray, TimestampMicrosecondArray, UInt64Array,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+
+use crate::latency::LatencySample;
+use crate::price;
+use crate::tick::Tick;
+
+/// Matches the `NUMERIC(18, 6)` price column in Postgres.
+const PRICE_PRECISION: u8 = 18;
+
+pub fn tick_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("stock_id", DataType::Int32, false),
+        Field::new("price", DataType::Decimal128(PRICE_PRECISION, price::SCALE as i8), false),
+        Field::new("ts", DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())), false),
+    ]))
+}
+
+pub fn tick_batch(ticks: &[Tick]) -> io::Result<RecordBatch> {
+    RecordBatch::try_new(
+        tick_schema(),
+        vec![
+            Arc::new(Int32Array::from_iter_values(ticks.iter().map(|t| t.stock_id))),
+            Arc::new(
+                Decimal128Array::from_iter_values(ticks.iter().map(|t| t.price.units() as i128))
+                    .with_precision_and_scale(PRICE_PRECISION, price::SCALE as i8)
+                    .map_err(io::Error::other)?,
+            ),
+            Arc::new(
+                TimestampMicrosecondArray::from_iter_values(ticks.iter().map(|t| t.unix_micros()))
+                    .with_timezone("UTC"),
+            ),
+        ],
+    )
+    .map_err(io::Error::other)
+}
+
+/// [`tick_batch`] with each tick's realized volatility, for the exported
+/// files; the Flight stream keeps to the plain schema.
+pub fn tick_vol_batch(ticks: &[Tick], realized_vol: &[Option<f64>]) -> io::Result<RecordBatch> {
+    let batch = tick_batch(ticks)?;
+    let mut fields = batch.schema().fields().to_vec();
+    fields.push(Arc::new(Field::new("realized_vol", DataType::Float64, true)));
+    let mut columns = batch.columns().to_vec();
+    columns.push(Arc::new(Float64Array::from(realized_vol.to_vec())));
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).map_err(io::Error::other)
+}
+
+pub fn latency_batch(samples: &[LatencySample]) -> io::Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        Field::new("stage", DataType::Utf8, false),
+        Field::new("stock_id", DataType::Int32, true),
+        Field::new("ts", DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())), false),
+        Field::new("nanos", DataType::UInt64, false),
+    ]);
+    RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            Arc::new(StringArray::from_iter_values(samples.iter().map(|s| s.stage.as_str()))),
+            Arc::new(Int32Array::from(samples.iter().map(|s| s.stock_id).collect::<Vec<_>>())),
+            Arc::new(
+                TimestampMicrosecondArray::from_iter_values(samples.iter().map(|s| unix_micros(s.at)))
+                    .with_timezone("UTC"),
+            ),
+            Arc::new(UInt64Array::from_iter_values(samples.iter().map(|s| s.nanos))),
+        ],
+    )
+    .map_err(io::Error::other)
+}
+
+pub fn unix_micros(ts: SystemTime) -> i64 {
+    ts.duration_since(UNIX_EPOCH).map(|d| d.as_micros() as i64).unwrap_or(0)
+}
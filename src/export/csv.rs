@@ -0,0 +1,29 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+
+use crate::tick::Tick;
+
+/// Writes one `stock_<id>.csv` per symbol into `dir`, returning the files
+/// written. `realized_vol` is by tick, and left empty where there is none.
+pub fn write_per_symbol(dir: &Path, ticks: &[Tick], realized_vol: &[Option<f64>]) -> io::Result<usize> {
+    fs::create_dir_all(dir)?;
+    let mut by_symbol: BTreeMap<i32, Vec<(&Tick, Option<f64>)>> = BTreeMap::new();
+    for (tick, &vol) in ticks.iter().zip(realized_vol) {
+        by_symbol.entry(tick.stock_id).or_default().push((tick, vol));
+    }
+    for (stock_id, ticks) in &by_symbol {
+        let mut out = BufWriter::new(File::create(dir.join(format!("stock_{}.csv", stock_id)))?);
+        writeln!(out, "stock_id,price,ts,realized_vol")?;
+        for (tick, vol) in ticks {
+            let ts = DateTime::<Utc>::from(tick.ts).format("%Y-%m-%d %H:%M:%S%.6f");
+            let vol = vol.map_or(String::new(), |vol| format!("{:.6}", vol));
+            writeln!(out, "{},{},{},{}", tick.stock_id, tick.price, ts, vol)?;
+        }
+        out.flush()?;
+    }
+    Ok(by_symbol.len())
+}
@@ -0,0 +1,113 @@
+mod batch;
+mod csv;
+mod parquet;
+
+use std::io;
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use sqlx::postgres::PgPoolOptions;
+
+use crate::cli::{ExportArgs, ExportFormat, ExportSource};
+use crate::config::Config;
+use crate::price::Price;
+use crate::secrets::Credentials;
+use crate::tls;
+use crate::spool;
+use crate::symbols;
+use crate::tick::Tick;
+use crate::ulid::Ulid;
+use crate::vol::RealizedVol;
+
+pub use self::batch::{tick_batch, tick_schema};
+pub use self::parquet::ParquetExporter;
+
+/// Entry point of the `export` subcommand.
+pub async fn run(args: ExportArgs, config: &Config) -> io::Result<()> {
+    let ticks = match args.source {
+        ExportSource::Postgres => load_postgres(config, args.from, args.to).await?,
+        ExportSource::Spool => load_spool(args.from, args.to)?,
+    };
+    println!("{}", write(ticks, args.format, &args.out, config)?);
+    Ok(())
+}
+
+/// Everything in the spool, as `export --source spool` writes it; for
+/// `:export` in the TUI.
+pub fn spool(format: ExportFormat, out: &Path, config: &Config) -> io::Result<String> {
+    write(load_spool(None, None)?, format, out, config)
+}
+
+/// Writes `ticks` under `out` with their realized volatility, returning what
+/// was written.
+fn write(ticks: Vec<Tick>, format: ExportFormat, out: &Path, config: &Config) -> io::Result<String> {
+    let vol = RealizedVol::new(&symbols::load(config)?);
+    let vols: Vec<Option<f64>> = ticks.iter().map(|t| vol.record(t)).collect();
+
+    match format {
+        ExportFormat::Csv => {
+            let dir = out.join("csv");
+            let files = csv::write_per_symbol(&dir, &ticks, &vols)?;
+            Ok(format!("Wrote {} ticks to {} CSV files in {}", ticks.len(), files, dir.display()))
+        }
+        ExportFormat::Parquet => {
+            let count = ticks.len();
+            let mut exporter = ParquetExporter::new(out);
+            ticks.into_iter().zip(vols).for_each(|(t, vol)| exporter.record_tick(t, vol));
+            exporter.flush()?;
+            Ok(format!("Wrote {} ticks to {}", count, out.join("ticks").display()))
+        }
+    }
+}
+
+async fn load_postgres(
+    config: &Config,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+) -> io::Result<Vec<Tick>> {
+    let credentials = Credentials::load(&config.secrets)?;
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect_with(tls::pg_connect_options(credentials.postgres()?, &config.tls.postgres)?)
+        .await
+        .map_err(io::Error::other)?;
+
+    // The cast also reads tables created before prices were NUMERIC.
+    let rows: Vec<(i32, Decimal, NaiveDateTime)> = sqlx::query_as(
+        "SELECT stock_id, price::numeric, ts FROM stock_data \
+         WHERE ($1::timestamp IS NULL OR ts >= $1) AND ($2::timestamp IS NULL OR ts < $2) \
+         ORDER BY ts",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(&pool)
+    .await
+    .map_err(io::Error::other)?;
+
+    rows.into_iter()
+        .map(|(stock_id, price, ts)| {
+            let price = Price::from_decimal(price)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("price {} out of range", price)))?;
+            let ts = ts.and_utc().into();
+            // The export writes no IDs, so the stored ones are not read.
+            Ok(Tick { stock_id, price, ts, seq: 0, id: Ulid::new(ts) })
+        })
+        .collect()
+}
+
+fn load_spool(from: Option<NaiveDateTime>, to: Option<NaiveDateTime>) -> io::Result<Vec<Tick>> {
+    let content = spool::read_spool_file()?;
+    Ok(content
+        .lines()
+        .filter_map(spool::parse_line)
+        // Lines spooled before timestamps were recorded are skipped.
+        .filter_map(|r| r.ts.map(|ts| (r, ts.naive_utc())))
+        .filter(|(_, ts)| from.is_none_or(|from| *ts >= from) && to.is_none_or(|to| *ts < to))
+        .map(|(r, ts)| {
+            let ts = ts.and_utc().into();
+            let id = r.id.unwrap_or_else(|| Ulid::new(ts));
+            Tick { stock_id: r.stock_id, price: r.price, ts, seq: r.seq.unwrap_or(0), id }
+        })
+        .collect())
+}
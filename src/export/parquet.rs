@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use arrow_array::RecordBatch;
+use chrono::{DateTime, NaiveDate, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use tracing::info;
+
+use super::batch::{latency_batch, tick_vol_batch, unix_micros};
+use crate::latency::LatencySample;
+use crate::tick::Tick;
+
+/// Rows buffered before a part file is written without waiting for shutdown.
+const ROWS_PER_FILE: usize = 64 * 1024;
+
+/// A tick with its realized volatility, which the tick files carry.
+struct ExportedTick {
+    tick: Tick,
+    realized_vol: Option<f64>,
+}
+
+/// Buffers ticks and latency samples and writes them as hive-partitioned
+/// Parquet files:
+///
+/// ```text
+/// <dir>/ticks/stock_id=<id>/date=<yyyy-mm-dd>/part-<unix_ms>.parquet
+/// <dir>/latency/date=<yyyy-mm-dd>/part-<unix_ms>.parquet
+/// ```
+///
+/// Every flush produces complete, self-contained files, so a crash only loses
+/// whatever is still buffered.
+pub struct ParquetExporter {
+    dir: PathBuf,
+    ticks: Vec<ExportedTick>,
+    latency: Vec<LatencySample>,
+}
+
+impl ParquetExporter {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        ParquetExporter {
+            dir: dir.into(),
+            ticks: Vec::new(),
+            latency: Vec::new(),
+        }
+    }
+
+    pub fn record_tick(&mut self, tick: Tick, realized_vol: Option<f64>) {
+        self.ticks.push(ExportedTick { tick, realized_vol });
+    }
+
+    pub fn record_latency(&mut self, sample: LatencySample) {
+        self.latency.push(sample);
+    }
+
+    pub fn should_flush(&self) -> bool {
+        self.ticks.len() >= ROWS_PER_FILE || self.latency.len() >= ROWS_PER_FILE
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.ticks.is_empty() && self.latency.is_empty() {
+            return Ok(());
+        }
+        let suffix = format!("part-{}.parquet", unix_micros(SystemTime::now()) / 1000);
+
+        let mut tick_parts: BTreeMap<(i32, NaiveDate), Vec<ExportedTick>> = BTreeMap::new();
+        for tick in self.ticks.drain(..) {
+            tick_parts.entry((tick.tick.stock_id, date_of(tick.tick.ts))).or_default().push(tick);
+        }
+        let mut tick_files = 0;
+        for ((stock_id, date), part) in tick_parts {
+            let (ticks, vols): (Vec<Tick>, Vec<Option<f64>>) =
+                part.into_iter().map(|t| (t.tick, t.realized_vol)).unzip();
+            let path = self
+                .dir
+                .join("ticks")
+                .join(format!("stock_id={}", stock_id))
+                .join(format!("date={}", date))
+                .join(&suffix);
+            write_batch(&path, tick_vol_batch(&ticks, &vols)?)?;
+            tick_files += 1;
+        }
+
+        let mut latency_parts: BTreeMap<NaiveDate, Vec<LatencySample>> = BTreeMap::new();
+        for sample in self.latency.drain(..) {
+            latency_parts.entry(date_of(sample.at)).or_default().push(sample);
+        }
+        let mut latency_files = 0;
+        for (date, samples) in latency_parts {
+            let path = self
+                .dir
+                .join("latency")
+                .join(format!("date={}", date))
+                .join(&suffix);
+            write_batch(&path, latency_batch(&samples)?)?;
+            latency_files += 1;
+        }
+
+        info!(
+            "Exported {} tick and {} latency Parquet files to {}",
+            tick_files,
+            latency_files,
+            self.dir.display()
+        );
+        Ok(())
+    }
+}
+
+fn write_batch(path: &Path, batch: RecordBatch) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer =
+        ArrowWriter::try_new(File::create(path)?, batch.schema(), Some(props)).map_err(io::Error::other)?;
+    writer.write(&batch).map_err(io::Error::other)?;
+    writer.close().map_err(io::Error::other)?;
+    Ok(())
+}
+
+fn date_of(ts: SystemTime) -> NaiveDate {
+    DateTime::<Utc>::from(ts).date_naive()
+}
@@ -0,0 +1,172 @@
+//! `multicast-recv`: joins the SBE multicast feed and reports sequence gaps and
+//! one-way latency. Latency is only meaningful when sender and receiver clocks
+//! agree, e.g. on the same host or with PTP.
+//!
+//! With `--kernel-timestamps` the one-way latency is further split at the
+//! kernel's RX timestamp into time on the wire and time spent getting the
+//! datagram to the application.
+
+use std::io;
+use std::time::{Duration, SystemTime};
+
+use hdrhistogram::Histogram;
+use tracing::warn;
+
+use crate::cli::MulticastRecvArgs;
+use crate::net::{enable_rx_timestamps, join_multicast, recv_timestamped};
+use crate::sbe::{self, PacketHeader};
+
+/// One-way latencies above this are clamped into the top histogram bucket.
+const MAX_TRACKED_NANOS: u64 = 60_000_000_000;
+
+#[derive(Default)]
+struct SeqTracker {
+    last: Option<u64>,
+    packets: u64,
+    gaps: u64,
+    missing: u64,
+    late: u64,
+}
+
+impl SeqTracker {
+    fn observe(&mut self, seq: u64) {
+        self.packets += 1;
+        match self.last {
+            // The publisher restarted and counts from 1 again.
+            Some(last) if seq == 1 && last > 1 => warn!("Sequence reset after {}", last),
+            Some(last) if seq <= last => {
+                self.late += 1;
+                return;
+            }
+            Some(last) if seq > last + 1 => {
+                self.gaps += 1;
+                self.missing += seq - last - 1;
+            }
+            _ => {}
+        }
+        self.last = Some(seq);
+    }
+}
+
+struct Interval {
+    ticks: u64,
+    /// Packet sending time to receipt.
+    wire: Histogram<u64>,
+    /// Tick creation to receipt, including the publisher's bus and batching.
+    tick: Histogram<u64>,
+    /// Packet sending time to the kernel RX timestamp.
+    kernel: Histogram<u64>,
+    /// Kernel RX timestamp to receipt by the application.
+    app: Histogram<u64>,
+    hardware_stamps: u64,
+    /// Samples dropped because the receive time preceded the send time.
+    negative: u64,
+}
+
+impl Interval {
+    fn new() -> Self {
+        Interval {
+            ticks: 0,
+            wire: Histogram::new_with_bounds(1, MAX_TRACKED_NANOS, 3).unwrap(),
+            tick: Histogram::new_with_bounds(1, MAX_TRACKED_NANOS, 3).unwrap(),
+            kernel: Histogram::new_with_bounds(1, MAX_TRACKED_NANOS, 3).unwrap(),
+            app: Histogram::new_with_bounds(1, MAX_TRACKED_NANOS, 3).unwrap(),
+            hardware_stamps: 0,
+            negative: 0,
+        }
+    }
+
+    fn record(hist: &mut Histogram<u64>, negative: &mut u64, sent_ns: u64, recv_ns: u64) {
+        match recv_ns.checked_sub(sent_ns) {
+            Some(nanos) => hist.saturating_record(nanos.max(1)),
+            None => *negative += 1,
+        }
+    }
+
+    fn report(&self, label: &str, seq: &SeqTracker) {
+        let kernel = if self.kernel.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " | kernel {} | app {} ({} hw)",
+                format_hist(&self.kernel),
+                format_hist(&self.app),
+                self.hardware_stamps
+            )
+        };
+        println!(
+            "{:<8} packets={} ticks={} gaps={} missing={} late={} | wire {} | tick {}{}{}",
+            label,
+            seq.packets,
+            self.ticks,
+            seq.gaps,
+            seq.missing,
+            seq.late,
+            format_hist(&self.wire),
+            format_hist(&self.tick),
+            kernel,
+            if self.negative > 0 { format!(" | {} negative (clock skew?)", self.negative) } else { String::new() },
+        );
+    }
+}
+
+fn format_hist(h: &Histogram<u64>) -> String {
+    if h.is_empty() {
+        return "-".to_string();
+    }
+    let us = |nanos: u64| nanos as f64 / 1000.0;
+    format!(
+        "p50={:.1}us p99={:.1}us max={:.1}us",
+        us(h.value_at_quantile(0.5)),
+        us(h.value_at_quantile(0.99)),
+        us(h.max())
+    )
+}
+
+pub async fn run(args: MulticastRecvArgs) -> io::Result<()> {
+    let socket = join_multicast(&args.group, args.interface.as_deref())?;
+    if args.kernel_timestamps {
+        enable_rx_timestamps(&socket)?;
+    }
+    println!("Joined {}, reporting every {} ms (Ctrl-C to stop)", args.group, args.report_ms);
+
+    let mut seq = SeqTracker::default();
+    let mut interval = Interval::new();
+    let mut total = Interval::new();
+    let mut report = tokio::time::interval(Duration::from_millis(args.report_ms.max(1)));
+    report.tick().await;
+    let mut buf = vec![0u8; 64 * 1024];
+
+    loop {
+        tokio::select! {
+            received = recv_timestamped(&socket, &mut buf) => {
+                let (len, rx_stamp) = received?;
+                let recv_ns = sbe::unix_nanos(SystemTime::now());
+                let Some((PacketHeader { seq: packet_seq, sending_time_ns }, ticks)) = sbe::decode_packet(&buf[..len]) else {
+                    warn!("Dropping truncated {}-byte packet", len);
+                    continue;
+                };
+                seq.observe(packet_seq);
+                for stats in [&mut interval, &mut total] {
+                    Interval::record(&mut stats.wire, &mut stats.negative, sending_time_ns, recv_ns);
+                    for tick in &ticks {
+                        Interval::record(&mut stats.tick, &mut stats.negative, sbe::unix_nanos(tick.ts), recv_ns);
+                    }
+                    if let Some(rx) = rx_stamp {
+                        Interval::record(&mut stats.kernel, &mut stats.negative, sending_time_ns, rx.unix_nanos);
+                        Interval::record(&mut stats.app, &mut stats.negative, rx.unix_nanos, recv_ns);
+                        stats.hardware_stamps += rx.hardware as u64;
+                    }
+                    stats.ticks += ticks.len() as u64;
+                }
+            }
+            _ = report.tick() => {
+                interval.report("interval", &seq);
+                interval = Interval::new();
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    total.report("total", &seq);
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::time::Duration;
+
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaAsIpc, SchemaResult, Ticket,
+};
+use arrow_array::RecordBatch;
+use arrow_schema::ArrowError;
+use futures::stream::{self, BoxStream};
+use futures::{Stream, StreamExt, TryStreamExt};
+use tonic::{Request, Response, Status, Streaming};
+use tracing::info;
+
+use crate::bus::recv_batch;
+use crate::export::{tick_batch, tick_schema};
+use crate::tick::{Tick, TickSender};
+
+/// How long ticks are coalesced into one record batch before being sent.
+const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+const TICKS_PATH: &str = "ticks";
+
+type FlightStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send + 'static>>;
+
+/// Serves the live tick stream as Arrow Flight `DoGet` streams.
+///
+/// The ticket `ticks` subscribes to every symbol, `ticks/<stock_id>` to one.
+struct TickFlightService {
+    ticks: TickSender,
+}
+
+pub async fn serve(addr: SocketAddr, ticks: TickSender) -> std::io::Result<()> {
+    info!("Arrow Flight server listening on {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(FlightServiceServer::new(TickFlightService { ticks }))
+        .serve(addr)
+        .await
+        .map_err(std::io::Error::other)
+}
+
+fn parse_ticket(ticket: &[u8]) -> Result<Option<i32>, Status> {
+    let ticket = std::str::from_utf8(ticket).map_err(|_| Status::invalid_argument("ticket is not UTF-8"))?;
+    match ticket.split_once('/') {
+        None if ticket == TICKS_PATH => Ok(None),
+        Some((TICKS_PATH, id)) => id
+            .parse()
+            .map(Some)
+            .map_err(|_| Status::invalid_argument(format!("invalid stock_id: {}", id))),
+        _ => Err(Status::not_found(format!("unknown ticket: {}", ticket))),
+    }
+}
+
+fn ticks_flight_info() -> Result<FlightInfo, Status> {
+    FlightInfo::new()
+        .try_with_schema(&tick_schema())
+        .map_err(|e| Status::internal(e.to_string()))
+        .map(|info| {
+            info.with_descriptor(FlightDescriptor::new_path(vec![TICKS_PATH.to_string()]))
+                .with_endpoint(FlightEndpoint::new().with_ticket(Ticket::new(TICKS_PATH)))
+                .with_total_records(-1)
+                .with_total_bytes(-1)
+        })
+}
+
+/// Turns broadcast ticks into a stream of record batches, one per
+/// `BATCH_INTERVAL` that saw at least one matching tick.
+fn batch_stream(ticks: TickSender, stock_id: Option<i32>) -> BoxStream<'static, Result<RecordBatch, FlightError>> {
+    stream::unfold(ticks.subscribe(), move |mut rx| async move {
+        let keep = |tick: &Tick| stock_id.is_none_or(|id| id == tick.stock_id);
+        let pending = recv_batch(&mut rx, "Flight subscriber", BATCH_INTERVAL, usize::MAX, keep).await?;
+        let batch = tick_batch(&pending).map_err(|e| FlightError::Arrow(ArrowError::ExternalError(Box::new(e))));
+        Some((batch, rx))
+    })
+    .boxed()
+}
+
+#[tonic::async_trait]
+impl FlightService for TickFlightService {
+    type HandshakeStream = FlightStream<HandshakeResponse>;
+    type ListFlightsStream = FlightStream<FlightInfo>;
+    type DoGetStream = FlightStream<FlightData>;
+    type DoPutStream = FlightStream<PutResult>;
+    type DoActionStream = FlightStream<arrow_flight::Result>;
+    type ListActionsStream = FlightStream<ActionType>;
+    type DoExchangeStream = FlightStream<FlightData>;
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented("handshake is not required"))
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        let info = ticks_flight_info()?;
+        Ok(Response::new(stream::iter([Ok(info)]).boxed()))
+    }
+
+    async fn get_flight_info(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let descriptor = request.into_inner();
+        if descriptor.path.first().map(String::as_str) != Some(TICKS_PATH) {
+            return Err(Status::not_found("only the `ticks` flight is available"));
+        }
+        Ok(Response::new(ticks_flight_info()?))
+    }
+
+    async fn poll_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<PollInfo>, Status> {
+        Err(Status::unimplemented("live flights cannot be polled"))
+    }
+
+    async fn get_schema(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, Status> {
+        let options = arrow_ipc::writer::IpcWriteOptions::default();
+        SchemaAsIpc::new(&tick_schema(), &options)
+            .try_into()
+            .map(Response::new)
+            .map_err(|e: ArrowError| Status::internal(e.to_string()))
+    }
+
+    async fn do_get(&self, request: Request<Ticket>) -> Result<Response<Self::DoGetStream>, Status> {
+        let stock_id = parse_ticket(&request.get_ref().ticket)?;
+        info!("Flight client subscribed to ticks (stock_id filter: {:?})", stock_id);
+        let flight_data = FlightDataEncoderBuilder::new()
+            .with_schema(tick_schema())
+            .build(batch_stream(self.ticks.clone(), stock_id))
+            .map_err(Status::from);
+        Ok(Response::new(flight_data.boxed()))
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, Status> {
+        Err(Status::unimplemented("tick stream is read-only"))
+    }
+
+    async fn do_action(&self, _request: Request<Action>) -> Result<Response<Self::DoActionStream>, Status> {
+        Err(Status::unimplemented("no actions are supported"))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, Status> {
+        Ok(Response::new(stream::empty().boxed()))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("tick stream is read-only"))
+    }
+}
@@ -0,0 +1,83 @@
+//! Time spent drawing the TUI. The terminal is the last consumer of every
+//! tick, so a slow frame is pipeline latency too: each draw is timed, the
+//! frame rate and p99 draw time over recent frames are shown in the top
+//! right corner, and draws over `[ui] frame_budget_ms` are logged.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+/// Draws the p99 is taken over.
+const WINDOW: usize = 600;
+/// Frames over budget are summed into at most one warning per interval.
+const WARN_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct FrameTimer {
+    budget: Duration,
+    draws: VecDeque<Duration>,
+    /// When each frame of the last second was drawn.
+    recent: VecDeque<Instant>,
+    over_budget: u64,
+    /// Over budget since the last warning, and the slowest of them.
+    unreported: u64,
+    worst_unreported: Duration,
+    last_warning: Option<Instant>,
+}
+
+impl FrameTimer {
+    pub fn new(budget: Duration) -> Self {
+        FrameTimer {
+            budget,
+            draws: VecDeque::with_capacity(WINDOW),
+            recent: VecDeque::new(),
+            over_budget: 0,
+            unreported: 0,
+            worst_unreported: Duration::ZERO,
+            last_warning: None,
+        }
+    }
+
+    pub fn record(&mut self, draw: Duration) {
+        let now = Instant::now();
+        if self.draws.len() == WINDOW {
+            self.draws.pop_front();
+        }
+        self.draws.push_back(draw);
+        self.recent.push_back(now);
+        while self.recent.front().is_some_and(|&at| now - at > Duration::from_secs(1)) {
+            self.recent.pop_front();
+        }
+
+        if draw <= self.budget {
+            return;
+        }
+        self.over_budget += 1;
+        self.unreported += 1;
+        self.worst_unreported = self.worst_unreported.max(draw);
+        if self.last_warning.is_none_or(|at| now - at >= WARN_INTERVAL) {
+            warn!(
+                "{} frames over the {:.1}ms draw budget, slowest {:.2}ms",
+                self.unreported,
+                self.budget.as_secs_f64() * 1e3,
+                self.worst_unreported.as_secs_f64() * 1e3
+            );
+            self.unreported = 0;
+            self.worst_unreported = Duration::ZERO;
+            self.last_warning = Some(now);
+        }
+    }
+
+    /// `17 fps, draw p99 3.21ms, 2 over budget`
+    pub fn describe(&self) -> String {
+        let mut sorted: Vec<Duration> = self.draws.iter().copied().collect();
+        sorted.sort();
+        let p99 = sorted.get((sorted.len() * 99 / 100).min(sorted.len().saturating_sub(1))).copied().unwrap_or_default();
+        format!(
+            "{} fps, draw p99 {:.2}ms, {} over budget",
+            self.recent.len(),
+            p99.as_secs_f64() * 1e3,
+            self.over_budget
+        )
+    }
+}
@@ -0,0 +1,107 @@
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::{stream, Stream, StreamExt};
+use tokio::sync::broadcast::error::RecvError;
+use tonic::{Request, Response, Status};
+use tracing::{info, warn};
+
+use crate::latency::LatencyRecorder;
+use crate::tick::TickSender;
+
+mod pb {
+    tonic::include_proto!("hft");
+}
+
+use pb::market_data_server::{MarketData, MarketDataServer};
+use pb::{LatencyStatsRequest, LatencyStatsSnapshot, StageStats, SubscribeTicksRequest, TickMessage};
+
+const DEFAULT_STATS_INTERVAL: Duration = Duration::from_secs(1);
+const MIN_STATS_INTERVAL: Duration = Duration::from_millis(10);
+
+type RpcStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send + 'static>>;
+
+/// gRPC front for the tick bus and the latency recorder (see `proto/hft.proto`).
+struct MarketDataService {
+    ticks: TickSender,
+    latency: LatencyRecorder,
+}
+
+pub async fn serve(addr: SocketAddr, ticks: TickSender, latency: LatencyRecorder) -> std::io::Result<()> {
+    info!("gRPC server listening on {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(MarketDataServer::new(MarketDataService { ticks, latency }))
+        .serve(addr)
+        .await
+        .map_err(std::io::Error::other)
+}
+
+#[tonic::async_trait]
+impl MarketData for MarketDataService {
+    type SubscribeTicksStream = RpcStream<TickMessage>;
+    type GetLatencyStatsStream = RpcStream<LatencyStatsSnapshot>;
+
+    async fn subscribe_ticks(
+        &self,
+        request: Request<SubscribeTicksRequest>,
+    ) -> Result<Response<Self::SubscribeTicksStream>, Status> {
+        let stock_ids = request.into_inner().stock_ids;
+        let ticks = stream::unfold(self.ticks.subscribe(), move |mut rx| {
+            let stock_ids = stock_ids.clone();
+            async move {
+                loop {
+                    match rx.recv().await {
+                        Ok(tick) if stock_ids.is_empty() || stock_ids.contains(&tick.stock_id) => {
+                            let msg = TickMessage { stock_id: tick.stock_id, price: tick.price.to_f64(), ts_us: tick.unix_micros() };
+                            return Some((Ok(msg), rx));
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(n)) => warn!("gRPC tick subscriber lagged, skipped {} ticks", n),
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        });
+        Ok(Response::new(ticks.boxed()))
+    }
+
+    async fn get_latency_stats(
+        &self,
+        request: Request<LatencyStatsRequest>,
+    ) -> Result<Response<Self::GetLatencyStatsStream>, Status> {
+        let interval = match request.into_inner().interval_ms {
+            0 => DEFAULT_STATS_INTERVAL,
+            ms => Duration::from_millis(ms as u64).max(MIN_STATS_INTERVAL),
+        };
+        let latency = self.latency.clone();
+        let snapshots = stream::unfold(tokio::time::interval(interval), move |mut timer| {
+            let latency = latency.clone();
+            async move {
+                timer.tick().await;
+                Some((Ok(snapshot(&latency)), timer))
+            }
+        });
+        Ok(Response::new(snapshots.boxed()))
+    }
+}
+
+fn snapshot(latency: &LatencyRecorder) -> LatencyStatsSnapshot {
+    let stages = latency
+        .summary()
+        .into_iter()
+        .map(|s| StageStats {
+            stage: s.stage.as_str().to_string(),
+            count: s.count,
+            min_ns: s.min,
+            mean_ns: s.mean,
+            p50_ns: s.p50,
+            p90_ns: s.p90,
+            p99_ns: s.p99,
+            p999_ns: s.p999,
+            max_ns: s.max,
+        })
+        .collect();
+    let ts_us = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_micros() as i64).unwrap_or(0);
+    LatencyStatsSnapshot { ts_us, stages }
+}
@@ -0,0 +1,218 @@
+//! Trading halts on the simulated symbols, from `[halts]`: at set times,
+//! and whenever a price would leave the limit-up/limit-down band around the
+//! symbol's reference price, the first it printed after startup or its
+//! last halt. While a symbol is halted the simulator leaves it alone, so it
+//! neither ticks nor trades. Each halt and resumption is logged, kept in
+//! Redis as `halt:<ticker>` and in the `halts` table, greys the symbol out on
+//! the charts and turns the Halts diagnostics line red.
+
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ratatui::style::{Color, Modifier, Style};
+use sqlx::PgPool;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::{info, warn};
+
+use crate::cache::RedisCache;
+use crate::config::HaltConfig;
+use crate::price::Price;
+use crate::symbols::Symbol;
+
+/// Starts the Halts line while any symbol is halted.
+const HALTED: &str = "HALTED";
+
+pub enum Event {
+    Halted { stock_id: usize, reason: String },
+    Resumed { stock_id: usize },
+}
+
+struct Halt {
+    until: Instant,
+    reason: String,
+}
+
+#[derive(Default)]
+struct State {
+    /// What the band is around; none until the first price after startup
+    /// or a halt.
+    reference: Option<Price>,
+    halt: Option<Halt>,
+}
+
+struct Inner {
+    symbols: Vec<State>,
+    /// Which of `[[halts.scheduled]]` have started.
+    fired: Vec<bool>,
+    halts: u64,
+}
+
+#[derive(Clone)]
+pub struct Halts {
+    cfg: Option<HaltConfig>,
+    started: Instant,
+    /// By config entry, the stock id a scheduled halt is for.
+    scheduled: Arc<Vec<usize>>,
+    tickers: Arc<Vec<String>>,
+    inner: Arc<Mutex<Inner>>,
+    events: UnboundedSender<Event>,
+}
+
+impl Halts {
+    /// Also returns the halts and resumptions, for [`record`].
+    pub fn new(cfg: Option<HaltConfig>, symbols: &[Symbol]) -> io::Result<(Self, UnboundedReceiver<Event>)> {
+        let scheduled = cfg
+            .iter()
+            .flat_map(|cfg| &cfg.scheduled)
+            .map(|halt| {
+                symbols.iter().position(|s| s.ticker == halt.ticker).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("halts.scheduled: unknown ticker {}", halt.ticker),
+                    )
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let inner = Inner {
+            symbols: symbols.iter().map(|_| State::default()).collect(),
+            fired: vec![false; scheduled.len()],
+            halts: 0,
+        };
+        let (events, events_rx) = mpsc::unbounded_channel();
+        let halts = Halts {
+            cfg,
+            started: Instant::now(),
+            scheduled: Arc::new(scheduled),
+            tickers: Arc::new(symbols.iter().map(|s| s.ticker.clone()).collect()),
+            inner: Arc::new(Mutex::new(inner)),
+            events,
+        };
+        Ok((halts, events_rx))
+    }
+
+    fn halt(&self, inner: &mut Inner, stock_id: usize, length: Duration, reason: String) {
+        warn!("{} halted for {}s: {}", self.tickers[stock_id], length.as_secs(), reason);
+        let state = &mut inner.symbols[stock_id];
+        state.halt = Some(Halt { until: Instant::now() + length, reason: reason.clone() });
+        state.reference = None;
+        inner.halts += 1;
+        let _ = self.events.send(Event::Halted { stock_id, reason });
+    }
+
+    /// Starts the scheduled halts that are due and ends those that are over.
+    pub fn advance(&self) {
+        let Some(cfg) = &self.cfg else { return };
+        let mut inner = self.inner.lock().unwrap();
+        let elapsed = self.started.elapsed();
+        for (i, halt) in cfg.scheduled.iter().enumerate() {
+            if !inner.fired[i] && elapsed >= Duration::from_secs(halt.at_s) {
+                inner.fired[i] = true;
+                let reason = format!("scheduled at {}s", halt.at_s);
+                self.halt(&mut inner, self.scheduled[i], Duration::from_secs(halt.for_s), reason);
+            }
+        }
+        let now = Instant::now();
+        for (stock_id, state) in inner.symbols.iter_mut().enumerate() {
+            if state.halt.as_ref().is_some_and(|h| h.until <= now) {
+                state.halt = None;
+                info!("{} resumed trading", self.tickers[stock_id]);
+                let _ = self.events.send(Event::Resumed { stock_id });
+            }
+        }
+    }
+
+    /// Whether the simulator may print `price` for `stock_id`: not while it
+    /// is halted, and not outside the band, which halts it.
+    pub fn admit(&self, stock_id: usize, price: Price) -> bool {
+        let Some(cfg) = &self.cfg else { return true };
+        let mut inner = self.inner.lock().unwrap();
+        let state = &mut inner.symbols[stock_id];
+        if state.halt.is_some() {
+            return false;
+        }
+        let reference = *state.reference.get_or_insert(price);
+        let Some(band) = cfg.band_pct else { return true };
+        let base = reference.to_f64().abs();
+        let moved = (price.to_f64() - reference.to_f64()) / base.max(f64::MIN_POSITIVE) * 100.0;
+        if moved.abs() <= band {
+            return true;
+        }
+        let limit = if moved > 0.0 { "limit up" } else { "limit down" };
+        let reason = format!("{}: {} is {:+.1}% from {}, band {}%", limit, price, moved, reference, band);
+        self.halt(&mut inner, stock_id, Duration::from_secs(cfg.halt_s), reason);
+        false
+    }
+
+    pub fn halted(&self, stock_id: usize) -> bool {
+        self.inner.lock().unwrap().symbols.get(stock_id).is_some_and(|s| s.halt.is_some())
+    }
+
+    /// `HALTED AAPL 3s left (limit down: ...) | 2 halts, band 5%` or `none halted | 2 halts, band 5%`
+    pub fn describe(&self) -> String {
+        let Some(cfg) = &self.cfg else { return "off (add a [halts] section)".to_string() };
+        let inner = self.inner.lock().unwrap();
+        let now = Instant::now();
+        let halted: Vec<String> = inner
+            .symbols
+            .iter()
+            .enumerate()
+            .filter_map(|(id, s)| s.halt.as_ref().map(|h| (id, h)))
+            .map(|(id, h)| {
+                let left = h.until.saturating_duration_since(now).as_secs();
+                format!("{} {}s left ({})", self.tickers[id], left, h.reason)
+            })
+            .collect();
+        let band = cfg.band_pct.map_or("no band".to_string(), |band| format!("band {}%", band));
+        let summary = format!("{} halts, {}", inner.halts, band);
+        if halted.is_empty() {
+            return format!("none halted | {}", summary);
+        }
+        format!("{} {} | {}", HALTED, halted.join(", "), summary)
+    }
+}
+
+/// Writes each halt to Redis and the `halts` table, and clears it there on
+/// resumption.
+pub async fn record(
+    mut events: UnboundedReceiver<Event>,
+    symbols: Arc<Vec<Symbol>>,
+    pool: Arc<PgPool>,
+    redis: Arc<RedisCache>,
+) {
+    while let Some(event) = events.recv().await {
+        let (stock_id, state) = match &event {
+            Event::Halted { stock_id, reason } => (*stock_id, format!("halted: {}", reason)),
+            Event::Resumed { stock_id } => (*stock_id, "trading".to_string()),
+        };
+        let ticker = &symbols[stock_id].ticker;
+        if let Err(e) = redis.set(&format!("halt:{}", ticker), state).await {
+            warn!("Recording the halt of {} in Redis failed: {}", ticker, e);
+        }
+        let query = match &event {
+            Event::Halted { reason, .. } => {
+                sqlx::query("INSERT INTO halts (stock_id, ticker, reason) VALUES ($1, $2, $3)")
+                    .bind(stock_id as i32)
+                    .bind(ticker)
+                    .bind(reason)
+            }
+            Event::Resumed { .. } => {
+                sqlx::query("UPDATE halts SET resumed_at = NOW() WHERE stock_id = $1 AND resumed_at IS NULL")
+                    .bind(stock_id as i32)
+            }
+        };
+        if let Err(e) = query.execute(&*pool).await {
+            warn!("Recording the halt of {} in Postgres failed (run with --migrate): {}", ticker, e);
+        }
+    }
+}
+
+/// For a diagnostics line, here or read back from `/status`: red while a
+/// symbol is halted.
+pub fn line_style(label: &str, value: &str) -> Style {
+    if label == "Halts" && value.starts_with(HALTED) {
+        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    }
+}
@@ -0,0 +1,162 @@
+//! Live connection status of Postgres, Redis and the TCP sinks for the
+//! Health panel. Postgres and Redis are pinged; sinks report as they
+//! connect, write and fail.
+
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::RedisCache;
+use crate::config::HealthConfig;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// Nothing reported yet.
+    Connecting,
+    Connected,
+    /// Reachable, but slower than `degraded_ms`.
+    Degraded,
+    Down,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Connecting => "connecting",
+            Status::Connected => "connected",
+            Status::Degraded => "degraded",
+            Status::Down => "down",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Status::Connecting => Color::Gray,
+            Status::Connected => Color::Green,
+            Status::Degraded => Color::Yellow,
+            Status::Down => Color::Red,
+        }
+    }
+}
+
+/// What the Health panel shows per connection; also served by `GET /status`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConnectionHealth {
+    pub name: String,
+    pub status: Status,
+    pub rtt: Option<Duration>,
+    pub last_error: Option<String>,
+    pub reconnects: u64,
+}
+
+impl ConnectionHealth {
+    /// `redis  connected  rtt    0.52ms  reconnects 0    last error: -`
+    pub fn line(&self) -> Line<'static> {
+        let rtt = self.rtt.map_or("-".to_string(), |rtt| format!("{:.2}ms", rtt.as_secs_f64() * 1e3));
+        Line::from(vec![
+            Span::raw(format!("{:<24} ", self.name)),
+            Span::styled(format!("{:<10}", self.status.as_str()), Style::default().fg(self.status.color())),
+            Span::raw(format!(
+                " rtt {:>9}  reconnects {:<4} last error: {}",
+                rtt,
+                self.reconnects,
+                self.last_error.as_deref().unwrap_or("-")
+            )),
+        ])
+    }
+}
+
+#[derive(Clone)]
+pub struct HealthRegistry {
+    degraded: Duration,
+    connections: Arc<Mutex<Vec<ConnectionHealth>>>,
+}
+
+impl HealthRegistry {
+    pub fn new(cfg: &HealthConfig) -> Self {
+        HealthRegistry { degraded: Duration::from_millis(cfg.degraded_ms), connections: Arc::default() }
+    }
+
+    pub fn register(&self, name: impl Into<String>) -> HealthHandle {
+        let mut connections = self.connections.lock().unwrap();
+        connections.push(ConnectionHealth {
+            name: name.into(),
+            status: Status::Connecting,
+            rtt: None,
+            last_error: None,
+            reconnects: 0,
+        });
+        HealthHandle { registry: self.clone(), index: connections.len() - 1 }
+    }
+
+    pub fn snapshot(&self) -> Vec<ConnectionHealth> {
+        self.connections.lock().unwrap().clone()
+    }
+}
+
+/// Reports on one registered connection.
+#[derive(Clone)]
+pub struct HealthHandle {
+    registry: HealthRegistry,
+    index: usize,
+}
+
+impl HealthHandle {
+    /// A successful round trip; `rtt` is `None` when it was not timed.
+    pub fn up(&self, rtt: Option<Duration>) {
+        let degraded = self.registry.degraded;
+        let mut connections = self.registry.connections.lock().unwrap();
+        let conn = &mut connections[self.index];
+        if conn.status == Status::Down {
+            conn.reconnects += 1;
+        }
+        conn.status = match rtt {
+            Some(rtt) if rtt > degraded => Status::Degraded,
+            _ => Status::Connected,
+        };
+        if rtt.is_some() {
+            conn.rtt = rtt;
+        }
+    }
+
+    pub fn down(&self, error: impl Display) {
+        let mut connections = self.registry.connections.lock().unwrap();
+        let conn = &mut connections[self.index];
+        conn.status = Status::Down;
+        conn.last_error = Some(error.to_string());
+    }
+}
+
+/// Pings Postgres with `SELECT 1` and Redis with `PING` every `interval_ms`.
+pub async fn ping_backends(
+    cfg: HealthConfig,
+    pg_pool: Arc<sqlx::PgPool>,
+    redis_cache: Arc<RedisCache>,
+    postgres: HealthHandle,
+    redis: HealthHandle,
+) {
+    let timeout = Duration::from_millis(cfg.interval_ms.max(1));
+    let mut interval = tokio::time::interval(timeout);
+    loop {
+        interval.tick().await;
+
+        let started = Instant::now();
+        match tokio::time::timeout(timeout, sqlx::query("SELECT 1").execute(&*pg_pool)).await {
+            Ok(Ok(_)) => postgres.up(Some(started.elapsed())),
+            Ok(Err(e)) => postgres.down(e),
+            Err(_) => postgres.down(format!("no reply within {:?}", timeout)),
+        }
+
+        let started = Instant::now();
+        match tokio::time::timeout(timeout, redis_cache.ping()).await {
+            Ok(Ok(_)) => redis.up(Some(started.elapsed())),
+            Ok(Err(e)) => redis.down(e),
+            Err(_) => redis.down(format!("no reply within {:?}", timeout)),
+        }
+    }
+}
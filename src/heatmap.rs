@@ -0,0 +1,154 @@
+//! Latency over time as a heatmap per stage: one column per second, one row
+//! per power-of-two latency bucket, shaded by how many samples fell in it.
+//! A shifting or widening distribution shows as the band moving or
+//! spreading, which a line of one percentile hides.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::warn;
+
+use crate::latency::{LatencyReceiver, Stage};
+
+const COLUMN: Duration = Duration::from_secs(1);
+/// Kept per stage; more than any panel is wide.
+const MAX_COLUMNS: usize = 300;
+/// Row `i` holds samples from 2^i µs up to 2^(i+1) µs; the first also holds
+/// anything faster and the last anything slower, so 1µs to about a second.
+const ROWS: usize = 20;
+const LABEL_WIDTH: usize = 7;
+/// From few samples to many.
+const SHADES: [Color; 6] = [
+    Color::Indexed(17),
+    Color::Indexed(19),
+    Color::Indexed(27),
+    Color::Indexed(45),
+    Color::Indexed(226),
+    Color::Indexed(196),
+];
+
+struct Columns {
+    started: Instant,
+    /// Index of the newest column since `started`.
+    newest: u64,
+    /// Oldest first.
+    stages: BTreeMap<Stage, VecDeque<[u32; ROWS]>>,
+}
+
+#[derive(Clone)]
+pub struct LatencyHeatmap {
+    columns: Arc<Mutex<Columns>>,
+}
+
+impl LatencyHeatmap {
+    pub fn new() -> Self {
+        LatencyHeatmap {
+            columns: Arc::new(Mutex::new(Columns { started: Instant::now(), newest: 0, stages: BTreeMap::new() })),
+        }
+    }
+
+    pub async fn run(self, mut rx: LatencyReceiver) {
+        loop {
+            match rx.recv().await {
+                Ok(sample) => {
+                    let mut columns = self.columns.lock().unwrap();
+                    columns.advance();
+                    let row = ((sample.nanos / 1000).max(1).ilog2() as usize).min(ROWS - 1);
+                    let newest = columns.newest;
+                    let stage = columns.stages.entry(sample.stage).or_insert_with(|| {
+                        // Started late: pad so every stage's newest column is the same second.
+                        VecDeque::from(vec![[0; ROWS]; (newest as usize + 1).min(MAX_COLUMNS)])
+                    });
+                    stage.back_mut().unwrap()[row] += 1;
+                }
+                Err(RecvError::Lagged(n)) => warn!("Latency heatmap lagged, skipped {} samples", n),
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// One panel per stage with samples, side by side, newest column on the
+    /// right.
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let mut columns = self.columns.lock().unwrap();
+        columns.advance();
+        let title = "Latency heatmap, 1s columns, shaded by count - h charts";
+        if columns.stages.is_empty() {
+            let empty = Paragraph::new("no latency samples yet");
+            f.render_widget(empty.block(Block::default().borders(Borders::ALL).title(title)), area);
+            return;
+        }
+        let outer = Block::default().borders(Borders::ALL).title(title);
+        let inner = outer.inner(area);
+        f.render_widget(outer, area);
+        let panels = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, columns.stages.len() as u32); columns.stages.len()])
+            .split(inner);
+        for ((stage, history), &panel) in columns.stages.iter().zip(panels.iter()) {
+            let block = Block::default().borders(Borders::ALL).title(stage.as_str());
+            let cells = block.inner(panel);
+            let width = (cells.width as usize).saturating_sub(LABEL_WIDTH + 1);
+            let lines_available = (cells.height as usize).max(1);
+            let rows_per_line = ROWS.div_ceil(lines_available);
+            let visible: Vec<&[u32; ROWS]> = history.iter().skip(history.len().saturating_sub(width)).collect();
+            let busiest = visible.iter().flat_map(|c| c.iter()).copied().max().unwrap_or(0).max(1);
+            let max = busiest as f64 * rows_per_line as f64;
+
+            // Slowest at the top.
+            let lines: Vec<Line> = (0..ROWS.div_ceil(rows_per_line))
+                .rev()
+                .map(|line| {
+                    let rows = line * rows_per_line..((line + 1) * rows_per_line).min(ROWS);
+                    let mut spans = vec![Span::raw(format!("{:>LABEL_WIDTH$} ", bucket_label(rows.start)))];
+                    spans.extend(visible.iter().map(|column| {
+                        let count: u32 = column[rows.clone()].iter().sum();
+                        if count == 0 {
+                            return Span::raw(" ");
+                        }
+                        // Log-scaled, so a handful of outliers still shows.
+                        let level = (count as f64).ln_1p() / max.ln_1p();
+                        let shade = ((level * (SHADES.len() - 1) as f64).round() as usize).min(SHADES.len() - 1);
+                        Span::styled(" ", Style::default().bg(SHADES[shade]))
+                    }));
+                    Line::from(spans)
+                })
+                .collect();
+            f.render_widget(Paragraph::new(lines).block(block), panel);
+        }
+    }
+}
+
+impl Columns {
+    /// Starts the columns of every second that began since the last sample.
+    fn advance(&mut self) {
+        let current = (self.started.elapsed().as_nanos() / COLUMN.as_nanos()) as u64;
+        let new = (current - self.newest).min(MAX_COLUMNS as u64) as usize;
+        self.newest = current;
+        for history in self.stages.values_mut() {
+            for _ in 0..new {
+                history.push_back([0; ROWS]);
+            }
+            while history.len() > MAX_COLUMNS {
+                history.pop_front();
+            }
+        }
+    }
+}
+
+/// Lower bound of row `row`, e.g. `512µs` or `2ms`.
+fn bucket_label(row: usize) -> String {
+    let micros = 1u64 << row;
+    match micros {
+        m if m >= 1_000_000 => format!("{}s", m / 1_000_000),
+        m if m >= 1000 => format!("{}ms", m / 1000),
+        m => format!("{}µs", m),
+    }
+}
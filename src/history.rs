@@ -0,0 +1,190 @@
+//! Every tick on disk, from `[history]`, for zooming the charts out past the
+//! points they keep in memory. Each symbol has a directory under `dir` with
+//! a file per hour of 16-byte records: the tick's time in Unix microseconds
+//! and its price, both little-endian. Files are written through a buffer
+//! flushed once a second and deleted after `retain_h` hours, so memory
+//! stays bounded however long the charts reach back; a zoomed chart reads
+//! the hours it spans and downsamples them before it draws.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+use crate::config::HistoryConfig;
+use crate::lttb;
+use crate::market;
+use crate::symbols::Symbol;
+use crate::tick::Tick;
+
+const FLUSH_EVERY: Duration = Duration::from_secs(1);
+const RECORD_LEN: usize = 16;
+const HOUR_US: i64 = 3600 * 1_000_000;
+/// Points of one symbol a window read returns, downsampled if there are
+/// more; charts narrow them further to their width.
+const MAX_POINTS: usize = 2000;
+
+/// The file a symbol's ticks are being appended to.
+struct Open {
+    hour: i64,
+    file: BufWriter<File>,
+}
+
+/// A window read, reused until the window moves a second.
+struct Read {
+    window_s: (i64, i64),
+    points: Vec<(f64, f64)>,
+    at: Instant,
+}
+
+#[derive(Clone)]
+pub struct DiskHistory {
+    dir: Arc<PathBuf>,
+    /// By stock id, the directory names.
+    tickers: Arc<Vec<String>>,
+    retain_h: i64,
+    reads: Arc<Mutex<HashMap<usize, Read>>>,
+}
+
+impl DiskHistory {
+    pub fn new(cfg: &HistoryConfig, symbols: &[Symbol]) -> io::Result<Self> {
+        if cfg.retain_h == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "history.retain_h: must be at least 1"));
+        }
+        fs::create_dir_all(&cfg.dir)?;
+        Ok(DiskHistory {
+            dir: Arc::new(PathBuf::from(&cfg.dir)),
+            tickers: Arc::new(symbols.iter().map(|s| s.ticker.clone()).collect()),
+            retain_h: cfg.retain_h as i64,
+            reads: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Appends the ticks of a bus to their symbols' files.
+    pub async fn run(self, mut rx: broadcast::Receiver<Tick>) {
+        let mut open: HashMap<usize, Open> = HashMap::new();
+        let mut flush = tokio::time::interval(FLUSH_EVERY);
+        loop {
+            tokio::select! {
+                msg = rx.recv() => match msg {
+                    Ok(tick) => {
+                        if let Err(e) = self.append(&mut open, &tick) {
+                            warn!("Writing tick history to {} failed: {}", self.dir.display(), e);
+                        }
+                    }
+                    Err(RecvError::Lagged(n)) => warn!("Tick history lagged, skipped {} ticks", n),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = flush.tick() => {
+                    for open in open.values_mut() {
+                        if let Err(e) = open.file.flush() {
+                            warn!("Flushing tick history failed: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+        for open in open.values_mut() {
+            let _ = open.file.flush();
+        }
+    }
+
+    /// Moves to a new file when the tick starts a later hour than the open
+    /// one, deleting those past retention; a late tick goes into the open
+    /// file.
+    fn append(&self, open: &mut HashMap<usize, Open>, tick: &Tick) -> io::Result<()> {
+        let Some((id, ticker)) = usize::try_from(tick.stock_id).ok().and_then(|id| Some((id, self.tickers.get(id)?)))
+        else {
+            return Ok(());
+        };
+        let us = unix_us(tick.ts);
+        let hour = us.div_euclid(HOUR_US);
+        if open.get(&id).is_none_or(|o| o.hour < hour) {
+            let dir = self.dir.join(ticker);
+            fs::create_dir_all(&dir)?;
+            if let Some(mut done) = open.remove(&id) {
+                done.file.flush()?;
+            }
+            let file = OpenOptions::new().create(true).append(true).open(dir.join(format!("{}.bin", hour)))?;
+            open.insert(id, Open { hour, file: BufWriter::new(file) });
+            prune(&dir, hour - self.retain_h)?;
+        }
+        let file = &mut open.get_mut(&id).expect("opened above").file;
+        file.write_all(&us.to_le_bytes())?;
+        file.write_all(&tick.price.to_f64().to_le_bytes())?;
+        Ok(())
+    }
+
+    /// The symbol's ticks from `from` to `to` at their chart x, as in
+    /// [`market::chart_x`], oldest first, followed by those in `recent`
+    /// newer than the last one on disk, which lags by up to a second.
+    pub fn points(
+        &self,
+        stock_id: usize,
+        from: SystemTime,
+        to: SystemTime,
+        recent: Vec<(f64, f64)>,
+    ) -> Vec<(f64, f64)> {
+        let window_s = (unix_us(from) / 1_000_000, unix_us(to) / 1_000_000);
+        let mut reads = self.reads.lock().unwrap();
+        reads.retain(|_, read| read.at.elapsed() < FLUSH_EVERY);
+        let mut points = match reads.get(&stock_id).filter(|read| read.window_s == window_s) {
+            Some(read) => read.points.clone(),
+            None => {
+                let points = self.read(stock_id, from, to).unwrap_or_else(|e| {
+                    warn!("Reading tick history from {} failed: {}", self.dir.display(), e);
+                    Vec::new()
+                });
+                let points = lttb::downsample(&points, MAX_POINTS);
+                reads.insert(stock_id, Read { window_s, points: points.clone(), at: Instant::now() });
+                points
+            }
+        };
+        let (last, to_x) = (points.last().map_or(f64::NEG_INFINITY, |p| p.0), market::chart_x(to));
+        points.extend(recent.into_iter().filter(|&(x, _)| x > last && x <= to_x));
+        points
+    }
+
+    fn read(&self, stock_id: usize, from: SystemTime, to: SystemTime) -> io::Result<Vec<(f64, f64)>> {
+        let Some(ticker) = self.tickers.get(stock_id) else { return Ok(Vec::new()) };
+        let (from_us, to_us) = (unix_us(from), unix_us(to));
+        let mut points = Vec::new();
+        for hour in from_us.div_euclid(HOUR_US)..=to_us.div_euclid(HOUR_US) {
+            let bytes = match fs::read(self.dir.join(ticker).join(format!("{}.bin", hour))) {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for record in bytes.chunks_exact(RECORD_LEN) {
+                let us = i64::from_le_bytes(record[..8].try_into().expect("8 bytes"));
+                if (from_us..=to_us).contains(&us) {
+                    points.push((us as f64 / 1e6, f64::from_le_bytes(record[8..].try_into().expect("8 bytes"))));
+                }
+            }
+        }
+        // Late ticks are appended where they arrived.
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(points)
+    }
+}
+
+/// Deletes the hour files in `dir` before `oldest`.
+fn prune(dir: &Path, oldest: i64) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hour = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.parse::<i64>().ok());
+        if hour.is_some_and(|hour| hour < oldest) {
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+fn unix_us(at: SystemTime) -> i64 {
+    at.duration_since(UNIX_EPOCH).unwrap_or_default().as_micros() as i64
+}
@@ -0,0 +1,26 @@
+//! Endpoints that change the running process.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Serialize;
+
+use super::AppState;
+
+#[derive(Serialize)]
+pub struct LogFilter {
+    filter: String,
+}
+
+/// `GET /log`
+pub async fn log_filter(State(state): State<AppState>) -> Json<LogFilter> {
+    Json(LogFilter { filter: state.log_filter.get() })
+}
+
+/// `PUT /log` with a `RUST_LOG`-style filter as the body, e.g.
+/// `info,hft_latency::spool=trace`.
+pub async fn set_log_filter(State(state): State<AppState>, body: String) -> Result<Json<LogFilter>, (StatusCode, String)> {
+    let filter = body.trim();
+    state.log_filter.set(filter).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(Json(LogFilter { filter: filter.to_string() }))
+}
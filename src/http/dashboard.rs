@@ -0,0 +1,12 @@
+//! `GET /`: a live dashboard for a browser, for those without a terminal on
+//! the box. The page is compiled into the binary and draws its charts from
+//! the same `/ws` stream and JSON endpoints as any other client.
+
+use axum::response::Html;
+
+const PAGE: &str = include_str!("dashboard.html");
+
+/// `GET /`
+pub async fn page() -> Html<&'static str> {
+    Html(PAGE)
+}
@@ -0,0 +1,133 @@
+//! The JSON datasource protocol Grafana's simple JSON plugins speak, under
+//! `/grafana`: point the datasource at `http://host:8080/grafana` to chart
+//! the series of [`SeriesStore`](crate::series::SeriesStore) and mark
+//! connection status changes as annotations.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+
+use super::AppState;
+
+#[derive(Deserialize)]
+pub struct Range {
+    from: String,
+    to: String,
+}
+
+impl Range {
+    /// Unix milliseconds.
+    fn millis(&self) -> Result<(i64, i64), (StatusCode, String)> {
+        let parse = |s: &str| {
+            DateTime::parse_from_rfc3339(s)
+                .map(|t| t.timestamp_millis())
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("bad time {:?}: {}", s, e)))
+        };
+        Ok((parse(&self.from)?, parse(&self.to)?))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Search {
+    #[serde(default)]
+    target: String,
+}
+
+#[derive(Deserialize)]
+pub struct Target {
+    target: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Query {
+    range: Range,
+    targets: Vec<Target>,
+    max_data_points: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct TimeSeries {
+    target: String,
+    /// `[value, unix milliseconds]`
+    datapoints: Vec<(f64, i64)>,
+}
+
+#[derive(Deserialize)]
+pub struct AnnotationQuery {
+    range: Range,
+    annotation: serde_json::Value,
+}
+
+#[derive(Serialize)]
+pub struct Annotation {
+    annotation: serde_json::Value,
+    time: i64,
+    title: String,
+    text: String,
+    tags: Vec<String>,
+}
+
+/// `GET /grafana`: the datasource's connection test.
+pub async fn test() -> &'static str {
+    "ok"
+}
+
+/// `POST /grafana/search`: series names containing the typed text.
+pub async fn search(State(state): State<AppState>, Json(body): Json<Search>) -> Json<Vec<String>> {
+    Json(state.series.names().into_iter().filter(|name| name.contains(&body.target)).collect())
+}
+
+/// `POST /grafana/query`: unknown names are an error rather than an empty
+/// series, so a typo shows in the panel.
+pub async fn query(
+    State(state): State<AppState>,
+    Json(body): Json<Query>,
+) -> Result<Json<Vec<TimeSeries>>, (StatusCode, String)> {
+    let (from, to) = body.range.millis()?;
+    let max_points = body.max_data_points.unwrap_or(usize::MAX).max(1);
+    body.targets
+        .into_iter()
+        .map(|t| {
+            let points = state
+                .series
+                .points(&t.target, from.div_euclid(1000), to.div_euclid(1000))
+                .ok_or_else(|| (StatusCode::NOT_FOUND, format!("no series {:?}", t.target)))?;
+            // Thinned to what the panel can show, keeping each run's largest value so spikes survive.
+            let run = points.len().div_ceil(max_points).max(1);
+            let datapoints = points
+                .chunks(run)
+                .map(|chunk| {
+                    let &(at, value) = chunk.iter().max_by(|a, b| a.1.total_cmp(&b.1)).unwrap();
+                    (value, at * 1000)
+                })
+                .collect();
+            Ok(TimeSeries { target: t.target, datapoints })
+        })
+        .collect::<Result<_, _>>()
+        .map(Json)
+}
+
+/// `POST /grafana/annotations`: connection status changes.
+pub async fn annotations(
+    State(state): State<AppState>,
+    Json(body): Json<AnnotationQuery>,
+) -> Result<Json<Vec<Annotation>>, (StatusCode, String)> {
+    let (from, to) = body.range.millis()?;
+    Ok(Json(
+        state
+            .series
+            .events(from, to)
+            .into_iter()
+            .map(|e| Annotation {
+                annotation: body.annotation.clone(),
+                time: e.time_ms,
+                title: e.title,
+                text: e.text,
+                tags: vec!["health".to_string()],
+            })
+            .collect(),
+    ))
+}
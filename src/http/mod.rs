@@ -0,0 +1,64 @@
+//! Embedded HTTP server, enabled by the `[http]` config section.
+
+mod admin;
+mod dashboard;
+mod grafana;
+mod rest;
+mod ws;
+
+pub use rest::{History as HistoryEntry, Price as PriceEntry, Symbol as SymbolEntry};
+pub use ws::Event;
+
+use std::sync::Arc;
+
+use axum::routing::{get, post};
+use axum::Router;
+use tracing::info;
+
+use crate::config::HttpConfig;
+use crate::latency::LatencyRecorder;
+use crate::logging::LogFilter;
+use crate::market::{SharedMarketData, SharedUiData};
+use crate::paper::{Marks, OrderFlow};
+use crate::series::SeriesStore;
+use crate::status::StatusBoard;
+use crate::symbols::Symbol;
+use crate::tick::TickSender;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub market: SharedMarketData,
+    pub ui: SharedUiData,
+    pub latency: LatencyRecorder,
+    pub ticks: TickSender,
+    pub symbols: Arc<Vec<Symbol>>,
+    pub log_filter: LogFilter,
+    pub status: StatusBoard,
+    pub series: SeriesStore,
+    /// Order-flow imbalance of the paper venues; none without paper trading.
+    pub flow: Option<OrderFlow>,
+    /// Microprice and weighted mid of the paper venues; none without paper trading.
+    pub marks: Option<Marks>,
+}
+
+pub async fn serve(cfg: HttpConfig, state: AppState) -> std::io::Result<()> {
+    let app = Router::new()
+        .route("/", get(dashboard::page))
+        .route("/symbols", get(rest::symbols))
+        .route("/prices", get(rest::prices))
+        .route("/indicators", get(rest::indicators))
+        .route("/latency/summary", get(rest::latency_summary))
+        .route("/status", get(rest::status))
+        .route("/history/{symbol}", get(rest::history))
+        .route("/ws", get(ws::handler))
+        .route("/grafana", get(grafana::test))
+        .route("/grafana/search", post(grafana::search))
+        .route("/grafana/query", post(grafana::query))
+        .route("/grafana/annotations", post(grafana::annotations))
+        .route("/log", get(admin::log_filter).put(admin::set_log_filter))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(&cfg.addr).await?;
+    info!("HTTP server listening on {}", cfg.addr);
+    axum::serve(listener, app).await
+}
@@ -0,0 +1,151 @@
+//! Read-only JSON endpoints over the in-memory market state.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use super::AppState;
+use crate::latency::StageSummary;
+use crate::status::StatusReport;
+
+/// Also read back by `attach`.
+#[derive(Serialize, Deserialize)]
+pub struct Symbol {
+    pub stock_id: usize,
+    pub ticker: String,
+    pub name: String,
+    pub tick_size: crate::price::Price,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Price {
+    pub stock_id: usize,
+    pub ticker: String,
+    pub price: crate::price::Price,
+    pub moving_avg: f64,
+    /// Time since the backend last updated the price.
+    pub age_ms: u128,
+}
+
+#[derive(Serialize)]
+pub struct Indicators {
+    stock_id: usize,
+    ticker: String,
+    /// Order-flow imbalance over `paper.flow_window_ms`, in shares; null
+    /// without paper trading or before the symbol's book has updated.
+    ofi: Option<i64>,
+    /// From the consolidated touch of the paper venues, in price units;
+    /// null without paper trading or a two-sided book.
+    microprice: Option<f64>,
+    /// The same from all the depth displayed.
+    weighted_mid: Option<f64>,
+}
+
+/// Also read back by `chart`.
+#[derive(Serialize, Deserialize)]
+pub struct History {
+    pub stock_id: usize,
+    pub ticker: String,
+    pub prices: Vec<f64>,
+    /// When the last prices were recorded, lined up with the end of
+    /// `prices`; shorter while the chart still holds padding.
+    pub times_unix_us: Vec<u64>,
+    pub moving_avg: Vec<f64>,
+    /// As `times_unix_us`, for `moving_avg`.
+    pub moving_avg_times_unix_us: Vec<u64>,
+}
+
+/// `GET /symbols`
+pub async fn symbols(State(state): State<AppState>) -> Json<Vec<Symbol>> {
+    Json(
+        state
+            .symbols
+            .iter()
+            .enumerate()
+            .map(|(stock_id, s)| Symbol {
+                stock_id,
+                ticker: s.ticker.clone(),
+                name: s.name.clone(),
+                tick_size: s.tick_size,
+            })
+            .collect(),
+    )
+}
+
+/// `GET /status`: the Diagnostics and Health panels.
+pub async fn status(State(state): State<AppState>) -> Json<StatusReport> {
+    Json(state.status.get())
+}
+
+/// `GET /prices`
+pub async fn prices(State(state): State<AppState>) -> Json<Vec<Price>> {
+    let md_vec = state.market.read().unwrap();
+    let ui_vec = state.ui.read().unwrap();
+    Json(
+        md_vec
+            .iter()
+            .zip(ui_vec.iter())
+            .map(|(md, ui)| Price {
+                stock_id: md.count,
+                ticker: state.symbols[md.count].ticker.clone(),
+                price: *md.price.read().unwrap(),
+                moving_avg: *ui.value,
+                age_ms: md.last_update.elapsed().as_millis(),
+            })
+            .collect(),
+    )
+}
+
+/// `GET /indicators`
+pub async fn indicators(State(state): State<AppState>) -> Json<Vec<Indicators>> {
+    Json(
+        state
+            .symbols
+            .iter()
+            .enumerate()
+            .map(|(stock_id, s)| {
+                let mark = state.marks.as_ref().and_then(|marks| marks.mark(stock_id as i32));
+                Indicators {
+                    stock_id,
+                    ticker: s.ticker.clone(),
+                    ofi: state.flow.as_ref().and_then(|flow| flow.imbalance(stock_id as i32)),
+                    microprice: mark.map(|m| m.microprice),
+                    weighted_mid: mark.map(|m| m.weighted_mid),
+                }
+            })
+            .collect(),
+    )
+}
+
+/// `GET /latency/summary`
+pub async fn latency_summary(State(state): State<AppState>) -> Json<Vec<StageSummary>> {
+    Json(state.latency.summary())
+}
+
+/// `GET /history/{symbol}`, by ticker or stock id.
+pub async fn history(State(state): State<AppState>, Path(symbol): Path<String>) -> Result<Json<History>, StatusCode> {
+    let stock_id = state
+        .symbols
+        .iter()
+        .position(|s| s.ticker == symbol)
+        .or_else(|| symbol.parse().ok())
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let md_vec = state.market.read().unwrap();
+    let ui_vec = state.ui.read().unwrap();
+    let (md, ui) = md_vec.get(stock_id).zip(ui_vec.get(stock_id)).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(History {
+        stock_id: md.count,
+        ticker: state.symbols[md.count].ticker.clone(),
+        prices: md.history.to_vec(),
+        times_unix_us: md.times.iter().map(|&t| unix_us(t)).collect(),
+        moving_avg: ui.history.to_vec(),
+        moving_avg_times_unix_us: ui.times.iter().map(|&t| unix_us(t)).collect(),
+    }))
+}
+
+fn unix_us(at: SystemTime) -> u64 {
+    at.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_micros() as u64)
+}
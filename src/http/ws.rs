@@ -0,0 +1,85 @@
+//! `GET /ws`: pushes every tick and latency sample to the client as JSON.
+//!
+//! ```json
+//! {"type":"tick","stock_id":0,"price":100.25,"ts_us":1760436000000000,"seq":42,"id":"01JA2Y7Q4M3X8E5B6C9D0F1G2H"}
+//! {"type":"latency","stage":"redis_set","stock_id":0,"nanos":51234,"ts_us":1760436000000000}
+//! ```
+
+use std::time::UNIX_EPOCH;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+use super::AppState;
+use crate::latency::{LatencyReceiver, LatencySample, Stage};
+use crate::price::Price;
+use crate::tick::{Tick, TickReceiver};
+use crate::ulid::Ulid;
+
+/// Also read back by `attach`.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Tick {
+        stock_id: i32,
+        price: Price,
+        ts_us: i64,
+        seq: u64,
+        /// Absent from pipelines that predate tick IDs.
+        #[serde(default)]
+        id: Option<Ulid>,
+    },
+    Latency { stage: Stage, stock_id: Option<i32>, nanos: u64, ts_us: i64 },
+}
+
+impl From<Tick> for Event {
+    fn from(tick: Tick) -> Self {
+        let ts_us = tick.unix_micros();
+        Event::Tick { stock_id: tick.stock_id, price: tick.price, ts_us, seq: tick.seq, id: Some(tick.id) }
+    }
+}
+
+impl From<LatencySample> for Event {
+    fn from(sample: LatencySample) -> Self {
+        let ts_us = sample.at.duration_since(UNIX_EPOCH).map(|d| d.as_micros() as i64).unwrap_or(0);
+        Event::Latency { stage: sample.stage, stock_id: sample.stock_id, nanos: sample.nanos, ts_us }
+    }
+}
+
+pub async fn handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
+    let ticks = state.ticks.subscribe();
+    let latency = state.latency.subscribe();
+    ws.on_upgrade(move |socket| broadcast(socket, ticks, latency))
+}
+
+async fn broadcast(mut socket: WebSocket, mut ticks: TickReceiver, mut latency: LatencyReceiver) {
+    info!("WebSocket client connected");
+    loop {
+        let event: Event = tokio::select! {
+            tick = ticks.recv() => match tick {
+                Ok(tick) => tick.into(),
+                Err(RecvError::Lagged(n)) => { warn!("WebSocket client lagged, skipped {} ticks", n); continue; }
+                Err(RecvError::Closed) => break,
+            },
+            sample = latency.recv() => match sample {
+                Ok(sample) => sample.into(),
+                Err(RecvError::Lagged(n)) => { warn!("WebSocket client lagged, skipped {} samples", n); continue; }
+                Err(RecvError::Closed) => break,
+            },
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by axum; anything else from the client is ignored.
+                Some(Ok(_)) => continue,
+            },
+        };
+        let Ok(text) = serde_json::to_string(&event) else { continue };
+        if socket.send(Message::Text(text.into())).await.is_err() {
+            break;
+        }
+    }
+    info!("WebSocket client disconnected");
+}
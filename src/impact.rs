@@ -0,0 +1,79 @@
+//! The strategy's own footprint on the paper book, from `[paper] impact`.
+//! Each fill pushes the symbol's mid the way the order traded, by a linear
+//! or square-root function of its size, and the push fades with a
+//! half-life. The maker quotes around the pushed mid and the strategy sees
+//! it, so a large order chases the price it moved and the position it built
+//! is marked against it as the impact fades.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::config::{ImpactConfig, ImpactFunction};
+use crate::engine::Side;
+
+/// A symbol's push, in engine price ticks, as of `at`.
+#[derive(Clone, Copy)]
+struct Push {
+    ticks: f64,
+    at: Duration,
+}
+
+pub struct Impact {
+    cfg: Option<ImpactConfig>,
+    pushes: HashMap<i32, Push>,
+    /// The last mid of each symbol from the ticks, before any push.
+    mids: HashMap<i32, i64>,
+}
+
+impl Impact {
+    pub fn new(cfg: Option<ImpactConfig>) -> Self {
+        Impact { cfg, pushes: HashMap::new(), mids: HashMap::new() }
+    }
+
+    /// The tick's `mid` with what is left at `now` of the push on the symbol.
+    pub fn mid(&mut self, stock_id: i32, mid: i64, now: Duration) -> i64 {
+        self.mids.insert(stock_id, mid);
+        mid + self.offset(stock_id, now)
+    }
+
+    fn offset(&self, stock_id: i32, now: Duration) -> i64 {
+        match (&self.cfg, self.pushes.get(&stock_id)) {
+            (Some(cfg), Some(push)) => faded(cfg, *push, now).round() as i64,
+            _ => 0,
+        }
+    }
+
+    /// Adds a fill of `qty` on `side`; buys push the mid up, sells down.
+    /// Returns the pushed mid to quote around at once, with impact on and
+    /// a tick seen for the symbol.
+    pub fn fill(&mut self, stock_id: i32, side: Side, qty: u32, now: Duration) -> Option<i64> {
+        let cfg = self.cfg.as_ref()?;
+        let size = match cfg.function {
+            ImpactFunction::Linear => cfg.ticks * qty as f64,
+            ImpactFunction::SquareRoot => cfg.ticks * (qty as f64).sqrt(),
+        };
+        let signed = match side {
+            Side::Buy => size,
+            Side::Sell => -size,
+        };
+        let left = self.pushes.get(&stock_id).map_or(0.0, |push| faded(cfg, *push, now));
+        self.pushes.insert(stock_id, Push { ticks: left + signed, at: now });
+        self.mids.get(&stock_id).map(|&mid| mid + self.offset(stock_id, now))
+    }
+
+    /// `impact square_root 0.5 ticks, half-life 1000ms` or `no impact`
+    pub fn describe(&self) -> String {
+        let Some(cfg) = &self.cfg else { return "no impact".to_string() };
+        let function = match cfg.function {
+            ImpactFunction::Linear => "linear",
+            ImpactFunction::SquareRoot => "square_root",
+        };
+        format!("impact {} {} ticks, half-life {}ms", function, cfg.ticks, cfg.half_life_ms)
+    }
+}
+
+fn faded(cfg: &ImpactConfig, push: Push, now: Duration) -> f64 {
+    let elapsed = now.saturating_sub(push.at).as_secs_f64();
+    let half_life = cfg.half_life_ms.max(1) as f64 / 1000.0;
+    push.ticks * 0.5f64.powf(elapsed / half_life)
+}
@@ -0,0 +1,103 @@
+//! Artificial delays for chosen pipeline stages, to see how the UI and sinks
+//! cope with a degraded stage and to check that the latency numbers show
+//! what was injected.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::Rng;
+use rand_distr::{Distribution, Normal, Pareto};
+
+use crate::config::DelayDistribution;
+use crate::latency::Stage;
+
+/// Draws delays from a configured [`DelayDistribution`].
+pub struct Sampler {
+    kind: SamplerKind,
+    desc: String,
+}
+
+enum SamplerKind {
+    Fixed(f64),
+    Normal(Normal<f64>),
+    Pareto(Pareto<f64>),
+}
+
+impl Sampler {
+    pub fn new(dist: &DelayDistribution) -> Result<Self, String> {
+        let (kind, desc) = match *dist {
+            DelayDistribution::Fixed { delay_us } => (SamplerKind::Fixed(delay_us), format!("{}µs", delay_us)),
+            DelayDistribution::Normal { mean_us, std_dev_us } => (
+                SamplerKind::Normal(Normal::new(mean_us, std_dev_us).map_err(|e| e.to_string())?),
+                format!("normal({}±{}µs)", mean_us, std_dev_us),
+            ),
+            DelayDistribution::Pareto { scale_us, shape } => (
+                SamplerKind::Pareto(Pareto::new(scale_us, shape).map_err(|e| e.to_string())?),
+                format!("pareto(≥{}µs, α={})", scale_us, shape),
+            ),
+        };
+        Ok(Sampler { kind, desc })
+    }
+
+    pub fn sample(&self, rng: &mut impl Rng) -> Duration {
+        let us = match &self.kind {
+            SamplerKind::Fixed(us) => *us,
+            SamplerKind::Normal(d) => d.sample(rng),
+            SamplerKind::Pareto(d) => d.sample(rng),
+        };
+        Duration::from_secs_f64(us.max(0.0) / 1e6)
+    }
+
+    /// `normal(50±10µs)`
+    pub fn describe(&self) -> &str {
+        &self.desc
+    }
+}
+
+#[derive(Clone)]
+pub struct Injector {
+    stages: Arc<BTreeMap<Stage, Sampler>>,
+    summary: Arc<String>,
+}
+
+impl Injector {
+    pub fn new(cfg: &BTreeMap<Stage, DelayDistribution>) -> io::Result<Self> {
+        let mut stages = BTreeMap::new();
+        let mut summary = Vec::new();
+        for (&stage, dist) in cfg {
+            let sampler = Sampler::new(dist).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("inject.{}: {}", stage.as_str(), e))
+            })?;
+            summary.push(format!("{}={}", stage.as_str(), sampler.describe()));
+            stages.insert(stage, sampler);
+        }
+        Ok(Injector { stages: Arc::new(stages), summary: Arc::new(summary.join(", ")) })
+    }
+
+    fn delay(&self, stage: Stage) -> Option<Duration> {
+        self.stages.get(&stage).map(|s| s.sample(&mut rand::thread_rng()))
+    }
+
+    /// Sleeps the calling thread for `stage`'s injected delay, if any.
+    pub fn apply_blocking(&self, stage: Stage) {
+        if let Some(delay) = self.delay(stage) {
+            std::thread::sleep(delay);
+        }
+    }
+
+    pub async fn apply(&self, stage: Stage) {
+        if let Some(delay) = self.delay(stage) {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    pub fn describe(&self) -> &str {
+        if self.summary.is_empty() {
+            "none"
+        } else {
+            &self.summary
+        }
+    }
+}
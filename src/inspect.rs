@@ -0,0 +1,150 @@
+//! The Redis pane: every symbol's `stock:<ticker>` key as the cache holds
+//! it, read again on demand, with its time to live and how long this
+//! process's last `SET` of it took, for checking the cache layer without a
+//! `redis-cli` session.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::{DateTime, Utc};
+use futures::future;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+
+use crate::cache::{LastWrite, RedisCache};
+use crate::keymap::{Action, Keymap};
+use crate::symbols::Symbol;
+
+/// One key as read.
+struct Entry {
+    key: String,
+    /// None if the key is missing.
+    value: Option<String>,
+    /// Milliseconds left, as `PTTL` returns them.
+    ttl_ms: i64,
+    write: Option<LastWrite>,
+}
+
+enum State {
+    Idle,
+    Reading { since: Instant },
+    Read { entries: Vec<Entry>, took: Duration, at: SystemTime },
+    Failed { error: String },
+}
+
+#[derive(Clone)]
+pub struct KeyPane {
+    cache: Arc<RedisCache>,
+    keys: Arc<Vec<String>>,
+    state: Arc<Mutex<State>>,
+}
+
+impl KeyPane {
+    pub fn new(cache: Arc<RedisCache>, symbols: &[Symbol]) -> Self {
+        KeyPane {
+            cache,
+            keys: Arc::new(symbols.iter().map(Symbol::redis_key).collect()),
+            state: Arc::new(Mutex::new(State::Idle)),
+        }
+    }
+
+    /// Reads every key again in the background, unless a read is running.
+    pub fn refresh(&self) {
+        let mut state = self.state.lock().unwrap();
+        if matches!(*state, State::Reading { .. }) {
+            return;
+        }
+        *state = State::Reading { since: Instant::now() };
+        let pane = self.clone();
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let reads = pane.keys.iter().map(|key| pane.read(key));
+            let result: Result<Vec<Entry>, _> = future::join_all(reads).await.into_iter().collect();
+            *pane.state.lock().unwrap() = match result {
+                Ok(entries) => State::Read { entries, took: started.elapsed(), at: SystemTime::now() },
+                Err(e) => State::Failed { error: e.to_string() },
+            };
+        });
+    }
+
+    async fn read(&self, key: &str) -> redis::RedisResult<Entry> {
+        let (value, ttl_ms) = tokio::try_join!(self.cache.get(key), self.cache.pttl(key))?;
+        Ok(Entry { key: key.to_string(), value, ttl_ms, write: self.cache.last_write(key) })
+    }
+
+    /// Pages in the last read at `area`'s height.
+    pub fn pages(&self, area: Rect) -> usize {
+        match &*self.state.lock().unwrap() {
+            State::Read { entries, .. } => entries.len().div_ceil(page_rows(area)).max(1),
+            _ => 1,
+        }
+    }
+
+    /// Page `page` of the last read.
+    pub fn render(&self, f: &mut Frame, area: Rect, page: usize, keymap: &Keymap) {
+        let mut lines = Vec::new();
+        let mut title = keymap.title(
+            "Redis",
+            &[
+                (&[], "Enter refreshes"),
+                (&[Action::NextPage, Action::PreviousPage], "page"),
+                (&[Action::RedisKeys], "closes"),
+            ],
+        );
+        match &*self.state.lock().unwrap() {
+            State::Idle => lines.push(Line::raw("press Enter to read the keys")),
+            State::Reading { since } => {
+                lines.push(Line::raw(format!("reading for {:.1}s", since.elapsed().as_secs_f64())))
+            }
+            State::Failed { error } => lines.push(Line::styled(error.clone(), Style::default().fg(Color::Red))),
+            State::Read { entries, took, at } => {
+                let per_page = page_rows(area);
+                title = format!("{} (page {}/{})", title, page + 1, entries.len().div_ceil(per_page).max(1));
+                let missing = entries.iter().filter(|e| e.value.is_none()).count();
+                lines.push(Line::raw(format!(
+                    "{} keys read in {:.1} ms at {} UTC, {} missing",
+                    entries.len(),
+                    took.as_secs_f64() * 1e3,
+                    DateTime::<Utc>::from(*at).format("%H:%M:%S"),
+                    missing
+                )));
+                let header =
+                    format!("{:<20} {:>14} {:>10} {:>12} {:>10}", "key", "value", "ttl", "last set", "set ago");
+                lines.push(Line::styled(header, Style::default().add_modifier(Modifier::BOLD)));
+                for entry in entries.iter().skip(page * per_page).take(per_page) {
+                    let (took, ago) = match entry.write {
+                        Some(write) => (
+                            format!("{:.0} µs", write.took.as_secs_f64() * 1e6),
+                            format!("{:.1}s", at.duration_since(write.at).unwrap_or_default().as_secs_f64()),
+                        ),
+                        None => ("-".to_string(), "-".to_string()),
+                    };
+                    let ttl = match entry.ttl_ms {
+                        -1 => "none".to_string(),
+                        -2 => "-".to_string(),
+                        ms => format!("{:.1}s", ms as f64 / 1e3),
+                    };
+                    let line = format!(
+                        "{:<20} {:>14} {:>10} {:>12} {:>10}",
+                        entry.key,
+                        entry.value.as_deref().unwrap_or("(missing)"),
+                        ttl,
+                        took,
+                        ago
+                    );
+                    let style = if entry.value.is_none() { Style::default().fg(Color::Red) } else { Style::default() };
+                    lines.push(Line::styled(line, style));
+                }
+            }
+        }
+        f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+    }
+}
+
+/// Keys under the status and header lines.
+fn page_rows(area: Rect) -> usize {
+    (area.height.saturating_sub(4) as usize).max(1)
+}
@@ -0,0 +1,318 @@
+//! What the TUI and `attach` keys do, from `[keymap]`: each action lists
+//! its keys, and an action left out keeps its default ones. A key is a
+//! character, case-sensitive, or one of the names in [`parse`]. Two actions
+//! on one key, or an action on a `[[shocks]]` key, stop the startup.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::sync::Arc;
+
+use crossterm::event::KeyCode;
+use serde::Deserialize;
+
+use crate::config::ShockConfig;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    /// Clears the running latency statistics.
+    ResetStats,
+    ExportChart,
+    /// Moves to the next log filter.
+    LogFilter,
+    Grid,
+    Stats,
+    Heatmap,
+    Positions,
+    Candles,
+    Combined,
+    CandleInterval,
+    NextPage,
+    PreviousPage,
+    FirstPage,
+    LastPage,
+    Search,
+    ClearFilter,
+    Watch,
+    Unwatch,
+    Watchlist,
+    Microprice,
+    LogBackend,
+    LogFrontend,
+    Layout,
+    Marker,
+    ZoomOut,
+    ZoomIn,
+    ScrollBack,
+    ScrollForward,
+    Help,
+    /// Opens the `:` command line.
+    Command,
+    /// Toggles the SQL pane.
+    Query,
+    /// Toggles the Redis pane.
+    RedisKeys,
+}
+
+/// Keys of every action unless `[keymap]` lists it, and what the help
+/// overlay says it does.
+const DEFAULTS: [(Action, &[&str], &str); 33] = [
+    (Action::Quit, &["q"], "quit"),
+    (Action::ResetStats, &["z"], "reset the latency statistics"),
+    (Action::ExportChart, &["e"], "write the price chart to SVG"),
+    (Action::LogFilter, &["L"], "next log filter"),
+    (Action::Grid, &["g"], "summary grid"),
+    (Action::Stats, &["s"], "Statistics panel"),
+    (Action::Heatmap, &["h"], "latency heatmap"),
+    (Action::Positions, &["o"], "paper positions"),
+    (Action::Candles, &["c"], "candlesticks"),
+    (Action::Combined, &["v"], "price and moving average chart"),
+    (Action::CandleInterval, &["i"], "next candle interval"),
+    (Action::NextPage, &["n", "PageDown"], "next page"),
+    (Action::PreviousPage, &["p", "PageUp"], "previous page"),
+    (Action::FirstPage, &["Home"], "first page"),
+    (Action::LastPage, &["End"], "last page"),
+    (Action::Search, &["/"], "search tickers"),
+    (Action::ClearFilter, &["Esc"], "clear the search"),
+    (Action::Watch, &["+"], "watch the matches"),
+    (Action::Unwatch, &["-"], "unwatch the matches"),
+    (Action::Watchlist, &["w"], "watchlist only"),
+    (Action::Microprice, &["m"], "microprice overlays"),
+    (Action::LogBackend, &["y"], "log scale, backend chart"),
+    (Action::LogFrontend, &["Y"], "log scale, frontend chart"),
+    (Action::Layout, &["l"], "next chart layout"),
+    (Action::Marker, &["b"], "next line marker"),
+    (Action::ZoomOut, &["["], "zoom out"),
+    (Action::ZoomIn, &["]"], "zoom in"),
+    (Action::ScrollBack, &["Left"], "scroll a zoomed chart back"),
+    (Action::ScrollForward, &["Right"], "scroll a zoomed chart forward"),
+    (Action::Help, &["?"], "this help, Esc closes it"),
+    (Action::Command, &[":"], "command line, e.g. :symbol AAPL"),
+    (Action::Query, &["x"], "SQL pane against Postgres"),
+    (Action::RedisKeys, &["k"], "Redis keys, Enter refreshes"),
+];
+
+#[derive(Clone)]
+pub struct Keymap {
+    actions: Arc<HashMap<KeyCode, Action>>,
+}
+
+impl Keymap {
+    /// The defaults with the actions in `cfg` rebound. Unbinding quit is
+    /// refused, since nothing would end the TUI.
+    pub fn new(cfg: &BTreeMap<Action, Vec<String>>, shocks: &[ShockConfig]) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+        let mut actions = HashMap::new();
+        for (action, defaults, _) in DEFAULTS {
+            let keys: Vec<&str> = match cfg.get(&action) {
+                Some(keys) => keys.iter().map(String::as_str).collect(),
+                None => defaults.to_vec(),
+            };
+            if action == Action::Quit && keys.is_empty() {
+                return Err(invalid("keymap.quit: needs at least one key".to_string()));
+            }
+            for key in keys {
+                let code =
+                    parse(key).ok_or_else(|| invalid(format!("keymap.{}: unknown key {:?}", name(action), key)))?;
+                if let Some(other) = actions.insert(code, action) {
+                    return Err(invalid(format!(
+                        "keymap: {:?} is bound to both {} and {}",
+                        key,
+                        name(other),
+                        name(action)
+                    )));
+                }
+            }
+        }
+        for shock in shocks {
+            if let Some(action) = shock.key.and_then(|c| actions.get(&KeyCode::Char(c))) {
+                return Err(invalid(format!(
+                    "shocks: the {} shock's key {:?} is bound to keymap.{}",
+                    shock.ticker,
+                    shock.key.unwrap_or_default(),
+                    name(*action)
+                )));
+            }
+        }
+        Ok(Keymap { actions: Arc::new(actions) })
+    }
+
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        self.actions.get(&code).copied()
+    }
+
+    /// The keys of every action, `unbound` for none, with what it does.
+    pub fn bindings(&self) -> Vec<(String, &'static str)> {
+        DEFAULTS
+            .iter()
+            .map(|&(action, _, what)| {
+                let keys = self.keys(action);
+                let keys = if keys.is_empty() { "unbound".to_string() } else { keys.join(", ") };
+                (keys, what)
+            })
+            .collect()
+    }
+
+    /// A panel title with key hints, e.g. `Grid - n/p page, g grid` for
+    /// `[(&[NextPage, PreviousPage], "page"), (&[Grid], "grid")]`: the first
+    /// key of each action, joined by `/`. A hint with an unbound action is
+    /// left out, one without actions, for a fixed key, always kept.
+    pub fn title(&self, title: &str, hints: &[(&[Action], &str)]) -> String {
+        let hints: Vec<String> = hints
+            .iter()
+            .filter_map(|&(actions, what)| {
+                let keys: Option<Vec<String>> =
+                    actions.iter().map(|&action| self.keys(action).into_iter().next()).collect();
+                keys.map(|keys| if keys.is_empty() { what.to_string() } else { format!("{} {}", keys.join("/"), what) })
+            })
+            .collect();
+        if hints.is_empty() {
+            title.to_string()
+        } else {
+            format!("{} - {}", title, hints.join(", "))
+        }
+    }
+
+    /// Characters first, as in the defaults.
+    fn keys(&self, action: Action) -> Vec<String> {
+        let mut keys: Vec<String> =
+            self.actions.iter().filter(|(_, &a)| a == action).map(|(&code, _)| key_name(code)).collect();
+        keys.sort_by(|a, b| (a.len(), a).cmp(&(b.len(), b)));
+        keys
+    }
+}
+
+/// The action `name` is, as in `[keymap]`.
+pub fn named(name: &str) -> Option<Action> {
+    DEFAULTS.iter().map(|&(action, _, _)| action).find(|&action| self::name(action) == name)
+}
+
+/// A single character, or Left, Right, Up, Down, Home, End, PageUp,
+/// PageDown, Tab, Enter, Esc, Backspace, Space or F1 to F12 in any case.
+fn parse(key: &str) -> Option<KeyCode> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    let code = match key.to_lowercase().as_str() {
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "tab" => KeyCode::Tab,
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "space" => KeyCode::Char(' '),
+        name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+            Some(n @ 1..=12) => KeyCode::F(n),
+            _ => return None,
+        },
+    };
+    Some(code)
+}
+
+/// As [`parse`] reads it.
+fn key_name(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        code => format!("{:?}", code),
+    }
+}
+
+/// As written in `[keymap]`.
+fn name(action: Action) -> String {
+    let debug = format!("{:?}", action);
+    let mut name = String::new();
+    for (i, c) in debug.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            name.push('_');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bound(pairs: &[(Action, &[&str])]) -> BTreeMap<Action, Vec<String>> {
+        pairs.iter().map(|&(action, keys)| (action, keys.iter().map(|k| k.to_string()).collect())).collect()
+    }
+
+    fn refused(cfg: &BTreeMap<Action, Vec<String>>, shocks: &[ShockConfig]) -> String {
+        match Keymap::new(cfg, shocks) {
+            Ok(_) => panic!("keymap accepted"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn shock(key: char) -> ShockConfig {
+        let ticker = "AAPL".to_string();
+        ShockConfig { ticker, at_s: None, key: Some(key), move_pct: -5.0, vol_mult: 3.0, for_s: 30 }
+    }
+
+    #[test]
+    fn rebinding_replaces_the_default_keys() {
+        let keymap = Keymap::new(&bound(&[(Action::Grid, &["G", "F2"])]), &[]).unwrap();
+        assert_eq!(keymap.action(KeyCode::Char('G')), Some(Action::Grid));
+        assert_eq!(keymap.action(KeyCode::F(2)), Some(Action::Grid));
+        assert_eq!(keymap.action(KeyCode::Char('g')), None);
+        assert_eq!(keymap.action(KeyCode::PageDown), Some(Action::NextPage));
+    }
+
+    #[test]
+    fn a_key_bound_twice_is_refused() {
+        let message = refused(&bound(&[(Action::Grid, &["s"])]), &[]);
+        assert_eq!(message, "keymap: \"s\" is bound to both grid and stats");
+        let message = refused(&bound(&[(Action::Grid, &["x"]), (Action::Stats, &["x"])]), &[]);
+        assert!(message.contains("bound to both"), "{}", message);
+    }
+
+    #[test]
+    fn unknown_keys_are_refused() {
+        assert_eq!(refused(&bound(&[(Action::Grid, &["F13"])]), &[]), "keymap.grid: unknown key \"F13\"");
+    }
+
+    #[test]
+    fn a_shock_key_bound_to_an_action_is_refused() {
+        let message = refused(&BTreeMap::new(), &[shock('h')]);
+        assert_eq!(message, "shocks: the AAPL shock's key 'h' is bound to keymap.heatmap");
+        assert!(Keymap::new(&BTreeMap::new(), &[shock('1')]).is_ok());
+    }
+
+    #[test]
+    fn quit_cannot_be_unbound() {
+        assert_eq!(refused(&bound(&[(Action::Quit, &[])]), &[]), "keymap.quit: needs at least one key");
+        let keymap = Keymap::new(&bound(&[(Action::Quit, &["Q"])]), &[]).unwrap();
+        assert_eq!(keymap.action(KeyCode::Char('Q')), Some(Action::Quit));
+    }
+
+    #[test]
+    fn titles_leave_out_hints_for_unbound_actions() {
+        let keymap = Keymap::new(&bound(&[(Action::Grid, &[]), (Action::PreviousPage, &["PageUp"])]), &[]).unwrap();
+        let hints: &[(&[Action], &str)] = &[
+            (&[Action::NextPage, Action::PreviousPage], "page"),
+            (&[Action::Grid], "grid"),
+            (&[], "Tab focus"),
+        ];
+        assert_eq!(keymap.title("Grid", hints), "Grid - n/PageUp page, Tab focus");
+        let keymap = Keymap::new(&bound(&[(Action::Grid, &[])]), &[]).unwrap();
+        assert_eq!(keymap.title("Grid", &[(&[Action::Grid], "grid")]), "Grid");
+    }
+
+    #[test]
+    fn names_are_the_config_keys() {
+        assert_eq!(name(Action::ResetStats), "reset_stats");
+        assert_eq!(named("redis_keys"), Some(Action::RedisKeys));
+        assert_eq!(named("ResetStats"), None);
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use hdrhistogram::Histogram;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::bus::BUS_CAPACITY;
+use crate::export::ParquetExporter;
+use crate::stats::{self, RunningStats};
+
+/// Samples above this are clamped into the top histogram bucket.
+const MAX_TRACKED_NANOS: u64 = 60_000_000_000;
+/// Percentile curves run from p0 to p99.99, evenly spaced in nines.
+const CURVE_NINES: f64 = 4.0;
+const CURVE_POINTS: usize = 81;
+
+/// Pipeline stage a latency sample was taken at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    SpoolAppend,
+    SpoolFlush,
+    RedisSet,
+    PgFlush,
+    /// Paper order sent until the engine booked it.
+    OrderAck,
+    /// Paper order sent until its last fill.
+    OrderFill,
+    /// A strategy callback, from call to return.
+    Decision,
+    /// The `[filter]` script deciding whether to pass a tick on.
+    TickFilter,
+    /// The `[[plugins]]` chain on one new price.
+    Plugins,
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::SpoolAppend => "spool_append",
+            Stage::SpoolFlush => "spool_flush",
+            Stage::RedisSet => "redis_set",
+            Stage::PgFlush => "pg_flush",
+            Stage::OrderAck => "order_ack",
+            Stage::OrderFill => "order_fill",
+            Stage::Decision => "decision",
+            Stage::TickFilter => "tick_filter",
+            Stage::Plugins => "plugins",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LatencySample {
+    pub stage: Stage,
+    pub stock_id: Option<i32>,
+    pub at: SystemTime,
+    pub nanos: u64,
+}
+
+pub type LatencyReceiver = broadcast::Receiver<LatencySample>;
+
+/// Distribution of one stage since startup, in nanoseconds.
+#[derive(Clone, Debug, Serialize)]
+pub struct StageSummary {
+    pub stage: Stage,
+    pub count: u64,
+    pub min: u64,
+    pub mean: f64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub p999: u64,
+    pub max: u64,
+}
+
+/// Cumulative per-stage latency histograms.
+#[derive(Default)]
+struct LatencyStats {
+    stages: BTreeMap<Stage, Histogram<u64>>,
+    /// Since startup or the last reset, by stage and then by symbol within
+    /// the stage (`None` for all of them).
+    running: BTreeMap<(Stage, Option<i32>), RunningStats>,
+}
+
+impl LatencyStats {
+    fn record(&mut self, stage: Stage, stock_id: Option<i32>, nanos: u64) {
+        self.record_n(stage, nanos, 1);
+        for key in [Some((stage, None)), stock_id.map(|id| (stage, Some(id)))].into_iter().flatten() {
+            self.running.entry(key).or_insert_with(RunningStats::new).record(nanos);
+        }
+    }
+
+    fn record_n(&mut self, stage: Stage, nanos: u64, count: u64) {
+        self.stages
+            .entry(stage)
+            .or_insert_with(|| Histogram::new_with_bounds(1, MAX_TRACKED_NANOS, 3).unwrap())
+            .saturating_record_n(nanos.max(1), count);
+    }
+
+    fn counts(&self) -> BTreeMap<Stage, Vec<(u64, u64)>> {
+        self.stages
+            .iter()
+            .map(|(&stage, h)| (stage, h.iter_recorded().map(|v| (v.value_iterated_to(), v.count_at_value())).collect()))
+            .collect()
+    }
+
+    fn curves(&self) -> BTreeMap<Stage, Vec<(f64, u64)>> {
+        self.stages
+            .iter()
+            .map(|(&stage, h)| {
+                let curve = (0..CURVE_POINTS)
+                    .map(|i| {
+                        let nines = CURVE_NINES * i as f64 / (CURVE_POINTS - 1) as f64;
+                        let quantile = 1.0 - 10f64.powf(-nines);
+                        (quantile * 100.0, h.value_at_quantile(quantile))
+                    })
+                    .collect();
+                (stage, curve)
+            })
+            .collect()
+    }
+
+    fn summary(&self) -> Vec<StageSummary> {
+        self.stages
+            .iter()
+            .map(|(&stage, h)| StageSummary {
+                stage,
+                count: h.len(),
+                min: h.min(),
+                mean: h.mean(),
+                p50: h.value_at_quantile(0.5),
+                p90: h.value_at_quantile(0.9),
+                p99: h.value_at_quantile(0.99),
+                p999: h.value_at_quantile(0.999),
+                max: h.max(),
+            })
+            .collect()
+    }
+}
+
+/// Hands every sample to the running statistics, the Parquet exporter and
+/// live subscribers.
+#[derive(Clone)]
+pub struct LatencyRecorder {
+    stats: Arc<Mutex<LatencyStats>>,
+    exporter: Arc<Mutex<ParquetExporter>>,
+    tx: broadcast::Sender<LatencySample>,
+}
+
+impl LatencyRecorder {
+    pub fn new(exporter: Arc<Mutex<ParquetExporter>>) -> Self {
+        LatencyRecorder {
+            stats: Arc::new(Mutex::new(LatencyStats::default())),
+            exporter,
+            tx: broadcast::channel(BUS_CAPACITY).0,
+        }
+    }
+
+    pub fn record(&self, stage: Stage, stock_id: Option<i32>, elapsed: Duration) {
+        let sample = LatencySample {
+            stage,
+            stock_id,
+            at: SystemTime::now(),
+            nanos: elapsed.as_nanos() as u64,
+        };
+        self.stats.lock().unwrap().record(stage, stock_id, sample.nanos);
+        let _ = self.tx.send(sample.clone());
+        self.exporter.lock().unwrap().record_latency(sample);
+    }
+
+    pub fn summary(&self) -> Vec<StageSummary> {
+        self.stats.lock().unwrap().summary()
+    }
+
+    /// Each stage's running statistics followed by those of `stock_ids` at
+    /// that stage, skipping symbols without samples there.
+    pub fn running(&self, stock_ids: &[usize]) -> Vec<(Stage, Option<i32>, RunningStats)> {
+        stats::rows(&self.stats.lock().unwrap().running, stock_ids)
+    }
+
+    /// Zeroes the running statistics; the histograms keep everything.
+    pub fn reset_running(&self) {
+        self.stats.lock().unwrap().running.clear();
+    }
+
+    /// `(percentile, nanos)` points per stage from p0 to p99.99, denser
+    /// towards the tail.
+    pub fn curves(&self) -> BTreeMap<Stage, Vec<(f64, u64)>> {
+        self.stats.lock().unwrap().curves()
+    }
+
+    /// `(nanos, count)` pairs per stage, enough to rebuild the histograms.
+    pub fn counts(&self) -> BTreeMap<Stage, Vec<(u64, u64)>> {
+        self.stats.lock().unwrap().counts()
+    }
+
+    /// Adds previously saved [`counts`](Self::counts) to the histograms.
+    pub fn restore_counts(&self, counts: &BTreeMap<Stage, Vec<(u64, u64)>>) {
+        let mut stats = self.stats.lock().unwrap();
+        for (&stage, values) in counts {
+            for &(nanos, count) in values {
+                stats.record_n(stage, nanos, count);
+            }
+        }
+    }
+
+    pub fn subscribe(&self) -> LatencyReceiver {
+        self.tx.subscribe()
+    }
+}
@@ -0,0 +1,87 @@
+//! JSON-lines logs in rotating files under `[logging] dir`. Every event
+//! carries the fields of the spans it was emitted in, such as the pipeline
+//! `stage` and the `symbol`; records from dependencies logging through the
+//! `log` crate are forwarded as events too. The filter can be swapped while
+//! running, from the TUI or over HTTP.
+
+use std::env;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use tracing::info;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+use crate::config::{LogRotation, LoggingConfig};
+
+/// Levels `L` steps through after the startup filter.
+const CYCLE: [&str; 2] = ["debug", "trace"];
+
+/// The live filter, in `RUST_LOG` syntax.
+#[derive(Clone)]
+pub struct LogFilter {
+    handle: reload::Handle<EnvFilter, Registry>,
+    initial: String,
+    current: Arc<Mutex<String>>,
+}
+
+impl LogFilter {
+    pub fn get(&self) -> String {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Replaces the filter, e.g. with `info,hft_latency::spool=trace`.
+    pub fn set(&self, directives: &str) -> io::Result<()> {
+        let filter = parse(directives)?;
+        self.handle.reload(filter).map_err(io::Error::other)?;
+        *self.current.lock().unwrap() = directives.to_string();
+        info!("Log filter set to {}", directives);
+        Ok(())
+    }
+
+    /// Startup filter, then each level of [`CYCLE`], then back.
+    pub fn cycle(&self) -> io::Result<()> {
+        let current = self.get();
+        let next = match CYCLE.iter().position(|&level| level == current) {
+            Some(i) if i + 1 < CYCLE.len() => CYCLE[i + 1].to_string(),
+            Some(_) => self.initial.clone(),
+            None => CYCLE[0].to_string(),
+        };
+        self.set(&next)
+    }
+}
+
+fn parse(directives: &str) -> io::Result<EnvFilter> {
+    EnvFilter::try_new(directives)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("log filter {:?}: {}", directives, e)))
+}
+
+/// Lines still buffered are written when the returned guard is dropped.
+pub fn init(cfg: &LoggingConfig) -> io::Result<(WorkerGuard, LogFilter)> {
+    let rotation = match cfg.rotation {
+        LogRotation::Minutely => Rotation::MINUTELY,
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+    let mut builder = RollingFileAppender::builder().rotation(rotation).filename_prefix(&cfg.file);
+    if let Some(max_files) = cfg.max_files {
+        builder = builder.max_log_files(max_files);
+    }
+    let appender = builder
+        .build(&cfg.dir)
+        .map_err(|e| io::Error::other(format!("[logging] cannot write to {}: {}", cfg.dir, e)))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    let initial = env::var("RUST_LOG").unwrap_or_else(|_| cfg.filter.clone());
+    let (filter, handle) = reload::Layer::new(parse(&initial)?);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().json().with_current_span(true).with_span_list(true).with_writer(writer))
+        .try_init()
+        .map_err(io::Error::other)?;
+    Ok((guard, LogFilter { handle, current: Arc::new(Mutex::new(initial.clone())), initial }))
+}
@@ -0,0 +1,80 @@
+//! Largest-triangle-three-buckets downsampling (Steinarsson, 2013) of chart
+//! points. The first and last points stay; the rest are cut into equal
+//! buckets and from each the point kept is the one spanning the largest
+//! triangle with the point kept before it and the average of the next
+//! bucket, so spikes and turns survive where plain decimation would drop
+//! them.
+
+/// At most `threshold` of `points`, which are in x order. Fewer points than
+/// that, or a threshold below 3, leave them as they are.
+pub fn downsample(points: &[(f64, f64)], threshold: usize) -> Vec<(f64, f64)> {
+    if threshold < 3 || points.len() <= threshold {
+        return points.to_vec();
+    }
+    let last = points.len() - 1;
+    // Buckets between the first and last point.
+    let every = (points.len() - 2) as f64 / (threshold - 2) as f64;
+    let bucket = |i: usize| {
+        let start = (i as f64 * every) as usize + 1;
+        let end = (((i + 1) as f64 * every) as usize + 1).min(last);
+        &points[start..end.max(start + 1)]
+    };
+    let mut kept = Vec::with_capacity(threshold);
+    kept.push(points[0]);
+    let mut a = points[0];
+    for i in 0..threshold - 2 {
+        let next = if i + 1 < threshold - 2 { bucket(i + 1) } else { &points[last..] };
+        let n = next.len() as f64;
+        let (cx, cy) = next.iter().fold((0.0, 0.0), |(x, y), p| (x + p.0 / n, y + p.1 / n));
+        let area = |b: &(f64, f64)| ((a.0 - cx) * (b.1 - a.1) - (a.0 - b.0) * (cy - a.1)).abs();
+        let chosen = bucket(i).iter().copied().max_by(|p, q| area(p).total_cmp(&area(q))).unwrap_or(a);
+        kept.push(chosen);
+        a = chosen;
+    }
+    kept.push(points[last]);
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wave(n: usize) -> Vec<(f64, f64)> {
+        (0..n).map(|i| (i as f64, (i as f64 / 7.0).sin())).collect()
+    }
+
+    #[test]
+    fn keeps_the_first_and_last_points() {
+        let points = wave(1_000);
+        let kept = downsample(&points, 50);
+        assert_eq!(kept.first(), points.first());
+        assert_eq!(kept.last(), points.last());
+    }
+
+    #[test]
+    fn keeps_as_many_points_as_the_threshold() {
+        let points = wave(1_000);
+        for threshold in [3, 4, 10, 99, 500, 999] {
+            let kept = downsample(&points, threshold);
+            assert_eq!(kept.len(), threshold);
+            assert!(kept.windows(2).all(|w| w[0].0 < w[1].0), "out of order at threshold {}", threshold);
+        }
+    }
+
+    #[test]
+    fn leaves_short_series_and_small_thresholds_alone() {
+        let points = wave(100);
+        assert_eq!(downsample(&points, 100), points);
+        assert_eq!(downsample(&points, 500), points);
+        assert_eq!(downsample(&points, 2), points);
+        assert_eq!(downsample(&[], 10), vec![]);
+    }
+
+    #[test]
+    fn a_single_spike_survives() {
+        let mut points: Vec<(f64, f64)> = (0..1_000).map(|i| (i as f64, 1.0)).collect();
+        points[617].1 = 40.0;
+        let kept = downsample(&points, 20);
+        assert!(kept.contains(&(617.0, 40.0)));
+    }
+}
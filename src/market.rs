@@ -0,0 +1,133 @@
+use std::collections::{vec_deque, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::price::Price;
+
+#[derive(Clone)]
+pub struct MarketData {
+    pub count: usize,
+    pub price: Arc<RwLock<Price>>,
+    pub last_update: Instant,
+    pub history: Ring<f64>,
+    /// When the last points of `history` were recorded, lined up with its
+    /// end; shorter while it still holds padding.
+    pub times: Ring<SystemTime>,
+    /// Sequence number of the last tick applied.
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+pub struct UiData {
+    pub count: usize,
+    pub value: Arc<f64>,
+    pub last_update: Instant,
+    pub history: Ring<f64>,
+    /// As in [`MarketData`].
+    pub times: Ring<SystemTime>,
+}
+
+impl MarketData {
+    /// Sets the price and appends it to the chart history with the tick's
+    /// time. Returns the tick's sequence number.
+    pub fn update(&mut self, price: Price, ts: SystemTime) -> u64 {
+        *self.price.write().unwrap() = price;
+        self.last_update = Instant::now();
+        self.history.push(price.to_f64());
+        self.times.push(ts);
+        self.seq += 1;
+        self.seq
+    }
+
+    pub fn points(&self) -> Vec<(f64, f64)> {
+        timed(&self.history, &self.times)
+    }
+}
+
+impl UiData {
+    /// Appends a point recorded at `at`.
+    pub fn record(&mut self, value: f64, at: SystemTime) {
+        self.value = Arc::new(value);
+        self.last_update = Instant::now();
+        self.history.push(value);
+        self.times.push(at);
+    }
+
+    pub fn points(&self) -> Vec<(f64, f64)> {
+        timed(&self.history, &self.times)
+    }
+}
+
+/// A chart history holding at most its capacity: pushing onto a full one
+/// drops the oldest item instead of shifting the rest. The capacity is the
+/// same for a symbol's points and their times, so both stay lined up with
+/// their ends.
+#[derive(Clone)]
+pub struct Ring<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T: Copy> Ring<T> {
+    /// The last `capacity` of `items`, at least one.
+    pub fn new(capacity: usize, items: impl IntoIterator<Item = T>) -> Self {
+        let mut ring = Ring { items: VecDeque::with_capacity(capacity.max(1)), capacity: capacity.max(1) };
+        ring.replace(items);
+        ring
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.items.len() == self.capacity {
+            self.items.pop_front();
+        }
+        self.items.push_back(item);
+    }
+
+    /// Swaps the items for the last `capacity` of `items`.
+    pub fn replace(&mut self, items: impl IntoIterator<Item = T>) {
+        self.items.clear();
+        for item in items {
+            self.push(item);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn first(&self) -> Option<T> {
+        self.items.front().copied()
+    }
+
+    /// Oldest first.
+    pub fn iter(&self) -> vec_deque::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// The items oldest first, as the two runs they are stored in; the
+    /// second is empty until the buffer wraps.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        self.items.as_slices()
+    }
+
+    pub fn to_vec(&self) -> Vec<T> {
+        self.items.iter().copied().collect()
+    }
+}
+
+/// Chart x of a point recorded at `at`: Unix seconds.
+pub fn chart_x(at: SystemTime) -> f64 {
+    at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64()
+}
+
+/// The points of `history` that have a time, at it.
+fn timed(history: &Ring<f64>, times: &Ring<SystemTime>) -> Vec<(f64, f64)> {
+    let (old, new) = history.as_slices();
+    let values = old.iter().chain(new).skip(history.len().saturating_sub(times.len()));
+    let (old, new) = times.as_slices();
+    let at = old.iter().chain(new).skip(times.len().saturating_sub(history.len()));
+    values.zip(at).map(|(&y, &at)| (chart_x(at), y)).collect()
+}
+
+pub type SharedMarketData = Arc<RwLock<Vec<MarketData>>>;
+pub type SharedUiData = Arc<RwLock<Vec<UiData>>>;
@@ -0,0 +1,144 @@
+//! Socket helpers shared by the multicast publisher and receiver.
+
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::UdpSocket;
+
+pub fn parse_multicast_group(group: &str) -> io::Result<SocketAddrV4> {
+    let addr: SocketAddrV4 = group
+        .parse()
+        .map_err(|_| invalid(format!("multicast group `{}` must be an IPv4 `addr:port`", group)))?;
+    if !addr.ip().is_multicast() {
+        return Err(invalid(format!("`{}` is not a multicast address", addr.ip())));
+    }
+    Ok(addr)
+}
+
+/// `None` leaves the choice of interface to the OS.
+pub fn parse_interface(interface: Option<&str>) -> io::Result<Ipv4Addr> {
+    interface.map_or(Ok(Ipv4Addr::UNSPECIFIED), |s| {
+        s.parse().map_err(|_| invalid(format!("invalid interface address `{}`", s)))
+    })
+}
+
+/// A socket joined to `group` on `interface`, bound to the group's port.
+pub fn join_multicast(group: &str, interface: Option<&str>) -> io::Result<UdpSocket> {
+    let group = parse_multicast_group(group)?;
+    let interface = parse_interface(interface)?;
+
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    // Lets several receivers on one host share the port.
+    socket.set_reuse_address(true)?;
+    socket.bind(&SocketAddr::from(([0, 0, 0, 0], group.port())).into())?;
+    socket.join_multicast_v4(group.ip(), &interface)?;
+    socket.set_nonblocking(true)?;
+    UdpSocket::from_std(socket.into())
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+/// When the kernel saw a datagram arrive.
+#[derive(Clone, Copy, Debug)]
+pub struct RxTimestamp {
+    pub unix_nanos: u64,
+    /// Stamped by the NIC rather than the network stack.
+    pub hardware: bool,
+}
+
+/// Asks the kernel to timestamp received datagrams (`SO_TIMESTAMPING`).
+/// Hardware stamps additionally need the NIC configured, e.g. with
+/// `hwstamp_ctl -i eth0 -r 1`; software stamps are used otherwise.
+#[cfg(target_os = "linux")]
+pub fn enable_rx_timestamps(socket: &UdpSocket) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let flags: libc::c_uint = libc::SOF_TIMESTAMPING_RX_SOFTWARE
+        | libc::SOF_TIMESTAMPING_RX_HARDWARE
+        | libc::SOF_TIMESTAMPING_SOFTWARE
+        | libc::SOF_TIMESTAMPING_RAW_HARDWARE;
+    // SAFETY: `flags` outlives the call and its size is passed alongside.
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_TIMESTAMPING,
+            &flags as *const libc::c_uint as *const libc::c_void,
+            std::mem::size_of::<libc::c_uint>() as libc::socklen_t,
+        )
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn enable_rx_timestamps(_socket: &UdpSocket) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "kernel RX timestamps need Linux"))
+}
+
+/// Receives one datagram along with its kernel timestamp, if the socket has
+/// them enabled and the kernel supplied one.
+#[cfg(target_os = "linux")]
+pub async fn recv_timestamped(socket: &UdpSocket, buf: &mut [u8]) -> io::Result<(usize, Option<RxTimestamp>)> {
+    use std::os::fd::AsRawFd;
+
+    loop {
+        socket.readable().await?;
+        match socket.try_io(tokio::io::Interest::READABLE, || recvmsg_timestamped(socket.as_raw_fd(), buf)) {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+            result => return result,
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub async fn recv_timestamped(socket: &UdpSocket, buf: &mut [u8]) -> io::Result<(usize, Option<RxTimestamp>)> {
+    Ok((socket.recv(buf).await?, None))
+}
+
+#[cfg(target_os = "linux")]
+fn recvmsg_timestamped(fd: std::os::fd::RawFd, buf: &mut [u8]) -> io::Result<(usize, Option<RxTimestamp>)> {
+    // Room for one SCM_TIMESTAMPING message: three timespecs.
+    let mut control = [0u64; 16];
+    let mut iov = libc::iovec { iov_base: buf.as_mut_ptr() as *mut libc::c_void, iov_len: buf.len() };
+    // SAFETY: all-zero is a valid msghdr.
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = std::mem::size_of_val(&control) as _;
+
+    // SAFETY: `msg` points at `iov`, `buf` and `control`, all live for the call.
+    let len = unsafe { libc::recvmsg(fd, &mut msg, 0) };
+    if len < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut stamp = None;
+    // SAFETY: the CMSG macros walk the control buffer the kernel just filled.
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_TIMESTAMPING {
+                let ts = std::ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const [libc::timespec; 3]);
+                // [0] is the software stamp, [2] the raw hardware one.
+                let to_nanos = |t: &libc::timespec| t.tv_sec as u64 * 1_000_000_000 + t.tv_nsec as u64;
+                stamp = if ts[2].tv_sec != 0 {
+                    Some(RxTimestamp { unix_nanos: to_nanos(&ts[2]), hardware: true })
+                } else if ts[0].tv_sec != 0 {
+                    Some(RxTimestamp { unix_nanos: to_nanos(&ts[0]), hardware: false })
+                } else {
+                    None
+                };
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+    Ok((len as usize, stamp))
+}
@@ -0,0 +1,115 @@
+//! Paces the producer against absolute per-symbol deadlines, so time spent
+//! producing ticks does not stretch the intervals and liquid and illiquid
+//! symbols update at their own rates.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::{PacingMode, ProducerConfig};
+
+/// Multiplier of the tick rate, `:speed` in the TUI.
+#[derive(Clone)]
+pub struct Speed(Arc<AtomicU64>);
+
+impl Speed {
+    pub fn set(&self, speed: f64) {
+        self.0.store(speed.to_bits(), Ordering::Relaxed);
+    }
+
+    fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+pub struct Pacer {
+    mode: PacingMode,
+    spin_threshold: Duration,
+    /// Per stock id.
+    intervals: Vec<Duration>,
+    /// Next deadline of each stock id, earliest first.
+    schedule: BinaryHeap<Reverse<(Instant, usize)>>,
+    speed: Speed,
+}
+
+impl Pacer {
+    /// One schedule per symbol, every one of them due right away.
+    pub fn new(cfg: &ProducerConfig, intervals: Vec<Duration>) -> Self {
+        let now = Instant::now();
+        Pacer {
+            mode: cfg.pacing,
+            spin_threshold: Duration::from_micros(cfg.spin_threshold_us),
+            schedule: (0..intervals.len()).map(|id| Reverse((now, id))).collect(),
+            intervals,
+            speed: Speed(Arc::new(AtomicU64::new(1f64.to_bits()))),
+        }
+    }
+
+    /// Changes the intervals from the next deadline of each symbol.
+    pub fn speed(&self) -> Speed {
+        self.speed.clone()
+    }
+
+    /// Blocks until the earliest deadline, then replaces `due` with every
+    /// stock id whose deadline has passed and schedules each for its next
+    /// interval. A symbol that fell more than a whole interval behind
+    /// restarts from now instead of bursting.
+    pub fn wait(&mut self, due: &mut Vec<usize>) {
+        due.clear();
+        let Some(&Reverse((deadline, _))) = self.schedule.peek() else { return };
+        match self.mode {
+            PacingMode::Sleep => sleep_until(deadline),
+            PacingMode::Hybrid => {
+                if let Some(early) = deadline.checked_sub(self.spin_threshold) {
+                    sleep_until(early);
+                }
+                spin_until(deadline);
+            }
+            PacingMode::Spin => spin_until(deadline),
+        }
+        let now = Instant::now();
+        let speed = self.speed.get();
+        while let Some(&Reverse((deadline, id))) = self.schedule.peek() {
+            if deadline > now {
+                break;
+            }
+            self.schedule.pop();
+            let interval = self.intervals[id].div_f64(speed);
+            let next = if now > deadline + interval { now + interval } else { deadline + interval };
+            self.schedule.push(Reverse((next, id)));
+            due.push(id);
+        }
+        due.sort_unstable();
+    }
+
+    pub fn describe(&self) -> String {
+        let mode = match self.mode {
+            PacingMode::Sleep => "sleep",
+            PacingMode::Hybrid => "hybrid",
+            PacingMode::Spin => "spin",
+        };
+        let fastest = self.intervals.iter().min().copied().unwrap_or_default();
+        let slowest = self.intervals.iter().max().copied().unwrap_or_default();
+        if fastest == slowest {
+            format!("{} every {:?}", mode, fastest)
+        } else {
+            format!("{} every {:?} to {:?} per symbol", mode, fastest, slowest)
+        }
+    }
+}
+
+fn sleep_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now {
+        thread::sleep(deadline - now);
+    }
+}
+
+fn spin_until(deadline: Instant) {
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+}
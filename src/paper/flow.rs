@@ -0,0 +1,117 @@
+//! Order-flow imbalance from the venues' book updates. Each time a venue
+//! reports its displayed depth, the change at its best bid and ask is signed
+//! as buying or selling pressure, as in Cont, Kukanov and Stoikov's OFI: size
+//! joining the bid or leaving the ask counts up, size leaving the bid or
+//! joining the ask counts down. Trades count as the size they take off the
+//! touch. Each symbol's imbalance is the sum over the last window of its
+//! updates, across venues.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::symbols::Symbol;
+
+/// Symbols named on the Order flow line.
+const SHOWN: usize = 3;
+
+/// A venue's best bid and ask with the size displayed at each.
+#[derive(Clone, Copy)]
+struct Touch {
+    bid: (i64, u32),
+    ask: (i64, u32),
+}
+
+impl Touch {
+    /// Signed shares of pressure from `before` to `self`.
+    fn flow(self, before: Touch) -> i64 {
+        let (bid, ask) = ((self.bid.0, self.bid.1 as i64), (self.ask.0, self.ask.1 as i64));
+        let (prev_bid, prev_ask) = ((before.bid.0, before.bid.1 as i64), (before.ask.0, before.ask.1 as i64));
+        let mut flow = 0;
+        if bid.0 >= prev_bid.0 {
+            flow += bid.1;
+        }
+        if bid.0 <= prev_bid.0 {
+            flow -= prev_bid.1;
+        }
+        if ask.0 <= prev_ask.0 {
+            flow -= ask.1;
+        }
+        if ask.0 >= prev_ask.0 {
+            flow += prev_ask.1;
+        }
+        flow
+    }
+}
+
+#[derive(Default)]
+struct Window {
+    /// Each update's flow, oldest first, with when it came in.
+    updates: VecDeque<(Duration, i64)>,
+    sum: i64,
+}
+
+#[derive(Default)]
+struct Inner {
+    touches: HashMap<(i32, usize), Touch>,
+    windows: BTreeMap<i32, Window>,
+}
+
+#[derive(Clone)]
+pub struct OrderFlow {
+    window: Duration,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl OrderFlow {
+    pub fn new(window: Duration) -> Self {
+        OrderFlow { window, inner: Arc::new(Mutex::new(Inner::default())) }
+    }
+
+    /// A venue's depth as it reported it at `now`, in run time; ignored
+    /// while either side is empty.
+    pub fn quoted(&self, venue: usize, stock_id: i32, bids: &[(i64, u32)], asks: &[(i64, u32)], now: Duration) {
+        let (Some(&bid), Some(&ask)) = (bids.first(), asks.first()) else { return };
+        let touch = Touch { bid, ask };
+        let mut inner = self.inner.lock().unwrap();
+        let Some(before) = inner.touches.insert((stock_id, venue), touch) else { return };
+        let window = inner.windows.entry(stock_id).or_default();
+        let flow = touch.flow(before);
+        window.updates.push_back((now, flow));
+        window.sum += flow;
+        while let Some(&(at, flow)) = window.updates.front() {
+            if now.saturating_sub(at) <= self.window {
+                break;
+            }
+            window.sum -= flow;
+            window.updates.pop_front();
+        }
+    }
+
+    /// Net shares of buying pressure on `stock_id` over the window; none
+    /// before its second book update.
+    pub fn imbalance(&self, stock_id: i32) -> Option<i64> {
+        self.inner.lock().unwrap().windows.get(&stock_id).map(|w| w.sum)
+    }
+
+    /// `over 1000ms: AAPL +340 (12 updates), MSFT -120 (9 updates), 1 more`
+    pub fn describe(&self, symbols: &[Symbol]) -> String {
+        let inner = self.inner.lock().unwrap();
+        if inner.windows.is_empty() {
+            return "no book updates yet".to_string();
+        }
+        let mut parts: Vec<String> = inner
+            .windows
+            .iter()
+            .take(SHOWN)
+            .map(|(&id, w)| {
+                let ticker = usize::try_from(id).ok().and_then(|id| symbols.get(id)).map_or("?", |s| &s.ticker);
+                format!("{} {:+} ({} updates)", ticker, w.sum, w.updates.len())
+            })
+            .collect();
+        if inner.windows.len() > SHOWN {
+            parts.push(format!("{} more", inner.windows.len() - SHOWN));
+        }
+        format!("over {}ms: {}", self.window.as_millis(), parts.join(", "))
+    }
+}
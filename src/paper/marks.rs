@@ -0,0 +1,167 @@
+//! Microprice and size-weighted mid of each symbol, from the depth the
+//! venues display, consolidated across venues. Both lean from the mid toward
+//! the side more likely to trade next: the microprice weighs the best bid
+//! and ask by the size at the other side of the touch, the weighted mid does
+//! the same with all the displayed depth on each side. Each symbol keeps
+//! them for its last ticks for the chart overlays, and every second they go
+//! to the `microprices` table.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use tracing::warn;
+
+use crate::price::Price;
+use crate::symbols::Symbol;
+use crate::HISTORY_LEN;
+
+const PERSIST_EVERY: Duration = Duration::from_secs(1);
+/// Symbols named on the Microprice line.
+const SHOWN: usize = 3;
+
+/// One venue's displayed depth, best level first.
+struct Depth {
+    bids: Vec<(i64, u32)>,
+    asks: Vec<(i64, u32)>,
+}
+
+#[derive(Clone, Copy)]
+pub struct Mark {
+    pub bid: f64,
+    pub ask: f64,
+    pub microprice: f64,
+    pub weighted_mid: f64,
+}
+
+impl Mark {
+    /// None unless both sides show something; `tick` is the symbol's tick
+    /// size, what the engine's prices count.
+    fn of(tick: Price, venues: &[&Depth]) -> Option<Mark> {
+        let bid = venues.iter().filter_map(|d| d.bids.first()).map(|&(price, _)| price).max()?;
+        let ask = venues.iter().filter_map(|d| d.asks.first()).map(|&(price, _)| price).min()?;
+        let at = |levels: &[(i64, u32)], price: i64| {
+            levels.iter().filter(|&&(p, _)| p == price).map(|&(_, qty)| qty as f64).sum::<f64>()
+        };
+        let bid_qty: f64 = venues.iter().map(|d| at(&d.bids, bid)).sum();
+        let ask_qty: f64 = venues.iter().map(|d| at(&d.asks, ask)).sum();
+        let side = |levels: &[(i64, u32)]| levels.iter().map(|&(_, qty)| qty as f64).sum::<f64>();
+        let bid_depth: f64 = venues.iter().map(|d| side(&d.bids)).sum();
+        let ask_depth: f64 = venues.iter().map(|d| side(&d.asks)).sum();
+        let (bid, ask) = ((tick * bid).to_f64(), (tick * ask).to_f64());
+        // Weight on the ask: how much of the size is bidding.
+        let lean = |bids: f64, asks: f64| bid + (ask - bid) * bids / (bids + asks).max(f64::MIN_POSITIVE);
+        Some(Mark { bid, ask, microprice: lean(bid_qty, ask_qty), weighted_mid: lean(bid_depth, ask_depth) })
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    /// By symbol and venue.
+    depth: HashMap<(i32, usize), Depth>,
+    marks: BTreeMap<i32, Mark>,
+    /// By symbol, the mark at each of its last ticks.
+    history: HashMap<i32, VecDeque<Option<Mark>>>,
+    /// Ticks kept in each history; none keeps [`HISTORY_LEN`].
+    keep: Option<usize>,
+}
+
+#[derive(Clone, Default)]
+pub struct Marks {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Marks {
+    /// A venue's depth as it reported it, in ticks of `tick`.
+    pub fn quoted(&self, venue: usize, stock_id: i32, tick: Price, bids: &[(i64, u32)], asks: &[(i64, u32)]) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.depth.insert((stock_id, venue), Depth { bids: bids.to_vec(), asks: asks.to_vec() });
+        let venues: Vec<_> = inner.depth.iter().filter(|((id, _), _)| *id == stock_id).map(|(_, d)| d).collect();
+        match Mark::of(tick, &venues) {
+            Some(mark) => inner.marks.insert(stock_id, mark),
+            None => inner.marks.remove(&stock_id),
+        };
+    }
+
+    /// Keeps the marks at the last `ticks` ticks of each symbol, as many as
+    /// its chart shows.
+    pub fn keep(&self, ticks: usize) {
+        self.inner.lock().unwrap().keep = Some(ticks);
+    }
+
+    /// A tick of `stock_id`: keeps its current mark for the overlays.
+    pub fn tick(&self, stock_id: i32) {
+        let mut inner = self.inner.lock().unwrap();
+        let mark = inner.marks.get(&stock_id).copied();
+        let keep = inner.keep.unwrap_or(HISTORY_LEN);
+        let history = inner.history.entry(stock_id).or_default();
+        history.push_back(mark);
+        if history.len() > keep {
+            history.pop_front();
+        }
+    }
+
+    pub fn mark(&self, stock_id: i32) -> Option<Mark> {
+        self.inner.lock().unwrap().marks.get(&stock_id).copied()
+    }
+
+    /// The marks at the symbol's last ticks, oldest first; none where
+    /// either side was empty.
+    pub fn history(&self, stock_id: i32) -> Vec<Option<Mark>> {
+        self.inner.lock().unwrap().history.get(&stock_id).map_or(Vec::new(), |h| h.iter().copied().collect())
+    }
+
+    /// `AAPL 187.20 x 187.22 micro 187.214 wmid 187.209, MSFT ..., 1 more`
+    pub fn describe(&self, symbols: &[Symbol]) -> String {
+        let inner = self.inner.lock().unwrap();
+        if inner.marks.is_empty() {
+            return "no two-sided book yet".to_string();
+        }
+        let mut parts: Vec<String> = inner
+            .marks
+            .iter()
+            .take(SHOWN)
+            .map(|(&id, m)| {
+                let ticker = usize::try_from(id).ok().and_then(|id| symbols.get(id)).map_or("?", |s| &s.ticker);
+                format!("{} {:.2} x {:.2} micro {:.3} wmid {:.3}", ticker, m.bid, m.ask, m.microprice, m.weighted_mid)
+            })
+            .collect();
+        if inner.marks.len() > SHOWN {
+            parts.push(format!("{} more", inner.marks.len() - SHOWN));
+        }
+        parts.join(", ")
+    }
+
+    /// Writes every symbol's current mark to `microprices` once a second.
+    pub async fn persist(self, pool: Arc<PgPool>) {
+        let mut interval = tokio::time::interval(PERSIST_EVERY);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.store(&pool).await {
+                warn!("Storing microprices failed: {}", e);
+            }
+        }
+    }
+
+    async fn store(&self, pool: &PgPool) -> Result<(), sqlx::Error> {
+        let marks: Vec<(i32, Mark)> = self.inner.lock().unwrap().marks.iter().map(|(&id, &m)| (id, m)).collect();
+        let price = |value: f64| Decimal::from_f64_retain(value).unwrap_or_default().round_dp(6);
+        let mut tx = pool.begin().await?;
+        for (stock_id, m) in marks {
+            sqlx::query(
+                "INSERT INTO microprices (stock_id, bid, ask, microprice, weighted_mid, recorded_at) \
+                 VALUES ($1, $2, $3, $4, $5, NOW())",
+            )
+            .bind(stock_id)
+            .bind(price(m.bid))
+            .bind(price(m.ask))
+            .bind(price(m.microprice))
+            .bind(price(m.weighted_mid))
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+}
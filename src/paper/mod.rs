@@ -0,0 +1,719 @@
+//! Paper orders against the live ticks, enabled by the `[paper]` config
+//! section. A market maker in the [matching engine](crate::engine) keeps a
+//! ladder of quotes around every tick and a taker trades at the touch, and a
+//! [strategy](crate::strategy) trades against them: its resting orders fill
+//! once takers work through the queue ahead of them or the price moves
+//! through them. Each order is timestamped as it moves from new to acked to
+//! filled; time-to-ack and time-to-fill are recorded as the `order_ack` and
+//! `order_fill` latency stages, so they reach the panels and the report like
+//! any other stage. [Risk limits](crate::risk) watch the portfolio after
+//! every tick and fill, and with [impact](crate::impact) on the strategy's
+//! fills move the mid the book is quoted around. With several
+//! [venues](venue) the same symbols trade on each, at their own distance
+//! from the strategy, and the Venues line shows what each one's fills were
+//! worth; the [router] splits orders across them. Their book updates feed
+//! the [order-flow imbalance](flow) and the [microprice](marks).
+
+mod flow;
+mod marks;
+mod router;
+mod venue;
+
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::{debug, error, info, warn};
+
+use crate::config::{PaperConfig, VenueConfig};
+use crate::costs::Costs;
+use crate::engine::{Order, Side, TimeInForce};
+use crate::impact::Impact;
+use crate::inject::Sampler;
+use crate::latency::{LatencyRecorder, Stage};
+use crate::portfolio::Portfolio;
+use crate::price::Price;
+use crate::risk::Risk;
+use crate::script::ScriptStats;
+use crate::strategy::{self, Action, Actions, MarketTick, OrderFill, Strategy, VenueQuote};
+use crate::symbols::Symbol;
+use crate::tick::TickReceiver;
+use crate::timing::SharedClock;
+pub use flow::OrderFlow;
+pub use marks::{Mark, Marks};
+pub use router::Router;
+use venue::{Link, Maker, Report, Request, Venue, HALF_SPREAD};
+
+/// How far through the mid a flattening IOC order may trade, in engine
+/// price ticks; past any ladder the maker keeps, so it takes what it needs.
+const FLATTEN_REACH: i64 = 1_000;
+/// Ids of a routed order's children count up from here, clear of the
+/// strategy's own ids and the venues' maker ids.
+const CHILD_ID_BASE: u64 = u64::MAX / 4;
+
+/// Where an open order is in its life; filled and cancelled orders are
+/// forgotten.
+#[derive(PartialEq)]
+enum State {
+    New,
+    Acked,
+    PartiallyFilled,
+}
+
+struct Lifecycle {
+    venue: usize,
+    /// The routed order this is a child of.
+    parent: Option<u64>,
+    stock_id: i32,
+    side: Side,
+    state: State,
+    /// When the strategy sent it, on the stage clock.
+    sent: u64,
+    /// And in the run's time, recorded time in a backtest.
+    placed: Duration,
+    left: u32,
+}
+
+#[derive(Default)]
+struct Counters {
+    sent: AtomicU64,
+    acked: AtomicU64,
+    filled: AtomicU64,
+    cancelled: AtomicU64,
+    /// Summed over acks.
+    queue_ahead: AtomicU64,
+}
+
+#[derive(Default)]
+struct Tally {
+    fills: u64,
+    shares: u64,
+    /// Against the mid the strategy saw when the fill came back, less fees, in price units.
+    edge: f64,
+    fees: f64,
+    /// By symbol, the shares resting in the book and how many of them it
+    /// displays, as last quoted.
+    book: HashMap<i32, (u32, u32)>,
+}
+
+/// What one venue's fills were worth.
+struct VenueStats {
+    name: String,
+    link: Link,
+    tally: Mutex<Tally>,
+}
+
+/// `[[paper.venues]]`, or a single venue next to the strategy.
+fn venues(cfg: &PaperConfig) -> Vec<VenueConfig> {
+    if !cfg.venues.is_empty() {
+        return cfg.venues.clone();
+    }
+    vec![VenueConfig { name: "paper".to_string(), latency_us: 0, quote_lag_us: 0, fees: None }]
+}
+
+/// An order the router split, while any of its children is open.
+struct Routed {
+    qty: u32,
+    left: u32,
+    children: usize,
+}
+
+/// The last mid of a symbol and each venue's touch as last reported.
+struct Book {
+    mid: i64,
+    venues: Vec<VenueQuote>,
+}
+
+#[derive(Clone)]
+pub struct PaperOrders {
+    counters: Arc<Counters>,
+    strategy: &'static str,
+    processing: Arc<String>,
+    script: Option<ScriptStats>,
+    portfolio: Portfolio,
+    risk: Risk,
+    costs: Costs,
+    impact: Arc<String>,
+    venues: Arc<Vec<VenueStats>>,
+    router: Router,
+    flow: OrderFlow,
+    marks: Marks,
+}
+
+impl PaperOrders {
+    /// Starts the venues and the strategy on `ticks` of `symbols`; fills go
+    /// to `portfolio`.
+    pub fn spawn(
+        cfg: PaperConfig,
+        symbols: &[Symbol],
+        portfolio: Portfolio,
+        ticks: TickReceiver,
+        latency: LatencyRecorder,
+        timer: SharedClock,
+    ) -> io::Result<Self> {
+        let sampler = || {
+            cfg.processing
+                .as_ref()
+                .map(Sampler::new)
+                .transpose()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("paper.processing: {}", e)))
+        };
+        let described = sampler()?.map_or("instant".to_string(), |p| p.describe().to_string());
+        let strategy = strategy::build(&cfg)?;
+        info!("Paper trading with the {} strategy", strategy.name());
+        let name = strategy.name();
+        let script = strategy.script_stats();
+        let (report_tx, report_rx) = mpsc::unbounded_channel();
+        let mut requests = Vec::new();
+        for (i, v) in venues(&cfg).iter().enumerate() {
+            let clock = SharedClock::clone(&timer);
+            requests.push(venue::spawn(i, Link::new(v), Maker::new(&cfg), sampler()?, clock, report_tx.clone()));
+        }
+        let timer_every = Duration::from_millis(cfg.timer_ms.max(1));
+        let harness = Harness::new(strategy, requests, latency, timer, portfolio.clone(), symbols, &cfg);
+        let counters = Arc::clone(&harness.counters);
+        let (risk, costs, venues) = (harness.risk.clone(), harness.costs.clone(), Arc::clone(&harness.venues));
+        let (processing, impact) = (Arc::new(described), Arc::new(harness.impact.describe()));
+        let (router, flow, marks) = (harness.router.clone(), harness.flow.clone(), harness.marks.clone());
+        tokio::spawn(harness.run(ticks, report_rx, timer_every));
+        Ok(PaperOrders {
+            counters,
+            strategy: name,
+            processing,
+            script,
+            portfolio,
+            risk,
+            costs,
+            impact,
+            venues,
+            router,
+            flow,
+            marks,
+        })
+    }
+
+    pub fn portfolio(&self) -> Portfolio {
+        self.portfolio.clone()
+    }
+
+    pub fn risk(&self) -> Risk {
+        self.risk.clone()
+    }
+
+    pub fn router(&self) -> Router {
+        self.router.clone()
+    }
+
+    pub fn flow(&self) -> OrderFlow {
+        self.flow.clone()
+    }
+
+    pub fn marks(&self) -> Marks {
+        self.marks.clone()
+    }
+
+    /// Time per callback of a `script` strategy.
+    pub fn script_stats(&self) -> Option<ScriptStats> {
+        self.script.clone()
+    }
+
+    /// `momentum: 120 sent, 120 acked, 87 filled, 30 cancelled, 3 open, queue ahead 140 avg, processing instant,
+    /// no fees, no slippage, no impact`
+    pub fn describe(&self) -> String {
+        let c = &self.counters;
+        let load = |n: &AtomicU64| n.load(Ordering::Relaxed);
+        let open = load(&c.sent).saturating_sub(load(&c.filled) + load(&c.cancelled));
+        format!(
+            "{}: {} sent, {} acked, {} filled, {} cancelled, {} open, queue ahead {} avg, processing {}, {}, {}",
+            self.strategy,
+            load(&c.sent),
+            load(&c.acked),
+            load(&c.filled),
+            load(&c.cancelled),
+            open,
+            load(&c.queue_ahead) / load(&c.acked).max(1),
+            self.processing,
+            self.costs.describe(),
+            self.impact
+        )
+    }
+
+    /// `near (20µs, quote lag 0µs, ...): 310 fills, 3100 shares, edge +41.20 after 2.10 fees, shows 1200 of 3000
+    /// resting | far (500µs, ...): ...`
+    pub fn describe_venues(&self) -> String {
+        self.venues
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let t = v.tally.lock().unwrap();
+                let (resting, shown) =
+                    t.book.values().fold((0, 0), |(r, s), &(resting, shown)| (r + resting, s + shown));
+                format!(
+                    "{} ({}µs, quote lag {}µs, {}): {} fills, {} shares, edge {:+.2} after {:.2} fees, \
+                     shows {} of {} resting",
+                    v.name,
+                    v.link.latency / 1000,
+                    v.link.quote_lag / 1000,
+                    self.costs.fees_on(i),
+                    t.fills,
+                    t.shares,
+                    t.edge,
+                    t.fees,
+                    shown,
+                    resting
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+/// Runs the strategy's callbacks, times them, and follows its orders
+/// through the engine's reports. Driven by the live event loop in
+/// [`run`](Self::run) or by a [`backtest`]. Once the kill switch trips the
+/// strategy is no longer called, and the harness only works the position
+/// down to flat.
+struct Harness {
+    strategy: Box<dyn Strategy>,
+    /// By venue.
+    requests: Vec<UnboundedSender<(Request, u64)>>,
+    latency: LatencyRecorder,
+    timer: SharedClock,
+    counters: Arc<Counters>,
+    portfolio: Portfolio,
+    /// By stock id, what the engine's integer prices count.
+    tick_sizes: Vec<Price>,
+    risk: Risk,
+    costs: Costs,
+    impact: Impact,
+    venues: Arc<Vec<VenueStats>>,
+    router: Router,
+    flow: OrderFlow,
+    marks: Marks,
+    books: HashMap<i32, Book>,
+    open: HashMap<u64, Lifecycle>,
+    routed: HashMap<u64, Routed>,
+    next_child: u64,
+    actions: Actions,
+}
+
+impl Harness {
+    fn new(
+        strategy: Box<dyn Strategy>,
+        requests: Vec<UnboundedSender<(Request, u64)>>,
+        latency: LatencyRecorder,
+        timer: SharedClock,
+        portfolio: Portfolio,
+        symbols: &[Symbol],
+        cfg: &PaperConfig,
+    ) -> Self {
+        let venues = venues(cfg);
+        let (risk, costs, impact) =
+            (Risk::new(cfg.risk.clone()), Costs::new(cfg, &venues), Impact::new(cfg.impact.clone()));
+        let router = Router::new(&venues);
+        let venues = venues
+            .iter()
+            .map(|v| VenueStats { name: v.name.clone(), link: Link::new(v), tally: Mutex::default() })
+            .collect();
+        Harness {
+            strategy,
+            requests,
+            latency,
+            timer,
+            counters: Arc::default(),
+            portfolio,
+            tick_sizes: symbols.iter().map(|s| s.tick_size).collect(),
+            risk,
+            costs,
+            impact,
+            venues: Arc::new(venues),
+            router,
+            flow: OrderFlow::new(Duration::from_millis(cfg.flow_window_ms)),
+            marks: Marks::default(),
+            books: HashMap::new(),
+            open: HashMap::new(),
+            routed: HashMap::new(),
+            next_child: CHILD_ID_BASE,
+            actions: Actions::default(),
+        }
+    }
+
+    /// None for a stock id with no symbol.
+    fn tick(&self, stock_id: i32) -> Option<Price> {
+        usize::try_from(stock_id).ok().and_then(|id| self.tick_sizes.get(id)).copied()
+    }
+
+    /// To every venue, ahead of any order sent after it.
+    fn quote(&self, stock_id: i32, mid: i64) {
+        let sent = self.timer.now_nanos();
+        for requests in &self.requests {
+            let _ = requests.send((Request::Quote { stock_id, mid }, sent));
+        }
+    }
+
+    async fn run(mut self, mut ticks: TickReceiver, mut reports: UnboundedReceiver<Report>, timer_every: Duration) {
+        let origin = self.timer.now_nanos();
+        let mut timer = tokio::time::interval(timer_every);
+        loop {
+            let now = Duration::from_nanos(self.timer.now_nanos().saturating_sub(origin));
+            tokio::select! {
+                tick = ticks.recv() => match tick {
+                    Ok(tick) => self.on_tick(tick.stock_id, tick.price, now),
+                    Err(RecvError::Lagged(n)) => warn!("Paper orders lagged, skipped {} ticks", n),
+                    Err(RecvError::Closed) => return,
+                },
+                Some(report) = reports.recv() => self.on_report(report, now),
+                _ = timer.tick() => self.on_timer(now),
+            }
+        }
+    }
+
+    /// `now` is the time since the run started. The price goes to the
+    /// engine on the symbol's tick grid.
+    fn on_tick(&mut self, stock_id: i32, price: Price, now: Duration) {
+        let Some(tick) = self.tick(stock_id) else { return };
+        self.marks.tick(stock_id);
+        let mid = self.impact.mid(stock_id, price.ticks(tick), now);
+        self.quote(stock_id, mid);
+        self.portfolio.mark(stock_id, tick * mid);
+        let n_venues = self.requests.len();
+        let book = self.books.entry(stock_id).or_insert_with(|| Book {
+            mid,
+            venues: vec![VenueQuote { bid: mid - HALF_SPREAD, ask: mid + HALF_SPREAD }; n_venues],
+        });
+        book.mid = mid;
+        self.actions.now = now;
+        if self.check_risk() {
+            return;
+        }
+        let venues = &self.books[&stock_id].venues;
+        let tick = MarketTick { stock_id, price: mid, bid: mid - HALF_SPREAD, ask: mid + HALF_SPREAD, venues };
+        let started = self.timer.now_nanos();
+        self.strategy.on_tick(&tick, &mut self.actions);
+        self.latency.record(Stage::Decision, Some(stock_id), self.timer.elapsed(started));
+        self.send();
+    }
+
+    fn on_report(&mut self, report: Report, now: Duration) {
+        match report {
+            Report::Acked { id, at, ahead } => {
+                let Some(order) = self.open.get_mut(&id).filter(|o| o.state == State::New) else { return };
+                order.state = State::Acked;
+                let time_to_ack = Duration::from_nanos(at.saturating_sub(order.sent));
+                self.latency.record(Stage::OrderAck, Some(order.stock_id), time_to_ack);
+                self.router.acked(order.venue, now.saturating_sub(order.placed));
+                self.counters.acked.fetch_add(1, Ordering::Relaxed);
+                self.counters.queue_ahead.fetch_add(ahead as u64, Ordering::Relaxed);
+            }
+            Report::Filled { id, price, qty, at, taker } => {
+                // Orders on stock ids with no symbol are never sent.
+                let Some(tick) = self.open.get(&id).and_then(|order| self.tick(order.stock_id)) else { return };
+                let Some(order) = self.open.get_mut(&id) else { return };
+                order.left = order.left.saturating_sub(qty);
+                let (price, fee) = self.costs.fill(order.venue, order.side, price, qty, taker);
+                let (venue, stock_id, side, parent) = (order.venue, order.stock_id, order.side, order.parent);
+                // A routed order's fills are the parent's to the strategy.
+                let (fill_id, left) = match parent.and_then(|p| self.routed.get_mut(&p).map(|routed| (p, routed))) {
+                    Some((p, routed)) => {
+                        routed.left = routed.left.saturating_sub(qty);
+                        self.router.filled(venue, qty);
+                        (p, routed.left)
+                    }
+                    None => (id, order.left),
+                };
+                let fill = OrderFill { id: fill_id, venue, stock_id, side, price, qty, left, fee };
+                if let Some(book) = self.books.get(&stock_id) {
+                    let signed = match side {
+                        Side::Buy => qty as f64,
+                        Side::Sell => -(qty as f64),
+                    };
+                    let mut tally = self.venues[venue].tally.lock().unwrap();
+                    tally.fills += 1;
+                    tally.shares += qty as u64;
+                    tally.edge += (tick * (book.mid - price)).to_f64() * signed - fee;
+                    tally.fees += fee;
+                }
+                debug!(
+                    "Paper order {} filled {} {:?} at {}, {} left",
+                    id,
+                    fill.qty,
+                    fill.side,
+                    tick * fill.price,
+                    fill.left
+                );
+                self.portfolio.fill(fill.stock_id, fill.side, tick * price, qty, Price::from_f64(fee));
+                if order.left > 0 {
+                    order.state = State::PartiallyFilled;
+                } else {
+                    let time_to_fill = Duration::from_nanos(at.saturating_sub(order.sent));
+                    self.latency.record(Stage::OrderFill, Some(order.stock_id), time_to_fill);
+                    self.counters.filled.fetch_add(1, Ordering::Relaxed);
+                    self.open.remove(&id);
+                    self.child_done(parent);
+                }
+                if let Some(mid) = self.impact.fill(stock_id, side, qty, now) {
+                    self.quote(stock_id, mid);
+                    self.portfolio.mark(stock_id, tick * mid);
+                }
+                self.actions.now = now;
+                if self.check_risk() {
+                    return;
+                }
+                let started = self.timer.now_nanos();
+                self.strategy.on_fill(&fill, &mut self.actions);
+                self.latency.record(Stage::Decision, Some(fill.stock_id), self.timer.elapsed(started));
+                self.send();
+            }
+            Report::Cancelled { id } => {
+                if let Some(order) = self.open.remove(&id) {
+                    self.counters.cancelled.fetch_add(1, Ordering::Relaxed);
+                    self.child_done(order.parent);
+                }
+            }
+            Report::Quoted { venue, stock_id, bid, ask, bids, asks, resting, shown } => {
+                let Some(tick) = self.tick(stock_id) else { return };
+                if let Some(quote) = self.books.get_mut(&stock_id).and_then(|b| b.venues.get_mut(venue)) {
+                    *quote = VenueQuote { bid, ask };
+                }
+                self.flow.quoted(venue, stock_id, &bids, &asks, now);
+                self.marks.quoted(venue, stock_id, tick, &bids, &asks);
+                self.router.quoted(venue, stock_id, bids, asks);
+                if let Some(stats) = self.venues.get(venue) {
+                    stats.tally.lock().unwrap().book.insert(stock_id, (resting, shown));
+                }
+            }
+        }
+    }
+
+    /// One of `parent`'s children is filled or cancelled; the last one
+    /// finishes the routed order.
+    fn child_done(&mut self, parent: Option<u64>) {
+        let Some(parent) = parent else { return };
+        let Some(routed) = self.routed.get_mut(&parent) else { return };
+        routed.children -= 1;
+        if routed.children == 0 {
+            self.router.finished(routed.qty, routed.qty - routed.left);
+            self.routed.remove(&parent);
+        }
+    }
+
+    fn on_timer(&mut self, now: Duration) {
+        if self.risk.tripped() {
+            return;
+        }
+        self.actions.now = now;
+        let started = self.timer.now_nanos();
+        self.strategy.on_timer(&mut self.actions);
+        self.latency.record(Stage::Decision, None, self.timer.elapsed(started));
+        self.send();
+    }
+
+    /// Trips the kill switch on a breach, cancelling every open order, and
+    /// while it is tripped sends an IOC order against each position with no
+    /// order still open in its symbol. True once tripped.
+    fn check_risk(&mut self) -> bool {
+        if let Some(breach) = self.risk.check(&self.portfolio) {
+            error!(target: "audit", event = "kill_switch", breach = %breach, "Kill switch tripped: {}", breach);
+            let open: Vec<(i32, u64)> = self.open.iter().map(|(&id, order)| (order.stock_id, id)).collect();
+            for (stock_id, id) in open {
+                self.actions.cancel(stock_id, id);
+            }
+        }
+        if !self.risk.tripped() {
+            return false;
+        }
+        for (stock_id, position) in self.portfolio.positions() {
+            let (Some(mark), Some(tick)) = (position.mark, self.tick(stock_id)) else { continue };
+            let mark = mark.ticks(tick);
+            if position.qty == 0 || self.open.values().any(|order| order.stock_id == stock_id) {
+                continue;
+            }
+            let (side, price) =
+                if position.qty > 0 { (Side::Sell, mark - FLATTEN_REACH) } else { (Side::Buy, mark + FLATTEN_REACH) };
+            let qty = u32::try_from(position.qty.unsigned_abs()).unwrap_or(u32::MAX);
+            info!("Kill switch flattening {} of stock {}", position.qty, stock_id);
+            self.actions.place(stock_id, side, price, qty, TimeInForce::Ioc);
+        }
+        self.send();
+        true
+    }
+
+    /// Orders for venues or symbols that do not exist are dropped, routed
+    /// orders go out as their children, and cancels go to the venue the order
+    /// is on, or to every open child of a routed order.
+    fn send(&mut self) {
+        for action in self.actions.take() {
+            let sent = self.timer.now_nanos();
+            let requests: Vec<(usize, Request)> = match action {
+                Action::Place { venue, .. } if venue >= self.requests.len() => {
+                    warn!("Dropping an order for venue {}, there are {}", venue, self.requests.len());
+                    continue;
+                }
+                Action::Place { order, .. } | Action::Route { order } if self.tick(order.stock_id).is_none() => {
+                    warn!("Dropping an order for stock {}, there is no such symbol", order.stock_id);
+                    continue;
+                }
+                Action::Place { venue, order } => {
+                    self.track(venue, None, &order, sent);
+                    vec![(venue, Request::New(order))]
+                }
+                Action::Route { order } => {
+                    let split = self.router.route(&order);
+                    self.routed.insert(order.id, Routed { qty: order.qty, left: order.qty, children: split.len() });
+                    split
+                        .into_iter()
+                        .map(|(venue, qty)| {
+                            self.next_child += 1;
+                            let child = Order { id: self.next_child, qty, ..order };
+                            self.track(venue, Some(order.id), &child, sent);
+                            (venue, Request::New(child))
+                        })
+                        .collect()
+                }
+                Action::Cancel { stock_id, id } if self.routed.contains_key(&id) => self
+                    .open
+                    .iter()
+                    .filter(|(_, o)| o.parent == Some(id))
+                    .map(|(&child, o)| (o.venue, Request::Cancel { stock_id, id: child }))
+                    .collect(),
+                Action::Cancel { stock_id, id } => {
+                    let Some(order) = self.open.get(&id) else { continue };
+                    vec![(order.venue, Request::Cancel { stock_id, id })]
+                }
+            };
+            for (venue, request) in requests {
+                let _ = self.requests[venue].send((request, sent));
+            }
+        }
+    }
+
+    fn track(&mut self, venue: usize, parent: Option<u64>, order: &Order, sent: u64) {
+        let lifecycle = Lifecycle {
+            venue,
+            parent,
+            stock_id: order.stock_id,
+            side: order.side,
+            state: State::New,
+            sent,
+            placed: self.actions.now,
+            left: order.qty,
+        };
+        self.open.insert(order.id, lifecycle);
+        self.counters.sent.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Feeds `ticks`, each a stock id, price and time since the first, through
+/// the strategy and venues that answer with no waiting between ticks. Time
+/// is the recording's: the timer fires every `timer_ms` of it, and each
+/// venue's latency and quote lag delay requests and reports by that much of
+/// it. `processing` is ignored.
+pub fn backtest(
+    cfg: &PaperConfig,
+    symbols: &[Symbol],
+    ticks: impl IntoIterator<Item = (i32, Price, Duration)>,
+    latency: LatencyRecorder,
+    timer: SharedClock,
+) -> io::Result<PaperOrders> {
+    let strategy = strategy::build(cfg)?;
+    let (name, script) = (strategy.name(), strategy.script_stats());
+    let mut replay = Replay { venues: Vec::new(), events: BTreeMap::new(), seq: 0, clock: SharedClock::clone(&timer) };
+    let mut requests = Vec::new();
+    for (i, v) in venues(cfg).iter().enumerate() {
+        let (request_tx, request_rx) = mpsc::unbounded_channel();
+        let (report_tx, report_rx) = mpsc::unbounded_channel();
+        requests.push(request_tx);
+        let venue = Venue::new(i, report_tx, Maker::new(cfg));
+        replay.venues.push(Simulated { venue, link: Link::new(v), requests: request_rx, reports: report_rx });
+    }
+    let portfolio = Portfolio::default();
+    let mut harness = Harness::new(strategy, requests, latency, timer, portfolio.clone(), symbols, cfg);
+    let orders = PaperOrders {
+        counters: Arc::clone(&harness.counters),
+        strategy: name,
+        processing: Arc::new("instant".to_string()),
+        script,
+        portfolio,
+        risk: harness.risk.clone(),
+        costs: harness.costs.clone(),
+        impact: Arc::new(harness.impact.describe()),
+        venues: Arc::clone(&harness.venues),
+        router: harness.router.clone(),
+        flow: harness.flow.clone(),
+        marks: harness.marks.clone(),
+    };
+    let timer_every = Duration::from_millis(cfg.timer_ms.max(1));
+    let mut next_timer = timer_every;
+    for (stock_id, price, at) in ticks {
+        while next_timer <= at {
+            replay.run_until(&mut harness, next_timer);
+            harness.on_timer(next_timer);
+            replay.collect(next_timer);
+            next_timer += timer_every;
+        }
+        replay.run_until(&mut harness, at);
+        harness.on_tick(stock_id, price, at);
+        replay.collect(at);
+        replay.run_until(&mut harness, at);
+    }
+    Ok(orders)
+}
+
+enum Event {
+    ToVenue(usize, Request),
+    ToHarness(Report),
+}
+
+/// A venue in a backtest, with what the harness sent it and what it has
+/// reported since the last look.
+struct Simulated {
+    venue: Venue,
+    link: Link,
+    requests: UnboundedReceiver<(Request, u64)>,
+    reports: UnboundedReceiver<(Report, u64)>,
+}
+
+/// A backtest's venues and what is on its way between them and the
+/// strategy, by the recorded time it arrives.
+struct Replay {
+    venues: Vec<Simulated>,
+    events: BTreeMap<(Duration, u64), Event>,
+    seq: u64,
+    /// Stamps the engine's reports, as live.
+    clock: SharedClock,
+}
+
+impl Replay {
+    /// Puts what the harness and the venues sent at `now` on its way.
+    fn collect(&mut self, now: Duration) {
+        let mut arriving = Vec::new();
+        for (i, v) in self.venues.iter_mut().enumerate() {
+            while let Ok((request, _)) = v.requests.try_recv() {
+                arriving.push((now + Duration::from_nanos(v.link.delay(&request)), Event::ToVenue(i, request)));
+            }
+            while let Ok((report, _)) = v.reports.try_recv() {
+                arriving.push((now + Duration::from_nanos(v.link.latency), Event::ToHarness(report)));
+            }
+        }
+        for (at, event) in arriving {
+            self.events.insert((at, self.seq), event);
+            self.seq += 1;
+        }
+    }
+
+    /// Delivers everything due by `until`, in order, with what it sets off.
+    fn run_until(&mut self, harness: &mut Harness, until: Duration) {
+        while let Some(entry) = self.events.first_entry().filter(|e| e.key().0 <= until) {
+            let ((at, _), event) = entry.remove_entry();
+            match event {
+                Event::ToVenue(i, request) => self.venues[i].venue.apply(request, self.clock.now_nanos()),
+                Event::ToHarness(report) => harness.on_report(report, at),
+            }
+            self.collect(at);
+        }
+    }
+}
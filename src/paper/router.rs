@@ -0,0 +1,180 @@
+//! The smart order router behind `route()`: splits an order across the
+//! venues by the liquidity each last displayed within its limit price,
+//! taking the best prices first and, at the same price, the venue whose
+//! acks have come back fastest. What it cannot place against displayed
+//! liquidity goes to the fastest venue. Each split and what became of it is
+//! counted for the Router line.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tracing::debug;
+
+use crate::config::VenueConfig;
+use crate::engine::{Order, Side};
+
+/// Weight of the newest ack in a venue's measured latency.
+const ACK_WEIGHT: f64 = 0.1;
+
+#[derive(Default)]
+struct Measured {
+    /// Moving average of time-to-ack, in nanoseconds; none before the first.
+    ack: Option<f64>,
+    /// Shares routed here, and how many of them filled.
+    routed: u64,
+    filled: u64,
+}
+
+#[derive(Default)]
+struct Outcomes {
+    orders: u64,
+    children: u64,
+    done: u64,
+    /// Finished with every share filled.
+    complete: u64,
+    /// Finished with none filled.
+    unfilled: u64,
+}
+
+struct Inner {
+    names: Vec<String>,
+    venues: Vec<Measured>,
+    /// Displayed depth by symbol and venue, best level first.
+    bids: HashMap<(i32, usize), Vec<(i64, u32)>>,
+    asks: HashMap<(i32, usize), Vec<(i64, u32)>>,
+    outcomes: Outcomes,
+}
+
+#[derive(Clone)]
+pub struct Router {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Router {
+    pub fn new(venues: &[VenueConfig]) -> Self {
+        let inner = Inner {
+            names: venues.iter().map(|v| v.name.clone()).collect(),
+            venues: venues.iter().map(|_| Measured::default()).collect(),
+            bids: HashMap::new(),
+            asks: HashMap::new(),
+            outcomes: Outcomes::default(),
+        };
+        Router { inner: Arc::new(Mutex::new(inner)) }
+    }
+
+    /// A venue's depth as it reported it.
+    pub fn quoted(&self, venue: usize, stock_id: i32, bids: Vec<(i64, u32)>, asks: Vec<(i64, u32)>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.bids.insert((stock_id, venue), bids);
+        inner.asks.insert((stock_id, venue), asks);
+    }
+
+    /// Any order's time-to-ack on `venue`, routed or not.
+    pub fn acked(&self, venue: usize, time_to_ack: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(measured) = inner.venues.get_mut(venue) else { return };
+        let ns = time_to_ack.as_nanos() as f64;
+        measured.ack = Some(measured.ack.map_or(ns, |ack| ack + ACK_WEIGHT * (ns - ack)));
+    }
+
+    /// Venue and quantity of each child order `order` splits into.
+    pub fn route(&self, order: &Order) -> Vec<(usize, u32)> {
+        let mut inner = self.inner.lock().unwrap();
+        // Unmeasured venues count as fastest, so each gets tried.
+        let latency = |m: &Measured| m.ack.unwrap_or(0.0);
+        let (displayed, better): (_, fn(i64, i64) -> bool) = match order.side {
+            Side::Buy => (&inner.asks, |price, limit| price <= limit),
+            Side::Sell => (&inner.bids, |price, limit| price >= limit),
+        };
+        let mut offers: Vec<(i64, usize, u32)> = (0..inner.venues.len())
+            .flat_map(|venue| {
+                let levels = displayed.get(&(order.stock_id, venue)).map_or(&[][..], Vec::as_slice);
+                levels
+                    .iter()
+                    .filter(|&&(price, _)| better(price, order.price))
+                    .map(move |&(price, qty)| (price, venue, qty))
+            })
+            .collect();
+        offers.sort_by(|a, b| {
+            let price = match order.side {
+                Side::Buy => a.0.cmp(&b.0),
+                Side::Sell => b.0.cmp(&a.0),
+            };
+            price.then(latency(&inner.venues[a.1]).total_cmp(&latency(&inner.venues[b.1])))
+        });
+        let mut split: BTreeMap<usize, u32> = BTreeMap::new();
+        let mut left = order.qty;
+        for (_, venue, qty) in offers {
+            if left == 0 {
+                break;
+            }
+            let take = qty.min(left);
+            *split.entry(venue).or_default() += take;
+            left -= take;
+        }
+        if left > 0 {
+            let fastest = (0..inner.venues.len())
+                .min_by(|&a, &b| latency(&inner.venues[a]).total_cmp(&latency(&inner.venues[b])))
+                .unwrap_or(0);
+            *split.entry(fastest).or_default() += left;
+        }
+        for (&venue, &qty) in &split {
+            inner.venues[venue].routed += qty as u64;
+        }
+        inner.outcomes.orders += 1;
+        inner.outcomes.children += split.len() as u64;
+        let names: Vec<String> = split.iter().map(|(&venue, qty)| format!("{} {}", inner.names[venue], qty)).collect();
+        debug!(
+            "Routed order {}, {} {:?} at {} ticks: {}",
+            order.id,
+            order.qty,
+            order.side,
+            order.price,
+            names.join(", ")
+        );
+        split.into_iter().collect()
+    }
+
+    /// Shares of a routed order filled on `venue`.
+    pub fn filled(&self, venue: usize, qty: u32) {
+        if let Some(measured) = self.inner.lock().unwrap().venues.get_mut(venue) {
+            measured.filled += qty as u64;
+        }
+    }
+
+    /// A routed order with no child left open, `filled` of its `qty` traded.
+    pub fn finished(&self, qty: u32, filled: u32) {
+        let outcomes = &mut self.inner.lock().unwrap().outcomes;
+        outcomes.done += 1;
+        if filled >= qty {
+            outcomes.complete += 1;
+        } else if filled == 0 {
+            outcomes.unfilled += 1;
+        }
+    }
+
+    /// `12 orders into 20 children, 11 done: 8 filled, 2 partly, 1 not | near ack 41µs, 700 shares, 95% filled | ...`
+    pub fn describe(&self) -> String {
+        let inner = self.inner.lock().unwrap();
+        let o = &inner.outcomes;
+        if o.orders == 0 {
+            return "nothing routed (route() in a script)".to_string();
+        }
+        let mut parts = vec![format!(
+            "{} orders into {} children, {} done: {} filled, {} partly, {} not",
+            o.orders,
+            o.children,
+            o.done,
+            o.complete,
+            o.done - o.complete - o.unfilled,
+            o.unfilled
+        )];
+        for (name, m) in inner.names.iter().zip(&inner.venues) {
+            let ack = m.ack.map_or("-".to_string(), |ack| format!("{:.0}µs", ack / 1000.0));
+            let rate = m.filled as f64 * 100.0 / m.routed.max(1) as f64;
+            parts.push(format!("{} ack {}, {} shares, {:.0}% filled", name, ack, m.routed, rate));
+        }
+        parts.join(" | ")
+    }
+}
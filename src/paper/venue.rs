@@ -0,0 +1,315 @@
+//! One simulated exchange and the link to it. Each venue has its own
+//! matching engine, market maker and taker, and sits `latency_us` from the
+//! strategy each way: orders take that long to arrive, and acks, fills and
+//! quotes that long to come back. Its maker re-quotes `quote_lag_us` after
+//! each tick, so a slow venue's book trails the price a fast strategy sees.
+//! With `maker_display` or `maker_hidden` the maker shows less than it
+//! quotes, so the depth the venue reports understates what will trade.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use rand::Rng;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::config::{PaperConfig, VenueConfig};
+use crate::engine::{Fill, MatchingEngine, Order, Side, TimeInForce, Visibility};
+use crate::inject::Sampler;
+use crate::timing::SharedClock;
+
+/// The market maker's half-spread, in engine price ticks.
+pub const HALF_SPREAD: i64 = 1;
+/// Per level of the maker's ladder; takers trade up to this much a tick.
+const QUOTE_QTY: u32 = 100;
+/// Market maker and taker order ids count up from here so they never clash
+/// with paper order ids.
+const MAKER_ID_BASE: u64 = u64::MAX / 2;
+
+pub enum Request {
+    Quote { stock_id: i32, mid: i64 },
+    New(Order),
+    Cancel { stock_id: i32, id: u64 },
+}
+
+/// From the engine, stamped with the stage clock when it reached the
+/// strategy.
+pub enum Report {
+    /// `ahead` is the quantity queued in front of the order at its price.
+    Acked { id: u64, at: u64, ahead: u32 },
+    /// `taker` if the paper order crossed the spread rather than rested.
+    Filled { id: u64, price: i64, qty: u32, at: u64, taker: bool },
+    /// Cancelled on request, or the unfilled rest of an IOC order.
+    Cancelled { id: u64 },
+    /// The maker's touch once it has re-quoted, and the displayed depth
+    /// after the taker's trade, best level first. `resting` is everything
+    /// in the book, shown or not, and `shown` what of it is displayed.
+    Quoted {
+        venue: usize,
+        stock_id: i32,
+        bid: i64,
+        ask: i64,
+        bids: Vec<(i64, u32)>,
+        asks: Vec<(i64, u32)>,
+        resting: u32,
+        shown: u32,
+    },
+}
+
+impl Report {
+    /// Stamped `by` nanoseconds later, at the strategy's end of the link.
+    fn delayed(self, by: u64) -> Self {
+        match self {
+            Report::Acked { id, at, ahead } => Report::Acked { id, at: at + by, ahead },
+            Report::Filled { id, price, qty, at, taker } => Report::Filled { id, price, qty, at: at + by, taker },
+            other => other,
+        }
+    }
+}
+
+/// How far a venue is from the strategy, in nanoseconds.
+#[derive(Clone, Copy)]
+pub struct Link {
+    pub latency: u64,
+    pub quote_lag: u64,
+}
+
+impl Link {
+    pub fn new(cfg: &VenueConfig) -> Self {
+        Link { latency: cfg.latency_us * 1000, quote_lag: cfg.quote_lag_us * 1000 }
+    }
+
+    /// From the strategy sending `request` to the venue acting on it:
+    /// quotes lag, orders and cancels travel.
+    pub fn delay(&self, request: &Request) -> u64 {
+        match request {
+            Request::Quote { .. } => self.quote_lag,
+            _ => self.latency,
+        }
+    }
+}
+
+/// How the market maker quotes, the same on every venue.
+#[derive(Clone, Copy)]
+pub struct Maker {
+    /// Levels on each side.
+    depth: i64,
+    visibility: Visibility,
+    /// Also rested hidden at each level; none if 0.
+    hidden: u32,
+}
+
+impl Maker {
+    pub fn new(cfg: &PaperConfig) -> Self {
+        let visibility = cfg.maker_display.map_or(Visibility::Lit, |display| Visibility::Iceberg { display });
+        Maker { depth: cfg.depth.max(1) as i64, visibility, hidden: cfg.maker_hidden }
+    }
+}
+
+/// Starts venue `index`, reporting to `reports`; returns where to send it
+/// requests, stamped with the stage clock when sent.
+pub fn spawn(
+    index: usize,
+    link: Link,
+    maker: Maker,
+    processing: Option<Sampler>,
+    timer: SharedClock,
+    reports: UnboundedSender<Report>,
+) -> UnboundedSender<(Request, u64)> {
+    let (request_tx, request_rx) = mpsc::unbounded_channel();
+    let (outbound_tx, outbound_rx) = mpsc::unbounded_channel();
+    tokio::spawn(forward(outbound_rx, reports, link.latency, SharedClock::clone(&timer)));
+    tokio::spawn(exchange(request_rx, Venue::new(index, outbound_tx, maker), timer, processing, link));
+    request_tx
+}
+
+/// Books quotes and works through orders and cancels once they arrive.
+/// With a `processing` distribution each order message takes a drawn time,
+/// one after the other, so a burst queues up and an order meets the book as
+/// it is when its turn comes rather than when it was sent.
+async fn exchange(
+    mut requests: UnboundedReceiver<(Request, u64)>,
+    mut venue: Venue,
+    timer: SharedClock,
+    processing: Option<Sampler>,
+    link: Link,
+) {
+    // Requests by the stage clock time they are done, then arrival order.
+    let mut pending: BTreeMap<(u64, u64), Request> = BTreeMap::new();
+    let mut seq = 0;
+    let mut busy_until = 0;
+    loop {
+        let wait = pending.keys().next().map(|&(done, _)| Duration::from_nanos(done.saturating_sub(timer.now_nanos())));
+        tokio::select! {
+            request = requests.recv() => {
+                let Some((request, sent)) = request else { return };
+                let mut done = sent + link.delay(&request);
+                if let (Request::New(_) | Request::Cancel { .. }, Some(processing)) = (&request, &processing) {
+                    let took = processing.sample(&mut rand::thread_rng()).as_nanos() as u64;
+                    busy_until = busy_until.max(done) + took;
+                    done = busy_until;
+                }
+                pending.insert((done, seq), request);
+                seq += 1;
+            }
+            _ = tokio::time::sleep(wait.unwrap_or_default()), if wait.is_some() => {}
+        }
+        let now = timer.now_nanos();
+        while let Some(entry) = pending.first_entry().filter(|e| e.key().0 <= now) {
+            let ((done, _), request) = entry.remove_entry();
+            venue.apply(request, done);
+        }
+    }
+}
+
+/// Passes reports back over the link, each `latency` after the venue sent it.
+async fn forward(
+    mut reports: UnboundedReceiver<(Report, u64)>,
+    to: UnboundedSender<Report>,
+    latency: u64,
+    timer: SharedClock,
+) {
+    while let Some((report, at)) = reports.recv().await {
+        let wait = (at + latency).saturating_sub(timer.now_nanos());
+        if wait > 0 {
+            tokio::time::sleep(Duration::from_nanos(wait)).await;
+        }
+        let _ = to.send(report.delayed(latency));
+    }
+}
+
+/// The engine, the market maker's ladders and where reports go, each
+/// stamped with when it was made.
+pub struct Venue {
+    index: usize,
+    engine: MatchingEngine,
+    /// By symbol and whether hidden, the maker's resting quote ids by price.
+    ladders: HashMap<(i32, bool), BTreeMap<i64, u64>>,
+    next_maker_id: u64,
+    maker: Maker,
+    reports: UnboundedSender<(Report, u64)>,
+}
+
+impl Venue {
+    pub fn new(index: usize, reports: UnboundedSender<(Report, u64)>, maker: Maker) -> Self {
+        Venue {
+            index,
+            engine: MatchingEngine::default(),
+            ladders: HashMap::new(),
+            next_maker_id: MAKER_ID_BASE,
+            maker,
+            reports,
+        }
+    }
+
+    fn report(&self, report: Report, at: u64) {
+        let _ = self.reports.send((report, at));
+    }
+
+    /// A quote, paper order or cancel, done at `at` on the stage clock.
+    pub fn apply(&mut self, request: Request, at: u64) {
+        match request {
+            Request::Quote { stock_id, mid } => self.quote(stock_id, mid, at),
+            Request::New(order) => {
+                let fills = self.engine.submit(&order);
+                let ahead = self.engine.queue_ahead(order.stock_id, order.id);
+                self.report(Report::Acked { id: order.id, at, ahead: ahead.unwrap_or(0) }, at);
+                self.report_fills(&fills, at);
+                if ahead.is_none() && fills.iter().map(|f| f.qty).sum::<u32>() < order.qty {
+                    self.report(Report::Cancelled { id: order.id }, at);
+                }
+            }
+            Request::Cancel { stock_id, id } => {
+                if self.engine.cancel(stock_id, id) {
+                    self.report(Report::Cancelled { id }, at);
+                }
+            }
+        }
+    }
+
+    /// Moves the maker's ladders to `mid`, then lets a taker trade a random
+    /// lot at the touch. Levels the price moved through are cancelled or, for
+    /// paper orders left there, traded by the new quotes; a level that stays
+    /// keeps its place in the queue, so paper orders joining it wait behind
+    /// the maker's size and fill as takers work through it.
+    fn quote(&mut self, stock_id: i32, mid: i64, at: u64) {
+        let depth = self.maker.depth;
+        let (bids, asks) = (
+            mid - HALF_SPREAD - depth + 1..=mid - HALF_SPREAD,
+            mid + HALF_SPREAD..=mid + HALF_SPREAD + depth - 1,
+        );
+        let mut fills = self.requote(stock_id, &bids, &asks, false);
+        if self.maker.hidden > 0 {
+            fills.extend(self.requote(stock_id, &bids, &asks, true));
+        }
+        let mut rng = rand::thread_rng();
+        let (side, price) = if rng.gen_bool(0.5) { (Side::Buy, *asks.start()) } else { (Side::Sell, *bids.end()) };
+        let id = self.next_maker_id;
+        self.next_maker_id += 1;
+        let qty = rng.gen_range(1..=QUOTE_QTY);
+        let (tif, visibility) = (TimeInForce::Ioc, Visibility::Lit);
+        fills.extend(self.engine.submit(&Order { id, stock_id, side, price, qty, tif, visibility }));
+        self.report_fills(&fills, at);
+        let (resting, shown) = self.engine.liquidity(stock_id);
+        let quoted = Report::Quoted {
+            venue: self.index,
+            stock_id,
+            bid: *bids.end(),
+            ask: *asks.start(),
+            bids: self.engine.depth(stock_id, Side::Buy, depth as usize),
+            asks: self.engine.depth(stock_id, Side::Sell, depth as usize),
+            resting,
+            shown,
+        };
+        self.report(quoted, at);
+    }
+
+    /// Keeps one maker order at each price of `bids` and `asks`, quoted or
+    /// `hidden`, and returns what placing the missing ones traded.
+    fn requote(
+        &mut self,
+        stock_id: i32,
+        bids: &RangeInclusive<i64>,
+        asks: &RangeInclusive<i64>,
+        hidden: bool,
+    ) -> Vec<Fill> {
+        let ladder = self.ladders.entry((stock_id, hidden)).or_default();
+        let engine = &mut self.engine;
+        ladder.retain(|price, &mut id| {
+            let keep = engine.queue_ahead(stock_id, id).is_some() && (bids.contains(price) || asks.contains(price));
+            if !keep {
+                engine.cancel(stock_id, id);
+            }
+            keep
+        });
+        let missing: Vec<(Side, i64)> = bids
+            .clone()
+            .map(|price| (Side::Buy, price))
+            .chain(asks.clone().map(|price| (Side::Sell, price)))
+            .filter(|(_, price)| !ladder.contains_key(price))
+            .collect();
+        let (qty, visibility) =
+            if hidden { (self.maker.hidden, Visibility::Hidden) } else { (QUOTE_QTY, self.maker.visibility) };
+        let mut fills = Vec::new();
+        for (side, price) in missing {
+            let id = self.next_maker_id;
+            self.next_maker_id += 1;
+            let tif = TimeInForce::Gtc;
+            fills.extend(self.engine.submit(&Order { id, stock_id, side, price, qty, tif, visibility }));
+            if self.engine.queue_ahead(stock_id, id).is_some() {
+                self.ladders.get_mut(&(stock_id, hidden)).unwrap().insert(price, id);
+            }
+        }
+        fills
+    }
+
+    /// Both sides of a fill can be paper orders.
+    fn report_fills(&self, fills: &[Fill], at: u64) {
+        for fill in fills {
+            for id in [fill.taker_id, fill.maker_id].into_iter().filter(|&id| id < MAKER_ID_BASE) {
+                let taker = id == fill.taker_id;
+                self.report(Report::Filled { id, price: fill.price, qty: fill.qty, at, taker }, at);
+            }
+        }
+    }
+}
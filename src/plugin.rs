@@ -0,0 +1,238 @@
+//! WASM plugins from `[[plugins]]`, run in order on every new price from the
+//! simulator and the feeds before it is applied to the market data. A
+//! plugin exports `on_tick(stock_id: i32, price: f64) -> f64` and returns
+//! the price to apply, or NaN to drop the tick. It may import
+//! `env.emit(stock_id: i32, price: f64)` to add ticks for other symbols,
+//! such as a synthetic spread of two others; emitted ticks are published
+//! like any other but skip the plugins.
+//!
+//! Plugins are sandboxed: `emit` is all they can import, each call has a
+//! fuel budget and memory is capped at [`MAX_MEMORY`]. A call that traps
+//! leaves the price as it was. A module whose file changes is reloaded,
+//! starting from fresh state; one that no longer loads keeps running the
+//! old version.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use tracing::{info, warn};
+use wasmi::{Caller, Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+use crate::config::PluginConfig;
+use crate::latency::{LatencyRecorder, Stage};
+use crate::price::Price;
+use crate::timing::SharedClock;
+
+/// Per plugin.
+const MAX_MEMORY: usize = 16 << 20;
+const RELOAD_POLL: Duration = Duration::from_secs(1);
+
+struct HostState {
+    emitted: Vec<(i32, f64)>,
+    limits: StoreLimits,
+}
+
+struct Instance {
+    store: Store<HostState>,
+    on_tick: TypedFunc<(i32, f64), f64>,
+}
+
+#[derive(Default)]
+struct Counters {
+    calls: u64,
+    total_nanos: u64,
+    max_nanos: u64,
+    dropped: u64,
+    emitted: u64,
+    reloads: u64,
+    /// Traps, emits for unknown symbols and failed reloads.
+    errors: u64,
+}
+
+struct Plugin {
+    name: String,
+    path: PathBuf,
+    fuel: u64,
+    modified: Option<SystemTime>,
+    instance: Instance,
+    counters: Counters,
+}
+
+#[derive(Clone)]
+pub struct PluginHost {
+    engine: Engine,
+    plugins: Arc<Mutex<Vec<Plugin>>>,
+    n_symbols: usize,
+    latency: LatencyRecorder,
+    timer: SharedClock,
+}
+
+impl PluginHost {
+    pub fn load(
+        cfgs: &[PluginConfig],
+        n_symbols: usize,
+        latency: LatencyRecorder,
+        timer: SharedClock,
+    ) -> io::Result<Self> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let mut plugins = Vec::new();
+        for (i, cfg) in cfgs.iter().enumerate() {
+            let path = PathBuf::from(&cfg.path);
+            let instance = instantiate(&engine, &path, cfg.fuel)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("plugins[{}]: {}", i, e)))?;
+            let name = path.file_name().map_or(cfg.path.clone(), |f| f.to_string_lossy().into_owned());
+            info!("Loaded plugin {}", name);
+            plugins.push(Plugin {
+                name,
+                modified: modified(&path),
+                path,
+                fuel: cfg.fuel,
+                instance,
+                counters: Counters::default(),
+            });
+        }
+        Ok(PluginHost { engine, plugins: Arc::new(Mutex::new(plugins)), n_symbols, latency, timer })
+    }
+
+    /// The price to apply to `stock_id`, `None` if a plugin dropped the
+    /// tick, and the ticks the plugins emitted by stock id. The time the
+    /// chain takes is the `plugins` stage.
+    pub fn apply(&self, stock_id: usize, price: Price) -> (Option<Price>, Vec<(usize, Price)>) {
+        let mut plugins = self.plugins.lock().unwrap();
+        if plugins.is_empty() {
+            return (Some(price), Vec::new());
+        }
+        let started = self.timer.now_nanos();
+        let mut price = Some(price);
+        let mut emitted = Vec::new();
+        for plugin in plugins.iter_mut() {
+            let Some(before) = price else { break };
+            let instance = &mut plugin.instance;
+            let counters = &mut plugin.counters;
+            let _ = instance.store.set_fuel(plugin.fuel);
+            let call_started = Instant::now();
+            let result = instance.on_tick.call(&mut instance.store, (stock_id as i32, before.to_f64()));
+            let nanos = call_started.elapsed().as_nanos() as u64;
+            counters.calls += 1;
+            counters.total_nanos += nanos;
+            counters.max_nanos = counters.max_nanos.max(nanos);
+            match result {
+                Ok(after) if after.is_finite() => price = Some(Price::from_f64(after)),
+                Ok(_) => {
+                    counters.dropped += 1;
+                    price = None;
+                }
+                Err(e) => {
+                    counters.errors += 1;
+                    warn!("Plugin {} trapped: {}", plugin.name, e);
+                }
+            }
+            for (id, value) in instance.store.data_mut().emitted.drain(..) {
+                match usize::try_from(id).ok().filter(|&id| id < self.n_symbols) {
+                    Some(id) if value.is_finite() => {
+                        counters.emitted += 1;
+                        emitted.push((id, Price::from_f64(value)));
+                    }
+                    _ => counters.errors += 1,
+                }
+            }
+        }
+        self.latency.record(Stage::Plugins, Some(stock_id as i32), self.timer.elapsed(started));
+        (price, emitted)
+    }
+
+    /// Reloads plugins whose file has changed.
+    pub async fn watch(self) {
+        let mut interval = tokio::time::interval(RELOAD_POLL);
+        loop {
+            interval.tick().await;
+            let changed: Vec<(usize, PathBuf, u64, Option<SystemTime>)> = {
+                let plugins = self.plugins.lock().unwrap();
+                plugins
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, p)| {
+                        let now = modified(&p.path);
+                        (now != p.modified).then(|| (i, p.path.clone(), p.fuel, now))
+                    })
+                    .collect()
+            };
+            for (i, path, fuel, now) in changed {
+                // Compiled without the lock, so ticks keep flowing through the old version meanwhile.
+                let instance = instantiate(&self.engine, &path, fuel);
+                let mut plugins = self.plugins.lock().unwrap();
+                let plugin = &mut plugins[i];
+                plugin.modified = now;
+                match instance {
+                    Ok(instance) => {
+                        info!("Reloaded plugin {}", plugin.name);
+                        plugin.instance = instance;
+                        plugin.counters.reloads += 1;
+                    }
+                    Err(e) => {
+                        warn!("Keeping the running version of plugin {}: {}", plugin.name, e);
+                        plugin.counters.errors += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// `spread.wat: 1200 × 2.1µs avg 15µs max, 400 dropped, 800 emitted, 1 reload, 0 errors`
+    pub fn describe(&self) -> String {
+        let plugins = self.plugins.lock().unwrap();
+        if plugins.is_empty() {
+            return "none".to_string();
+        }
+        plugins
+            .iter()
+            .map(|p| {
+                let c = &p.counters;
+                format!(
+                    "{}: {} × {:.1}µs avg {}µs max, {} dropped, {} emitted, {} reloads, {} errors",
+                    p.name,
+                    c.calls,
+                    c.total_nanos as f64 / c.calls.max(1) as f64 / 1e3,
+                    c.max_nanos / 1000,
+                    c.dropped,
+                    c.emitted,
+                    c.reloads,
+                    c.errors
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+/// Reads `.wasm` or `.wat`, links `emit` and runs the start function.
+fn instantiate(engine: &Engine, path: &Path, fuel: u64) -> Result<Instance, String> {
+    let wasm = wat::parse_file(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let module = Module::new(engine, &wasm).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let limits = StoreLimitsBuilder::new().memory_size(MAX_MEMORY).build();
+    let mut store = Store::new(engine, HostState { emitted: Vec::new(), limits });
+    store.limiter(|state| &mut state.limits);
+    store.set_fuel(fuel).map_err(|e| e.to_string())?;
+    let mut linker = Linker::new(engine);
+    linker
+        .func_wrap("env", "emit", |mut caller: Caller<'_, HostState>, stock_id: i32, price: f64| {
+            caller.data_mut().emitted.push((stock_id, price));
+        })
+        .map_err(|e| e.to_string())?;
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .and_then(|pre| pre.start(&mut store))
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let on_tick = instance
+        .get_typed_func::<(i32, f64), f64>(&store, "on_tick")
+        .map_err(|e| format!("{}: on_tick(i32, f64) -> f64: {}", path.display(), e))?;
+    Ok(Instance { store, on_tick })
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
@@ -0,0 +1,255 @@
+//! What the paper strategy holds: per symbol, the position, its average
+//! cost, the PnL realized by closing trades, the open position marked to
+//! the last mid and the fees paid. Updated from the paper venue's fills, shown in the
+//! Positions panel and kept in the `positions` table so a run can pick up
+//! the book the last one left.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use tracing::{info, warn};
+
+use crate::engine::Side;
+use crate::price::{Price, SCALE};
+use crate::symbols::Symbol;
+
+const PERSIST_EVERY: Duration = Duration::from_secs(1);
+
+/// One symbol's holding. Money is exact decimal price units.
+#[derive(Clone, Copy, Default)]
+pub struct Position {
+    /// Long if positive.
+    pub qty: i64,
+    /// Of the open quantity; 0 when flat.
+    pub avg_cost: Decimal,
+    /// Price times quantity, summed over closing trades.
+    pub realized: Decimal,
+    /// The last mid; none until a tick after loading.
+    pub mark: Option<Price>,
+    /// Paid on fills; negative for net rebates.
+    pub fees: Decimal,
+}
+
+impl Position {
+    /// Adding to the position moves the average cost; reducing it realizes
+    /// the difference to it, and a fill through flat opens the rest at the
+    /// fill price.
+    fn fill(&mut self, side: Side, price: Price, qty: u32) {
+        let signed = match side {
+            Side::Buy => qty as i64,
+            Side::Sell => -(qty as i64),
+        };
+        let price = price.to_decimal();
+        if self.qty == 0 || self.qty.signum() == signed.signum() {
+            let (held, qty) = (Decimal::from(self.qty.abs()), Decimal::from(qty));
+            self.avg_cost = (self.avg_cost * held + price * qty) / (held + qty);
+            self.qty += signed;
+            return;
+        }
+        let closed = self.qty.abs().min(signed.abs());
+        self.realized += (price - self.avg_cost) * Decimal::from(closed * self.qty.signum());
+        self.qty += signed;
+        if self.qty == 0 {
+            self.avg_cost = Decimal::ZERO;
+        } else if self.qty.signum() == signed.signum() {
+            self.avg_cost = price;
+        }
+    }
+
+    /// 0 without a mark.
+    pub fn unrealized(&self) -> Decimal {
+        self.mark.map_or(Decimal::ZERO, |mark| (mark.to_decimal() - self.avg_cost) * Decimal::from(self.qty))
+    }
+
+    /// Realized plus unrealized less fees.
+    pub fn pnl(&self) -> Decimal {
+        self.realized + self.unrealized() - self.fees
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Portfolio {
+    positions: Arc<Mutex<BTreeMap<i32, Position>>>,
+}
+
+impl Portfolio {
+    pub fn mark(&self, stock_id: i32, mid: Price) {
+        self.positions.lock().unwrap().entry(stock_id).or_default().mark = Some(mid);
+    }
+
+    pub fn fill(&self, stock_id: i32, side: Side, price: Price, qty: u32, fee: Price) {
+        let mut positions = self.positions.lock().unwrap();
+        let position = positions.entry(stock_id).or_default();
+        position.fill(side, price, qty);
+        position.fees += fee.to_decimal();
+    }
+
+    /// By stock id, for symbols the strategy has seen or held.
+    pub fn positions(&self) -> BTreeMap<i32, Position> {
+        self.positions.lock().unwrap().clone()
+    }
+
+    /// `+12.34 (realized +10.00, fees 1.20), 3 symbols held`
+    pub fn describe(&self) -> String {
+        let positions = self.positions.lock().unwrap();
+        let total: Decimal = positions.values().map(Position::pnl).sum();
+        let realized: Decimal = positions.values().map(|p| p.realized).sum();
+        let fees: Decimal = positions.values().map(|p| p.fees).sum();
+        let held = positions.values().filter(|p| p.qty != 0).count();
+        format!("{:+.2} (realized {:+.2}, fees {:.2}), {} symbols held", total, realized, fees, held)
+    }
+
+    /// The positions the last run stored; marks come with the next tick.
+    pub async fn load(pool: &PgPool) -> io::Result<Self> {
+        let rows: Vec<(i32, i64, Decimal, Decimal, Decimal)> =
+            sqlx::query_as("SELECT stock_id, qty, avg_cost, realized, fees FROM positions")
+                .fetch_all(pool)
+                .await
+                .map_err(io::Error::other)?;
+        let positions = rows
+            .into_iter()
+            .map(|(stock_id, qty, avg_cost, realized, fees)| {
+                (stock_id, Position { qty, avg_cost, realized, mark: None, fees })
+            })
+            .collect::<BTreeMap<_, _>>();
+        info!("Loaded {} positions from Postgres", positions.len());
+        Ok(Portfolio { positions: Arc::new(Mutex::new(positions)) })
+    }
+
+    /// Upserts every position once a second, with the mark-to-market PnL
+    /// as of then.
+    pub async fn persist(self, pool: Arc<PgPool>) {
+        let mut interval = tokio::time::interval(PERSIST_EVERY);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.store(&pool).await {
+                warn!("Storing positions failed: {}", e);
+            }
+        }
+    }
+
+    async fn store(&self, pool: &PgPool) -> Result<(), sqlx::Error> {
+        let positions = self.positions();
+        // As `NUMERIC(18, 6)` keeps them.
+        let price = |money: Decimal| money.round_dp(SCALE);
+        let mut tx = pool.begin().await?;
+        for (stock_id, p) in positions {
+            sqlx::query(
+                "INSERT INTO positions (stock_id, qty, avg_cost, realized, unrealized, fees, updated_at) \
+                 VALUES ($1, $2, $3, $4, $5, $6, NOW()) \
+                 ON CONFLICT (stock_id) DO UPDATE SET qty = $2, avg_cost = $3, realized = $4, unrealized = $5, \
+                 fees = $6, updated_at = NOW()",
+            )
+            .bind(stock_id)
+            .bind(p.qty)
+            .bind(price(p.avg_cost))
+            .bind(price(p.realized))
+            .bind(price(p.unrealized()))
+            .bind(price(p.fees))
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect, symbols: &[Symbol]) {
+        let positions = self.positions();
+        let header = format!(
+            "{:<8} {:>8} {:>12} {:>12} {:>12} {:>12} {:>10} {:>12}",
+            "symbol", "qty", "avg cost", "mark", "realized", "unrealized", "fees", "pnl"
+        );
+        let mut lines = vec![Line::styled(header, Style::default().add_modifier(Modifier::BOLD))];
+        for (&stock_id, p) in &positions {
+            let ticker = usize::try_from(stock_id).ok().and_then(|id| symbols.get(id)).map_or("?", |s| &s.ticker);
+            let line = format!(
+                "{:<8} {:>8} {:>12.2} {:>12} {:>+12.2} {:>+12.2} {:>10.2} {:>+12.2}",
+                ticker,
+                p.qty,
+                p.avg_cost,
+                p.mark.map_or("-".to_string(), |mark| format!("{:.2}", mark.to_decimal())),
+                p.realized,
+                p.unrealized(),
+                p.fees,
+                p.pnl()
+            );
+            let color = if p.pnl() < Decimal::ZERO { Color::Red } else { Color::Green };
+            lines.push(Line::styled(line, Style::default().fg(color)));
+        }
+        if positions.is_empty() {
+            lines.push(Line::raw("no fills yet"));
+        } else {
+            lines.push(Line::styled(
+                format!("total {}", self.describe()),
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+        }
+        let title = "Positions - o charts";
+        f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(s: &str) -> Price {
+        s.parse().unwrap()
+    }
+
+    fn decimal(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn adding_averages_the_cost() {
+        let mut p = Position::default();
+        p.fill(Side::Buy, price("100"), 10);
+        p.fill(Side::Buy, price("100.03"), 20);
+        assert_eq!((p.qty, p.avg_cost, p.realized), (30, decimal("100.02"), Decimal::ZERO));
+    }
+
+    #[test]
+    fn reducing_realizes_against_the_average_cost() {
+        let mut p = Position::default();
+        p.fill(Side::Sell, price("50.10"), 10);
+        p.fill(Side::Buy, price("50.04"), 4);
+        assert_eq!((p.qty, p.avg_cost, p.realized), (-6, decimal("50.10"), decimal("0.24")));
+    }
+
+    #[test]
+    fn a_fill_through_flat_realizes_the_close_and_opens_the_rest_at_its_price() {
+        let mut p = Position::default();
+        p.fill(Side::Buy, price("100"), 10);
+        p.fill(Side::Sell, price("101.25"), 15);
+        assert_eq!((p.qty, p.avg_cost, p.realized), (-5, decimal("101.25"), decimal("12.5")));
+        p.fill(Side::Buy, price("100.25"), 8);
+        assert_eq!((p.qty, p.avg_cost, p.realized), (3, decimal("100.25"), decimal("17.5")));
+    }
+
+    #[test]
+    fn closing_to_flat_clears_the_cost() {
+        let mut p = Position::default();
+        p.fill(Side::Buy, price("10.01"), 3);
+        p.fill(Side::Sell, price("10.00"), 3);
+        assert_eq!((p.qty, p.avg_cost, p.realized), (0, Decimal::ZERO, decimal("-0.03")));
+    }
+
+    #[test]
+    fn pnl_marks_the_open_position_less_fees() {
+        let portfolio = Portfolio::default();
+        portfolio.fill(1, Side::Buy, price("20"), 100, price("0.30"));
+        portfolio.mark(1, price("20.05"));
+        let p = portfolio.positions()[&1];
+        assert_eq!((p.unrealized(), p.pnl()), (decimal("5"), decimal("4.7")));
+        assert_eq!(format!("{:+.2} {:>8.2}", p.pnl(), p.fees), "+4.70     0.30");
+    }
+}
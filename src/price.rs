@@ -0,0 +1,189 @@
+//! Fixed-point prices. A [`Price`] counts millionths, so prices add up and
+//! compare exactly, render as the decimal they are, and round-trip through
+//! the spool file and Postgres `NUMERIC(18, 6)` without loss.
+
+use std::fmt;
+use std::io;
+use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Decimal places a [`Price`] keeps.
+pub const SCALE: u32 = 6;
+const UNITS_PER_WHOLE: i64 = 10i64.pow(SCALE);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Price(i64);
+
+impl Price {
+    /// Nearest representable price; for values read from floating-point
+    /// sources such as the config file and old snapshots.
+    pub fn from_f64(value: f64) -> Self {
+        Price((value * UNITS_PER_WHOLE as f64).round() as i64)
+    }
+
+    /// For charts and binary wire formats that carry an `f64`.
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / UNITS_PER_WHOLE as f64
+    }
+
+    /// `None` if `value` does not fit once rounded to [`SCALE`] places.
+    pub fn from_decimal(value: Decimal) -> Option<Self> {
+        let mut value = value.round_dp(SCALE);
+        value.rescale(SCALE);
+        i64::try_from(value.mantissa()).ok().map(Price)
+    }
+
+    /// Millionths, as Arrow's `Decimal128(_, 6)` stores them.
+    pub fn units(self) -> i64 {
+        self.0
+    }
+
+    pub fn to_decimal(self) -> Decimal {
+        Decimal::new(self.0, SCALE)
+    }
+
+    /// How many `tick`s the nearest multiple of `tick` is, the matching
+    /// engine's integer price; `tick * n` turns it back into a price.
+    pub fn ticks(self, tick: Price) -> i64 {
+        self.round_to(tick).0 / tick.0
+    }
+
+    /// The nearest multiple of `tick`, halves rounding up.
+    pub fn round_to(self, tick: Price) -> Self {
+        let rem = self.0.rem_euclid(tick.0);
+        if rem * 2 >= tick.0 {
+            Price(self.0 - rem + tick.0)
+        } else {
+            Price(self.0 - rem)
+        }
+    }
+}
+
+impl Add for Price {
+    type Output = Price;
+
+    fn add(self, rhs: Price) -> Price {
+        Price(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Price {
+    type Output = Price;
+
+    fn sub(self, rhs: Price) -> Price {
+        Price(self.0 - rhs.0)
+    }
+}
+
+/// A whole number of ticks.
+impl Mul<i64> for Price {
+    type Output = Price;
+
+    fn mul(self, rhs: i64) -> Price {
+        Price(self.0 * rhs)
+    }
+}
+
+/// Shortest exact decimal: `100.25`, `99`, `-0.000001`.
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let units = self.0.unsigned_abs();
+        let whole = units / UNITS_PER_WHOLE as u64;
+        let frac = units % UNITS_PER_WHOLE as u64;
+        if frac == 0 {
+            return write!(f, "{}{}", sign, whole);
+        }
+        let digits = format!("{:0width$}", frac, width = SCALE as usize);
+        write!(f, "{}{}.{}", sign, whole, digits.trim_end_matches('0'))
+    }
+}
+
+impl FromStr for Price {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("invalid price {:?}", s));
+        let value = Decimal::from_str(s).map_err(|_| invalid())?;
+        Price::from_decimal(value).ok_or_else(invalid)
+    }
+}
+
+/// As a JSON number; every price with up to 15 significant digits survives
+/// the trip through `f64` unchanged.
+impl Serialize for Price {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_f64())
+    }
+}
+
+impl<'de> Deserialize<'de> for Price {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        f64::deserialize(deserializer).map(Price::from_f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(s: &str) -> Price {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn text_round_trips() {
+        for text in ["0", "99", "100.25", "-100.25", "-0.000001", "0.123456", "-42", "9223372036854.775807"] {
+            assert_eq!(price(text).to_string(), text);
+        }
+        assert_eq!(price("100.250000").to_string(), "100.25");
+        assert_eq!(price("-0.50").to_string(), "-0.5");
+        assert_eq!(price("+7").to_string(), "7");
+    }
+
+    #[test]
+    fn parse_rounds_past_six_decimals() {
+        assert_eq!(price("1.0000004"), price("1"));
+        assert_eq!(price("1.1234567"), price("1.123457"));
+        assert_eq!(price("-1.1234567"), price("-1.123457"));
+        assert_eq!(price("0.12345649999"), price("0.123456"));
+    }
+
+    #[test]
+    fn parse_refuses_what_is_not_a_price() {
+        for text in ["", "abc", "1.2.3", "1e", "9223372036855"] {
+            assert!(text.parse::<Price>().is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn round_to_takes_the_nearest_tick_halves_up() {
+        let tick = price("0.05");
+        assert_eq!(price("1.02").round_to(tick), price("1"));
+        assert_eq!(price("1.025").round_to(tick), price("1.05"));
+        assert_eq!(price("1.03").round_to(tick), price("1.05"));
+        assert_eq!(price("1.05").round_to(tick), price("1.05"));
+        assert_eq!(price("-1.02").round_to(tick), price("-1"));
+        assert_eq!(price("-1.025").round_to(tick), price("-1"));
+        assert_eq!(price("-1.03").round_to(tick), price("-1.05"));
+    }
+
+    #[test]
+    fn from_decimal_refuses_what_does_not_fit() {
+        assert_eq!(Price::from_decimal(Decimal::new(i64::MAX, SCALE)), Some(Price(i64::MAX)));
+        assert_eq!(Price::from_decimal(Decimal::new(i64::MIN, SCALE)), Some(Price(i64::MIN)));
+        assert_eq!(Price::from_decimal(Decimal::new(i64::MAX, SCALE) + Decimal::new(1, SCALE)), None);
+        assert_eq!(Price::from_decimal(Decimal::MAX), None);
+        assert_eq!(Price::from_decimal(Decimal::MIN), None);
+    }
+
+    #[test]
+    fn decimals_round_trip() {
+        for text in ["100.25", "-0.000001", "0"] {
+            assert_eq!(Price::from_decimal(price(text).to_decimal()), Some(price(text)));
+        }
+    }
+}
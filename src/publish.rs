@@ -0,0 +1,124 @@
+//! Hands a new tick to everything downstream: the tick bus, the TUI's
+//! conflator, the Parquet exporter, the Redis cache and the spool writer's
+//! queue. Shared by the
+//! simulator and the feed aggregator so every source is treated alike.
+//! Ticks written to Redis are checked for sequence gaps. New prices pass the
+//! `[[plugins]]` before they are applied to the market data, and a
+//! `[filter]` script sees each tick before it is published; what either
+//! drops goes nowhere.
+
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use tokio::runtime::Handle;
+use tracing::{info_span, Instrument};
+
+use crate::breaker::CircuitBreaker;
+use crate::cache::RedisCache;
+use crate::conflate::Conflator;
+use crate::export::ParquetExporter;
+use crate::inject::Injector;
+use crate::latency::{LatencyRecorder, Stage};
+use crate::market::MarketData;
+use crate::plugin::PluginHost;
+use crate::price::Price;
+use crate::queue::BoundedQueue;
+use crate::retry::Retrier;
+use crate::script::TickFilter;
+use crate::sequence::SeqCheck;
+use crate::symbols::Symbol;
+use crate::tick::{Tick, TickSender};
+use crate::timing::SharedClock;
+use crate::ulid::Ulid;
+use crate::vol::RealizedVol;
+
+#[derive(Clone)]
+pub struct Publisher {
+    pub ticks: TickSender,
+    pub conflator: Conflator,
+    pub exporter: Arc<Mutex<ParquetExporter>>,
+    pub realized_vol: RealizedVol,
+    pub queue: BoundedQueue<Tick>,
+    pub redis_cache: Arc<RedisCache>,
+    pub redis_retry: Retrier,
+    pub redis_breaker: CircuitBreaker,
+    pub redis_seq: Arc<Mutex<SeqCheck>>,
+    pub latency: LatencyRecorder,
+    pub timer: SharedClock,
+    pub injector: Injector,
+    pub filter: Option<Arc<Mutex<TickFilter>>>,
+    pub plugins: PluginHost,
+    /// By stock id, for ticks the plugins emit.
+    pub symbols: Arc<Vec<Symbol>>,
+}
+
+impl Publisher {
+    /// A source's new price for `stock_id`: runs it through the plugins,
+    /// applies what comes out and any ticks they emitted to `market`, and
+    /// publishes them. The price's tick keeps `tick_id` if the source sent
+    /// one; the others get a new ID. Returns the ticks to enqueue once the
+    /// market-data lock is released.
+    pub fn admit(
+        &self,
+        rt: &Handle,
+        market: &mut [MarketData],
+        stock_id: usize,
+        price: Price,
+        ts: SystemTime,
+        tick_id: Option<Ulid>,
+    ) -> Vec<Tick> {
+        let (price, emitted) = self.plugins.apply(stock_id, price);
+        price
+            .map(|price| (stock_id, price, tick_id))
+            .into_iter()
+            .chain(emitted.into_iter().map(|(id, price)| (id, price, None)))
+            .filter_map(|(id, price, tick_id)| {
+                let seq = market[id].update(price, ts);
+                let tick = Tick { stock_id: id as i32, price, ts, seq, id: tick_id.unwrap_or_else(|| Ulid::new(ts)) };
+                self.publish(rt, tick, &self.symbols[id]).then_some(tick)
+            })
+            .collect()
+    }
+
+    /// Everything but the spool queue; Redis is written on a task on `rt`.
+    /// `false` if the filter dropped the tick, which then should not be
+    /// enqueued either.
+    pub fn publish(&self, rt: &Handle, tick: Tick, symbol: &Symbol) -> bool {
+        if let Some(filter) = &self.filter {
+            if !filter.lock().unwrap().keep(&tick, symbol) {
+                return false;
+            }
+        }
+        let _ = self.ticks.send(tick);
+        self.conflator.record(tick);
+        let vol = self.realized_vol.record(&tick);
+        self.exporter.lock().unwrap().record_tick(tick, vol);
+
+        let this = self.clone();
+        let redis_key = symbol.redis_key();
+        let span = info_span!("stage", stage = Stage::RedisSet.as_str(), symbol = %symbol.ticker);
+        let task = async move {
+            if !this.redis_breaker.allow() {
+                return;
+            }
+            let started = this.timer.now_nanos();
+            this.injector.apply(Stage::RedisSet).await;
+            let set = this.redis_retry.run("set", || this.redis_cache.set(&redis_key, tick.price.to_string())).await;
+            if set.is_ok() {
+                this.redis_breaker.record_success();
+                this.redis_seq.lock().unwrap().observe(tick.stock_id, tick.seq);
+                this.latency.record(Stage::RedisSet, Some(tick.stock_id), this.timer.elapsed(started));
+            } else {
+                this.redis_breaker.record_failure();
+            }
+        };
+        rt.spawn(task.instrument(span));
+        true
+    }
+
+    /// May block under the `block` overflow policy, so callers hold no
+    /// market-data lock.
+    pub fn enqueue(&self, tick: Tick) {
+        self.queue.push(tick);
+    }
+}
@@ -0,0 +1,178 @@
+//! The SQL pane: one statement typed in the TUI, run against the pipeline's
+//! Postgres in a read-only transaction with a statement timeout, and its
+//! first rows paged in place, for checking what the flush wrote. Every
+//! value is shown as Postgres prints it, NULL as an empty cell.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::TryStreamExt;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+use sqlx::{Column, PgPool, Row};
+
+use crate::keymap::{Action, Keymap};
+
+/// Rows kept of a result; the rest are counted as cut.
+const MAX_ROWS: usize = 1000;
+const STATEMENT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Widest a column is drawn, its header included.
+const MAX_COLUMN_WIDTH: usize = 32;
+
+enum State {
+    Idle,
+    Running { sql: String, since: Instant },
+    Done { sql: String, columns: Vec<String>, rows: Vec<Vec<String>>, cut: bool, took: Duration },
+    Failed { sql: String, error: String },
+}
+
+#[derive(Clone)]
+pub struct QueryPane {
+    pool: Arc<PgPool>,
+    state: Arc<Mutex<State>>,
+}
+
+impl QueryPane {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        QueryPane { pool, state: Arc::new(Mutex::new(State::Idle)) }
+    }
+
+    /// Starts `sql` in the background, replacing the last result. A query
+    /// still running is left to finish and its result dropped.
+    pub fn run(&self, sql: String) {
+        let sql = sql.trim().trim_end_matches(';').trim().to_string();
+        if sql.is_empty() {
+            return;
+        }
+        // A second statement could end the read-only transaction.
+        if sql.contains(';') {
+            *self.state.lock().unwrap() = State::Failed { sql, error: "one statement at a time".to_string() };
+            return;
+        }
+        *self.state.lock().unwrap() = State::Running { sql: sql.clone(), since: Instant::now() };
+        let pane = self.clone();
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let result = pane.fetch(&sql).await;
+            let mut state = pane.state.lock().unwrap();
+            if !matches!(&*state, State::Running { sql: running, .. } if *running == sql) {
+                return;
+            }
+            *state = match result {
+                Ok((columns, rows, cut)) => State::Done { sql, columns, rows, cut, took: started.elapsed() },
+                Err(e) => State::Failed { sql, error: e.to_string() },
+            };
+        });
+    }
+
+    /// The column names and the first [`MAX_ROWS`] rows as text, and
+    /// whether there were more.
+    async fn fetch(&self, sql: &str) -> sqlx::Result<(Vec<String>, Vec<Vec<String>>, bool)> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SET TRANSACTION READ ONLY").execute(&mut *tx).await?;
+        sqlx::query(&format!("SET LOCAL statement_timeout = {}", STATEMENT_TIMEOUT.as_millis()))
+            .execute(&mut *tx)
+            .await?;
+        let (mut columns, mut rows, mut cut) = (Vec::new(), Vec::new(), false);
+        {
+            // The simple protocol, which returns every value as text.
+            let mut stream = sqlx::raw_sql(sql).fetch(&mut *tx);
+            while let Some(row) = stream.try_next().await? {
+                if rows.len() == MAX_ROWS {
+                    cut = true;
+                    break;
+                }
+                if columns.is_empty() {
+                    columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+                }
+                let values = (0..row.len())
+                    .map(|i| row.try_get_unchecked::<Option<String>, _>(i).map(Option::unwrap_or_default))
+                    .collect::<sqlx::Result<_>>()?;
+                rows.push(values);
+            }
+        }
+        tx.rollback().await?;
+        Ok((columns, rows, cut))
+    }
+
+    /// Pages in the last result at `area`'s height.
+    pub fn pages(&self, area: Rect) -> usize {
+        match &*self.state.lock().unwrap() {
+            State::Done { rows, .. } => rows.len().div_ceil(page_rows(area)).max(1),
+            _ => 1,
+        }
+    }
+
+    /// The statement being typed as `input`, and below it the state of the
+    /// last one run or page `page` of its rows.
+    pub fn render(&self, f: &mut Frame, area: Rect, input: Line, page: usize, keymap: &Keymap) {
+        let mut lines = vec![input, Line::raw("")];
+        let mut title = keymap.title(
+            "SQL",
+            &[
+                (&[], "Enter edits and runs"),
+                (&[], "Esc stops editing"),
+                (&[Action::NextPage, Action::PreviousPage], "page"),
+                (&[Action::Query], "closes"),
+            ],
+        );
+        match &*self.state.lock().unwrap() {
+            State::Idle => lines.push(Line::raw("type a statement, e.g. SELECT * FROM stock_data ORDER BY ts DESC")),
+            State::Running { sql, since } => {
+                lines.push(Line::raw(format!("running for {:.1}s: {}", since.elapsed().as_secs_f64(), sql)))
+            }
+            State::Failed { sql, error } => {
+                lines.push(Line::raw(sql.clone()));
+                lines.push(Line::styled(error.clone(), Style::default().fg(Color::Red)));
+            }
+            State::Done { sql, columns, rows, cut, took } => {
+                let per_page = page_rows(area);
+                title = format!("{} (page {}/{})", title, page + 1, rows.len().div_ceil(per_page).max(1));
+                let more = if *cut { format!(", first {} kept", MAX_ROWS) } else { String::new() };
+                lines.push(Line::raw(format!(
+                    "{} rows in {:.1} ms{}: {}",
+                    rows.len(),
+                    took.as_secs_f64() * 1e3,
+                    more,
+                    sql
+                )));
+                let widths: Vec<usize> = (0..columns.len())
+                    .map(|i| {
+                        let widest = rows.iter().map(|row| row[i].chars().count()).max().unwrap_or(0);
+                        widest.max(columns[i].chars().count()).min(MAX_COLUMN_WIDTH)
+                    })
+                    .collect();
+                let bold = Style::default().add_modifier(Modifier::BOLD);
+                lines.push(Line::styled(table_row(columns, &widths), bold));
+                for row in rows.iter().skip(page * per_page).take(per_page) {
+                    lines.push(Line::raw(table_row(row, &widths)));
+                }
+            }
+        }
+        f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+    }
+}
+
+/// Result rows under the input, status and header lines.
+fn page_rows(area: Rect) -> usize {
+    (area.height.saturating_sub(6) as usize).max(1)
+}
+
+/// The cells padded to `widths`, cut with `…` past them.
+fn table_row(cells: &[String], widths: &[usize]) -> String {
+    let cells: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, &width)| {
+            if cell.chars().count() > width {
+                format!("{}…", cell.chars().take(width - 1).collect::<String>())
+            } else {
+                format!("{:<width$}", cell, width = width)
+            }
+        })
+        .collect();
+    cells.join(" | ")
+}
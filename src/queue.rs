@@ -0,0 +1,110 @@
+//! Bounded hand-off from the producer to the spool writer, so a slow Postgres
+//! flush pushes back on the producer instead of growing the spool forever.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::{OverflowPolicy, QueueConfig};
+
+struct State<T> {
+    items: VecDeque<T>,
+    peak: usize,
+    dropped: u64,
+    blocked: Duration,
+}
+
+struct Shared<T> {
+    capacity: usize,
+    policy: OverflowPolicy,
+    state: Mutex<State<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+/// Multi-producer queue holding at most `capacity` items, overflowing as
+/// configured by `on_full`.
+pub struct BoundedQueue<T> {
+    inner: Arc<Shared<T>>,
+}
+
+impl<T> Clone for BoundedQueue<T> {
+    fn clone(&self) -> Self {
+        BoundedQueue { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<T> BoundedQueue<T> {
+    pub fn new(cfg: &QueueConfig) -> Self {
+        let capacity = cfg.capacity.max(1);
+        BoundedQueue {
+            inner: Arc::new(Shared {
+                capacity,
+                policy: cfg.on_full,
+                state: Mutex::new(State {
+                    items: VecDeque::with_capacity(capacity),
+                    peak: 0,
+                    dropped: 0,
+                    blocked: Duration::ZERO,
+                }),
+                not_empty: Condvar::new(),
+                not_full: Condvar::new(),
+            }),
+        }
+    }
+
+    pub fn push(&self, item: T) {
+        let shared = &*self.inner;
+        let mut state = shared.state.lock().unwrap();
+        if state.items.len() >= shared.capacity {
+            match shared.policy {
+                OverflowPolicy::Block => {
+                    let started = Instant::now();
+                    while state.items.len() >= shared.capacity {
+                        state = shared.not_full.wait(state).unwrap();
+                    }
+                    state.blocked += started.elapsed();
+                }
+                OverflowPolicy::DropOldest => {
+                    state.items.pop_front();
+                    state.dropped += 1;
+                }
+                OverflowPolicy::DropNewest => {
+                    state.dropped += 1;
+                    return;
+                }
+            }
+        }
+        state.items.push_back(item);
+        state.peak = state.peak.max(state.items.len());
+        shared.not_empty.notify_one();
+    }
+
+    /// Takes up to `max` items, waiting at most `timeout` for the first one.
+    pub fn pop_batch(&self, max: usize, timeout: Duration) -> Vec<T> {
+        let shared = &*self.inner;
+        let state = shared.state.lock().unwrap();
+        let (mut state, _) = shared
+            .not_empty
+            .wait_timeout_while(state, timeout, |s| s.items.is_empty())
+            .unwrap();
+        let n = state.items.len().min(max);
+        let batch: Vec<T> = state.items.drain(..n).collect();
+        if !batch.is_empty() {
+            shared.not_full.notify_all();
+        }
+        batch
+    }
+
+    /// One-line summary for the TUI.
+    pub fn describe(&self) -> String {
+        let shared = &*self.inner;
+        let state = shared.state.lock().unwrap();
+        let overflow = match shared.policy {
+            OverflowPolicy::Block => format!("block, {:.1}s blocked", state.blocked.as_secs_f64()),
+            OverflowPolicy::DropOldest => format!("drop_oldest, {} dropped", state.dropped),
+            OverflowPolicy::DropNewest => format!("drop_newest, {} dropped", state.dropped),
+        };
+        format!("{}/{} (peak {}), {}", state.items.len(), shared.capacity, state.peak, overflow)
+    }
+}
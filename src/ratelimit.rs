@@ -0,0 +1,227 @@
+//! Per-sink token buckets, so a slow or expensive destination is fed at a
+//! bounded rate instead of silently backing up the pipeline.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SinkKind {
+    /// Rows flushed from the spool.
+    Postgres,
+    Kdb,
+    Clickhouse,
+    Questdb,
+    Influxdb,
+    Kafka,
+    Nats,
+    Zmq,
+    Multicast,
+    Shm,
+}
+
+impl SinkKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SinkKind::Postgres => "postgres",
+            SinkKind::Kdb => "kdb",
+            SinkKind::Clickhouse => "clickhouse",
+            SinkKind::Questdb => "questdb",
+            SinkKind::Influxdb => "influxdb",
+            SinkKind::Kafka => "kafka",
+            SinkKind::Nats => "nats",
+            SinkKind::Zmq => "zmq",
+            SinkKind::Multicast => "multicast",
+            SinkKind::Shm => "shm",
+        }
+    }
+}
+
+/// What happens to rows over the limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitPolicy {
+    /// Hold them until tokens are available. Postgres keeps them in the spool
+    /// for the next flush; other sinks wait, and may lag the tick bus.
+    #[default]
+    Defer,
+    Drop,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub rows_per_sec: f64,
+    /// Bucket size [default: one second's worth].
+    #[serde(default)]
+    pub burst: Option<f64>,
+    #[serde(default)]
+    pub on_limit: LimitPolicy,
+}
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+struct Limited {
+    rate: f64,
+    burst: f64,
+    policy: LimitPolicy,
+    bucket: Mutex<Bucket>,
+    passed: AtomicU64,
+    deferred: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl Limited {
+    /// Takes up to `n` whole tokens; returns how many were taken.
+    fn take_up_to(&self, n: usize) -> usize {
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        bucket.tokens = (bucket.tokens + (now - bucket.last).as_secs_f64() * self.rate).min(self.burst);
+        bucket.last = now;
+        let taken = (bucket.tokens.floor().max(0.0) as usize).min(n);
+        bucket.tokens -= taken as f64;
+        taken
+    }
+}
+
+/// Handle to one sink's bucket; unlimited when the sink has none configured.
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    inner: Option<Arc<Limited>>,
+}
+
+impl RateLimiter {
+    fn new(kind: SinkKind, cfg: &RateLimitConfig) -> io::Result<Self> {
+        let invalid = |key: &str, message: &str| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("rate_limits.{}.{}: {}", kind.as_str(), key, message))
+        };
+        // A rate of 0 would make a deferred write wait forever.
+        if !(cfg.rows_per_sec.is_finite() && cfg.rows_per_sec > 0.0) {
+            return Err(invalid("rows_per_sec", "must be a number above 0"));
+        }
+        if cfg.burst.is_some_and(|burst| !(burst.is_finite() && burst > 0.0)) {
+            return Err(invalid("burst", "must be a number above 0"));
+        }
+        let rate = cfg.rows_per_sec;
+        let burst = cfg.burst.unwrap_or(rate).max(1.0);
+        Ok(RateLimiter {
+            inner: Some(Arc::new(Limited {
+                rate,
+                burst,
+                policy: cfg.on_limit,
+                bucket: Mutex::new(Bucket { tokens: burst, last: Instant::now() }),
+                passed: AtomicU64::new(0),
+                deferred: AtomicU64::new(0),
+                dropped: AtomicU64::new(0),
+            })),
+        })
+    }
+
+    /// Admits `n` rows, waiting for tokens or dropping the excess depending
+    /// on the policy. Returns how many rows may be written.
+    pub async fn admit(&self, n: usize) -> usize {
+        let Some(l) = &self.inner else {
+            return n;
+        };
+        let mut granted = l.take_up_to(n);
+        if granted < n {
+            match l.policy {
+                LimitPolicy::Drop => {
+                    l.dropped.fetch_add((n - granted) as u64, Ordering::Relaxed);
+                }
+                LimitPolicy::Defer => {
+                    l.deferred.fetch_add((n - granted) as u64, Ordering::Relaxed);
+                    while granted < n {
+                        let missing = (n - granted).min(l.burst as usize).max(1);
+                        tokio::time::sleep(Duration::from_secs_f64(missing as f64 / l.rate)).await;
+                        granted += l.take_up_to(n - granted);
+                    }
+                }
+            }
+        }
+        l.passed.fetch_add(granted as u64, Ordering::Relaxed);
+        granted
+    }
+
+    /// Admits as much of `batch` as the policy allows, truncating the rest.
+    /// Returns whether anything is left to write.
+    pub async fn admit_batch<T>(&self, batch: &mut Vec<T>) -> bool {
+        let granted = self.admit(batch.len()).await;
+        batch.truncate(granted);
+        !batch.is_empty()
+    }
+
+    /// Takes what is available without waiting. For rows not admitted the
+    /// caller applies [`policy`](Self::policy) and reports it through
+    /// [`record_held`](Self::record_held).
+    pub fn try_admit(&self, n: usize) -> usize {
+        let Some(l) = &self.inner else {
+            return n;
+        };
+        let granted = l.take_up_to(n);
+        l.passed.fetch_add(granted as u64, Ordering::Relaxed);
+        granted
+    }
+
+    pub fn policy(&self) -> Option<LimitPolicy> {
+        self.inner.as_ref().map(|l| l.policy)
+    }
+
+    /// Counts rows `try_admit` turned away as deferred or dropped.
+    pub fn record_held(&self, n: usize) {
+        if let Some(l) = &self.inner {
+            let counter = match l.policy {
+                LimitPolicy::Defer => &l.deferred,
+                LimitPolicy::Drop => &l.dropped,
+            };
+            counter.fetch_add(n as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Every configured limiter, by sink.
+#[derive(Clone, Default)]
+pub struct RateLimits {
+    limiters: BTreeMap<SinkKind, RateLimiter>,
+}
+
+impl RateLimits {
+    pub fn new(cfg: &BTreeMap<SinkKind, RateLimitConfig>) -> io::Result<Self> {
+        let limiters = cfg.iter().map(|(&kind, c)| Ok((kind, RateLimiter::new(kind, c)?))).collect::<io::Result<_>>()?;
+        Ok(RateLimits { limiters })
+    }
+
+    pub fn get(&self, kind: SinkKind) -> RateLimiter {
+        self.limiters.get(&kind).cloned().unwrap_or_default()
+    }
+
+    /// One-line summary for the TUI.
+    pub fn describe(&self) -> String {
+        if self.limiters.is_empty() {
+            return "none".to_string();
+        }
+        self.limiters
+            .iter()
+            .filter_map(|(kind, limiter)| limiter.inner.as_ref().map(|l| (kind, l)))
+            .map(|(kind, l)| {
+                format!(
+                    "{} {}/s: {} written, {} deferred, {} dropped",
+                    kind.as_str(),
+                    l.rate,
+                    l.passed.load(Ordering::Relaxed),
+                    l.deferred.load(Ordering::Relaxed),
+                    l.dropped.load(Ordering::Relaxed)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
@@ -0,0 +1,43 @@
+//! How long the TUI waits between frames. A quiet market needs a frame every
+//! half second at most; a busy one as many as the terminal takes. The
+//! interval follows the tick rate of the last frame, dropping to the new
+//! rate at once and relaxing slowly, so a burst is drawn smoothly and a
+//! brief pause does not make the next one stutter. Key presses are drawn
+//! immediately whatever the interval.
+
+use std::time::Duration;
+
+use crate::config::UiConfig;
+
+/// Ticks per frame the interval aims for, so each frame shows news.
+const TICKS_PER_FRAME: f64 = 1.0;
+/// Growth per frame while the rate falls.
+const RELAX: f64 = 1.25;
+
+pub struct RefreshScheduler {
+    min: Duration,
+    max: Duration,
+    interval: Duration,
+}
+
+impl RefreshScheduler {
+    pub fn new(cfg: &UiConfig) -> Self {
+        let min = Duration::from_millis(cfg.min_frame_ms);
+        let max = Duration::from_millis(cfg.max_frame_ms).max(min);
+        RefreshScheduler { min, max, interval: min }
+    }
+
+    /// The wait before the next frame, given the ticks that arrived over the
+    /// last `elapsed`.
+    pub fn next(&mut self, ticks: u64, elapsed: Duration) -> Duration {
+        let rate = ticks as f64 / elapsed.as_secs_f64().max(f64::MIN_POSITIVE);
+        let target = if rate > 0.0 { Duration::from_secs_f64((TICKS_PER_FRAME / rate).min(3600.0)) } else { self.max };
+        self.interval = target.min(self.interval.mul_f64(RELAX)).clamp(self.min, self.max);
+        self.interval
+    }
+
+    /// `60ms (16 to 500)`
+    pub fn describe(&self) -> String {
+        format!("{}ms ({} to {})", self.interval.as_millis(), self.min.as_millis(), self.max.as_millis())
+    }
+}
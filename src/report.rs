@@ -0,0 +1,312 @@
+//! The summary printed when the TUI exits, so every run leaves a result
+//! behind: per-stage latency percentiles, then the drop and error counters
+//! the Diagnostics panel showed last. `--report` writes the same run as a
+//! standalone HTML page with percentile curves, or as JSON for CI.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+
+use crate::latency::{Stage, StageSummary};
+
+const COLORS: [&str; 4] = ["#4e79a7", "#f28e2b", "#59a14f", "#e15759"];
+const WIDTH: f64 = 760.0;
+const HEIGHT: f64 = 340.0;
+const MARGIN: f64 = 56.0;
+
+/// `counters` are `(label, description)` pairs, e.g. the spool queue's drops.
+pub fn render(run_time: Duration, stages: &[StageSummary], counters: &[(&str, String)]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "Latency summary after {:.1}s", run_time.as_secs_f64());
+    if stages.is_empty() {
+        let _ = writeln!(out, "no latency samples recorded");
+    } else {
+        let width = stages.iter().map(|s| s.stage.as_str().len()).max().unwrap_or(0).max(5);
+        let _ = writeln!(
+            out,
+            "{:<width$} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+            "stage", "count", "min", "mean", "p50", "p90", "p99", "p99.9", "max"
+        );
+        let us = |nanos: f64| format!("{:.2}", nanos / 1000.0);
+        for s in stages {
+            let _ = writeln!(
+                out,
+                "{:<width$} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+                s.stage.as_str(),
+                s.count,
+                us(s.min as f64),
+                us(s.mean),
+                us(s.p50 as f64),
+                us(s.p90 as f64),
+                us(s.p99 as f64),
+                us(s.p999 as f64),
+                us(s.max as f64)
+            );
+        }
+        let _ = writeln!(out, "(latencies in µs)");
+    }
+    let _ = writeln!(out);
+    for (label, description) in counters {
+        let _ = writeln!(out, "{}: {}", label, description);
+    }
+    out
+}
+
+/// Everything `--report` writes about a run.
+pub struct Report<'a> {
+    pub run_time: Duration,
+    pub stages: &'a [StageSummary],
+    /// `(percentile, nanos)` points per stage.
+    pub curves: &'a BTreeMap<Stage, Vec<(f64, u64)>>,
+    pub counters: &'a [(&'a str, String)],
+}
+
+#[derive(Serialize)]
+struct JsonPoint {
+    percentile: f64,
+    nanos: u64,
+}
+
+#[derive(Serialize)]
+struct JsonStage<'a> {
+    #[serde(flatten)]
+    summary: &'a StageSummary,
+    percentiles: Vec<JsonPoint>,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    generated: String,
+    run_secs: f64,
+    /// Latencies in nanoseconds.
+    stages: Vec<JsonStage<'a>>,
+    counters: BTreeMap<&'a str, &'a str>,
+}
+
+impl Report<'_> {
+    /// JSON if `path` ends in `.json`, HTML otherwise.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let contents = if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+            self.json()?
+        } else {
+            self.html()
+        };
+        fs::write(path, contents).map_err(|e| io::Error::other(format!("writing {}: {}", path.display(), e)))
+    }
+
+    fn json(&self) -> io::Result<String> {
+        let report = JsonReport {
+            generated: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            run_secs: self.run_time.as_secs_f64(),
+            stages: self
+                .stages
+                .iter()
+                .map(|summary| JsonStage {
+                    summary,
+                    percentiles: self.curves.get(&summary.stage).map_or(Vec::new(), |curve| {
+                        curve.iter().map(|&(percentile, nanos)| JsonPoint { percentile, nanos }).collect()
+                    }),
+                })
+                .collect(),
+            counters: self.counters.iter().map(|(label, description)| (*label, description.as_str())).collect(),
+        };
+        serde_json::to_string_pretty(&report).map_err(io::Error::other)
+    }
+
+    fn html(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Latency report</title>\n<style>\n\
+             body {{ font-family: sans-serif; margin: 2em; color: #222; }}\n\
+             table {{ border-collapse: collapse; }}\n\
+             td, th {{ padding: 2px 10px; text-align: right; border-bottom: 1px solid #ddd; }}\n\
+             td:first-child, th:first-child {{ text-align: left; }}\n\
+             svg text {{ font-size: 11px; fill: #444; }}\n\
+             </style></head><body>"
+        );
+        let _ = writeln!(
+            out,
+            "<h1>Latency report</h1>\n<p>{:.1}s run, generated {}</p>",
+            self.run_time.as_secs_f64(),
+            Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+        );
+        if self.stages.is_empty() {
+            let _ = writeln!(out, "<p>No latency samples recorded.</p>");
+        } else {
+            let values = self.curves.values().flatten().map(|&(_, nanos)| nanos);
+            let scale = LogScale::new(values.clone().min().unwrap_or(1), values.max().unwrap_or(1));
+            let _ = writeln!(out, "<h2>Percentile curves</h2>");
+            self.curves_svg(&mut out, &scale);
+            let _ = writeln!(out, "<h2>Per-stage comparison</h2>");
+            self.bars_svg(&mut out, &scale);
+            let _ = writeln!(out, "<h2>Summary (µs)</h2>");
+            self.summary_table(&mut out);
+        }
+        let _ = writeln!(out, "<h2>Counters</h2>\n<table>");
+        for (label, description) in self.counters {
+            let _ =
+                writeln!(out, "<tr><td>{}</td><td style=\"text-align: left\">{}</td></tr>", label, escape(description));
+        }
+        let _ = writeln!(out, "</table>\n</body></html>");
+        out
+    }
+
+    /// Latency against percentile, spaced in nines so the tail gets most of
+    /// the width.
+    fn curves_svg(&self, out: &mut String, scale: &LogScale) {
+        let nines = |percentile: f64| -(1.0 - percentile / 100.0).max(f64::MIN_POSITIVE).log10();
+        let max_nines = self.curves.values().flatten().map(|&(p, _)| nines(p)).fold(1.0, f64::max);
+        let x = |percentile: f64| MARGIN + nines(percentile) / max_nines * (WIDTH - 2.0 * MARGIN);
+        let _ = writeln!(out, "<svg width=\"{}\" height=\"{}\">", WIDTH, HEIGHT);
+        scale.gridlines(out);
+        for k in 0..=(max_nines + 1e-9).floor() as i32 {
+            let label = match k {
+                0 => "p0".to_string(),
+                1 => "p90".to_string(),
+                _ => format!("p99{}{}", if k > 2 { "." } else { "" }, "9".repeat(k as usize - 2)),
+            };
+            let at = MARGIN + k as f64 / max_nines * (WIDTH - 2.0 * MARGIN);
+            let _ = writeln!(
+                out,
+                "<line x1=\"{at:.1}\" y1=\"{}\" x2=\"{at:.1}\" y2=\"{}\" stroke=\"#eee\"/>\
+                 <text x=\"{at:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+                MARGIN / 2.0,
+                HEIGHT - MARGIN,
+                HEIGHT - MARGIN + 16.0,
+                label
+            );
+        }
+        for (i, (stage, curve)) in self.curves.iter().enumerate() {
+            let points: Vec<String> =
+                curve.iter().map(|&(p, nanos)| format!("{:.1},{:.1}", x(p), scale.y(nanos as f64))).collect();
+            let color = COLORS[i % COLORS.len()];
+            let _ = writeln!(
+                out,
+                "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" points=\"{}\"><title>{}</title></polyline>\
+                 <text x=\"{}\" y=\"{}\" style=\"fill: {}\">{}</text>",
+                color,
+                points.join(" "),
+                stage.as_str(),
+                MARGIN + 10.0,
+                MARGIN / 2.0 + 14.0 * (i + 1) as f64,
+                color,
+                stage.as_str()
+            );
+        }
+        let _ = writeln!(out, "</svg>");
+    }
+
+    /// p50, p99 and p99.9 side by side per stage, on the curves' scale.
+    fn bars_svg(&self, out: &mut String, scale: &LogScale) {
+        let group = (WIDTH - 2.0 * MARGIN) / self.stages.len() as f64;
+        let bar = group / 4.0;
+        let _ = writeln!(out, "<svg width=\"{}\" height=\"{}\">", WIDTH, HEIGHT);
+        scale.gridlines(out);
+        for (i, s) in self.stages.iter().enumerate() {
+            let left = MARGIN + i as f64 * group + bar / 2.0;
+            for (j, (name, nanos)) in [("p50", s.p50), ("p99", s.p99), ("p99.9", s.p999)].into_iter().enumerate() {
+                let top = scale.y(nanos as f64);
+                let _ = writeln!(
+                    out,
+                    "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\">\
+                     <title>{} {} {:.2} µs</title></rect>",
+                    left + j as f64 * bar,
+                    top,
+                    bar - 2.0,
+                    HEIGHT - MARGIN - top,
+                    COLORS[j],
+                    s.stage.as_str(),
+                    name,
+                    nanos as f64 / 1000.0
+                );
+            }
+            let _ = writeln!(
+                out,
+                "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+                left + 1.5 * bar,
+                HEIGHT - MARGIN + 16.0,
+                s.stage.as_str()
+            );
+        }
+        for (j, name) in ["p50", "p99", "p99.9"].into_iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "<text x=\"{}\" y=\"{}\" style=\"fill: {}\">{}</text>",
+                WIDTH - MARGIN - 40.0,
+                MARGIN / 2.0 + 14.0 * (j + 1) as f64,
+                COLORS[j],
+                name
+            );
+        }
+        let _ = writeln!(out, "</svg>");
+    }
+
+    fn summary_table(&self, out: &mut String) {
+        let _ = writeln!(
+            out,
+            "<table>\n<tr><th>stage</th><th>count</th><th>min</th><th>mean</th><th>p50</th><th>p90</th>\
+             <th>p99</th><th>p99.9</th><th>max</th></tr>"
+        );
+        for s in self.stages {
+            let _ = write!(out, "<tr><td>{}</td><td>{}</td>", s.stage.as_str(), s.count);
+            for nanos in [s.min as f64, s.mean, s.p50 as f64, s.p90 as f64, s.p99 as f64, s.p999 as f64, s.max as f64] {
+                let _ = write!(out, "<td>{:.2}</td>", nanos / 1000.0);
+            }
+            let _ = writeln!(out, "</tr>");
+        }
+        let _ = writeln!(out, "</table>");
+    }
+}
+
+/// Whole decades of nanoseconds mapped onto the chart height.
+struct LogScale {
+    lo: i32,
+    hi: i32,
+}
+
+impl LogScale {
+    fn new(min: u64, max: u64) -> Self {
+        let lo = (min.max(1) as f64).log10().floor() as i32;
+        let hi = ((max.max(1) as f64).log10().ceil() as i32).max(lo + 1);
+        LogScale { lo, hi }
+    }
+
+    fn y(&self, nanos: f64) -> f64 {
+        let fraction = (nanos.max(1.0).log10() - self.lo as f64) / (self.hi - self.lo) as f64;
+        HEIGHT - MARGIN - fraction.clamp(0.0, 1.0) * (HEIGHT - 1.5 * MARGIN)
+    }
+
+    fn gridlines(&self, out: &mut String) {
+        for decade in self.lo..=self.hi {
+            let nanos = 10f64.powi(decade);
+            let y = self.y(nanos);
+            let label = match nanos {
+                n if n >= 1e9 => format!("{} s", n / 1e9),
+                n if n >= 1e6 => format!("{} ms", n / 1e6),
+                n if n >= 1e3 => format!("{} µs", n / 1e3),
+                n => format!("{} ns", n),
+            };
+            let _ = writeln!(
+                out,
+                "<line x1=\"{}\" y1=\"{y:.1}\" x2=\"{}\" y2=\"{y:.1}\" stroke=\"#ddd\"/>\
+                 <text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+                MARGIN,
+                WIDTH - MARGIN,
+                MARGIN - 6.0,
+                y + 4.0,
+                label
+            );
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
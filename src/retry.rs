@@ -0,0 +1,101 @@
+//! Jittered exponential backoff for connects and writes to Postgres and Redis.
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::Rng;
+use tracing::{info, warn};
+
+use crate::config::RetryConfig;
+
+#[derive(Default)]
+struct RetryStats {
+    retries: AtomicU64,
+    reconnects: AtomicU64,
+    gave_up: AtomicU64,
+}
+
+/// Retries operations against one backend and counts how that went.
+#[derive(Clone)]
+pub struct Retrier {
+    name: &'static str,
+    cfg: RetryConfig,
+    stats: Arc<RetryStats>,
+}
+
+impl Retrier {
+    /// Refuses a `multiplier` below 1 or a `jitter` outside 0 to 1, which
+    /// would make the delays shrink, go negative or stop being numbers.
+    pub fn new(name: &'static str, cfg: &RetryConfig) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, format!("retry.{}", message));
+        if !(cfg.multiplier.is_finite() && cfg.multiplier >= 1.0) {
+            return Err(invalid("multiplier: must be a number of at least 1"));
+        }
+        if !(0.0..=1.0).contains(&cfg.jitter) {
+            return Err(invalid("jitter: must be from 0 to 1"));
+        }
+        Ok(Retrier { name, cfg: cfg.clone(), stats: Arc::default() })
+    }
+
+    /// Runs `op` until it succeeds or `max_attempts` are used up, sleeping
+    /// with exponential backoff in between. Returns the last error.
+    pub async fn run<T, E, F, Fut>(&self, what: &str, mut op: F) -> Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: Debug,
+    {
+        let max_attempts = self.cfg.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Ok(value) => {
+                    if attempt > 1 {
+                        self.stats.reconnects.fetch_add(1, Ordering::Relaxed);
+                        info!("{} {} succeeded again after {} attempts", self.name, what, attempt);
+                    }
+                    return Ok(value);
+                }
+                Err(e) if attempt >= max_attempts => {
+                    self.stats.gave_up.fetch_add(1, Ordering::Relaxed);
+                    warn!("{} {} failed, giving up after {} attempts: {:?}", self.name, what, attempt, e);
+                    return Err(e);
+                }
+                Err(e) => {
+                    let delay = self.backoff(attempt);
+                    self.stats.retries.fetch_add(1, Ordering::Relaxed);
+                    warn!(
+                        "{} {} failed (attempt {}/{}), retrying in {:?}: {:?}",
+                        self.name, what, attempt, max_attempts, delay, e
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// `initial * multiplier^(attempt - 1)`, capped, then spread by `±jitter`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let base = self.cfg.initial_backoff_ms as f64 * self.cfg.multiplier.powi(attempt as i32 - 1);
+        let capped = base.min(self.cfg.max_backoff_ms as f64);
+        let jitter = self.cfg.jitter;
+        let factor = if jitter > 0.0 { rand::thread_rng().gen_range(1.0 - jitter..=1.0 + jitter) } else { 1.0 };
+        Duration::from_secs_f64(capped * factor / 1000.0)
+    }
+
+    /// One-line summary for the TUI.
+    pub fn describe(&self) -> String {
+        format!(
+            "{} {} retries, {} reconnects, {} gave up",
+            self.name,
+            self.stats.retries.load(Ordering::Relaxed),
+            self.stats.reconnects.load(Ordering::Relaxed),
+            self.stats.gave_up.load(Ordering::Relaxed)
+        )
+    }
+}
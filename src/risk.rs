@@ -0,0 +1,104 @@
+//! Limits on what the paper strategy holds, from `[paper.risk]`: shares in
+//! any one symbol, the notional of all positions at the last mid, and the
+//! fall of total PnL from its high. The first breach trips the kill switch,
+//! which stays tripped until restart: the paper harness stops the strategy,
+//! cancels its open orders and flattens every position, and the Risk
+//! diagnostics line turns red.
+
+use std::sync::{Arc, Mutex};
+
+use ratatui::style::{Color, Modifier, Style};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use crate::config::RiskConfig;
+use crate::portfolio::Portfolio;
+
+/// Starts the Risk line once the switch has tripped.
+const TRIPPED: &str = "KILL SWITCH";
+
+#[derive(Default)]
+struct Inner {
+    /// Highest total PnL seen.
+    peak: Option<f64>,
+    drawdown: f64,
+    notional: f64,
+    breach: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct Risk {
+    limits: Option<RiskConfig>,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Risk {
+    pub fn new(limits: Option<RiskConfig>) -> Self {
+        Risk { limits, inner: Arc::new(Mutex::new(Inner::default())) }
+    }
+
+    /// Measures `portfolio` against the limits. Returns the breach when it
+    /// trips the switch, and `None` after that.
+    pub fn check(&self, portfolio: &Portfolio) -> Option<String> {
+        let limits = self.limits.as_ref()?;
+        let positions = portfolio.positions();
+        let pnl = positions.values().map(|p| p.pnl()).sum::<Decimal>().to_f64().unwrap_or(0.0);
+        let mut inner = self.inner.lock().unwrap();
+        let peak = inner.peak.map_or(pnl, |peak| peak.max(pnl));
+        inner.peak = Some(peak);
+        inner.drawdown = peak - pnl;
+        let notional = positions.values().filter_map(|p| p.mark.map(|mark| (mark.to_decimal() * Decimal::from(p.qty)).abs()));
+        inner.notional = notional.sum::<Decimal>().to_f64().unwrap_or(0.0);
+        if inner.breach.is_some() {
+            return None;
+        }
+        let over_position = limits
+            .max_position
+            .and_then(|max| positions.iter().find(|(_, p)| p.qty.unsigned_abs() > max).map(|(id, p)| (id, p.qty, max)));
+        let breach = if let Some((stock_id, qty, max)) = over_position {
+            format!("position {} in stock {} over max_position {}", qty, stock_id, max)
+        } else if let Some(max) = limits.max_notional.filter(|&max| inner.notional > max) {
+            format!("notional {:.2} over max_notional {:.2}", inner.notional, max)
+        } else if let Some(max) = limits.max_drawdown.filter(|&max| inner.drawdown > max) {
+            format!("drawdown {:.2} from {:+.2} over max_drawdown {:.2}", inner.drawdown, peak, max)
+        } else {
+            return None;
+        };
+        inner.breach = Some(breach.clone());
+        Some(breach)
+    }
+
+    pub fn tripped(&self) -> bool {
+        self.inner.lock().unwrap().breach.is_some()
+    }
+
+    /// `ok, max position 100, notional 1234.50 of 5000.00, drawdown 12.00 of 100.00` or
+    /// `KILL SWITCH: drawdown 101.50 from +40.00 over max_drawdown 100.00`
+    pub fn describe(&self) -> String {
+        let Some(limits) = &self.limits else { return "no limits (add [paper.risk])".to_string() };
+        let inner = self.inner.lock().unwrap();
+        if let Some(breach) = &inner.breach {
+            return format!("{}: {}", TRIPPED, breach);
+        }
+        let of = |max: Option<f64>| max.map_or("no limit".to_string(), |max| format!("of {:.2}", max));
+        let position = limits.max_position.map_or("no limit".to_string(), |max| format!("{}", max));
+        format!(
+            "ok, max position {}, notional {:.2} {}, drawdown {:.2} {}",
+            position,
+            inner.notional,
+            of(limits.max_notional),
+            inner.drawdown,
+            of(limits.max_drawdown)
+        )
+    }
+}
+
+/// For a diagnostics line, here or read back from `/status`: red for a
+/// tripped kill switch.
+pub fn line_style(label: &str, value: &str) -> Style {
+    if label == "Risk" && value.starts_with(TRIPPED) {
+        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    }
+}
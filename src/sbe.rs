@@ -0,0 +1,137 @@
+//! Simple Binary Encoding of ticks for the multicast feed, per
+//! `proto/hft-sbe.xml`. All integers are little-endian.
+//!
+//! A packet is a header followed by one or more SBE messages:
+//!
+//! ```text
+//! packet header : seq u64, sending_time_ns u64
+//! message header: block_length u16, template_id u16, schema_id u16, version u16
+//! Tick block    : stock_id i32, price f64, ts_ns u64
+//! ```
+//!
+//! `seq` counts packets, so a receiver can detect loss and reordering.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::price::Price;
+use crate::tick::Tick;
+use crate::ulid::Ulid;
+
+pub const SCHEMA_ID: u16 = 1;
+pub const SCHEMA_VERSION: u16 = 0;
+pub const TICK_TEMPLATE_ID: u16 = 1;
+
+pub const PACKET_HEADER_LEN: usize = 16;
+pub const MESSAGE_HEADER_LEN: usize = 8;
+pub const TICK_BLOCK_LEN: usize = 20;
+pub const TICK_MESSAGE_LEN: usize = MESSAGE_HEADER_LEN + TICK_BLOCK_LEN;
+
+pub struct PacketHeader {
+    pub seq: u64,
+    pub sending_time_ns: u64,
+}
+
+pub fn unix_nanos(ts: SystemTime) -> u64 {
+    ts.duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0)
+}
+
+pub fn encode_packet_header(buf: &mut Vec<u8>, header: &PacketHeader) {
+    buf.extend_from_slice(&header.seq.to_le_bytes());
+    buf.extend_from_slice(&header.sending_time_ns.to_le_bytes());
+}
+
+pub fn encode_tick(buf: &mut Vec<u8>, tick: &Tick) {
+    buf.extend_from_slice(&(TICK_BLOCK_LEN as u16).to_le_bytes());
+    buf.extend_from_slice(&TICK_TEMPLATE_ID.to_le_bytes());
+    buf.extend_from_slice(&SCHEMA_ID.to_le_bytes());
+    buf.extend_from_slice(&SCHEMA_VERSION.to_le_bytes());
+    buf.extend_from_slice(&tick.stock_id.to_le_bytes());
+    buf.extend_from_slice(&tick.price.to_f64().to_le_bytes());
+    buf.extend_from_slice(&unix_nanos(tick.ts).to_le_bytes());
+}
+
+/// Decodes a packet, skipping messages of other templates or schemas.
+/// Returns `None` for truncated packets.
+pub fn decode_packet(buf: &[u8]) -> Option<(PacketHeader, Vec<Tick>)> {
+    let header = PacketHeader {
+        seq: u64::from_le_bytes(buf.get(0..8)?.try_into().ok()?),
+        sending_time_ns: u64::from_le_bytes(buf.get(8..16)?.try_into().ok()?),
+    };
+    let mut ticks = Vec::new();
+    let mut pos = PACKET_HEADER_LEN;
+    while pos < buf.len() {
+        let msg = buf.get(pos..pos + MESSAGE_HEADER_LEN)?;
+        let block_length = u16::from_le_bytes([msg[0], msg[1]]) as usize;
+        let template_id = u16::from_le_bytes([msg[2], msg[3]]);
+        let schema_id = u16::from_le_bytes([msg[4], msg[5]]);
+        let block = buf.get(pos + MESSAGE_HEADER_LEN..pos + MESSAGE_HEADER_LEN + block_length)?;
+        if schema_id == SCHEMA_ID && template_id == TICK_TEMPLATE_ID && block_length >= TICK_BLOCK_LEN {
+            let ts_ns = u64::from_le_bytes(block[12..20].try_into().ok()?);
+            ticks.push(Tick {
+                stock_id: i32::from_le_bytes(block[0..4].try_into().ok()?),
+                price: Price::from_f64(f64::from_le_bytes(block[4..12].try_into().ok()?)),
+                ts: UNIX_EPOCH + Duration::from_nanos(ts_ns),
+                seq: 0,
+                // The wire format carries none.
+                id: Ulid::new(UNIX_EPOCH + Duration::from_nanos(ts_ns)),
+            });
+        }
+        pos += MESSAGE_HEADER_LEN + block_length;
+    }
+    Some((header, ticks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(stock_id: i32, price: &str, ts_ns: u64) -> Tick {
+        let ts = UNIX_EPOCH + Duration::from_nanos(ts_ns);
+        Tick { stock_id, price: price.parse().unwrap(), ts, seq: 0, id: Ulid::new(ts) }
+    }
+
+    fn packet(seq: u64, ticks: &[Tick]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        encode_packet_header(&mut buf, &PacketHeader { seq, sending_time_ns: 1_760_436_000_000_000_001 });
+        for tick in ticks {
+            encode_tick(&mut buf, tick);
+        }
+        buf
+    }
+
+    #[test]
+    fn packets_round_trip() {
+        let sent = [tick(0, "100.25", 1_760_436_000_000_000_123), tick(7, "-3.5", 1_760_436_000_000_000_456)];
+        let buf = packet(42, &sent);
+        assert_eq!(buf.len(), PACKET_HEADER_LEN + 2 * TICK_MESSAGE_LEN);
+
+        let (header, ticks) = decode_packet(&buf).unwrap();
+        assert_eq!((header.seq, header.sending_time_ns), (42, 1_760_436_000_000_000_001));
+        let decoded: Vec<_> = ticks.iter().map(|t| (t.stock_id, t.price, t.ts)).collect();
+        let expected: Vec<_> = sent.iter().map(|t| (t.stock_id, t.price, t.ts)).collect();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn truncated_packets_are_refused() {
+        let buf = packet(1, &[tick(0, "100.25", 1)]);
+        assert!(decode_packet(&buf[..PACKET_HEADER_LEN - 1]).is_none());
+        assert!(decode_packet(&buf[..PACKET_HEADER_LEN + MESSAGE_HEADER_LEN - 1]).is_none());
+        assert!(decode_packet(&buf[..buf.len() - 1]).is_none());
+        assert!(decode_packet(&buf[..PACKET_HEADER_LEN]).is_some_and(|(_, ticks)| ticks.is_empty()));
+    }
+
+    #[test]
+    fn other_templates_are_skipped() {
+        let mut buf = packet(1, &[tick(1, "1.5", 1)]);
+        // A 4-byte block of template 9 between two ticks.
+        for field in [4u16, 9, SCHEMA_ID, SCHEMA_VERSION] {
+            buf.extend_from_slice(&field.to_le_bytes());
+        }
+        buf.extend_from_slice(&[0xff; 4]);
+        encode_tick(&mut buf, &tick(2, "2.5", 2));
+
+        let (_, ticks) = decode_packet(&buf).unwrap();
+        assert_eq!(ticks.iter().map(|t| t.stock_id).collect::<Vec<_>>(), [1, 2]);
+    }
+}
@@ -0,0 +1,141 @@
+//! How the TUI and `attach` divide the terminal: the Pointers, Diagnostics
+//! and Health panels stacked above the charts. Below a minimum size only a
+//! notice is drawn, and a terminal too short for every panel line cuts the
+//! diagnostics first, then the health lines, before the charts shrink
+//! below a usable height. Both redraw the whole screen on a resize, and
+//! with `[ui] glyphs` in ASCII redraw every cell the widgets drew outside
+//! it: borders as `-`, `|` and `+`, markers as `*`, bars as `_`, `=` and `#`.
+//! The help overlay and the command line are drawn over everything else.
+
+use std::io;
+use std::rc::Rc;
+
+use ratatui::backend::Backend;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+use ratatui::{Frame, Terminal};
+
+/// Smallest terminal the panels are drawn in.
+pub const MIN_WIDTH: u16 = 80;
+pub const MIN_HEIGHT: u16 = 24;
+/// Rows the charts keep when the panels above them are cut.
+const MIN_CHART_ROWS: u16 = 10;
+
+/// Draws a notice in place of the panels when the terminal is under the
+/// minimum; returns whether it did.
+pub fn too_small(f: &mut Frame) -> bool {
+    let area = f.area();
+    if area.width >= MIN_WIDTH && area.height >= MIN_HEIGHT {
+        return false;
+    }
+    let text = format!(
+        "Terminal too small: {}x{}, needs at least {}x{}. Enlarge it or press q.",
+        area.width, area.height, MIN_WIDTH, MIN_HEIGHT
+    );
+    let middle = Rect { y: area.y + area.height / 2, height: area.height - area.height / 2, ..area };
+    f.render_widget(Paragraph::new(text).alignment(Alignment::Center).wrap(Wrap { trim: true }), middle);
+    true
+}
+
+/// Draws a frame with `render`, under the two columns of `help` and the
+/// command line `prompt` on the bottom row if any, in ASCII if `ascii`.
+pub fn draw<B: Backend>(
+    terminal: &mut Terminal<B>,
+    ascii: bool,
+    help: Option<[Vec<Line>; 2]>,
+    prompt: Option<Line>,
+    render: impl FnOnce(&mut Frame),
+) -> io::Result<()> {
+    terminal.draw(|f| {
+        render(f);
+        if let Some([keys, settings]) = help {
+            let area = f.area();
+            let area = Rect {
+                x: area.width / 20,
+                y: area.height / 20,
+                width: area.width - area.width / 10,
+                height: area.height - area.height / 10,
+            };
+            let block = Block::default().borders(Borders::ALL).title("Help - Esc closes");
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(block.inner(area));
+            f.render_widget(Clear, area);
+            f.render_widget(block, area);
+            f.render_widget(Paragraph::new(keys), columns[0]);
+            f.render_widget(Paragraph::new(settings).wrap(Wrap { trim: false }), columns[1]);
+        }
+        if let Some(prompt) = prompt {
+            let area = f.area();
+            let bottom = Rect { y: area.bottom().saturating_sub(1), height: area.height.min(1), ..area };
+            f.render_widget(Clear, bottom);
+            f.render_widget(Paragraph::new(prompt), bottom);
+        }
+        if ascii {
+            to_ascii(f.buffer_mut());
+        }
+    })?;
+    Ok(())
+}
+
+fn to_ascii(buf: &mut Buffer) {
+    for cell in buf.content.iter_mut() {
+        let mut chars = cell.symbol().chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else { continue };
+        if c.is_ascii() {
+            continue;
+        }
+        let ascii = match c {
+            '─' | '━' | '═' | '╌' | '╍' | '┄' | '┅' | '┈' | '┉' => "-",
+            '│' | '┃' | '║' | '╎' | '╏' | '┆' | '┇' | '┊' | '┋' => "|",
+            '\u{2500}'..='\u{257f}' => "+",
+            // A Braille cell with no dots set.
+            '\u{2800}' => " ",
+            '\u{2801}'..='\u{28ff}' | '•' | '·' => "*",
+            '▁' | '▂' | '▃' => "_",
+            '▄' | '▅' | '▆' => "=",
+            '\u{2580}'..='\u{259f}' => "#",
+            '…' => ".",
+            'µ' => "u",
+            '×' => "x",
+            '±' => "~",
+            '≥' => ">",
+            _ => "?",
+        };
+        cell.set_symbol(ascii);
+    }
+}
+
+/// The Pointers, Diagnostics and Health panels at `heights` rows, borders
+/// included, then the charts in the rest of `area`. Also returns how many
+/// diagnostics lines did not fit.
+pub fn panels(area: Rect, heights: [u16; 3]) -> (Rc<[Rect]>, u16) {
+    let [pointers, diagnostics, health] = heights;
+    let mut spare = area.height.saturating_sub(MIN_CHART_ROWS);
+    let pointers = pointers.min(spare);
+    spare -= pointers;
+    let health = health.min(spare);
+    spare -= health;
+    let shown = diagnostics.min(spare);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(pointers),
+            Constraint::Length(shown),
+            Constraint::Length(health),
+            Constraint::Min(MIN_CHART_ROWS),
+        ])
+        .split(area);
+    (chunks, diagnostics - shown)
+}
+
+/// `title`, saying how many lines were cut if any.
+pub fn cut_title(title: &str, cut: u16) -> String {
+    match cut {
+        0 => title.to_string(),
+        n => format!("{} ({} lines cut, enlarge the terminal)", title, n),
+    }
+}
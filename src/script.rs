@@ -0,0 +1,164 @@
+//! Rhai scripts loaded at startup, for strategies and tick filters that
+//! should not need a rebuild. A script is a set of functions the app calls
+//! by name; whatever a script keeps between calls lives in `this`, the map
+//! its `init()` returns, since Rhai functions cannot see global variables.
+//! Every call is timed per function, and a call that fails or runs past
+//! [`MAX_OPERATIONS`] is logged and counted rather than stopping the run.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use rhai::{CallFnOptions, Dynamic, Engine, FuncArgs, Map, Scope, AST};
+use tracing::{info, warn};
+
+use crate::latency::{LatencyRecorder, Stage};
+use crate::symbols::Symbol;
+use crate::tick::Tick;
+use crate::timing::SharedClock;
+
+/// Per call, so a runaway loop fails instead of stalling the pipeline.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+#[derive(Default)]
+struct CallTime {
+    calls: u64,
+    total_nanos: u64,
+    max_nanos: u64,
+}
+
+struct StatsInner {
+    file: String,
+    by_function: BTreeMap<&'static str, CallTime>,
+    errors: u64,
+}
+
+/// Execution time of one script's functions since startup.
+#[derive(Clone)]
+pub struct ScriptStats {
+    inner: Arc<Mutex<StatsInner>>,
+}
+
+impl ScriptStats {
+    /// `momentum.rhai: on_tick 1200 × 4.1µs avg 38µs max, on_timer 50 × 1.2µs avg 3µs max, 0 errors`
+    pub fn describe(&self) -> String {
+        let inner = self.inner.lock().unwrap();
+        let calls: Vec<String> = inner
+            .by_function
+            .iter()
+            .map(|(name, t)| {
+                let avg = t.total_nanos as f64 / t.calls.max(1) as f64 / 1e3;
+                format!("{} {} × {:.1}µs avg {}µs max", name, t.calls, avg, t.max_nanos / 1000)
+            })
+            .collect();
+        let calls = if calls.is_empty() { "no calls".to_string() } else { calls.join(", ") };
+        format!("{}: {}, {} errors", inner.file, calls, inner.errors)
+    }
+}
+
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    stats: ScriptStats,
+}
+
+impl Script {
+    /// Compiles `path` with `engine`, which has the app's functions
+    /// registered. `print` and `debug` go to the log, not the terminal.
+    pub fn load(path: &str, mut engine: Engine) -> io::Result<Self> {
+        let file = Path::new(path).file_name().map_or(path.to_string(), |f| f.to_string_lossy().into_owned());
+        let target = file.clone();
+        engine.on_print(move |s| info!("{}: {}", target, s));
+        let target = file.clone();
+        engine.on_debug(move |s, _, pos| info!("{} {}: {}", target, pos, s));
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine
+            .compile_file(PathBuf::from(path))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", path, e)))?;
+        let stats =
+            ScriptStats { inner: Arc::new(Mutex::new(StatsInner { file, by_function: BTreeMap::new(), errors: 0 })) };
+        Ok(Script { engine, ast, stats })
+    }
+
+    pub fn stats(&self) -> ScriptStats {
+        self.stats.clone()
+    }
+
+    fn defines(&self, name: &str) -> bool {
+        self.ast.iter_functions().any(|f| f.name == name)
+    }
+
+    /// The map `init()` returns, or an empty one without an `init`.
+    pub fn init(&self) -> Dynamic {
+        let mut this = Dynamic::from_map(Map::new());
+        self.call::<Dynamic>("init", &mut this, ()).unwrap_or(this)
+    }
+
+    /// `None` if the script has no such function or the call failed.
+    pub fn call<T: Clone + Send + Sync + 'static>(
+        &self,
+        name: &'static str,
+        this: &mut Dynamic,
+        args: impl FuncArgs,
+    ) -> Option<T> {
+        if !self.defines(name) {
+            return None;
+        }
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(this);
+        let started = Instant::now();
+        let result = self.engine.call_fn_with_options::<T>(options, &mut Scope::new(), &self.ast, name, args);
+        let nanos = started.elapsed().as_nanos() as u64;
+        let mut inner = self.stats.inner.lock().unwrap();
+        let time = inner.by_function.entry(name).or_default();
+        time.calls += 1;
+        time.total_nanos += nanos;
+        time.max_nanos = time.max_nanos.max(nanos);
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                inner.errors += 1;
+                warn!("{} {}(): {}", inner.file, name, e);
+                None
+            }
+        }
+    }
+}
+
+/// `filter(tick)` from the `[filter]` script, deciding which ticks the
+/// publisher passes on. Its time per tick is the `tick_filter` stage.
+pub struct TickFilter {
+    script: Script,
+    this: Dynamic,
+    latency: LatencyRecorder,
+    timer: SharedClock,
+}
+
+impl TickFilter {
+    pub fn load(path: &str, latency: LatencyRecorder, timer: SharedClock) -> io::Result<Self> {
+        let script = Script::load(path, Engine::new())?;
+        if !script.defines("filter") {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{}: no filter(tick) function", path)));
+        }
+        let this = script.init();
+        Ok(TickFilter { script, this, latency, timer })
+    }
+
+    pub fn stats(&self) -> ScriptStats {
+        self.script.stats()
+    }
+
+    /// Keeps the tick unless `filter` returns `false`; a failed call keeps it.
+    pub fn keep(&mut self, tick: &Tick, symbol: &Symbol) -> bool {
+        let mut map = Map::new();
+        map.insert("stock_id".into(), (tick.stock_id as i64).into());
+        map.insert("ticker".into(), symbol.ticker.clone().into());
+        map.insert("price".into(), tick.price.to_f64().into());
+        map.insert("seq".into(), (tick.seq as i64).into());
+        let started = self.timer.now_nanos();
+        let keep = self.script.call::<bool>("filter", &mut self.this, (map,)).unwrap_or(true);
+        self.latency.record(Stage::TickFilter, Some(tick.stock_id), self.timer.elapsed(started));
+        keep
+    }
+}
@@ -0,0 +1,132 @@
+//! Postgres and Redis connection strings, read from the environment or a
+//! secrets file instead of the source. A [`Dsn`] never prints its password.
+
+use std::fmt;
+use std::fs;
+use std::io;
+
+use serde::Deserialize;
+use url::Url;
+
+use crate::config::SecretsConfig;
+
+pub const POSTGRES_URL_ENV: &str = "HFT_POSTGRES_URL";
+pub const REDIS_URL_ENV: &str = "HFT_REDIS_URL";
+const DEFAULT_REDIS_URL: &str = "redis://127.0.0.1/";
+
+const POSTGRES_SCHEMES: &[&str] = &["postgres", "postgresql"];
+const REDIS_SCHEMES: &[&str] = &["redis", "rediss"];
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SecretsFile {
+    postgres_url: Option<String>,
+    redis_url: Option<String>,
+}
+
+/// A validated connection URL. `Display` and `Debug` mask the password, so
+/// it is safe to log; [`expose`](Self::expose) is for the driver only.
+#[derive(Clone)]
+pub struct Dsn(Url);
+
+impl Dsn {
+    fn parse(raw: &str, source: &str, schemes: &[&str]) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", source, msg));
+        // The parse error never echoes the input, which may hold a password.
+        let url = Url::parse(raw).map_err(|e| invalid(e.to_string()))?;
+        if !schemes.contains(&url.scheme()) {
+            return Err(invalid(format!("scheme must be one of {}", schemes.join(", "))));
+        }
+        if url.host_str().is_none_or(str::is_empty) {
+            return Err(invalid("missing host".to_string()));
+        }
+        Ok(Dsn(url))
+    }
+
+    /// A Redis URL given outside the environment and secrets file, e.g. on
+    /// the command line.
+    pub fn redis(raw: &str, source: &str) -> io::Result<Self> {
+        Dsn::parse(raw, source, REDIS_SCHEMES)
+    }
+
+    pub fn expose(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl fmt::Display for Dsn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut url = self.0.clone();
+        if url.password().is_some() {
+            let _ = url.set_password(Some("***"));
+        }
+        f.write_str(url.as_str())
+    }
+}
+
+impl fmt::Debug for Dsn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Dsn({})", self)
+    }
+}
+
+pub struct Credentials {
+    postgres: Option<Dsn>,
+    pub redis: Dsn,
+}
+
+impl Credentials {
+    /// Takes each URL from its environment variable, then from the secrets
+    /// file. Redis falls back to a local server; Postgres has no default.
+    pub fn load(cfg: &SecretsConfig) -> io::Result<Self> {
+        let file = match &cfg.file {
+            Some(path) => read_secrets_file(path)?,
+            None => SecretsFile::default(),
+        };
+        let postgres = resolve(POSTGRES_URL_ENV, file.postgres_url, cfg.file.as_deref(), POSTGRES_SCHEMES)?;
+        let redis = resolve(REDIS_URL_ENV, file.redis_url, cfg.file.as_deref(), REDIS_SCHEMES)?;
+        Ok(Credentials {
+            postgres,
+            redis: match redis {
+                Some(dsn) => dsn,
+                None => Dsn::redis(DEFAULT_REDIS_URL, "default Redis URL")?,
+            },
+        })
+    }
+
+    pub fn postgres(&self) -> io::Result<&Dsn> {
+        self.postgres.as_ref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no Postgres URL: set {} or postgres_url in the [secrets] file", POSTGRES_URL_ENV),
+            )
+        })
+    }
+}
+
+fn resolve(env: &str, from_file: Option<String>, file: Option<&str>, schemes: &[&str]) -> io::Result<Option<Dsn>> {
+    match std::env::var(env) {
+        Ok(raw) if !raw.is_empty() => return Dsn::parse(&raw, env, schemes).map(Some),
+        _ => {}
+    }
+    match (from_file, file) {
+        (Some(raw), Some(path)) => Dsn::parse(&raw, path, schemes).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn read_secrets_file(path: &str) -> io::Result<SecretsFile> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("Cannot read secrets file {}: {}", path, e)))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if fs::metadata(path)?.permissions().mode() & 0o077 != 0 {
+            tracing::warn!("Secrets file {} is accessible by other users; consider chmod 600", path);
+        }
+    }
+    // Only the message: the full error quotes the offending line.
+    toml::from_str(&content).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Invalid secrets file {}: {}", path, e.message()))
+    })
+}
@@ -0,0 +1,87 @@
+//! Per-symbol sequence numbers and the consumers checking them. Each tick
+//! takes the next number of its symbol when it is applied to the market
+//! data; the TUI, the Redis writer and the Postgres flush each track the
+//! numbers they see, so ticks lost or repeated on the way to a consumer show
+//! up as counters in the Diagnostics panel.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// What one consumer has seen go wrong.
+pub struct GapCounters {
+    name: &'static str,
+    /// Jumps in a symbol's sequence.
+    gaps: AtomicU64,
+    /// Ticks skipped by those jumps.
+    missing: AtomicU64,
+    /// Sequence numbers at or below the last one seen: repeats, or ticks
+    /// that arrived out of order.
+    duplicates: AtomicU64,
+}
+
+impl GapCounters {
+    /// `redis 2 gaps (17 missing), 1 dup`
+    fn describe(&self) -> String {
+        format!(
+            "{} {} gaps ({} missing), {} dup",
+            self.name,
+            self.gaps.load(Ordering::Relaxed),
+            self.missing.load(Ordering::Relaxed),
+            self.duplicates.load(Ordering::Relaxed)
+        )
+    }
+}
+
+/// The last sequence number a consumer saw per symbol.
+pub struct SeqCheck {
+    last: Vec<u64>,
+    counters: Arc<GapCounters>,
+}
+
+impl SeqCheck {
+    /// Sequence 0 means a tick has none, e.g. one read back from storage.
+    pub fn observe(&mut self, stock_id: i32, seq: u64) {
+        let Some(last) = usize::try_from(stock_id).ok().and_then(|id| self.last.get_mut(id)) else {
+            return;
+        };
+        let prev = *last;
+        if seq == 0 {
+            return;
+        }
+        // Nothing seen yet, or the producer restarted and counts from 1 again.
+        let restarted = prev == 0 || (seq == 1 && prev > 1);
+        if !restarted {
+            if seq <= prev {
+                self.counters.duplicates.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            if seq > prev + 1 {
+                self.counters.gaps.fetch_add(1, Ordering::Relaxed);
+                self.counters.missing.fetch_add(seq - prev - 1, Ordering::Relaxed);
+            }
+        }
+        *last = seq;
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct GapRegistry {
+    consumers: Arc<Mutex<Vec<Arc<GapCounters>>>>,
+}
+
+impl GapRegistry {
+    pub fn register(&self, name: &'static str, n_stocks: usize) -> SeqCheck {
+        let counters = Arc::new(GapCounters {
+            name,
+            gaps: AtomicU64::new(0),
+            missing: AtomicU64::new(0),
+            duplicates: AtomicU64::new(0),
+        });
+        self.consumers.lock().unwrap().push(Arc::clone(&counters));
+        SeqCheck { last: vec![0; n_stocks], counters }
+    }
+
+    pub fn describe(&self) -> String {
+        self.consumers.lock().unwrap().iter().map(|c| c.describe()).collect::<Vec<_>>().join(" | ")
+    }
+}
@@ -0,0 +1,249 @@
+//! The last hour of prices and latency at one point per second, plus
+//! connection status changes, kept for `[http]` clients that chart a run
+//! over time such as Grafana. Every symbol's series holds its last price in
+//! each second, and with paper trading its order-flow imbalance; every stage
+//! has a count, p50, p99 and max series.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::broadcast::error::RecvError;
+use tracing::warn;
+
+use crate::health::{HealthRegistry, Status};
+use crate::latency::{LatencyReceiver, Stage};
+use crate::paper::OrderFlow;
+use crate::symbols::Symbol;
+use crate::tick::TickReceiver;
+
+/// Seconds kept per series.
+const RETAIN: usize = 3600;
+/// Status changes kept.
+const MAX_EVENTS: usize = 1000;
+const HEALTH_POLL: Duration = Duration::from_secs(1);
+const FLOW_SAMPLE: Duration = Duration::from_secs(1);
+const STATS: [&str; 4] = ["count", "p50", "p99", "max"];
+
+/// A connection changing status.
+#[derive(Clone)]
+pub struct StatusChange {
+    pub time_ms: i64,
+    pub title: String,
+    pub text: String,
+}
+
+/// One closed second of a stage.
+#[derive(Clone, Copy)]
+struct Second {
+    at: i64,
+    count: u64,
+    p50: u64,
+    p99: u64,
+    max: u64,
+}
+
+#[derive(Default)]
+struct StageSeries {
+    /// Samples of the second still open, and which second that is.
+    open: Vec<u64>,
+    open_at: i64,
+    closed: VecDeque<Second>,
+}
+
+struct Inner {
+    tickers: Vec<String>,
+    /// By stock id: (second, last price).
+    prices: Vec<VecDeque<(i64, f64)>>,
+    /// By stock id: (second, order-flow imbalance); none without paper trading.
+    flow: Option<Vec<VecDeque<(i64, f64)>>>,
+    stages: BTreeMap<Stage, StageSeries>,
+    events: VecDeque<StatusChange>,
+}
+
+#[derive(Clone)]
+pub struct SeriesStore {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl SeriesStore {
+    pub fn new(symbols: &[Symbol]) -> Self {
+        SeriesStore {
+            inner: Arc::new(Mutex::new(Inner {
+                tickers: symbols.iter().map(|s| s.ticker.clone()).collect(),
+                prices: vec![VecDeque::new(); symbols.len()],
+                flow: None,
+                stages: BTreeMap::new(),
+                events: VecDeque::new(),
+            })),
+        }
+    }
+
+    pub async fn run_ticks(self, mut rx: TickReceiver) {
+        loop {
+            match rx.recv().await {
+                Ok(tick) => {
+                    let at = unix_secs(tick.ts);
+                    let mut inner = self.inner.lock().unwrap();
+                    let Some(series) = usize::try_from(tick.stock_id).ok().and_then(|id| inner.prices.get_mut(id))
+                    else {
+                        continue;
+                    };
+                    match series.back_mut() {
+                        Some(last) if last.0 >= at => last.1 = tick.price.to_f64(),
+                        _ => series.push_back((at, tick.price.to_f64())),
+                    }
+                    if series.len() > RETAIN {
+                        series.pop_front();
+                    }
+                }
+                Err(RecvError::Lagged(n)) => warn!("Series store lagged, skipped {} ticks", n),
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    pub async fn run_latency(self, mut rx: LatencyReceiver) {
+        loop {
+            match rx.recv().await {
+                Ok(sample) => {
+                    let at = unix_secs(sample.at);
+                    let mut inner = self.inner.lock().unwrap();
+                    let stage = inner.stages.entry(sample.stage).or_default();
+                    if at > stage.open_at {
+                        stage.close();
+                        stage.open_at = at;
+                    }
+                    stage.open.push(sample.nanos);
+                }
+                Err(RecvError::Lagged(n)) => warn!("Series store lagged, skipped {} samples", n),
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// Records every status change of the registered connections.
+    pub async fn watch_health(self, health: HealthRegistry) {
+        let mut last: Vec<Status> = Vec::new();
+        let mut interval = tokio::time::interval(HEALTH_POLL);
+        loop {
+            interval.tick().await;
+            let connections = health.snapshot();
+            let time_ms = unix_millis(SystemTime::now());
+            let mut inner = self.inner.lock().unwrap();
+            for (i, conn) in connections.iter().enumerate() {
+                let before = last.get(i).copied().unwrap_or(Status::Connecting);
+                if conn.status == before {
+                    continue;
+                }
+                inner.events.push_back(StatusChange {
+                    time_ms,
+                    title: format!("{} {}", conn.name, conn.status.as_str()),
+                    text: conn.last_error.clone().filter(|_| conn.status == Status::Down).unwrap_or_default(),
+                });
+                if inner.events.len() > MAX_EVENTS {
+                    inner.events.pop_front();
+                }
+            }
+            last = connections.iter().map(|conn| conn.status).collect();
+        }
+    }
+
+    /// Samples each symbol's order-flow imbalance once a second.
+    pub async fn sample_flow(self, flow: OrderFlow) {
+        let mut interval = tokio::time::interval(FLOW_SAMPLE);
+        loop {
+            interval.tick().await;
+            let at = unix_secs(SystemTime::now());
+            let mut inner = self.inner.lock().unwrap();
+            let n = inner.tickers.len();
+            let series = inner.flow.get_or_insert_with(|| vec![VecDeque::new(); n]);
+            for (id, series) in series.iter_mut().enumerate() {
+                let Some(imbalance) = flow.imbalance(id as i32) else { continue };
+                series.push_back((at, imbalance as f64));
+                if series.len() > RETAIN {
+                    series.pop_front();
+                }
+            }
+        }
+    }
+
+    /// `price.AAPL`, with paper trading `ofi.AAPL`, then
+    /// `latency.<stage>.count`, `.p50`, `.p99` and `.max` for every stage
+    /// seen so far.
+    pub fn names(&self) -> Vec<String> {
+        let inner = self.inner.lock().unwrap();
+        let prices = inner.tickers.iter().map(|t| format!("price.{}", t));
+        let flow = inner.flow.iter().flat_map(|_| inner.tickers.iter().map(|t| format!("ofi.{}", t)));
+        let stages = inner
+            .stages
+            .keys()
+            .flat_map(|stage| STATS.iter().map(move |stat| format!("latency.{}.{}", stage.as_str(), stat)));
+        prices.chain(flow).chain(stages).collect()
+    }
+
+    /// `(unix seconds, value)` within `from..=to`, or `None` for a name
+    /// [`names`](Self::names) does not list. Latency is in microseconds and
+    /// order-flow imbalance in shares.
+    pub fn points(&self, name: &str, from: i64, to: i64) -> Option<Vec<(i64, f64)>> {
+        let mut inner = self.inner.lock().unwrap();
+        let in_range = |at: &i64| (from..=to).contains(at);
+        if let Some(ticker) = name.strip_prefix("price.") {
+            let id = inner.tickers.iter().position(|t| t == ticker)?;
+            return Some(inner.prices[id].iter().copied().filter(|(at, _)| in_range(at)).collect());
+        }
+        if let Some(ticker) = name.strip_prefix("ofi.") {
+            let id = inner.tickers.iter().position(|t| t == ticker)?;
+            return Some(inner.flow.as_ref()?[id].iter().copied().filter(|(at, _)| in_range(at)).collect());
+        }
+        let (stage, stat) = name.strip_prefix("latency.")?.rsplit_once('.')?;
+        let (_, series) = inner.stages.iter_mut().find(|(s, _)| s.as_str() == stage)?;
+        // The open second is only complete once it has passed.
+        if series.open_at < unix_secs(SystemTime::now()) {
+            series.close();
+        }
+        let pick: fn(&Second) -> f64 = match stat {
+            "count" => |s| s.count as f64,
+            "p50" => |s| s.p50 as f64 / 1e3,
+            "p99" => |s| s.p99 as f64 / 1e3,
+            "max" => |s| s.max as f64 / 1e3,
+            _ => return None,
+        };
+        Some(series.closed.iter().filter(|s| in_range(&s.at)).map(|s| (s.at, pick(s))).collect())
+    }
+
+    /// Status changes within `from..=to`, in unix milliseconds.
+    pub fn events(&self, from_ms: i64, to_ms: i64) -> Vec<StatusChange> {
+        let inner = self.inner.lock().unwrap();
+        inner.events.iter().filter(|e| (from_ms..=to_ms).contains(&e.time_ms)).cloned().collect()
+    }
+}
+
+impl StageSeries {
+    fn close(&mut self) {
+        if self.open.is_empty() {
+            return;
+        }
+        self.open.sort_unstable();
+        let at = |q: usize| self.open[(self.open.len() * q / 100).min(self.open.len() - 1)];
+        self.closed.push_back(Second {
+            at: self.open_at,
+            count: self.open.len() as u64,
+            p50: at(50),
+            p99: at(99),
+            max: self.open[self.open.len() - 1],
+        });
+        self.open.clear();
+        if self.closed.len() > RETAIN {
+            self.closed.pop_front();
+        }
+    }
+}
+
+fn unix_secs(at: SystemTime) -> i64 {
+    at.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64)
+}
+
+fn unix_millis(at: SystemTime) -> i64 {
+    at.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as i64)
+}
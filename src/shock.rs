@@ -0,0 +1,161 @@
+//! News shocks in the simulator, from `[[shocks]]`: at a set time or on a
+//! key in the TUI, a symbol's price jumps by `move_pct` and its volatility
+//! is multiplied by `vol_mult` for `for_s` seconds, to see how the pipeline
+//! and whatever consumes it cope with the burst. Each shock is logged and
+//! the Shocks diagnostics line turns magenta while one is raising volatility.
+
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ratatui::style::{Color, Style};
+use tracing::warn;
+
+use crate::config::ShockConfig;
+use crate::price::Price;
+use crate::symbols::Symbol;
+
+/// Starts the Shocks line while any volatility is raised.
+const SHOCKED: &str = "SHOCKED";
+
+struct Inner {
+    /// Which timed shocks have fired.
+    fired: Vec<bool>,
+    /// Shocks fired by key, not yet applied.
+    keyed: Vec<usize>,
+    /// By stock id, the raised volatility and when it ends.
+    spikes: Vec<Option<(f64, Instant)>>,
+    shocks: u64,
+}
+
+#[derive(Clone)]
+pub struct Shocks {
+    cfg: Arc<Vec<ShockConfig>>,
+    started: Instant,
+    /// By config entry, the stock id it shocks.
+    stock_ids: Arc<Vec<usize>>,
+    tickers: Arc<Vec<String>>,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Shocks {
+    pub fn new(cfg: &[ShockConfig], symbols: &[Symbol]) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+        let stock_ids = cfg
+            .iter()
+            .map(|shock| {
+                if shock.at_s.is_none() && shock.key.is_none() {
+                    return Err(invalid(format!("shocks: the {} shock needs at_s or key", shock.ticker)));
+                }
+                symbols
+                    .iter()
+                    .position(|s| s.ticker == shock.ticker)
+                    .ok_or_else(|| invalid(format!("shocks: unknown ticker {}", shock.ticker)))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let inner =
+            Inner { fired: vec![false; cfg.len()], keyed: Vec::new(), spikes: vec![None; symbols.len()], shocks: 0 };
+        Ok(Shocks {
+            cfg: Arc::new(cfg.to_vec()),
+            started: Instant::now(),
+            stock_ids: Arc::new(stock_ids),
+            tickers: Arc::new(symbols.iter().map(|s| s.ticker.clone()).collect()),
+            inner: Arc::new(Mutex::new(inner)),
+        })
+    }
+
+    /// Fires the shocks bound to `key`, if any.
+    pub fn key(&self, key: char) {
+        let bound = self.cfg.iter().enumerate().filter(|(_, s)| s.key == Some(key)).map(|(i, _)| i);
+        self.inner.lock().unwrap().keyed.extend(bound);
+    }
+
+    /// Fires the shocks that are due or keyed and ends volatility spikes
+    /// that are over. Returns the price moves to make, in percent by stock
+    /// id.
+    pub fn advance(&self) -> Vec<(usize, f64)> {
+        let mut inner = self.inner.lock().unwrap();
+        let now = Instant::now();
+        for spike in inner.spikes.iter_mut() {
+            if spike.is_some_and(|(_, until)| until <= now) {
+                *spike = None;
+            }
+        }
+        if self.cfg.is_empty() {
+            return Vec::new();
+        }
+        let elapsed = self.started.elapsed();
+        let mut firing = std::mem::take(&mut inner.keyed);
+        for (i, shock) in self.cfg.iter().enumerate() {
+            if !inner.fired[i] && shock.at_s.is_some_and(|at| elapsed >= Duration::from_secs(at)) {
+                inner.fired[i] = true;
+                firing.push(i);
+            }
+        }
+        let mut moves = Vec::new();
+        for i in firing {
+            let (shock, stock_id) = (&self.cfg[i], self.stock_ids[i]);
+            let mut what = Vec::new();
+            if shock.move_pct != 0.0 {
+                moves.push((stock_id, shock.move_pct));
+                what.push(format!("{:+}% move", shock.move_pct));
+            }
+            if shock.vol_mult != 1.0 {
+                inner.spikes[stock_id] = Some((shock.vol_mult, now + Duration::from_secs(shock.for_s)));
+                what.push(format!("volatility x{} for {}s", shock.vol_mult, shock.for_s));
+            }
+            warn!("{} shocked: {}", shock.ticker, what.join(", "));
+            inner.shocks += 1;
+        }
+        moves
+    }
+
+    /// Largest move per tick for `stock_id`, in ticks, raised by any spike.
+    pub fn max_steps(&self, stock_id: usize, symbol: &Symbol) -> i64 {
+        let mult = self.inner.lock().unwrap().spikes[stock_id].map_or(1.0, |(mult, _)| mult);
+        ((symbol.max_steps() as f64 * mult).round() as i64).max(1)
+    }
+
+    /// `SHOCKED MSFT x4 6s left | 3 shocks, keys 1 AAPL, 2 MSFT` or `calm | 3 shocks, ...`
+    pub fn describe(&self) -> String {
+        if self.cfg.is_empty() {
+            return "none (add [[shocks]])".to_string();
+        }
+        let inner = self.inner.lock().unwrap();
+        let now = Instant::now();
+        let spiked: Vec<String> = inner
+            .spikes
+            .iter()
+            .enumerate()
+            .filter_map(|(id, spike)| spike.map(|(mult, until)| (id, mult, until)))
+            .map(|(id, mult, until)| {
+                format!("{} x{} {}s left", self.tickers[id], mult, until.saturating_duration_since(now).as_secs())
+            })
+            .collect();
+        let keys: Vec<String> =
+            self.cfg.iter().filter_map(|s| s.key.map(|key| format!("{} {}", key, s.ticker))).collect();
+        let mut summary = format!("{} shocks", inner.shocks);
+        if !keys.is_empty() {
+            summary = format!("{}, keys {}", summary, keys.join(", "));
+        }
+        if spiked.is_empty() {
+            return format!("calm | {}", summary);
+        }
+        format!("{} {} | {}", SHOCKED, spiked.join(", "), summary)
+    }
+}
+
+/// `last` moved by `pct` percent, on the symbol's tick grid.
+pub fn moved(last: Price, pct: f64, symbol: &Symbol) -> Price {
+    Price::from_f64(last.to_f64() * (1.0 + pct / 100.0)).round_to(symbol.tick_size)
+}
+
+/// For a diagnostics line, here or read back from `/status`: magenta while
+/// volatility is raised.
+pub fn line_style(label: &str, value: &str) -> Style {
+    if label == "Shocks" && value.starts_with(SHOCKED) {
+        Style::default().fg(Color::Magenta)
+    } else {
+        Style::default()
+    }
+}
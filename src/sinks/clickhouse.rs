@@ -0,0 +1,72 @@
+//! Batched inserts into ClickHouse over its HTTP interface.
+//!
+//! Expects a table such as
+//! `CREATE TABLE ticks (ts DateTime64(6, 'UTC'), stock_id Int32, price Float64)
+//!  ENGINE = MergeTree ORDER BY (stock_id, ts)`.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use tracing::{error, info};
+
+use crate::bus::recv_batch;
+use crate::config::ClickHouseConfig;
+use crate::ratelimit::RateLimiter;
+use crate::tick::{Tick, TickReceiver};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub async fn run(cfg: ClickHouseConfig, mut ticks: TickReceiver, limiter: RateLimiter) {
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            error!("ClickHouse sink disabled, cannot build HTTP client: {:?}", e);
+            return;
+        }
+    };
+    let query = format!("INSERT INTO {}.{} (ts, stock_id, price) FORMAT JSONEachRow", cfg.database, cfg.table);
+    let async_insert = if cfg.async_insert { "1" } else { "0" };
+    let window = Duration::from_millis(cfg.batch_ms);
+    info!("ClickHouse sink writing to {} ({}.{})", cfg.url, cfg.database, cfg.table);
+
+    while let Some(mut batch) = recv_batch(&mut ticks, "ClickHouse sink", window, cfg.max_batch, |_| true).await {
+        if !limiter.admit_batch(&mut batch).await {
+            continue;
+        }
+        let mut request = client
+            .post(&cfg.url)
+            .query(&[
+                ("query", query.as_str()),
+                ("date_time_input_format", "best_effort"),
+                ("async_insert", async_insert),
+                ("wait_for_async_insert", "0"),
+            ])
+            .body(encode_rows(&batch));
+        if let Some(user) = &cfg.user {
+            request = request.header("X-ClickHouse-User", user);
+        }
+        if let Some(password) = &cfg.password {
+            request = request.header("X-ClickHouse-Key", password);
+        }
+
+        match request.send().await {
+            Ok(resp) if resp.status().is_success() => {}
+            Ok(resp) => {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                error!("ClickHouse insert of {} ticks rejected ({}): {}", batch.len(), status, body.trim());
+            }
+            Err(e) => error!("ClickHouse insert of {} ticks failed: {:?}", batch.len(), e),
+        }
+    }
+}
+
+fn encode_rows(ticks: &[Tick]) -> String {
+    let mut body = String::with_capacity(ticks.len() * 72);
+    for tick in ticks {
+        let ts = DateTime::<Utc>::from(tick.ts).to_rfc3339_opts(SecondsFormat::Micros, true);
+        let _ = writeln!(body, r#"{{"ts":"{}","stock_id":{},"price":{}}}"#, ts, tick.stock_id, tick.price);
+    }
+    body
+}
@@ -0,0 +1,114 @@
+//! Writes prices and latency samples to an InfluxDB v2 bucket.
+//!
+//! Two measurements are produced:
+//! `price,stock_id=0 value=100.25 <ns>` and
+//! `latency,stage=redis_set,stock_id=0 nanos=5123i <ns>`.
+
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tracing::{error, info};
+
+use crate::bus::recv_batch;
+use crate::config::InfluxDbConfig;
+use crate::ratelimit::RateLimiter;
+use crate::latency::{LatencyReceiver, LatencySample};
+use crate::tick::{Tick, TickReceiver};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_BATCH: usize = 5_000;
+
+struct Writer {
+    client: reqwest::Client,
+    url: String,
+    token: String,
+}
+
+impl Writer {
+    async fn write(&self, what: &str, count: usize, body: String) {
+        let result = self
+            .client
+            .post(&self.url)
+            .header("Authorization", format!("Token {}", self.token))
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .body(body)
+            .send()
+            .await;
+        match result {
+            Ok(resp) if resp.status().is_success() => {}
+            Ok(resp) => {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                error!("InfluxDB write of {} {} rejected ({}): {}", count, what, status, body.trim());
+            }
+            Err(e) => error!("InfluxDB write of {} {} failed: {:?}", count, what, e),
+        }
+    }
+}
+
+pub async fn run(cfg: InfluxDbConfig, mut ticks: TickReceiver, mut latency: LatencyReceiver, limiter: RateLimiter) {
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            error!("InfluxDB sink disabled, cannot build HTTP client: {:?}", e);
+            return;
+        }
+    };
+    let url = match reqwest::Url::parse_with_params(
+        &format!("{}/api/v2/write", cfg.url.trim_end_matches('/')),
+        &[("org", cfg.org.as_str()), ("bucket", cfg.bucket.as_str()), ("precision", "ns")],
+    ) {
+        Ok(url) => url.to_string(),
+        Err(e) => {
+            error!("InfluxDB sink disabled, invalid url {}: {:?}", cfg.url, e);
+            return;
+        }
+    };
+    info!("InfluxDB sink writing to {} (bucket {})", cfg.url, cfg.bucket);
+
+    let writer = Arc::new(Writer { client, url, token: cfg.token });
+    let window = Duration::from_millis(cfg.batch_ms);
+
+    let latency_writer = Arc::clone(&writer);
+    let latency_limiter = limiter.clone();
+    tokio::spawn(async move {
+        while let Some(mut batch) = recv_batch(&mut latency, "InfluxDB latency sink", window, MAX_BATCH, |_| true).await {
+            if !latency_limiter.admit_batch(&mut batch).await {
+                continue;
+            }
+            latency_writer.write("latency samples", batch.len(), encode_latency(&batch)).await;
+        }
+    });
+
+    while let Some(mut batch) = recv_batch(&mut ticks, "InfluxDB price sink", window, MAX_BATCH, |_| true).await {
+        if !limiter.admit_batch(&mut batch).await {
+            continue;
+        }
+        writer.write("prices", batch.len(), encode_prices(&batch)).await;
+    }
+}
+
+fn encode_prices(ticks: &[Tick]) -> String {
+    let mut body = String::with_capacity(ticks.len() * 48);
+    for tick in ticks {
+        let _ = writeln!(body, "price,stock_id={} value={} {}", tick.stock_id, tick.price, unix_nanos(tick.ts));
+    }
+    body
+}
+
+fn encode_latency(samples: &[LatencySample]) -> String {
+    let mut body = String::with_capacity(samples.len() * 64);
+    for sample in samples {
+        let _ = write!(body, "latency,stage={}", sample.stage.as_str());
+        if let Some(stock_id) = sample.stock_id {
+            let _ = write!(body, ",stock_id={}", stock_id);
+        }
+        let _ = writeln!(body, " nanos={}i {}", sample.nanos, unix_nanos(sample.at));
+    }
+    body
+}
+
+fn unix_nanos(ts: SystemTime) -> u128 {
+    ts.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0)
+}
@@ -0,0 +1,87 @@
+//! Publishes every tick to a Kafka topic, keyed by ticker so each symbol
+//! stays ordered within its partition.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rdkafka::config::ClientConfig;
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{BaseRecord, DeliveryResult, Producer, ProducerContext, ThreadedProducer};
+use rdkafka::ClientContext;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, warn};
+
+use crate::config::KafkaConfig;
+use crate::ratelimit::RateLimiter;
+use crate::symbols::Symbol;
+use crate::tick::TickReceiver;
+
+const QUEUE_FULL_BACKOFF: Duration = Duration::from_millis(5);
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Logs failed deliveries; librdkafka reports them from its poll thread.
+struct DeliveryLogger;
+
+impl ClientContext for DeliveryLogger {}
+
+impl ProducerContext for DeliveryLogger {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: Self::DeliveryOpaque) {
+        if let Err((e, _)) = result {
+            error!("Kafka delivery failed: {:?}", e);
+        }
+    }
+}
+
+pub async fn run(cfg: KafkaConfig, mut ticks: TickReceiver, limiter: RateLimiter, symbols: Arc<Vec<Symbol>>) {
+    let mut client = ClientConfig::new();
+    client
+        .set("bootstrap.servers", &cfg.brokers)
+        .set("acks", &cfg.acks)
+        .set("linger.ms", cfg.linger_ms.to_string());
+    for (key, value) in &cfg.properties {
+        client.set(key, value);
+    }
+    let producer: ThreadedProducer<DeliveryLogger> = match client.create_with_context(DeliveryLogger) {
+        Ok(producer) => producer,
+        Err(e) => {
+            error!("Kafka sink disabled, cannot create producer: {:?}", e);
+            return;
+        }
+    };
+    info!("Kafka sink publishing to {} on {}", cfg.topic, cfg.brokers);
+
+    loop {
+        let tick = match ticks.recv().await {
+            Ok(tick) => tick,
+            Err(RecvError::Lagged(n)) => {
+                warn!("Kafka sink lagged, skipped {} ticks", n);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        if limiter.admit(1).await == 0 {
+            continue;
+        }
+        let key = super::ticker(&symbols, tick.stock_id);
+        let payload = tick.to_json();
+        let mut record = BaseRecord::to(&cfg.topic).key(key).payload(&payload);
+        loop {
+            match producer.send(record) {
+                Ok(()) => break,
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), rejected)) => {
+                    // Local queue is full: give librdkafka a moment to drain it.
+                    record = rejected;
+                    tokio::time::sleep(QUEUE_FULL_BACKOFF).await;
+                }
+                Err((e, _)) => {
+                    error!("Kafka produce failed for {}: {:?}", key, e);
+                    break;
+                }
+            }
+        }
+    }
+
+    let _ = producer.flush(FLUSH_TIMEOUT);
+}
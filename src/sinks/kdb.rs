@@ -0,0 +1,134 @@
+//! Upserts ticks into a q process over kdb+ IPC.
+//!
+//! The target table is expected to look like
+//! `ticks:([] time:`timestamp$(); stock_id:`int$(); price:`float$())`.
+//! Each batch is sent as an async `(`upsert; `ticks; columns)` message.
+
+use std::io;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{error, info};
+
+use crate::bus::recv_batch;
+use crate::config::KdbConfig;
+use crate::health::HealthHandle;
+use crate::ratelimit::RateLimiter;
+use crate::tick::{Tick, TickReceiver};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_BATCH: usize = 10_000;
+/// Nanoseconds between the Unix epoch and the kdb+ epoch (2000.01.01).
+const KDB_EPOCH_OFFSET_NANOS: i64 = 946_684_800 * 1_000_000_000;
+
+const MSG_ASYNC: u8 = 0;
+const LITTLE_ENDIAN: u8 = 1;
+const CAPABILITY: u8 = 3;
+
+const KDB_LIST: i8 = 0;
+const KDB_INT_VEC: i8 = 6;
+const KDB_FLOAT_VEC: i8 = 9;
+const KDB_TIMESTAMP_VEC: i8 = 12;
+const KDB_SYMBOL: i8 = -11;
+
+pub async fn run(cfg: KdbConfig, mut ticks: TickReceiver, limiter: RateLimiter, health: HealthHandle) {
+    let mut conn: Option<TcpStream> = None;
+    let window = Duration::from_millis(cfg.batch_ms);
+
+    while let Some(mut batch) = recv_batch(&mut ticks, "kdb+ sink", window, MAX_BATCH, |_| true).await {
+        if !limiter.admit_batch(&mut batch).await {
+            continue;
+        }
+        if conn.is_none() {
+            let started = Instant::now();
+            match connect(&cfg).await {
+                Ok(stream) => {
+                    info!("kdb+ sink connected to {}", cfg.addr);
+                    health.up(Some(started.elapsed()));
+                    conn = Some(stream);
+                }
+                Err(e) => {
+                    error!("kdb+ connect to {} failed, dropping {} ticks: {:?}", cfg.addr, batch.len(), e);
+                    health.down(&e);
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                    continue;
+                }
+            }
+        }
+        let stream = conn.as_mut().unwrap();
+        if let Err(e) = stream.write_all(&encode_upsert(&cfg.table, &batch)).await {
+            error!("kdb+ write failed, dropping {} ticks: {:?}", batch.len(), e);
+            health.down(&e);
+            conn = None;
+        }
+    }
+}
+
+async fn connect(cfg: &KdbConfig) -> io::Result<TcpStream> {
+    let mut stream = TcpStream::connect(&cfg.addr).await?;
+    stream.set_nodelay(true)?;
+
+    let credentials = match (&cfg.user, &cfg.password) {
+        (Some(user), Some(password)) => format!("{}:{}", user, password),
+        (Some(user), None) => user.clone(),
+        _ => String::new(),
+    };
+    let mut hello = credentials.into_bytes();
+    hello.extend_from_slice(&[CAPABILITY, 0]);
+    stream.write_all(&hello).await?;
+
+    // The server answers with its capability byte, or closes on bad credentials.
+    let mut capability = [0u8; 1];
+    if stream.read(&mut capability).await? == 0 {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "kdb+ handshake rejected"));
+    }
+    Ok(stream)
+}
+
+/// Serializes `(`upsert; `table; (time; stock_id; price))` as an async IPC message.
+fn encode_upsert(table: &str, ticks: &[Tick]) -> Vec<u8> {
+    let n = ticks.len() as i32;
+    let mut body = Vec::with_capacity(32 + table.len() + ticks.len() * 20);
+
+    write_list_header(&mut body, KDB_LIST, 3);
+    write_symbol(&mut body, "upsert");
+    write_symbol(&mut body, table);
+
+    write_list_header(&mut body, KDB_LIST, 3);
+    write_list_header(&mut body, KDB_TIMESTAMP_VEC, n);
+    for tick in ticks {
+        body.extend_from_slice(&kdb_timestamp(tick.ts).to_le_bytes());
+    }
+    write_list_header(&mut body, KDB_INT_VEC, n);
+    for tick in ticks {
+        body.extend_from_slice(&tick.stock_id.to_le_bytes());
+    }
+    write_list_header(&mut body, KDB_FLOAT_VEC, n);
+    for tick in ticks {
+        body.extend_from_slice(&tick.price.to_f64().to_le_bytes());
+    }
+
+    let mut msg = Vec::with_capacity(8 + body.len());
+    msg.extend_from_slice(&[LITTLE_ENDIAN, MSG_ASYNC, 0, 0]);
+    msg.extend_from_slice(&((8 + body.len()) as u32).to_le_bytes());
+    msg.extend_from_slice(&body);
+    msg
+}
+
+fn write_list_header(buf: &mut Vec<u8>, kind: i8, len: i32) {
+    buf.push(kind as u8);
+    buf.push(0); // attributes
+    buf.extend_from_slice(&len.to_le_bytes());
+}
+
+fn write_symbol(buf: &mut Vec<u8>, sym: &str) {
+    buf.push(KDB_SYMBOL as u8);
+    buf.extend_from_slice(sym.as_bytes());
+    buf.push(0);
+}
+
+fn kdb_timestamp(ts: SystemTime) -> i64 {
+    let unix_nanos = ts.duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as i64).unwrap_or(0);
+    unix_nanos - KDB_EPOCH_OFFSET_NANOS
+}
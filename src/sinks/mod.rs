@@ -0,0 +1,104 @@
+mod clickhouse;
+mod influxdb;
+#[cfg(feature = "kafka")]
+mod kafka;
+mod kdb;
+mod multicast;
+mod nats;
+#[cfg(feature = "zmq")]
+mod zmq;
+mod questdb;
+mod shm;
+
+use std::future::Future;
+use std::sync::Arc;
+
+use tracing::{info_span, Instrument};
+
+use crate::config::SinksConfig;
+use crate::health::HealthRegistry;
+use crate::latency::LatencyRecorder;
+use crate::ratelimit::{RateLimits, SinkKind};
+use crate::symbols::Symbol;
+use crate::tick::TickSender;
+
+/// Spawns a task on the current runtime for every sink configured in `cfg`.
+pub fn spawn_configured(
+    cfg: &SinksConfig,
+    ticks: &TickSender,
+    latency: &LatencyRecorder,
+    limits: &RateLimits,
+    health: &HealthRegistry,
+    symbols: &Arc<Vec<Symbol>>,
+) {
+    if let Some(kdb) = &cfg.kdb {
+        spawn(SinkKind::Kdb, kdb::run(
+            kdb.clone(),
+            ticks.subscribe(),
+            limits.get(SinkKind::Kdb),
+            health.register(format!("kdb+ {}", kdb.addr)),
+        ));
+    }
+    if let Some(clickhouse) = &cfg.clickhouse {
+        let limiter = limits.get(SinkKind::Clickhouse);
+        spawn(SinkKind::Clickhouse, clickhouse::run(clickhouse.clone(), ticks.subscribe(), limiter));
+    }
+    if let Some(questdb) = &cfg.questdb {
+        spawn(SinkKind::Questdb, questdb::run(
+            questdb.clone(),
+            ticks.subscribe(),
+            limits.get(SinkKind::Questdb),
+            health.register(format!("questdb {}", questdb.addr)),
+        ));
+    }
+    if let Some(influxdb) = &cfg.influxdb {
+        spawn(SinkKind::Influxdb, influxdb::run(
+            influxdb.clone(),
+            ticks.subscribe(),
+            latency.subscribe(),
+            limits.get(SinkKind::Influxdb),
+        ));
+    }
+    if let Some(nats) = &cfg.nats {
+        spawn(SinkKind::Nats, nats::run(
+            nats.clone(),
+            ticks.subscribe(),
+            limits.get(SinkKind::Nats),
+            Arc::clone(symbols),
+        ));
+    }
+    if let Some(multicast) = &cfg.multicast {
+        spawn(SinkKind::Multicast, multicast::run(multicast.clone(), ticks.subscribe(), limits.get(SinkKind::Multicast)));
+    }
+    if let Some(shm) = &cfg.shm {
+        spawn(SinkKind::Shm, shm::run(shm.clone(), ticks.subscribe(), limits.get(SinkKind::Shm)));
+    }
+    if let Some(zmq) = &cfg.zmq {
+        #[cfg(feature = "zmq")]
+        spawn(SinkKind::Zmq, zmq::run(zmq.clone(), ticks.subscribe(), limits.get(SinkKind::Zmq), Arc::clone(symbols)));
+        #[cfg(not(feature = "zmq"))]
+        tracing::warn!("Ignoring [sinks.zmq] for {}: built without the `zmq` feature", zmq.endpoint);
+    }
+    if let Some(kafka) = &cfg.kafka {
+        #[cfg(feature = "kafka")]
+        spawn(SinkKind::Kafka, kafka::run(
+            kafka.clone(),
+            ticks.subscribe(),
+            limits.get(SinkKind::Kafka),
+            Arc::clone(symbols),
+        ));
+        #[cfg(not(feature = "kafka"))]
+        tracing::warn!("Ignoring [sinks.kafka] for {}: built without the `kafka` feature", kafka.brokers);
+    }
+}
+
+/// What the message-bus sinks key and route `stock_id`'s ticks by, as the
+/// Redis cache does: its ticker.
+fn ticker(symbols: &[Symbol], stock_id: i32) -> &str {
+    usize::try_from(stock_id).ok().and_then(|id| symbols.get(id)).map_or("unknown", |s| s.ticker.as_str())
+}
+
+/// In a span naming the sink as its stage, for the log.
+fn spawn(kind: SinkKind, task: impl Future<Output = ()> + Send + 'static) {
+    tokio::spawn(task.instrument(info_span!("sink", stage = kind.as_str())));
+}
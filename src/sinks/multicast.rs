@@ -0,0 +1,104 @@
+//! Publishes SBE-encoded ticks to a UDP multicast group.
+//!
+//! Ticks already waiting on the bus are packed into the same datagram, up to
+//! what fits in one Ethernet frame; an idle feed sends each tick on its own.
+//! Run `hft-latency multicast-recv` to consume the feed. With a
+//! `secondary_group` every packet is sent to both groups under the same
+//! sequence number, for receivers arbitrating the pair.
+
+use std::io;
+use std::net::SocketAddr;
+use std::time::SystemTime;
+
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::UdpSocket;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tracing::{error, info, warn};
+
+use crate::config::MulticastConfig;
+use crate::net::{parse_interface, parse_multicast_group};
+use crate::ratelimit::RateLimiter;
+use crate::sbe::{self, PacketHeader, PACKET_HEADER_LEN, TICK_MESSAGE_LEN};
+use crate::tick::TickReceiver;
+
+/// Largest UDP payload that avoids IP fragmentation on a 1500-byte MTU.
+const MAX_PAYLOAD: usize = 1472;
+const MAX_TICKS_PER_PACKET: usize = (MAX_PAYLOAD - PACKET_HEADER_LEN) / TICK_MESSAGE_LEN;
+
+pub async fn run(cfg: MulticastConfig, mut ticks: TickReceiver, limiter: RateLimiter) {
+    let (socket, groups) = match open(&cfg) {
+        Ok(open) => open,
+        Err(e) => {
+            error!("Multicast sink disabled: {}", e);
+            return;
+        }
+    };
+    let names: Vec<_> = groups.iter().map(|g| g.to_string()).collect();
+    info!("Multicast sink publishing to {}", names.join(" and "));
+
+    let mut seq = 0u64;
+    let mut buf = Vec::with_capacity(MAX_PAYLOAD);
+    loop {
+        let first = match ticks.recv().await {
+            Ok(tick) => tick,
+            Err(RecvError::Lagged(n)) => {
+                warn!("Multicast sink lagged, skipped {} ticks", n);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        if limiter.admit(1).await == 0 {
+            continue;
+        }
+
+        seq += 1;
+        buf.clear();
+        sbe::encode_packet_header(&mut buf, &PacketHeader { seq, sending_time_ns: 0 });
+        sbe::encode_tick(&mut buf, &first);
+        let mut closed = false;
+        for _ in 1..MAX_TICKS_PER_PACKET {
+            match ticks.try_recv() {
+                Ok(tick) => {
+                    if limiter.admit(1).await == 1 {
+                        sbe::encode_tick(&mut buf, &tick);
+                    }
+                }
+                Err(TryRecvError::Lagged(n)) => warn!("Multicast sink lagged, skipped {} ticks", n),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Closed) => {
+                    closed = true;
+                    break;
+                }
+            }
+        }
+        // Stamped last so receivers measure wire latency, not batching.
+        buf[8..16].copy_from_slice(&sbe::unix_nanos(SystemTime::now()).to_le_bytes());
+
+        for &group in &groups {
+            if let Err(e) = socket.send_to(&buf, group).await {
+                error!("Multicast send of packet {} to {} failed: {:?}", seq, group, e);
+            }
+        }
+        if closed {
+            break;
+        }
+    }
+}
+
+fn open(cfg: &MulticastConfig) -> io::Result<(UdpSocket, Vec<SocketAddr>)> {
+    let mut groups = vec![SocketAddr::from(parse_multicast_group(&cfg.group)?)];
+    if let Some(secondary) = &cfg.secondary_group {
+        groups.push(parse_multicast_group(secondary)?.into());
+    }
+    let interface = parse_interface(cfg.interface.as_deref())?;
+
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_multicast_ttl_v4(cfg.ttl)?;
+    socket.set_multicast_loop_v4(cfg.loopback)?;
+    if !interface.is_unspecified() {
+        socket.set_multicast_if_v4(&interface)?;
+    }
+    socket.bind(&SocketAddr::from((interface, 0)).into())?;
+    socket.set_nonblocking(true)?;
+    Ok((UdpSocket::from_std(socket.into())?, groups))
+}
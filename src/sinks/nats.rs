@@ -0,0 +1,101 @@
+//! Publishes ticks to NATS on `<subject_prefix>.<ticker>`, optionally
+//! through a JetStream stream so subscribers can replay the feed.
+
+use std::future::IntoFuture;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_nats::jetstream;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, warn};
+
+use crate::config::NatsConfig;
+use crate::ratelimit::RateLimiter;
+use crate::symbols::Symbol;
+use crate::tick::TickReceiver;
+
+/// JetStream publishes awaiting their ack before the sink stops reading ticks.
+const MAX_PENDING_ACKS: usize = 256;
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+pub async fn run(cfg: NatsConfig, mut ticks: TickReceiver, limiter: RateLimiter, symbols: Arc<Vec<Symbol>>) {
+    let mut options = async_nats::ConnectOptions::new().name("hft-latency");
+    if let (Some(user), Some(password)) = (&cfg.user, &cfg.password) {
+        options = options.user_and_password(user.clone(), password.clone());
+    } else if let Some(token) = &cfg.token {
+        options = options.token(token.clone());
+    }
+    // The client reconnects on its own; this only fails if the first connect does.
+    let client = match options.connect(&cfg.url).await {
+        Ok(client) => client,
+        Err(e) => {
+            error!("NATS sink disabled, cannot connect to {}: {:?}", cfg.url, e);
+            return;
+        }
+    };
+
+    let js = if cfg.jetstream {
+        let js = jetstream::new(client.clone());
+        let stream = jetstream::stream::Config {
+            name: cfg.stream.clone(),
+            subjects: vec![format!("{}.>", cfg.subject_prefix)],
+            max_age: MAX_AGE,
+            ..Default::default()
+        };
+        if let Err(e) = js.get_or_create_stream(stream).await {
+            error!("NATS sink disabled, cannot create JetStream stream {}: {:?}", cfg.stream, e);
+            return;
+        }
+        Some(js)
+    } else {
+        None
+    };
+    info!(
+        "NATS sink publishing to {}.* on {}{}",
+        cfg.subject_prefix,
+        cfg.url,
+        if js.is_some() { " (JetStream)" } else { "" }
+    );
+
+    let mut pending = FuturesUnordered::new();
+    loop {
+        let tick = match ticks.recv().await {
+            Ok(tick) => tick,
+            Err(RecvError::Lagged(n)) => {
+                warn!("NATS sink lagged, skipped {} ticks", n);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        if limiter.admit(1).await == 0 {
+            continue;
+        }
+        let ticker = super::ticker(&symbols, tick.stock_id);
+        let subject = format!("{}.{}", cfg.subject_prefix, ticker);
+        let payload = tick.to_json().into();
+
+        let Some(js) = &js else {
+            if let Err(e) = client.publish(subject, payload).await {
+                error!("NATS publish failed for {}: {:?}", ticker, e);
+            }
+            continue;
+        };
+        match js.publish(subject, payload).await {
+            Ok(ack) => pending.push(ack.into_future()),
+            Err(e) => error!("JetStream publish failed for {}: {:?}", ticker, e),
+        }
+        while pending.len() >= MAX_PENDING_ACKS {
+            if let Some(Err(e)) = pending.next().await {
+                error!("JetStream ack failed: {:?}", e);
+            }
+        }
+    }
+
+    while let Some(ack) = pending.next().await {
+        if let Err(e) = ack {
+            error!("JetStream ack failed: {:?}", e);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Streams ticks to QuestDB using the InfluxDB Line Protocol over TCP.
+//!
+//! Lines look like `ticks,stock_id=0 price=100.25 1760436000000000000` and
+//! QuestDB creates the table on first write.
+//!
+//! Lines are kept in a pending buffer until the socket accepts them. A slow or
+//! unreachable server fills the buffer up to `max_buffer_bytes`, after which
+//! the oldest lines are dropped so the subscriber never stalls the tick bus.
+//! A line cut off by a failed write is resent after reconnecting.
+
+use std::fmt::Write as _;
+use std::time::{Duration, UNIX_EPOCH};
+
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::time::{timeout, Instant};
+use tracing::{error, info, warn};
+
+use crate::bus::recv_batch;
+use crate::config::QuestDbConfig;
+use crate::health::HealthHandle;
+use crate::ratelimit::RateLimiter;
+use crate::tick::{Tick, TickReceiver};
+
+const MAX_BATCH: usize = 10_000;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+pub async fn run(cfg: QuestDbConfig, mut ticks: TickReceiver, limiter: RateLimiter, health: HealthHandle) {
+    let window = Duration::from_millis(cfg.batch_ms);
+    let write_timeout = Duration::from_millis(cfg.write_timeout_ms);
+    let mut pending = String::new();
+    let mut dropped_lines: u64 = 0;
+    let mut conn: Option<TcpStream> = None;
+    let mut backoff = INITIAL_BACKOFF;
+    let mut next_attempt = Instant::now();
+
+    while let Some(mut batch) = recv_batch(&mut ticks, "QuestDB sink", window, MAX_BATCH, |_| true).await {
+        if !limiter.admit_batch(&mut batch).await {
+            continue;
+        }
+        encode_lines(&cfg.table, &batch, &mut pending);
+        let dropped = trim_oldest(&mut pending, cfg.max_buffer_bytes);
+        if dropped > 0 {
+            dropped_lines += dropped as u64;
+            warn!("QuestDB sink buffer full, dropped {} lines ({} total)", dropped, dropped_lines);
+        }
+
+        if conn.is_none() && Instant::now() >= next_attempt {
+            let started = Instant::now();
+            match TcpStream::connect(&cfg.addr).await {
+                Ok(stream) => {
+                    let _ = stream.set_nodelay(true);
+                    info!("QuestDB sink connected to {}", cfg.addr);
+                    health.up(Some(started.elapsed()));
+                    conn = Some(stream);
+                    backoff = INITIAL_BACKOFF;
+                }
+                Err(e) => {
+                    error!("QuestDB connect to {} failed, retrying in {:?}: {:?}", cfg.addr, backoff, e);
+                    health.down(&e);
+                    next_attempt = Instant::now() + backoff;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+
+        if let Some(stream) = conn.as_mut() {
+            let (written, result) = write_pending(stream, pending.as_bytes(), write_timeout).await;
+            // Keep the line the failure cut through so it is resent whole.
+            let delivered = pending[..written].rfind('\n').map_or(0, |i| i + 1);
+            pending.drain(..delivered);
+            if let Err(e) = result {
+                error!("QuestDB write failed, reconnecting: {}", e);
+                health.down(&e);
+                conn = None;
+                next_attempt = Instant::now();
+            }
+        }
+    }
+}
+
+fn encode_lines(table: &str, ticks: &[Tick], out: &mut String) {
+    for tick in ticks {
+        let nanos = tick.ts.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+        let _ = writeln!(out, "{},stock_id={} price={} {}", table, tick.stock_id, tick.price, nanos);
+    }
+}
+
+/// Drops whole lines from the front until `buf` fits in `max_bytes`,
+/// returning how many lines were dropped.
+fn trim_oldest(buf: &mut String, max_bytes: usize) -> usize {
+    if buf.len() <= max_bytes {
+        return 0;
+    }
+    let excess = buf.len() - max_bytes;
+    let cut = buf[excess..].find('\n').map_or(buf.len(), |i| excess + i + 1);
+    let dropped = buf[..cut].matches('\n').count();
+    buf.drain(..cut);
+    dropped
+}
+
+/// Writes as much of `buf` as the socket takes within `limit` per call,
+/// returning the number of bytes written and the error that stopped it.
+async fn write_pending(stream: &mut TcpStream, buf: &[u8], limit: Duration) -> (usize, Result<(), String>) {
+    let mut written = 0;
+    while written < buf.len() {
+        match timeout(limit, stream.write(&buf[written..])).await {
+            Ok(Ok(0)) => return (written, Err("connection closed".to_string())),
+            Ok(Ok(n)) => written += n,
+            Ok(Err(e)) => return (written, Err(e.to_string())),
+            Err(_) => return (written, Err(format!("write stalled for {:?}", limit))),
+        }
+    }
+    (written, Ok(()))
+}
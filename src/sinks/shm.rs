@@ -0,0 +1,108 @@
+//! Single-writer, many-reader tick ring in a memory-mapped file.
+//!
+//! Readers map the same file (normally under `/dev/shm`) and spin on the
+//! write cursor, so hand-off costs a cache-line transfer rather than a
+//! syscall. Readers never slow the writer down: one that falls more than
+//! `capacity` ticks behind loses the overwritten ticks and skips ahead.
+//!
+//! Layout, every field a little-endian `u64` used atomically:
+//!
+//! ```text
+//! offset 0    magic, capacity                  (header)
+//! offset 64   write_seq: ticks written so far  (own cache line)
+//! offset 128  capacity slots of 64 bytes:
+//!             seq, stock_id, price bits, ts_ns, publish_ns, 3 x padding
+//! ```
+//!
+//! Tick `n` lives in slot `n % capacity`. The slot `seq` is a seqlock: odd
+//! (`2n + 1`) while the writer fills it, `2n + 2` once tick `n` is complete.
+//! A reader must see the same even value before and after copying the slot.
+
+use std::fs::OpenOptions;
+use std::io;
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use memmap2::MmapMut;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, warn};
+
+use crate::config::ShmConfig;
+use crate::ratelimit::RateLimiter;
+use crate::sbe::unix_nanos;
+use crate::tick::{Tick, TickReceiver};
+
+const MAGIC: u64 = u64::from_le_bytes(*b"HFTRING1");
+const WORD: usize = 8;
+const WRITE_SEQ_WORD: usize = 8;
+const SLOTS_OFFSET_WORDS: usize = 16;
+const SLOT_WORDS: usize = 8;
+
+struct ShmRing {
+    map: MmapMut,
+    mask: u64,
+    next: u64,
+}
+
+impl ShmRing {
+    fn create(path: &str, capacity: usize) -> io::Result<Self> {
+        if !capacity.is_power_of_two() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "capacity must be a power of two"));
+        }
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        file.set_len(((SLOTS_OFFSET_WORDS + capacity * SLOT_WORDS) * WORD) as u64)?;
+        // SAFETY: the file is ours; concurrent readers only touch it through
+        // atomics, see `word`.
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        let ring = ShmRing { map, mask: capacity as u64 - 1, next: 0 };
+        ring.word(1).store(capacity as u64, Ordering::Relaxed);
+        ring.word(WRITE_SEQ_WORD).store(0, Ordering::Relaxed);
+        // Published last so readers never see a half-initialised header.
+        ring.word(0).store(MAGIC, Ordering::Release);
+        Ok(ring)
+    }
+
+    fn word(&self, index: usize) -> &AtomicU64 {
+        debug_assert!((index + 1) * WORD <= self.map.len());
+        // SAFETY: in-bounds, and the mapping is page-aligned so every word is
+        // 8-byte aligned. All access, ours and other processes', is atomic.
+        unsafe { &*(self.map.as_ptr() as *const AtomicU64).add(index) }
+    }
+
+    fn push(&mut self, tick: &Tick) {
+        let n = self.next;
+        let slot = SLOTS_OFFSET_WORDS + (n & self.mask) as usize * SLOT_WORDS;
+        self.word(slot).store(2 * n + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        self.word(slot + 1).store(tick.stock_id as u32 as u64, Ordering::Relaxed);
+        self.word(slot + 2).store(tick.price.to_f64().to_bits(), Ordering::Relaxed);
+        self.word(slot + 3).store(unix_nanos(tick.ts), Ordering::Relaxed);
+        self.word(slot + 4).store(unix_nanos(SystemTime::now()), Ordering::Relaxed);
+        self.word(slot).store(2 * n + 2, Ordering::Release);
+        self.next = n + 1;
+        self.word(WRITE_SEQ_WORD).store(self.next, Ordering::Release);
+    }
+}
+
+pub async fn run(cfg: ShmConfig, mut ticks: TickReceiver, limiter: RateLimiter) {
+    let mut ring = match ShmRing::create(&cfg.path, cfg.capacity) {
+        Ok(ring) => ring,
+        Err(e) => {
+            error!("Shared-memory sink disabled, cannot map {}: {}", cfg.path, e);
+            return;
+        }
+    };
+    info!("Shared-memory ring of {} ticks at {}", cfg.capacity, cfg.path);
+
+    loop {
+        match ticks.recv().await {
+            Ok(tick) => {
+                if limiter.admit(1).await == 1 {
+                    ring.push(&tick);
+                }
+            }
+            Err(RecvError::Lagged(n)) => warn!("Shared-memory sink lagged, skipped {} ticks", n),
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Brokerless ZeroMQ PUB endpoint. Each tick is a two-frame message:
+//! topic `stock:<ticker>` followed by the JSON tick. ZeroMQ subscriptions
+//! match on prefix, so subscribers wanting one symbol should compare the
+//! topic frame exactly (`stock:AB` also matches `stock:ABC`).
+//!
+//! See `examples/zmq_sub.rs` for a matching subscriber.
+
+use std::sync::Arc;
+
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, warn};
+use zeromq::{PubSocket, Socket, SocketSend, ZmqMessage};
+
+use crate::config::ZmqConfig;
+use crate::ratelimit::RateLimiter;
+use crate::symbols::Symbol;
+use crate::tick::TickReceiver;
+
+pub async fn run(cfg: ZmqConfig, mut ticks: TickReceiver, limiter: RateLimiter, symbols: Arc<Vec<Symbol>>) {
+    let mut socket = PubSocket::new();
+    if let Err(e) = socket.bind(&cfg.endpoint).await {
+        error!("ZeroMQ sink disabled, cannot bind {}: {:?}", cfg.endpoint, e);
+        return;
+    }
+    info!("ZeroMQ PUB bound to {}", cfg.endpoint);
+
+    loop {
+        let tick = match ticks.recv().await {
+            Ok(tick) => tick,
+            Err(RecvError::Lagged(n)) => {
+                warn!("ZeroMQ sink lagged, skipped {} ticks", n);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        if limiter.admit(1).await == 0 {
+            continue;
+        }
+        let ticker = super::ticker(&symbols, tick.stock_id);
+        let mut msg = ZmqMessage::from(format!("stock:{}", ticker));
+        msg.push_back(tick.to_json().into());
+        if let Err(e) = socket.send(msg).await {
+            error!("ZeroMQ publish failed for {}: {:?}", ticker, e);
+        }
+    }
+}
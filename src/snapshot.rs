@@ -0,0 +1,132 @@
+//! Periodic JSON snapshots of the prices, chart histories and latency
+//! histograms, restored on the next start so a long experiment survives a
+//! crash.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::config::SnapshotConfig;
+use crate::latency::{LatencyRecorder, Stage};
+use crate::market::{Ring, SharedMarketData, SharedUiData};
+use crate::price::Price;
+
+#[derive(Serialize, Deserialize)]
+struct SymbolState {
+    count: usize,
+    value: f64,
+    history: Vec<f64>,
+    /// When the last points of `history` were recorded; none in snapshots
+    /// taken before times were kept.
+    #[serde(default)]
+    times_unix_us: Vec<u64>,
+}
+
+impl SymbolState {
+    fn new(count: usize, value: f64, history: &Ring<f64>, times: &Ring<SystemTime>) -> Self {
+        let times_unix_us = times.iter().map(|t| t.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_micros() as u64));
+        SymbolState { count, value, history: history.to_vec(), times_unix_us: times_unix_us.collect() }
+    }
+
+    fn times(&self) -> Vec<SystemTime> {
+        self.times_unix_us.iter().map(|&us| UNIX_EPOCH + Duration::from_micros(us)).collect()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    saved_at_unix_ms: u64,
+    market: Vec<SymbolState>,
+    ui: Vec<SymbolState>,
+    /// `(nanos, count)` pairs per stage.
+    latency: BTreeMap<Stage, Vec<(u64, u64)>>,
+}
+
+impl Snapshot {
+    fn take(market: &SharedMarketData, ui: &SharedUiData, latency: &LatencyRecorder) -> Self {
+        let market = market
+            .read()
+            .unwrap()
+            .iter()
+            .map(|md| SymbolState::new(md.count, md.price.read().unwrap().to_f64(), &md.history, &md.times))
+            .collect();
+        let ui = ui
+            .read()
+            .unwrap()
+            .iter()
+            .map(|ui| SymbolState::new(ui.count, *ui.value, &ui.history, &ui.times))
+            .collect();
+        Snapshot {
+            saved_at_unix_ms: unix_ms(),
+            market,
+            ui,
+            latency: latency.counts(),
+        }
+    }
+
+    /// Overwrites the symbols present in both the snapshot and the running
+    /// state, and adds the saved latency samples to the histograms.
+    pub fn restore(self, market: &SharedMarketData, ui: &SharedUiData, latency: &LatencyRecorder) {
+        for (md, saved) in market.write().unwrap().iter_mut().zip(self.market) {
+            if md.count == saved.count {
+                *md.price.write().unwrap() = Price::from_f64(saved.value);
+                md.times.replace(saved.times());
+                md.history.replace(saved.history);
+                md.last_update = Instant::now();
+            }
+        }
+        for (ui, saved) in ui.write().unwrap().iter_mut().zip(self.ui) {
+            if ui.count == saved.count {
+                ui.value = Arc::new(saved.value);
+                ui.times.replace(saved.times());
+                ui.history.replace(saved.history);
+                ui.last_update = Instant::now();
+            }
+        }
+        latency.restore_counts(&self.latency);
+        let age = unix_ms().saturating_sub(self.saved_at_unix_ms);
+        info!("Restored state snapshot taken {:.1}s ago", age as f64 / 1000.0);
+    }
+}
+
+fn unix_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+/// The snapshot at `path`, or `None` if there is none yet.
+pub fn load(path: &str) -> io::Result<Option<Snapshot>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    serde_json::from_slice(&bytes)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e)))
+}
+
+/// Writes to a temporary file first, so a crash mid-write leaves the
+/// previous snapshot intact.
+pub fn save(path: &str, market: &SharedMarketData, ui: &SharedUiData, latency: &LatencyRecorder) -> io::Result<()> {
+    let json = serde_json::to_vec(&Snapshot::take(market, ui, latency)).map_err(io::Error::other)?;
+    let tmp = Path::new(path).with_extension("tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, path)
+}
+
+pub async fn run(cfg: SnapshotConfig, market: SharedMarketData, ui: SharedUiData, latency: LatencyRecorder) {
+    let mut interval = tokio::time::interval(Duration::from_millis(cfg.interval_ms.max(1)));
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        if let Err(e) = save(&cfg.path, &market, &ui, &latency) {
+            error!("Writing snapshot {} failed: {:?}", cfg.path, e);
+        }
+    }
+}
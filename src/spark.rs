@@ -0,0 +1,89 @@
+//! Recent end-to-end latency per symbol as a text sparkline in the Pointers
+//! panel: from a tick's timestamp, set by the simulator or the feed, to a
+//! subscriber of the tick bus receiving it. Each column is the worst tick of
+//! one frame, scaled to the worst on the line, so a spike stands out without
+//! opening the Statistics panel.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+use crate::tick::Tick;
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// Columns per sparkline.
+const SPARK_FRAMES: usize = 24;
+
+struct Columns {
+    /// By stock id, oldest first; `None` for frames the symbol did not tick.
+    frames: Vec<VecDeque<Option<Duration>>>,
+    current: Vec<Option<Duration>>,
+}
+
+#[derive(Clone)]
+pub struct LatencySparks {
+    columns: Arc<Mutex<Columns>>,
+}
+
+impl LatencySparks {
+    pub fn new(n_stocks: usize) -> Self {
+        LatencySparks {
+            columns: Arc::new(Mutex::new(Columns {
+                frames: vec![VecDeque::from(vec![None; SPARK_FRAMES]); n_stocks],
+                current: vec![None; n_stocks],
+            })),
+        }
+    }
+
+    /// Measures every tick on the bus as it arrives. Reading the bus from
+    /// the frame loop instead would add up to a frame of waiting to each.
+    pub async fn run(self, mut rx: broadcast::Receiver<Tick>) {
+        loop {
+            match rx.recv().await {
+                Ok(tick) => {
+                    let latency = tick.ts.elapsed().unwrap_or_default();
+                    let mut columns = self.columns.lock().unwrap();
+                    let Some(worst) = usize::try_from(tick.stock_id).ok().and_then(|id| columns.current.get_mut(id))
+                    else {
+                        continue;
+                    };
+                    *worst = Some(worst.map_or(latency, |w| w.max(latency)));
+                }
+                Err(RecvError::Lagged(n)) => warn!("Latency sparklines lagged, skipped {} ticks", n),
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// Moves every symbol on by one column.
+    pub fn end_frame(&self) {
+        let columns = &mut *self.columns.lock().unwrap();
+        for (frames, current) in columns.frames.iter_mut().zip(&mut columns.current) {
+            frames.pop_front();
+            frames.push_back(current.take());
+        }
+    }
+
+    /// `▁▁▂ ▁█▁▁ 1.20ms`: blank for frames without ticks, then the worst.
+    pub fn describe(&self, stock_id: usize) -> String {
+        let columns = self.columns.lock().unwrap();
+        let frames = &columns.frames[stock_id];
+        let Some(worst) = frames.iter().flatten().max() else {
+            return format!("{} -", " ".repeat(SPARK_FRAMES));
+        };
+        let line: String = frames
+            .iter()
+            .map(|frame| match frame {
+                Some(latency) => {
+                    let level = latency.as_secs_f64() / worst.as_secs_f64().max(f64::MIN_POSITIVE);
+                    BARS[((level * (BARS.len() - 1) as f64).round() as usize).min(BARS.len() - 1)]
+                }
+                None => ' ',
+            })
+            .collect();
+        format!("{} {:.2}ms", line, worst.as_secs_f64() * 1e3)
+    }
+}
@@ -0,0 +1,840 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::{DateTime, Utc};
+use memmap2::MmapMut;
+use ratatui::style::{Color, Modifier, Style};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tracing::{error, info, warn};
+
+use crate::config::{FsyncPolicy, SpoolBackend, SpoolConfig};
+use crate::price::Price;
+use crate::ratelimit::{LimitPolicy, RateLimiter};
+use crate::retry::Retrier;
+use crate::sequence::SeqCheck;
+use crate::tick::Tick;
+use crate::ulid::Ulid;
+
+/// Local append-only file ticks are spooled to between Postgres flushes.
+pub const SPOOL_PATH: &str = "stock_data.txt";
+/// Without a successful Postgres flush for this long, the Spool backlog
+/// line starts with [`BEHIND`].
+const STALE_AFTER: Duration = Duration::from_secs(5);
+/// Span the flush rate is averaged over.
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+const BEHIND: &str = "BEHIND";
+
+/// One spooled tick. Lines written before timestamps were spooled have no
+/// `ts`, those written before sequence numbers no `seq`, those written
+/// before tick IDs no `id`, and those written before run IDs no `run`.
+pub struct SpoolRecord {
+    pub stock_id: i32,
+    pub price: Price,
+    pub ts: Option<DateTime<Utc>>,
+    pub seq: Option<u64>,
+    pub id: Option<Ulid>,
+    /// Of the process that spooled the line, whose numbering `seq` is in.
+    pub run: Option<Ulid>,
+}
+
+/// What waits in the spool for Postgres, and how the flushes keep up.
+#[derive(Default)]
+struct BacklogState {
+    lines: usize,
+    bytes: usize,
+    /// Of the last flush that left nothing failed behind.
+    last_flush: Option<Instant>,
+    /// Rows inserted by each flush within [`RATE_WINDOW`].
+    flushed: VecDeque<(Instant, usize)>,
+    /// Lines moved to the dead letters.
+    dead: usize,
+    /// Ticks the spool could not take, e.g. with the disk full.
+    failed_appends: usize,
+}
+
+#[derive(Clone)]
+pub struct Backlog {
+    state: Arc<Mutex<BacklogState>>,
+    started: Instant,
+}
+
+impl Backlog {
+    fn new() -> Self {
+        Backlog { state: Arc::new(Mutex::new(BacklogState::default())), started: Instant::now() }
+    }
+
+    fn set(&self, lines: usize, bytes: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.lines = lines;
+        state.bytes = bytes;
+    }
+
+    fn appended(&self, bytes: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.lines += 1;
+        state.bytes += bytes;
+    }
+
+    fn append_failed(&self) {
+        self.state.lock().unwrap().failed_appends += 1;
+    }
+
+    fn dead_lettered(&self, lines: usize) {
+        self.state.lock().unwrap().dead += lines;
+    }
+
+    fn flushed(&self, rows: usize, ok: bool) {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        if ok {
+            state.last_flush = Some(now);
+        }
+        state.flushed.push_back((now, rows));
+        while state.flushed.front().is_some_and(|&(at, _)| now.duration_since(at) > RATE_WINDOW) {
+            state.flushed.pop_front();
+        }
+    }
+
+    /// `412 lines, 18.3 KB pending, last flush 0.4s ago, 1200 rows/s`,
+    /// starting with [`BEHIND`] once the last flush is stale.
+    pub fn describe(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let since = state.last_flush.unwrap_or(self.started).elapsed();
+        let span = self.started.elapsed().min(RATE_WINDOW);
+        let rate = state.flushed.iter().map(|&(_, rows)| rows).sum::<usize>() as f64 / span.as_secs_f64().max(1.0);
+        let last = match state.last_flush {
+            Some(_) => format!("last flush {:.1}s ago", since.as_secs_f64()),
+            None => format!("no flush in {:.1}s", since.as_secs_f64()),
+        };
+        let mut line = format!(
+            "{} lines, {:.1} KB pending, {}, {:.0} rows/s",
+            state.lines,
+            state.bytes as f64 / 1024.0,
+            last,
+            rate
+        );
+        if state.dead > 0 {
+            line += &format!(", {} dead letters", state.dead);
+        }
+        if state.failed_appends > 0 {
+            line += &format!(", {} failed appends", state.failed_appends);
+        }
+        if since >= STALE_AFTER {
+            format!("{} {}", BEHIND, line)
+        } else {
+            line
+        }
+    }
+}
+
+/// How often the spool goes to Postgres and how much at once, from
+/// `[spool]`, changed live by `:flush_ms` and `:batch_rows`.
+#[derive(Clone)]
+pub struct FlushSettings {
+    backend: SpoolBackend,
+    interval_ms: Arc<AtomicU64>,
+    batch_rows: Arc<AtomicUsize>,
+}
+
+impl FlushSettings {
+    pub fn new(cfg: &SpoolConfig) -> io::Result<Self> {
+        check_flush_ms(cfg.pg_flush_ms)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("spool.pg_flush_ms: {}", e)))?;
+        Ok(FlushSettings {
+            backend: cfg.backend,
+            interval_ms: Arc::new(AtomicU64::new(cfg.pg_flush_ms)),
+            batch_rows: Arc::new(AtomicUsize::new(cfg.batch_rows)),
+        })
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms.load(Ordering::Relaxed))
+    }
+
+    pub fn set_interval_ms(&self, ms: u64) -> Result<(), String> {
+        check_flush_ms(ms)?;
+        self.interval_ms.store(ms, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// `None` for no limit.
+    fn batch_rows(&self) -> Option<usize> {
+        Some(self.batch_rows.load(Ordering::Relaxed)).filter(|&n| n > 0)
+    }
+
+    /// `0` for no limit.
+    pub fn set_batch_rows(&self, rows: usize) {
+        self.batch_rows.store(rows, Ordering::Relaxed);
+    }
+
+    /// `file spool, every 1000 ms, up to 5000 rows`.
+    pub fn describe(&self) -> String {
+        let batch = self.batch_rows().map_or("every pending row".to_string(), |n| format!("up to {} rows", n));
+        let spool = match self.backend {
+            SpoolBackend::File => "file spool",
+            SpoolBackend::Mmap => "mmap spool",
+            SpoolBackend::Memory => "in memory, not durable",
+        };
+        format!("{}, every {} ms, {}", spool, self.interval().as_millis(), batch)
+    }
+}
+
+fn check_flush_ms(ms: u64) -> Result<(), String> {
+    if (10..=60_000).contains(&ms) {
+        Ok(())
+    } else {
+        Err(format!("must be from 10 to 60000 ms, not {}", ms))
+    }
+}
+
+/// For a diagnostics line, here or read back from `/status`: red while
+/// Postgres flushes are behind.
+pub fn line_style(label: &str, value: &str) -> Style {
+    if label == "Spool backlog" && value.starts_with(BEHIND) {
+        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    }
+}
+
+/// Where spooled ticks are kept until the next Postgres flush.
+pub struct SpoolWriter {
+    backend: Spool,
+    backlog: Backlog,
+    dead_letter: PathBuf,
+    /// Written with every line, to tell this run's lines from those left by
+    /// an earlier one.
+    run: Ulid,
+}
+
+enum Spool {
+    File(FileSpool),
+    Mmap(MmapSpool),
+    Memory(MemorySpool),
+}
+
+impl SpoolWriter {
+    pub async fn open(cfg: &SpoolConfig) -> io::Result<Self> {
+        let backend = match cfg.backend {
+            SpoolBackend::File => FileSpool::open(cfg).await.map(Spool::File)?,
+            SpoolBackend::Mmap => MmapSpool::open(cfg).map(Spool::Mmap)?,
+            SpoolBackend::Memory => Spool::Memory(MemorySpool::open()?),
+        };
+        let mut spool = SpoolWriter {
+            backend,
+            backlog: Backlog::new(),
+            dead_letter: PathBuf::from(&cfg.dead_letter),
+            run: Ulid::new(SystemTime::now()),
+        };
+        // Lines left unflushed by an earlier run.
+        let pending = spool.pending().await?;
+        spool.backlog.set(pending.lines().count(), pending.len());
+        Ok(spool)
+    }
+
+    pub fn backlog(&self) -> Backlog {
+        self.backlog.clone()
+    }
+
+    /// `stock_data.txt (mmap)`, or `the memory spool`.
+    fn describe(&self) -> String {
+        match self.backend {
+            Spool::File(_) => format!("{} (file)", SPOOL_PATH),
+            Spool::Mmap(_) => format!("{} (mmap)", SPOOL_PATH),
+            Spool::Memory(_) => "the memory spool".to_string(),
+        }
+    }
+
+    pub async fn append(&mut self, tick: Tick) -> io::Result<()> {
+        let bytes = match &mut self.backend {
+            Spool::File(spool) => spool.append(tick, self.run).await,
+            Spool::Mmap(spool) => spool.append(tick, self.run),
+            Spool::Memory(spool) => spool.append(tick, self.run),
+        };
+        match bytes {
+            Ok(bytes) => self.backlog.appended(bytes),
+            Err(_) => self.backlog.append_failed(),
+        }
+        bytes.map(|_| ())
+    }
+
+    pub fn flush_due(&self) -> bool {
+        match &self.backend {
+            Spool::File(spool) => spool.last_flush.elapsed() >= spool.flush_interval,
+            Spool::Mmap(spool) => spool.last_sync.elapsed() >= spool.sync_interval,
+            Spool::Memory(_) => false,
+        }
+    }
+
+    /// Writes buffered lines to the file, or msyncs the mapped region.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        match &mut self.backend {
+            Spool::File(spool) => spool.flush().await,
+            Spool::Mmap(spool) => spool.sync(),
+            Spool::Memory(_) => Ok(()),
+        }
+    }
+
+    /// Everything spooled since the last [`clear`](Self::clear).
+    async fn pending(&mut self) -> io::Result<String> {
+        self.flush().await?;
+        match &self.backend {
+            Spool::File(_) => tokio::fs::read_to_string(SPOOL_PATH).await,
+            Spool::Mmap(spool) => Ok(String::from_utf8_lossy(&spool.map[..spool.len]).into_owned()),
+            Spool::Memory(spool) => Ok(String::from_utf8_lossy(&spool.lines).into_owned()),
+        }
+    }
+
+    async fn clear(&mut self) -> io::Result<()> {
+        match &mut self.backend {
+            Spool::File(spool) => spool.file.get_ref().set_len(0).await,
+            Spool::Mmap(spool) => spool.clear(),
+            Spool::Memory(spool) => {
+                spool.lines.clear();
+                Ok(())
+            }
+        }?;
+        self.backlog.set(0, 0);
+        Ok(())
+    }
+
+    /// Appends each line of `dead` to the dead letters with its error, in
+    /// one write: should it fail, the file is cut back to where it ended so
+    /// that none of them is written twice when the lines go again.
+    async fn dead_letter(&mut self, dead: &[(&str, String)]) -> io::Result<()> {
+        if dead.is_empty() {
+            return Ok(());
+        }
+        let mut entries = Vec::new();
+        for (line, error) in dead {
+            warn!("Moving spool line {:?} to {}: {}", line, self.dead_letter.display(), error);
+            let entry = serde_json::json!({ "at": Utc::now().to_rfc3339(), "line": line, "error": error });
+            writeln!(entries, "{}", entry)?;
+        }
+        let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(&self.dead_letter).await?;
+        let len = file.metadata().await?.len();
+        let written = match file.write_all(&entries).await {
+            Ok(()) => file.sync_data().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = written {
+            file.set_len(len).await?;
+            return Err(e);
+        }
+        self.backlog.dead_lettered(dead.len());
+        Ok(())
+    }
+
+    /// Replaces the spool's contents with `rest`, the still unflushed tail.
+    /// The durable backends write it to a temporary file, fsynced, that is
+    /// then renamed over the spool and the directory fsynced, so a crash
+    /// leaves either every line or just `rest`, never fewer.
+    async fn retain(&mut self, rest: &str) -> io::Result<()> {
+        match &mut self.backend {
+            Spool::File(spool) => spool.replace(rest).await,
+            Spool::Mmap(spool) => spool.replace(rest).await,
+            Spool::Memory(spool) => {
+                spool.lines = rest.as_bytes().to_vec();
+                Ok(())
+            }
+        }?;
+        self.backlog.set(rest.lines().count(), rest.len());
+        Ok(())
+    }
+}
+
+/// Buffers spool lines in memory and writes them out at most every
+/// `flush_interval`, keeping a syscall per tick out of the hot path. The
+/// writes go through `tokio::fs`, on the runtime's blocking threads, and
+/// are fsynced per the [`FsyncPolicy`]. Ticks still buffered when the
+/// process dies are lost, and those written but not yet fsynced can be on
+/// a power loss.
+pub struct FileSpool {
+    file: BufWriter<tokio::fs::File>,
+    line: Vec<u8>,
+    flush_interval: Duration,
+    last_flush: Instant,
+    fsync: FsyncPolicy,
+    fsync_interval: Duration,
+    last_fsync: Instant,
+}
+
+impl FileSpool {
+    async fn open(cfg: &SpoolConfig) -> io::Result<Self> {
+        // Append mode keeps writes landing at the end after a flush to
+        // Postgres truncates the file.
+        let file = tokio::fs::OpenOptions::new().create(true).read(true).append(true).open(SPOOL_PATH).await?;
+        // Drop the zero padding left behind by the mmap backend.
+        let len = data_len(&tokio::fs::read(SPOOL_PATH).await?);
+        file.set_len(len as u64).await?;
+        Ok(FileSpool {
+            file: BufWriter::with_capacity(cfg.buffer_bytes, file),
+            line: Vec::with_capacity(64),
+            flush_interval: Duration::from_millis(cfg.flush_ms),
+            last_flush: Instant::now(),
+            fsync: cfg.fsync,
+            fsync_interval: Duration::from_millis(cfg.fsync_ms),
+            last_fsync: Instant::now(),
+        })
+    }
+
+    /// Returns the bytes written.
+    async fn append(&mut self, tick: Tick, run: Ulid) -> io::Result<usize> {
+        self.line.clear();
+        write_line(&mut self.line, tick, run)?;
+        self.file.write_all(&self.line).await?;
+        Ok(self.line.len())
+    }
+
+    async fn flush(&mut self) -> io::Result<()> {
+        self.last_flush = Instant::now();
+        self.file.flush().await?;
+        let due = match self.fsync {
+            FsyncPolicy::Never => false,
+            FsyncPolicy::Flush => true,
+            FsyncPolicy::Interval => self.last_fsync.elapsed() >= self.fsync_interval,
+        };
+        if due {
+            self.last_fsync = Instant::now();
+            self.file.get_ref().sync_data().await?;
+        }
+        Ok(())
+    }
+
+    /// Swaps the file for one holding just `rest`, see [`SpoolWriter::retain`].
+    async fn replace(&mut self, rest: &str) -> io::Result<()> {
+        self.file.flush().await?;
+        let tmp = tmp_path();
+        let mut file = tokio::fs::File::create(&tmp).await?;
+        file.write_all(rest.as_bytes()).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&tmp, SPOOL_PATH).await?;
+        tokio::fs::File::open(spool_dir()).await?.sync_all().await?;
+        *self.file.get_mut() =
+            tokio::fs::OpenOptions::new().create(true).read(true).append(true).open(SPOOL_PATH).await?;
+        Ok(())
+    }
+}
+
+/// Writes lines straight into a pre-allocated, memory-mapped copy of the
+/// spool file, so an append is a `memcpy`. The kernel writes dirty pages back
+/// on its own; `sync_interval` bounds what a power loss can take with it.
+/// Unused space is zero-filled, and the first zero byte marks the end of the
+/// data when the file is reopened.
+pub struct MmapSpool {
+    file: File,
+    map: MmapMut,
+    len: usize,
+    synced: usize,
+    line: Vec<u8>,
+    sync_interval: Duration,
+    last_sync: Instant,
+}
+
+impl MmapSpool {
+    fn open(cfg: &SpoolConfig) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).read(true).write(true).truncate(false).open(SPOOL_PATH)?;
+        if (file.metadata()?.len() as usize) < cfg.mmap_bytes {
+            file.set_len(cfg.mmap_bytes as u64)?;
+        }
+        // SAFETY: only this process writes the spool while it runs, and the
+        // file is never shrunk while mapped.
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        let len = data_len(&map);
+        if len > 0 {
+            info!("Recovered {} bytes of unflushed ticks from {}", len, SPOOL_PATH);
+        }
+        Ok(MmapSpool {
+            file,
+            map,
+            len,
+            synced: len,
+            line: Vec::with_capacity(64),
+            sync_interval: Duration::from_millis(cfg.flush_ms),
+            last_sync: Instant::now(),
+        })
+    }
+
+    /// Returns the bytes written.
+    fn append(&mut self, tick: Tick, run: Ulid) -> io::Result<usize> {
+        let mut line = std::mem::take(&mut self.line);
+        line.clear();
+        write_line(&mut line, tick, run)?;
+        let result = self.write_bytes(&line).map(|()| line.len());
+        self.line = line;
+        result
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if self.len + bytes.len() > self.map.len() {
+            self.grow(self.len + bytes.len())?;
+        }
+        self.map[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+        Ok(())
+    }
+
+    /// Doubles the region when Postgres has fallen behind.
+    fn grow(&mut self, needed: usize) -> io::Result<()> {
+        let size = (self.map.len() * 2).max(needed);
+        warn!("Spool region full, growing {} to {} bytes", SPOOL_PATH, size);
+        self.map.flush()?;
+        self.file.set_len(size as u64)?;
+        // SAFETY: as in `open`.
+        self.map = unsafe { MmapMut::map_mut(&self.file)? };
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.last_sync = Instant::now();
+        if self.len > self.synced {
+            self.map.flush_range(self.synced, self.len - self.synced)?;
+            self.synced = self.len;
+        }
+        Ok(())
+    }
+
+    /// Maps a copy of the region holding just `rest`, see
+    /// [`SpoolWriter::retain`]. The copy is written on the runtime's
+    /// blocking threads.
+    async fn replace(&mut self, rest: &str) -> io::Result<()> {
+        let (bytes, size) = (rest.as_bytes().to_vec(), self.map.len().max(rest.len()));
+        let write = move || -> io::Result<File> {
+            let tmp = tmp_path();
+            let mut file = OpenOptions::new().create(true).read(true).write(true).truncate(true).open(&tmp)?;
+            file.write_all(&bytes)?;
+            file.set_len(size as u64)?;
+            file.sync_all()?;
+            fs::rename(&tmp, SPOOL_PATH)?;
+            File::open(spool_dir())?.sync_all()?;
+            Ok(file)
+        };
+        let file = tokio::task::spawn_blocking(write).await.map_err(io::Error::other)??;
+        // SAFETY: as in `open`.
+        self.map = unsafe { MmapMut::map_mut(&file)? };
+        self.file = file;
+        self.len = rest.len();
+        self.synced = rest.len();
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.map[..self.len].fill(0);
+        self.map.flush_range(0, self.len)?;
+        self.len = 0;
+        self.synced = 0;
+        Ok(())
+    }
+}
+
+/// Spool lines kept in memory only, for the lowest append latency: no file
+/// is written, and whatever has not reached Postgres when the process
+/// dies is gone.
+pub struct MemorySpool {
+    lines: Vec<u8>,
+}
+
+impl MemorySpool {
+    /// Leaves lines a durable backend did not flush where they are, for a
+    /// run with that backend to pick up.
+    fn open() -> io::Result<Self> {
+        match read_spool_file() {
+            Ok(left) if !left.is_empty() => warn!(
+                "{} has {} bytes of unflushed ticks the memory spool does not flush; run with the file or mmap \
+                 backend to store them",
+                SPOOL_PATH,
+                left.len()
+            ),
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(MemorySpool { lines: Vec::new() })
+    }
+
+    fn append(&mut self, tick: Tick, run: Ulid) -> io::Result<usize> {
+        let len = self.lines.len();
+        write_line(&mut self.lines, tick, run)?;
+        Ok(self.lines.len() - len)
+    }
+}
+
+fn write_line(out: &mut impl Write, tick: Tick, run: Ulid) -> io::Result<()> {
+    writeln!(out, "{},{},{},{},{},{}", tick.stock_id, tick.price, tick.unix_micros(), tick.seq, tick.id, run)
+}
+
+/// Where [`SpoolWriter::retain`] writes the new spool before renaming it.
+fn tmp_path() -> PathBuf {
+    Path::new(SPOOL_PATH).with_extension("tmp")
+}
+
+/// The spool's directory, fsynced after the rename in
+/// [`SpoolWriter::retain`] so that the rename itself survives a crash.
+fn spool_dir() -> &'static Path {
+    Path::new(SPOOL_PATH).parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."))
+}
+
+/// Length of the spooled data, ignoring the mmap backend's zero padding.
+fn data_len(bytes: &[u8]) -> usize {
+    bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len())
+}
+
+/// Reads the spool file as written by either backend.
+pub fn read_spool_file() -> io::Result<String> {
+    let mut bytes = fs::read(SPOOL_PATH)?;
+    bytes.truncate(data_len(&bytes));
+    String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// The timestamped ticks of a recording in the spool format, in file order,
+/// with their IDs if they were spooled with one.
+pub fn recorded_ticks(content: &str) -> Vec<(i32, Price, DateTime<Utc>, Option<Ulid>)> {
+    // The mmap spool backend pads its file with zeros.
+    content
+        .trim_end_matches('\0')
+        .lines()
+        .filter_map(parse_line)
+        .filter_map(|r| r.ts.map(|ts| (r.stock_id, r.price, ts, r.id)))
+        .collect()
+}
+
+/// Parses a `stock_id,price[,unix_micros[,seq[,id[,run]]]]` spool line, logging malformed lines.
+pub fn parse_line(line: &str) -> Option<SpoolRecord> {
+    parse_record(line).map_err(|e| error!("{}", e)).ok()
+}
+
+/// As [`parse_line`], with what is malformed.
+fn parse_record(line: &str) -> Result<SpoolRecord, String> {
+    let parts: Vec<&str> = line.split(',').collect();
+    if !(2..=6).contains(&parts.len()) { return Err(format!("Expected 2 to 6 fields: {}", line)); }
+
+    let stock_id: i32 = parts[0].parse().map_err(|_| format!("Failed to parse stock_id: {}", parts[0]))?;
+    let price: Price = parts[1].parse().map_err(|_| format!("Failed to parse price: {}", parts[1]))?;
+    let ts = match parts.get(2) {
+        None => None,
+        Some(raw) => match raw.parse().ok().and_then(DateTime::from_timestamp_micros) {
+            Some(ts) => Some(ts),
+            None => return Err(format!("Failed to parse ts: {}", raw)),
+        },
+    };
+    let seq = match parts.get(3) {
+        None => None,
+        Some(raw) => Some(raw.parse().map_err(|_| format!("Failed to parse seq: {}", raw))?),
+    };
+    let id = match parts.get(4) {
+        None => None,
+        Some(raw) => Some(raw.parse().map_err(|_| format!("Failed to parse id: {}", raw))?),
+    };
+    let run = match parts.get(5) {
+        None => None,
+        Some(raw) => Some(raw.parse().map_err(|_| format!("Failed to parse run: {}", raw))?),
+    };
+    Ok(SpoolRecord { stock_id, price, ts, seq, id, run })
+}
+
+/// How a flush transaction ended other than with an error worth retrying.
+enum Outcome {
+    /// With the rows inserted, those already stored not counted.
+    Committed(usize),
+    /// Rolled back at the record at this index, which Postgres refused.
+    Rejected(usize, String),
+}
+
+/// Inserts `records` in one transaction, committed only if every insert
+/// succeeds. A tick already stored by an earlier flush is skipped by its ID.
+async fn insert_all(pool: &sqlx::PgPool, records: &[(&str, SpoolRecord)]) -> sqlx::Result<Outcome> {
+    let mut tx = pool.begin().await?;
+    let mut inserted = 0;
+    for (i, (_, record)) in records.iter().enumerate() {
+        let insert = sqlx::query(
+            "INSERT INTO stock_data (stock_id, price, ts, tick_id) VALUES ($1, $2, COALESCE($3, NOW()), $4) \
+             ON CONFLICT (tick_id) DO NOTHING",
+        )
+        .bind(record.stock_id)
+        .bind(record.price.to_decimal())
+        .bind(record.ts)
+        .bind(record.id.map(|id| id.to_string()))
+        .execute(&mut *tx)
+        .await;
+        match insert {
+            Ok(done) => inserted += done.rows_affected() as usize,
+            Err(e) if rejected(&e) => {
+                tx.rollback().await?;
+                return Ok(Outcome::Rejected(i, e.to_string()));
+            }
+            // Dropping the transaction rolls it back.
+            Err(e) => return Err(e),
+        }
+    }
+    tx.commit().await?;
+    Ok(Outcome::Committed(inserted))
+}
+
+/// Errors retrying cannot fix: bad data (SQLSTATE class 22) or a violated
+/// constraint (class 23).
+fn rejected(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::Database(db) => db.code().is_some_and(|code| code.starts_with("22") || code.starts_with("23")),
+        _ => false,
+    }
+}
+
+/// Inserts spooled ticks, up to the batch size of `settings` and as many
+/// as `limiter` admits, in one transaction. Lines past the batch stay
+/// spooled; with the `defer` policy so do those the limit holds back, with
+/// `drop` they are discarded. The spool loses the admitted lines only once
+/// the transaction has committed: one that still fails after the retries of
+/// `retry` is rolled back and leaves all of them spooled for the next flush.
+/// A row Postgres rejects itself rolls the transaction back too, and it
+/// goes again without that row, which joins the lines that do not parse in
+/// the dead letters once the rest is committed. Inserted ticks are checked
+/// by `seq`.
+pub async fn flush_to_postgres(
+    pool: Arc<sqlx::PgPool>,
+    spool: &mut SpoolWriter,
+    settings: &FlushSettings,
+    limiter: &RateLimiter,
+    retry: &Retrier,
+    seq: &mut SeqCheck,
+) -> io::Result<()> {
+    let content = spool.pending().await?;
+    if content.is_empty() {
+        spool.backlog.flushed(0, true);
+        return Ok(());
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let batch = settings.batch_rows().map_or(lines.len(), |n| n.min(lines.len()));
+    let admitted = limiter.try_admit(batch);
+    let held = batch - admitted;
+    limiter.record_held(held);
+    info!(
+        "Flushing {} lines to Postgres ({} held back by the rate limit, {} past the batch)...",
+        admitted,
+        held,
+        lines.len() - batch
+    );
+
+    let (mut records, mut dead) = (Vec::new(), Vec::new());
+    for line in &lines[..admitted] {
+        match parse_record(line) {
+            Ok(record) => records.push((*line, record)),
+            Err(e) => dead.push((*line, e)),
+        }
+    }
+    let (mut failed, mut inserted) = (None, 0);
+    loop {
+        match retry.run("flush", || insert_all(&pool, &records)).await {
+            Ok(Outcome::Committed(rows)) => {
+                inserted = rows;
+                break;
+            }
+            Ok(Outcome::Rejected(i, e)) => dead.push((records.remove(i).0, e)),
+            Err(e) => {
+                failed = Some(format!("{:?}", e));
+                break;
+            }
+        }
+    }
+    let committed = failed.is_none();
+    // Should this fail, only the lines it was moving stay spooled: the rest
+    // are committed, and those from before tick IDs would go in twice.
+    let mut undelivered = Vec::new();
+    if committed {
+        if let Err(e) = spool.dead_letter(&dead).await {
+            undelivered = dead.iter().map(|&(line, _)| line).collect();
+            failed = Some(format!("writing {}: {}", spool.dead_letter.display(), e));
+        }
+        // Lines left over from an earlier run are numbered by that run.
+        for (_, record) in records.iter().filter(|(_, record)| record.run == Some(spool.run)) {
+            if let Some(n) = record.seq {
+                seq.observe(record.stock_id, n);
+            }
+        }
+    }
+
+    // Held lines survive unless the rate limit drops them, and those past
+    // the batch always do.
+    let end = if limiter.policy() == Some(LimitPolicy::Drop) { admitted } else { batch };
+    let start = if committed { admitted } else { 0 };
+    let kept = undelivered.len() + end.saturating_sub(start) + lines.len() - batch;
+    if kept > 0 {
+        let offset = |i: usize| lines.get(i).map_or(content.len(), |l| l.as_ptr() as usize - content.as_ptr() as usize);
+        let mut retained: String = undelivered.iter().map(|line| format!("{}\n", line)).collect();
+        if start < end {
+            retained.push_str(&content[offset(start)..offset(end)]);
+        }
+        retained.push_str(&content[offset(batch)..]);
+        spool.retain(&retained).await?;
+    } else {
+        spool.clear().await?;
+    }
+
+    spool.backlog.flushed(inserted, failed.is_none());
+    if let Some(e) = failed {
+        return Err(io::Error::other(format!("Postgres flush failed, keeping {} lines spooled: {}", kept, e)));
+    }
+    info!("Flushed {} to Postgres successfully.", spool.describe());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn written_lines_parse_back() {
+        let ts = UNIX_EPOCH + Duration::from_micros(1_760_436_000_123_456);
+        let tick = Tick { stock_id: 3, price: "100.25".parse().unwrap(), ts, seq: 42, id: Ulid::new(ts) };
+        let run = Ulid::new(SystemTime::now());
+        let mut line = Vec::new();
+        write_line(&mut line, tick, run).unwrap();
+        let line = String::from_utf8(line).unwrap();
+
+        let record = parse_record(line.trim_end()).unwrap();
+        assert_eq!((record.stock_id, record.price), (3, tick.price));
+        assert_eq!(record.ts.map(SystemTime::from), Some(ts));
+        assert_eq!((record.seq, record.id, record.run), (Some(42), Some(tick.id), Some(run)));
+    }
+
+    #[test]
+    fn older_lines_leave_the_later_fields_out() {
+        let record = parse_record("0,12.5").unwrap();
+        assert!(record.ts.is_none() && record.seq.is_none() && record.id.is_none() && record.run.is_none());
+
+        let record = parse_record("0,12.5,1760436000000000,7").unwrap();
+        assert_eq!(record.ts.map(|ts| ts.timestamp_micros()), Some(1_760_436_000_000_000));
+        assert_eq!(record.seq, Some(7));
+        assert!(record.id.is_none() && record.run.is_none());
+    }
+
+    #[test]
+    fn malformed_lines_are_refused() {
+        let id = "01JA2Y7Q4M3X8E5B6C9D0F1G2H";
+        for line in [
+            "0".to_string(),
+            "x,12.5".to_string(),
+            "0,twelve".to_string(),
+            "0,12.5,soon".to_string(),
+            "0,12.5,1760436000000000,-1".to_string(),
+            "0,12.5,1760436000000000,7,not-an-id".to_string(),
+            format!("0,12.5,1760436000000000,7,{},{},extra", id, id),
+        ] {
+            assert!(parse_record(&line).is_err(), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn recordings_skip_lines_without_a_timestamp() {
+        let content = "0,12.5\n1,13.5,1760436000000000,1,01JA2Y7Q4M3X8E5B6C9D0F1G2H\n\0\0";
+        let ticks = recorded_ticks(content);
+        assert_eq!(ticks.len(), 1);
+        assert_eq!((ticks[0].0, ticks[0].3.map(|id| id.to_string())), (1, Some("01JA2Y7Q4M3X8E5B6C9D0F1G2H".into())));
+    }
+}
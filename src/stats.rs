@@ -0,0 +1,222 @@
+//! The Statistics panel: running min, max, mean, standard deviation and p99
+//! per stage and per stage and symbol, updated with every latency sample
+//! instead of recomputed from the histograms each frame. Mean and deviation
+//! use Welford's method and p99 the P² estimator, so each row costs a few
+//! dozen bytes however many symbols there are. `z` zeroes them, to read the
+//! current regime rather than everything since startup. Above them is the
+//! realized volatility of the symbols on the chart page.
+
+use std::collections::BTreeMap;
+
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+
+use crate::keymap::{Action, Keymap};
+use crate::latency::Stage;
+use crate::symbols::Symbol;
+
+const P99: f64 = 0.99;
+
+/// One stage's samples, or one symbol's samples at a stage.
+#[derive(Clone)]
+pub struct RunningStats {
+    count: u64,
+    min: u64,
+    max: u64,
+    mean: f64,
+    /// Sum of squared differences from the mean.
+    m2: f64,
+    p99: P2Quantile,
+}
+
+impl RunningStats {
+    pub fn new() -> Self {
+        RunningStats { count: 0, min: u64::MAX, max: 0, mean: 0.0, m2: 0.0, p99: P2Quantile::new(P99) }
+    }
+
+    pub fn record(&mut self, nanos: u64) {
+        self.count += 1;
+        self.min = self.min.min(nanos);
+        self.max = self.max.max(nanos);
+        let x = nanos as f64;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+        self.p99.record(x);
+    }
+
+    fn stddev(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        (self.m2 / (self.count - 1) as f64).sqrt()
+    }
+}
+
+/// Jain and Chlamtac's P² estimate of one quantile: five markers whose
+/// heights are nudged towards their ideal positions as samples arrive.
+#[derive(Clone)]
+struct P2Quantile {
+    p: f64,
+    /// Marker heights; the first samples verbatim until there are five.
+    heights: Vec<f64>,
+    positions: [f64; 5],
+    desired: [f64; 5],
+    increments: [f64; 5],
+}
+
+impl P2Quantile {
+    fn new(p: f64) -> Self {
+        P2Quantile {
+            p,
+            heights: Vec::with_capacity(5),
+            positions: [0.0, 1.0, 2.0, 3.0, 4.0],
+            desired: [0.0, 2.0 * p, 4.0 * p, 2.0 + 2.0 * p, 4.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+        }
+    }
+
+    fn record(&mut self, x: f64) {
+        if self.heights.len() < 5 {
+            self.heights.push(x);
+            self.heights.sort_by(f64::total_cmp);
+            return;
+        }
+        let q = &mut self.heights;
+        let n = &mut self.positions;
+        let k = if x < q[0] {
+            q[0] = x;
+            0
+        } else if x >= q[4] {
+            q[4] = x;
+            3
+        } else {
+            (0..4).find(|&i| x < q[i + 1]).unwrap()
+        };
+        for position in &mut n[k + 1..] {
+            *position += 1.0;
+        }
+        for (desired, increment) in self.desired.iter_mut().zip(self.increments) {
+            *desired += increment;
+        }
+        for i in 1..4 {
+            let d = self.desired[i] - n[i];
+            if (d >= 1.0 && n[i + 1] - n[i] > 1.0) || (d <= -1.0 && n[i - 1] - n[i] < -1.0) {
+                let d = d.signum();
+                // Parabolic prediction, or linear if it would leave the neighbours' range.
+                let parabolic = q[i]
+                    + d / (n[i + 1] - n[i - 1])
+                        * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                            + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]));
+                q[i] = if q[i - 1] < parabolic && parabolic < q[i + 1] {
+                    parabolic
+                } else {
+                    let j = if d > 0.0 { i + 1 } else { i - 1 };
+                    q[i] + d * (q[j] - q[i]) / (n[j] - n[i])
+                };
+                n[i] += d;
+            }
+        }
+    }
+
+    fn estimate(&self) -> f64 {
+        match self.heights.len() {
+            0 => 0.0,
+            len if len < 5 => self.heights[((len - 1) as f64 * self.p).round() as usize],
+            _ => self.heights[2],
+        }
+    }
+}
+
+/// Each stage's statistics followed by those of `stock_ids` at that stage,
+/// skipping symbols without samples there.
+pub fn rows(
+    running: &BTreeMap<(Stage, Option<i32>), RunningStats>,
+    stock_ids: &[usize],
+) -> Vec<(Stage, Option<i32>, RunningStats)> {
+    let mut rows = Vec::new();
+    for (&(stage, stock_id), all) in running {
+        if stock_id.is_some() {
+            continue;
+        }
+        rows.push((stage, None, all.clone()));
+        for &id in stock_ids {
+            if let Some(symbol) = running.get(&(stage, Some(id as i32))) {
+                rows.push((stage, Some(id as i32), symbol.clone()));
+            }
+        }
+    }
+    rows
+}
+
+/// Each page symbol's realized volatility and the returns it is over, then a
+/// stage's row followed by the rows of the symbols on the chart page that
+/// have samples there, in µs.
+pub fn render(
+    f: &mut Frame,
+    area: Rect,
+    rows: &[(Stage, Option<i32>, RunningStats)],
+    vols: &[(usize, Option<(f64, usize)>)],
+    symbols: &[Symbol],
+    page_label: &str,
+    keymap: &Keymap,
+) {
+    let mut lines = Vec::new();
+    if !vols.is_empty() {
+        let header = format!("{:<23} {:>9} {:>10} {:>10}", "realized volatility", "window", "returns", "annual");
+        lines.push(Line::styled(header, Style::default().add_modifier(Modifier::BOLD)));
+        for &(id, vol) in vols {
+            let Some(symbol) = symbols.get(id) else { continue };
+            let window = format!("{}s", symbol.vol_window.as_secs());
+            let line = match vol {
+                Some((vol, returns)) => {
+                    format!("{:<14} {:<8} {:>9} {:>10} {:>9.2}%", "", symbol.ticker, window, returns, vol * 100.0)
+                }
+                None => format!("{:<14} {:<8} {:>9} {:>10} {:>10}", "", symbol.ticker, window, 0, "-"),
+            };
+            lines.push(Line::raw(line));
+        }
+        lines.push(Line::raw(""));
+    }
+    let header = format!(
+        "{:<14} {:<8} {:>9} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "stage", "symbol", "count", "min", "mean", "stddev", "p99", "max"
+    );
+    lines.push(Line::styled(header, Style::default().add_modifier(Modifier::BOLD)));
+    for (stage, stock_id, stats) in rows {
+        let symbol = match stock_id {
+            Some(id) => usize::try_from(*id).ok().and_then(|id| symbols.get(id)).map_or("?", |s| s.ticker.as_str()),
+            None => "all",
+        };
+        let us = |nanos: f64| nanos / 1000.0;
+        let line = format!(
+            "{:<14} {:<8} {:>9} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10.2}",
+            if stock_id.is_some() { "" } else { stage.as_str() },
+            symbol,
+            stats.count,
+            us(stats.min as f64),
+            us(stats.mean),
+            us(stats.stddev()),
+            us(stats.p99.estimate()),
+            us(stats.max as f64)
+        );
+        lines.push(match stock_id {
+            Some(_) => Line::raw(line),
+            None => Line::styled(line, Style::default().add_modifier(Modifier::BOLD)),
+        });
+    }
+    if rows.is_empty() {
+        lines.push(Line::raw("no latency samples yet"));
+    }
+    let title = keymap.title(&format!("Statistics in µs ({})", page_label), &[
+        (&[Action::Stats], "charts"),
+        (&[Action::ResetStats], "reset"),
+        (&[Action::NextPage, Action::PreviousPage], "page"),
+        (&[Action::Search], "search"),
+        (&[Action::Watchlist], "watchlist"),
+    ]);
+    f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+}
@@ -0,0 +1,40 @@
+//! The Diagnostics and Health panels as data. The frame loop, or the status
+//! loop of a headless run, publishes them here; `GET /status` serves them to
+//! attached viewers.
+
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::health::ConnectionHealth;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatusLine {
+    pub label: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StatusReport {
+    pub diagnostics: Vec<StatusLine>,
+    pub health: Vec<ConnectionHealth>,
+}
+
+#[derive(Clone, Default)]
+pub struct StatusBoard {
+    status: Arc<RwLock<StatusReport>>,
+}
+
+impl StatusBoard {
+    pub fn set(&self, diagnostics: &[(&str, String)], health: Vec<ConnectionHealth>) {
+        let diagnostics = diagnostics
+            .iter()
+            .map(|(label, value)| StatusLine { label: label.to_string(), value: value.clone() })
+            .collect();
+        *self.status.write().unwrap() = StatusReport { diagnostics, health };
+    }
+
+    pub fn get(&self) -> StatusReport {
+        self.status.read().unwrap().clone()
+    }
+}
@@ -0,0 +1,133 @@
+//! Strategies trading paper orders, run by [`paper`](crate::paper) inside the
+//! engine's event loop. A strategy sees every tick, its own fills and a
+//! periodic timer, and answers with orders and cancels; the time each
+//! callback takes is its decision latency, recorded as the `decision` stage.
+//!
+//! To add one, implement [`Strategy`] in a module here and give it a name
+//! in [`StrategyKind`](crate::config::StrategyKind) and [`build`], or write
+//! it as a Rhai script and run it with `strategy = "script"`.
+
+mod momentum;
+mod passive;
+mod script;
+
+use std::io;
+use std::time::Duration;
+
+use crate::config::{PaperConfig, StrategyKind};
+use crate::engine::{Order, Side, TimeInForce, Visibility};
+use crate::script::ScriptStats;
+
+/// What a strategy sees of a tick. Prices are engine ticks.
+pub struct MarketTick<'a> {
+    pub stock_id: i32,
+    pub price: i64,
+    /// The market maker's best quotes once the tick is in the book.
+    pub bid: i64,
+    pub ask: i64,
+    /// Each venue's best quotes as last reported, which trail the tick by
+    /// the venue's quote lag and latency.
+    pub venues: &'a [VenueQuote],
+}
+
+#[derive(Clone, Copy)]
+pub struct VenueQuote {
+    pub bid: i64,
+    pub ask: i64,
+}
+
+/// Part or all of one of the strategy's orders trading.
+pub struct OrderFill {
+    pub id: u64,
+    /// Where it traded.
+    pub venue: usize,
+    pub stock_id: i32,
+    pub side: Side,
+    pub price: i64,
+    pub qty: u32,
+    /// Still open after this fill; 0 once the order is done.
+    pub left: u32,
+    /// Charged for this fill, in price units. `price` already has any slippage.
+    pub fee: f64,
+}
+
+/// Orders and cancels a callback asks for, sent in order once it returns.
+#[derive(Default)]
+pub struct Actions {
+    next_id: u64,
+    pending: Vec<Action>,
+    /// Time since the run started: wall time live, recorded time in a
+    /// backtest. Strategies keep time with this rather than the system clock.
+    pub now: Duration,
+}
+
+pub enum Action {
+    /// `venue` indexes `[[paper.venues]]`.
+    Place { venue: usize, order: Order },
+    /// Split across the venues by the smart order router.
+    Route { order: Order },
+    Cancel { stock_id: i32, id: u64 },
+}
+
+impl Actions {
+    /// On the first venue. Returns the new order's id.
+    pub fn place(&mut self, stock_id: i32, side: Side, price: i64, qty: u32, tif: TimeInForce) -> u64 {
+        self.place_on(0, stock_id, side, price, qty, tif)
+    }
+
+    pub fn place_on(&mut self, venue: usize, stock_id: i32, side: Side, price: i64, qty: u32, tif: TimeInForce) -> u64 {
+        self.next_id += 1;
+        let id = self.next_id;
+        let order = Order { id, stock_id, side, price, qty: qty.max(1), tif, visibility: Visibility::Lit };
+        self.pending.push(Action::Place { venue, order });
+        id
+    }
+
+    /// Through the smart order router, which may split it across venues;
+    /// its fills all carry the id returned here.
+    pub fn route(&mut self, stock_id: i32, side: Side, price: i64, qty: u32, tif: TimeInForce) -> u64 {
+        self.next_id += 1;
+        let id = self.next_id;
+        let order = Order { id, stock_id, side, price, qty: qty.max(1), tif, visibility: Visibility::Lit };
+        self.pending.push(Action::Route { order });
+        id
+    }
+
+    pub fn cancel(&mut self, stock_id: i32, id: u64) {
+        self.pending.push(Action::Cancel { stock_id, id });
+    }
+
+    /// What the last callback asked for.
+    pub fn take(&mut self) -> Vec<Action> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+pub trait Strategy: Send {
+    fn name(&self) -> &'static str;
+
+    fn on_tick(&mut self, tick: &MarketTick, actions: &mut Actions);
+
+    fn on_fill(&mut self, fill: &OrderFill, actions: &mut Actions);
+
+    /// Every `[paper] timer_ms`.
+    fn on_timer(&mut self, actions: &mut Actions);
+
+    /// Time per callback, for strategies that are scripts.
+    fn script_stats(&self) -> Option<ScriptStats> {
+        None
+    }
+}
+
+pub fn build(cfg: &PaperConfig) -> io::Result<Box<dyn Strategy>> {
+    Ok(match cfg.strategy {
+        StrategyKind::Passive => Box::new(passive::Passive::new(cfg)),
+        StrategyKind::Momentum => Box::new(momentum::Momentum::new(cfg)),
+        StrategyKind::Script => {
+            let path = cfg.script.as_deref().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "paper.script: required with strategy = \"script\"")
+            })?;
+            Box::new(script::ScriptStrategy::load(path)?)
+        }
+    })
+}
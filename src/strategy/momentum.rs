@@ -0,0 +1,42 @@
+//! Every `order_every` ticks of a symbol, follows its last move with an IOC
+//! order that crosses the spread, like `bench tick-to-trade`.
+
+use std::collections::HashMap;
+
+use super::{Actions, MarketTick, OrderFill, Strategy};
+use crate::config::PaperConfig;
+use crate::engine::{Side, TimeInForce};
+
+pub struct Momentum {
+    order_every: u64,
+    qty: u32,
+    /// By symbol: ticks seen and the price at the last order.
+    last: HashMap<i32, (u64, i64)>,
+}
+
+impl Momentum {
+    pub fn new(cfg: &PaperConfig) -> Self {
+        Momentum { order_every: cfg.order_every.max(1), qty: cfg.qty, last: HashMap::new() }
+    }
+}
+
+impl Strategy for Momentum {
+    fn name(&self) -> &'static str {
+        "momentum"
+    }
+
+    fn on_tick(&mut self, tick: &MarketTick, actions: &mut Actions) {
+        let (count, last) = self.last.entry(tick.stock_id).or_insert((0, tick.price));
+        *count += 1;
+        if !count.is_multiple_of(self.order_every) || tick.price == *last {
+            return;
+        }
+        let (side, price) = if tick.price > *last { (Side::Buy, tick.ask) } else { (Side::Sell, tick.bid) };
+        *last = tick.price;
+        actions.place(tick.stock_id, side, price, self.qty, TimeInForce::Ioc);
+    }
+
+    fn on_fill(&mut self, _: &OrderFill, _: &mut Actions) {}
+
+    fn on_timer(&mut self, _: &mut Actions) {}
+}
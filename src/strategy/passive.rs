@@ -0,0 +1,72 @@
+//! Rests a limit order on a random side every `order_every` ticks of a
+//! symbol, `offset_ticks` behind the quote, and cancels it after `ttl_ms`.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rand::Rng;
+
+use super::{Actions, MarketTick, OrderFill, Strategy};
+use crate::config::PaperConfig;
+use crate::engine::{Side, TimeInForce};
+
+pub struct Passive {
+    order_every: u64,
+    qty: u32,
+    offset: i64,
+    ttl: Duration,
+    seen: HashMap<i32, u64>,
+    /// Open orders: stock id and when to cancel.
+    open: HashMap<u64, (i32, Duration)>,
+}
+
+impl Passive {
+    pub fn new(cfg: &PaperConfig) -> Self {
+        Passive {
+            order_every: cfg.order_every.max(1),
+            qty: cfg.qty,
+            offset: cfg.offset_ticks,
+            ttl: Duration::from_millis(cfg.ttl_ms),
+            seen: HashMap::new(),
+            open: HashMap::new(),
+        }
+    }
+}
+
+impl Strategy for Passive {
+    fn name(&self) -> &'static str {
+        "passive"
+    }
+
+    fn on_tick(&mut self, tick: &MarketTick, actions: &mut Actions) {
+        let count = self.seen.entry(tick.stock_id).or_default();
+        *count += 1;
+        if !count.is_multiple_of(self.order_every) {
+            return;
+        }
+        let (side, price) = if rand::thread_rng().gen_bool(0.5) {
+            (Side::Buy, tick.bid - self.offset)
+        } else {
+            (Side::Sell, tick.ask + self.offset)
+        };
+        let id = actions.place(tick.stock_id, side, price, self.qty, TimeInForce::Gtc);
+        self.open.insert(id, (tick.stock_id, actions.now + self.ttl));
+    }
+
+    fn on_fill(&mut self, fill: &OrderFill, _: &mut Actions) {
+        if fill.left == 0 {
+            self.open.remove(&fill.id);
+        }
+    }
+
+    fn on_timer(&mut self, actions: &mut Actions) {
+        let now = actions.now;
+        self.open.retain(|&id, &mut (stock_id, expires)| {
+            if expires > now {
+                return true;
+            }
+            actions.cancel(stock_id, id);
+            false
+        });
+    }
+}
@@ -0,0 +1,136 @@
+//! A strategy written in Rhai, loaded from `[paper] script`. The script
+//! defines any of `init()`, `on_tick(tick)`, `on_fill(fill)` and
+//! `on_timer()`, and trades with `place(stock_id, side, price, qty, tif)`,
+//! which returns the order id, `place_on(venue, ...)` with the same
+//! arguments after the venue's index, `route(...)` with the same arguments
+//! for the smart order router to split across venues, and
+//! `cancel(stock_id, id)`; `now_ms()` is the time since the run started.
+//! Sides are `"buy"` and `"sell"`, time in force `"gtc"` and `"ioc"`,
+//! prices engine ticks.
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, INT};
+
+use super::{Actions, MarketTick, OrderFill, Strategy};
+use crate::engine::{Side, TimeInForce};
+use crate::script::{Script, ScriptStats};
+
+pub struct ScriptStrategy {
+    script: Script,
+    this: Dynamic,
+    /// What `place` and `cancel` write to: the callback's actions, swapped
+    /// in for the length of the call.
+    actions: Arc<Mutex<Actions>>,
+}
+
+impl ScriptStrategy {
+    pub fn load(path: &str) -> io::Result<Self> {
+        let actions = Arc::new(Mutex::new(Actions::default()));
+        let mut engine = Engine::new();
+        let place_to = Arc::clone(&actions);
+        let place = move |venue: INT, stock_id: INT, side: &str, price: INT, qty: INT, tif: &str| {
+            let (side, qty, tif) = order_args(side, qty, tif)?;
+            let venue = usize::try_from(venue).map_err(|_| format!("venue {} out of range", venue))?;
+            let id = place_to.lock().unwrap().place_on(venue, stock_id as i32, side, price, qty, tif);
+            Ok::<INT, Box<EvalAltResult>>(id as INT)
+        };
+        let place_first = place.clone();
+        engine.register_fn("place", move |stock_id: INT, side: &str, price: INT, qty: INT, tif: &str| {
+            place_first(0, stock_id, side, price, qty, tif)
+        });
+        engine.register_fn("place_on", place);
+        let route_to = Arc::clone(&actions);
+        engine.register_fn("route", move |stock_id: INT, side: &str, price: INT, qty: INT, tif: &str| {
+            let (side, qty, tif) = order_args(side, qty, tif)?;
+            let id = route_to.lock().unwrap().route(stock_id as i32, side, price, qty, tif);
+            Ok::<INT, Box<EvalAltResult>>(id as INT)
+        });
+        let clock = Arc::clone(&actions);
+        engine.register_fn("now_ms", move || clock.lock().unwrap().now.as_millis() as INT);
+        let cancel_to = Arc::clone(&actions);
+        engine.register_fn("cancel", move |stock_id: INT, id: INT| {
+            cancel_to.lock().unwrap().cancel(stock_id as i32, id as u64);
+        });
+        let script = Script::load(path, engine)?;
+        let this = script.init();
+        Ok(ScriptStrategy { script, this, actions })
+    }
+
+    fn call(&mut self, name: &'static str, arg: Option<Map>, actions: &mut Actions) {
+        std::mem::swap(actions, &mut self.actions.lock().unwrap());
+        match arg {
+            Some(map) => self.script.call::<Dynamic>(name, &mut self.this, (map,)),
+            None => self.script.call::<Dynamic>(name, &mut self.this, ()),
+        };
+        std::mem::swap(actions, &mut self.actions.lock().unwrap());
+    }
+}
+
+/// A script's side, quantity and time in force.
+fn order_args(side: &str, qty: INT, tif: &str) -> Result<(Side, u32, TimeInForce), Box<EvalAltResult>> {
+    let side = match side {
+        "buy" => Side::Buy,
+        "sell" => Side::Sell,
+        other => return Err(format!("side {:?} is not \"buy\" or \"sell\"", other).into()),
+    };
+    let tif = match tif {
+        "gtc" => TimeInForce::Gtc,
+        "ioc" => TimeInForce::Ioc,
+        other => return Err(format!("tif {:?} is not \"gtc\" or \"ioc\"", other).into()),
+    };
+    let qty = u32::try_from(qty).map_err(|_| format!("qty {} out of range", qty))?;
+    Ok((side, qty, tif))
+}
+
+impl Strategy for ScriptStrategy {
+    fn name(&self) -> &'static str {
+        "script"
+    }
+
+    fn on_tick(&mut self, tick: &MarketTick, actions: &mut Actions) {
+        let mut map = Map::new();
+        map.insert("stock_id".into(), (tick.stock_id as INT).into());
+        map.insert("price".into(), tick.price.into());
+        map.insert("bid".into(), tick.bid.into());
+        map.insert("ask".into(), tick.ask.into());
+        let venues: Array = tick
+            .venues
+            .iter()
+            .map(|quote| {
+                let mut venue = Map::new();
+                venue.insert("bid".into(), quote.bid.into());
+                venue.insert("ask".into(), quote.ask.into());
+                Dynamic::from_map(venue)
+            })
+            .collect();
+        map.insert("venues".into(), venues.into());
+        self.call("on_tick", Some(map), actions);
+    }
+
+    fn on_fill(&mut self, fill: &OrderFill, actions: &mut Actions) {
+        let mut map = Map::new();
+        map.insert("id".into(), (fill.id as INT).into());
+        map.insert("venue".into(), (fill.venue as INT).into());
+        map.insert("stock_id".into(), (fill.stock_id as INT).into());
+        let side = match fill.side {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        };
+        map.insert("side".into(), side.into());
+        map.insert("price".into(), fill.price.into());
+        map.insert("qty".into(), (fill.qty as INT).into());
+        map.insert("left".into(), (fill.left as INT).into());
+        map.insert("fee".into(), fill.fee.into());
+        self.call("on_fill", Some(map), actions);
+    }
+
+    fn on_timer(&mut self, actions: &mut Actions) {
+        self.call("on_timer", None, actions);
+    }
+
+    fn script_stats(&self) -> Option<ScriptStats> {
+        Some(self.script.stats())
+    }
+}
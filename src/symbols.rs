@@ -0,0 +1,174 @@
+//! The simulated instruments. A symbol's stock id is its position in
+//! `[[symbols]]` and stays the key in `stock_data`; the ticker names it in
+//! the charts, the Redis keys and the `symbols` table.
+
+use std::collections::HashSet;
+use std::io;
+use std::str::FromStr;
+use std::time::Duration;
+
+use ratatui::style::Color;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::config::{Config, Palette, SymbolConfig};
+use crate::price::Price;
+
+const DEFAULT: [Color; 6] = [Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Magenta, Color::Cyan];
+const DEUTERANOPIA: [Color; 7] = [
+    Color::Rgb(230, 159, 0),
+    Color::Rgb(86, 180, 233),
+    Color::Rgb(0, 158, 115),
+    Color::Rgb(240, 228, 66),
+    Color::Rgb(0, 114, 178),
+    Color::Rgb(213, 94, 0),
+    Color::Rgb(204, 121, 167),
+];
+const HIGH_CONTRAST: [Color; 6] =
+    [Color::White, Color::LightYellow, Color::LightCyan, Color::LightMagenta, Color::LightGreen, Color::LightRed];
+const MONOCHROME: [Color; 2] = [Color::White, Color::DarkGray];
+/// Line styles the monochrome palette cycles through, coprime with its
+/// shades so six neighbouring symbols all differ.
+const MONOCHROME_STYLES: usize = 3;
+
+/// Chart color of the symbol at `stock_id` if it does not set one.
+pub fn color(palette: Palette, stock_id: usize) -> Color {
+    let colors: &[Color] = match palette {
+        Palette::Default => &DEFAULT,
+        Palette::Deuteranopia => &DEUTERANOPIA,
+        Palette::HighContrast => &HIGH_CONTRAST,
+        Palette::Monochrome => &MONOCHROME,
+    };
+    colors[stock_id % colors.len()]
+}
+
+/// Which line style tells the symbol at `stock_id` apart, 0 the plain one
+/// and the only one outside the monochrome palette.
+pub fn line_style(palette: Palette, stock_id: usize) -> usize {
+    match palette {
+        Palette::Monochrome => stock_id % MONOCHROME_STYLES,
+        _ => 0,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Symbol {
+    pub ticker: String,
+    pub name: String,
+    pub tick_size: Price,
+    pub initial_price: Price,
+    pub color: Color,
+    /// Largest price move per tick.
+    pub volatility: f64,
+    /// Time between ticks.
+    pub interval: Duration,
+    /// Returns its realized volatility covers.
+    pub vol_window: Duration,
+}
+
+impl Symbol {
+    pub fn redis_key(&self) -> String {
+        format!("stock:{}", self.ticker)
+    }
+
+    /// Whole ticks `volatility` spans, at least one.
+    pub fn max_steps(&self) -> i64 {
+        ((self.volatility / self.tick_size.to_f64()) as i64).max(1)
+    }
+}
+
+/// Symbols used when the config lists none.
+fn defaults() -> Vec<SymbolConfig> {
+    [("AAPL", "Apple Inc."), ("MSFT", "Microsoft Corp."), ("GOOG", "Alphabet Inc.")]
+        .into_iter()
+        .map(|(ticker, name)| SymbolConfig {
+            ticker: ticker.to_string(),
+            name: Some(name.to_string()),
+            tick_size: None,
+            initial_price: Price::from_f64(100.0),
+            color: None,
+            volatility: 2.0,
+            interval_us: None,
+            realized_vol_window_s: None,
+        })
+        .collect()
+}
+
+/// Filler for `[universe] size`, spread over a range of starting prices.
+fn generated(i: usize) -> SymbolConfig {
+    SymbolConfig {
+        ticker: format!("SYM{:04}", i),
+        name: None,
+        tick_size: None,
+        initial_price: Price::from_f64(20.0 + (i * 37 % 480) as f64),
+        color: None,
+        volatility: 1.0,
+        interval_us: None,
+        realized_vol_window_s: None,
+    }
+}
+
+pub fn load(config: &Config) -> io::Result<Vec<Symbol>> {
+    let mut configured = if config.symbols.is_empty() { defaults() } else { config.symbols.clone() };
+    let listed = configured.len();
+    configured.extend((listed..config.universe.size).map(generated));
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    let mut seen = HashSet::new();
+    configured
+        .into_iter()
+        .enumerate()
+        .map(|(i, cfg)| {
+            if cfg.ticker.is_empty() || cfg.ticker.contains(char::is_whitespace) {
+                return Err(invalid(format!("[[symbols]] #{}: invalid ticker {:?}", i, cfg.ticker)));
+            }
+            if !seen.insert(cfg.ticker.clone()) {
+                return Err(invalid(format!("[[symbols]] ticker {} is listed twice", cfg.ticker)));
+            }
+            let tick_size = cfg.tick_size.unwrap_or(config.prices.tick_size);
+            if tick_size <= Price::default() {
+                return Err(invalid(format!("{}: tick size must be at least 0.000001, got {}", cfg.ticker, tick_size)));
+            }
+            if cfg.volatility.is_nan() || cfg.volatility < 0.0 {
+                return Err(invalid(format!("{}: volatility must not be negative", cfg.ticker)));
+            }
+            let vol_window_s = cfg.realized_vol_window_s.unwrap_or(config.realized_vol.window_s);
+            if vol_window_s == 0 {
+                return Err(invalid(format!("{}: realized volatility window must be at least 1s", cfg.ticker)));
+            }
+            let color = match &cfg.color {
+                Some(raw) => Color::from_str(raw).map_err(|_| invalid(format!("{}: unknown color {:?}", cfg.ticker, raw)))?,
+                None => color(config.ui.palette, i),
+            };
+            Ok(Symbol {
+                name: cfg.name.unwrap_or_else(|| cfg.ticker.clone()),
+                ticker: cfg.ticker,
+                tick_size,
+                initial_price: cfg.initial_price.round_to(tick_size),
+                color,
+                volatility: cfg.volatility,
+                interval: Duration::from_micros(cfg.interval_us.unwrap_or(config.producer.interval_us).max(1)),
+                vol_window: Duration::from_secs(vol_window_s),
+            })
+        })
+        .collect()
+}
+
+/// Replaces the `symbols` table with the configured symbols, so `stock_data`
+/// can be joined to tickers.
+pub async fn store(pool: &PgPool, symbols: &[Symbol]) -> io::Result<()> {
+    let mut tx = pool.begin().await.map_err(io::Error::other)?;
+    sqlx::query("DELETE FROM symbols").execute(&mut *tx).await.map_err(io::Error::other)?;
+    for (stock_id, symbol) in symbols.iter().enumerate() {
+        sqlx::query("INSERT INTO symbols (stock_id, ticker, name, tick_size) VALUES ($1, $2, $3, $4)")
+            .bind(stock_id as i32)
+            .bind(&symbol.ticker)
+            .bind(&symbol.name)
+            .bind(symbol.tick_size.to_decimal())
+            .execute(&mut *tx)
+            .await
+            .map_err(io::Error::other)?;
+    }
+    tx.commit().await.map_err(io::Error::other)?;
+    info!("Stored {} symbols", symbols.len());
+    Ok(())
+}
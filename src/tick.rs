@@ -0,0 +1,46 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tokio::sync::broadcast;
+
+use crate::bus::BUS_CAPACITY;
+use crate::price::Price;
+use crate::ulid::Ulid;
+
+/// A single price update as published to downstream consumers.
+#[derive(Clone, Copy, Debug)]
+pub struct Tick {
+    pub stock_id: i32,
+    pub price: Price,
+    pub ts: SystemTime,
+    /// Per-symbol, from 1; 0 where unknown, e.g. ticks read back from storage.
+    pub seq: u64,
+    /// Unique across runs, so a tick stored twice is one row.
+    pub id: Ulid,
+}
+
+impl Tick {
+    pub fn unix_micros(self) -> i64 {
+        self.ts.duration_since(UNIX_EPOCH).map(|d| d.as_micros() as i64).unwrap_or(0)
+    }
+
+    /// Wire format shared by the message-bus sinks:
+    /// `{"stock_id":0,"price":100.25,"ts_us":1760436000000000,"seq":42,"id":"01JA2Y7Q4M3X8E5B6C9D0F1G2H"}`.
+    pub fn to_json(self) -> String {
+        format!(
+            r#"{{"stock_id":{},"price":{},"ts_us":{},"seq":{},"id":"{}"}}"#,
+            self.stock_id,
+            self.price,
+            self.unix_micros(),
+            self.seq,
+            self.id
+        )
+    }
+}
+
+/// Fan-out of live ticks from the backend updater to every subscriber.
+pub type TickSender = broadcast::Sender<Tick>;
+pub type TickReceiver = broadcast::Receiver<Tick>;
+
+pub fn tick_bus() -> TickSender {
+    broadcast::channel(BUS_CAPACITY).0
+}
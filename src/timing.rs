@@ -0,0 +1,156 @@
+//! Swappable monotonic time sources for hot-path latency measurements.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+use crate::config::{TimerKind, TimingConfig};
+
+/// How long the TSC is sampled against `Instant` at startup.
+const CALIBRATION_WINDOW: Duration = Duration::from_millis(50);
+
+pub trait Clock: Send + Sync {
+    /// Nanoseconds since an arbitrary, fixed origin.
+    fn now_nanos(&self) -> u64;
+
+    fn name(&self) -> &'static str;
+
+    fn elapsed(&self, start: u64) -> Duration {
+        Duration::from_nanos(self.now_nanos().saturating_sub(start))
+    }
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+pub struct MonotonicClock {
+    origin: Instant,
+}
+
+impl MonotonicClock {
+    pub fn new() -> Self {
+        MonotonicClock { origin: Instant::now() }
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now_nanos(&self) -> u64 {
+        self.origin.elapsed().as_nanos() as u64
+    }
+
+    fn name(&self) -> &'static str {
+        "monotonic"
+    }
+}
+
+/// Time Stamp Counter scaled to nanoseconds. Requires an invariant TSC, i.e.
+/// one that ticks at a constant rate across cores and power states.
+pub struct TscClock {
+    origin_tsc: u64,
+    origin: Instant,
+    /// `f64` bits, so drift checks can recalibrate without locking readers.
+    nanos_per_tick: AtomicU64,
+}
+
+impl TscClock {
+    /// Returns `None` if this CPU has no invariant TSC. Blocks for the
+    /// calibration window.
+    #[cfg(target_arch = "x86_64")]
+    pub fn calibrate() -> Option<Self> {
+        use std::arch::x86_64::__cpuid;
+
+        // CPUID.80000007H:EDX[8] advertises the invariant TSC.
+        if __cpuid(0x8000_0000).eax < 0x8000_0007 || __cpuid(0x8000_0007).edx & (1 << 8) == 0 {
+            return None;
+        }
+        let origin = Instant::now();
+        let origin_tsc = rdtsc();
+        std::thread::sleep(CALIBRATION_WINDOW);
+        let clock = TscClock { origin_tsc, origin, nanos_per_tick: AtomicU64::new(0) };
+        clock.recalibrate();
+        Some(clock)
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    pub fn calibrate() -> Option<Self> {
+        None
+    }
+
+    fn nanos_per_tick(&self) -> f64 {
+        f64::from_bits(self.nanos_per_tick.load(Ordering::Relaxed))
+    }
+
+    /// Rescales over everything seen since the origin, which gets more
+    /// precise the longer the process runs.
+    fn recalibrate(&self) {
+        let ticks = rdtsc().wrapping_sub(self.origin_tsc).max(1);
+        let nanos = self.origin.elapsed().as_nanos() as f64;
+        self.nanos_per_tick.store((nanos / ticks as f64).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Drift of the TSC estimate from `Instant`, in parts per million.
+    fn drift_ppm(&self) -> f64 {
+        let real = self.origin.elapsed().as_nanos() as f64;
+        (self.now_nanos() as f64 - real) / real * 1e6
+    }
+}
+
+impl Clock for TscClock {
+    fn now_nanos(&self) -> u64 {
+        (rdtsc().wrapping_sub(self.origin_tsc) as f64 * self.nanos_per_tick()) as u64
+    }
+
+    fn name(&self) -> &'static str {
+        "tsc"
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn rdtsc() -> u64 {
+    // SAFETY: `rdtsc` is available on every x86_64 CPU.
+    unsafe { std::arch::x86_64::_rdtsc() }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn rdtsc() -> u64 {
+    0
+}
+
+/// Builds the configured clock, falling back to [`MonotonicClock`] when the
+/// TSC is unusable. A TSC clock gets a background drift check on the current
+/// runtime.
+pub fn from_config(cfg: &TimingConfig) -> SharedClock {
+    match cfg.clock {
+        TimerKind::Monotonic => Arc::new(MonotonicClock::new()),
+        TimerKind::Tsc => match TscClock::calibrate() {
+            Some(tsc) => {
+                let tsc = Arc::new(tsc);
+                info!("TSC clock calibrated at {:.4} ns/tick", tsc.nanos_per_tick());
+                tokio::spawn(check_drift(Arc::clone(&tsc), cfg.clone()));
+                tsc
+            }
+            None => {
+                warn!("No invariant TSC on this CPU, using the monotonic clock");
+                Arc::new(MonotonicClock::new())
+            }
+        },
+    }
+}
+
+async fn check_drift(tsc: Arc<TscClock>, cfg: TimingConfig) {
+    let mut interval = tokio::time::interval(Duration::from_millis(cfg.drift_check_ms.max(100)));
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let drift = tsc.drift_ppm();
+        if drift.abs() > cfg.max_drift_ppm {
+            tsc.recalibrate();
+            warn!(
+                "TSC drifted {:+.1} ppm from the monotonic clock, recalibrated to {:.4} ns/tick",
+                drift,
+                tsc.nanos_per_tick()
+            );
+        }
+    }
+}
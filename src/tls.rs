@@ -0,0 +1,61 @@
+//! TLS settings for the Postgres pool and Redis connections.
+
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use sqlx::postgres::{PgConnectOptions, PgSslMode};
+use url::Url;
+
+use crate::config::{PgSslModeConfig, PostgresTlsConfig, RedisTlsConfig};
+use crate::secrets::Dsn;
+
+/// Connect options for `url`, with `[tls.postgres]` taking precedence over
+/// any `sslmode` etc. in the URL itself.
+pub fn pg_connect_options(url: &Dsn, cfg: &PostgresTlsConfig) -> io::Result<PgConnectOptions> {
+    let mut options = PgConnectOptions::from_str(url.expose())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("Postgres URL {}: {}", url, e)))?;
+    if let Some(mode) = cfg.ssl_mode {
+        options = options.ssl_mode(match mode {
+            PgSslModeConfig::Disable => PgSslMode::Disable,
+            PgSslModeConfig::Allow => PgSslMode::Allow,
+            PgSslModeConfig::Prefer => PgSslMode::Prefer,
+            PgSslModeConfig::Require => PgSslMode::Require,
+            PgSslModeConfig::VerifyCa => PgSslMode::VerifyCa,
+            PgSslModeConfig::VerifyFull => PgSslMode::VerifyFull,
+        });
+    }
+    if let Some(path) = &cfg.ca_cert {
+        options = options.ssl_root_cert(readable(path)?);
+    }
+    match (&cfg.client_cert, &cfg.client_key) {
+        (Some(cert), Some(key)) => {
+            options = options.ssl_client_cert(readable(cert)?).ssl_client_key(readable(key)?);
+        }
+        (None, None) => {}
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "[tls.postgres] client_cert and client_key must be set together",
+            ))
+        }
+    }
+    Ok(options)
+}
+
+/// `url` with TLS verification turned off (`#insecure`) when
+/// `[tls.redis] insecure` is set. `rediss://` URLs are otherwise verified
+/// against the system trust store.
+pub fn redis_url(url: &Dsn, cfg: &RedisTlsConfig) -> io::Result<Url> {
+    let mut url = Url::parse(url.expose()).map_err(io::Error::other)?;
+    if cfg.insecure && url.scheme() == "rediss" {
+        url.set_fragment(Some("insecure"));
+    }
+    Ok(url)
+}
+
+/// Fails at startup rather than on the first connect.
+fn readable(path: &str) -> io::Result<&Path> {
+    std::fs::metadata(path).map_err(|e| io::Error::new(e.kind(), format!("Cannot read {}: {}", path, e)))?;
+    Ok(Path::new(path))
+}
@@ -0,0 +1,127 @@
+//! Tick IDs: a [`Ulid`] is given to every tick where it enters the
+//! pipeline and carried with it through the spool file to the `tick_id`
+//! column of `stock_data`, where a unique index turns a second insert of the
+//! same tick, from a retried flush or one repeated after a crash, into a
+//! no-op. IDs sort by the tick's millisecond, then at random.
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Crockford's base 32, which leaves out I, L, O and U.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+/// Characters of the text form; the first carries only 3 bits.
+const LEN: usize = 26;
+const RANDOM_BITS: u32 = 80;
+
+/// A 48-bit Unix millisecond timestamp over 80 random bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ulid(u128);
+
+impl Ulid {
+    pub fn new(ts: SystemTime) -> Self {
+        let ms = ts.duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0) & ((1 << 48) - 1);
+        Ulid(ms << RANDOM_BITS | rand::random::<u128>() >> (128 - RANDOM_BITS))
+    }
+}
+
+/// 26 upper-case characters: `01JA2Y7Q4M3X8E5B6C9D0F1G2H`.
+impl fmt::Display for Ulid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut text = [0u8; LEN];
+        for (i, c) in text.iter_mut().enumerate() {
+            *c = ALPHABET[(self.0 >> (5 * (LEN - 1 - i)) & 31) as usize];
+        }
+        f.write_str(std::str::from_utf8(&text).map_err(|_| fmt::Error)?)
+    }
+}
+
+/// Either case, with O read as 0 and I and L as 1.
+impl FromStr for Ulid {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("invalid tick ID {:?}", s));
+        if s.len() != LEN {
+            return Err(invalid());
+        }
+        s.chars()
+            .try_fold(0u128, |value, c| {
+                let c = match c.to_ascii_uppercase() {
+                    'O' => '0',
+                    'I' | 'L' => '1',
+                    c => c,
+                };
+                let digit = ALPHABET.iter().position(|&a| a as char == c).ok_or_else(invalid)?;
+                // Overflows past a first character of 7.
+                value.checked_mul(32).map(|value| value | digit as u128).ok_or_else(invalid)
+            })
+            .map(Ulid)
+    }
+}
+
+/// As its text form.
+impl Serialize for Ulid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Ulid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn text_round_trips() {
+        for id in [Ulid(0), Ulid(u128::MAX), Ulid::new(SystemTime::now())] {
+            let text = id.to_string();
+            assert_eq!(text.len(), LEN);
+            assert_eq!(text.parse::<Ulid>().unwrap(), id);
+        }
+        assert_eq!(Ulid(0).to_string(), "00000000000000000000000000");
+        assert_eq!(Ulid(u128::MAX).to_string(), "7ZZZZZZZZZZZZZZZZZZZZZZZZZ");
+    }
+
+    #[test]
+    fn parse_takes_either_case_and_the_look_alikes() {
+        let id: Ulid = "01JA2Y7Q4M3X8E5B6C9D0F1G2H".parse().unwrap();
+        assert_eq!("01ja2y7q4m3x8e5b6c9d0f1g2h".parse::<Ulid>().unwrap(), id);
+        assert_eq!("O1JA2Y7Q4M3X8E5B6C9DOF1G2H".parse::<Ulid>().unwrap(), id);
+        assert_eq!("0IJA2Y7Q4M3X8E5B6C9D0FLG2H".parse::<Ulid>().unwrap(), id);
+    }
+
+    #[test]
+    fn parse_rejects_malformed_ids() {
+        for text in ["", "01JA2Y7Q4M3X8E5B6C9D0F1G2", "01JA2Y7Q4M3X8E5B6C9D0F1G2HX", "81JA2Y7Q4M3X8E5B6C9D0F1G2H"] {
+            assert!(text.parse::<Ulid>().is_err(), "{:?}", text);
+        }
+        assert!("01JA2Y7Q4M3X8E5B6C9D0F1G2U".parse::<Ulid>().is_err());
+    }
+
+    #[test]
+    fn sorts_by_millisecond() {
+        let ts = UNIX_EPOCH + Duration::from_millis(1_760_436_000_000);
+        let (earlier, later) = (Ulid::new(ts), Ulid::new(ts + Duration::from_millis(1)));
+        assert!(earlier.0 < later.0);
+        assert!(earlier.to_string() < later.to_string());
+        assert_eq!(earlier.0 >> RANDOM_BITS, 1_760_436_000_000);
+    }
+
+    #[test]
+    fn serializes_as_text() {
+        let id = Ulid::new(SystemTime::now());
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, format!("\"{}\"", id));
+        assert_eq!(serde_json::from_str::<Ulid>(&json).unwrap(), id);
+    }
+}
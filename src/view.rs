@@ -0,0 +1,599 @@
+//! What the TUI shows below the status panels: one page of line, combined or
+//! candle charts, a grid summarizing every symbol, the latency statistics of a page
+//! or the latency heatmap, optionally narrowed down by a ticker search and
+//! the watchlist, or the SQL or Redis pane.
+
+use std::collections::BTreeSet;
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Utc};
+use crossterm::event::KeyCode;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Axis, Block, Borders, Paragraph};
+use ratatui::Frame;
+
+use crate::config::{ChartLayout, ChartMarker, Glyphs, Palette, UiConfig};
+use crate::keymap::{Action, Keymap};
+use crate::lttb;
+use crate::market::{self, MarketData};
+use crate::symbols::{self, Symbol};
+
+/// Symbols per page of charts, and lines in the Pointers panel.
+pub const CHART_PAGE_SIZE: usize = 6;
+/// Spans the charts zoom out to with `[history]`, past the points they keep.
+const ZOOMS: [Duration; 4] = [
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(30 * 60),
+    Duration::from_secs(2 * 3600),
+    Duration::from_secs(8 * 3600),
+];
+/// Width of one grid cell, e.g. `*BRK.A    650123.5 +12.34%`.
+const GRID_CELL_WIDTH: u16 = 28;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Charts,
+    Grid,
+    /// The Statistics panel, for the chart page.
+    Stats,
+    Heatmap,
+    /// The paper strategy's positions.
+    Portfolio,
+    /// Candlesticks of the chart page.
+    Candles,
+    /// Each symbol's price and moving average in one chart.
+    Combined,
+    /// The SQL pane.
+    Query,
+    /// The Redis pane.
+    Keys,
+}
+
+pub struct View {
+    symbols: Arc<Vec<Symbol>>,
+    pub mode: Mode,
+    chart_page: usize,
+    grid_page: usize,
+    /// Cells that fit at the last render; pages the grid.
+    grid_page_size: usize,
+    /// Case-insensitive ticker substring entered after `/`.
+    filter: String,
+    /// Keys go to `filter` until Enter or Esc.
+    searching: bool,
+    /// Stock ids.
+    watchlist: BTreeSet<usize>,
+    watchlist_only: bool,
+    /// Microprice and weighted mid drawn over the backend chart.
+    pub overlays: bool,
+    /// Index into `[candles] intervals_s`, wrapping.
+    pub candle_interval: usize,
+    /// Log-scale y axes on the backend chart, or the only chart of `attach`,
+    /// and on the frontend chart.
+    pub log_backend: bool,
+    pub log_frontend: bool,
+    /// Arrangement of the line charts.
+    pub layout: ChartLayout,
+    /// What the price lines are drawn with.
+    pub marker: ChartMarker,
+    palette: Palette,
+    glyphs: Glyphs,
+    keymap: Keymap,
+    /// The help overlay is up.
+    help: bool,
+    /// The command line being typed, without its `:`.
+    command: Option<String>,
+    /// Entered and not yet taken.
+    entered: Option<String>,
+    /// Outcome of the last command.
+    message: Option<String>,
+    /// The SQL pane's statement, and whether keys go to it.
+    sql: String,
+    editing_sql: bool,
+    /// Run and not yet taken.
+    entered_sql: Option<String>,
+    /// Of the SQL pane's result, clamped when drawn.
+    sql_page: usize,
+    /// The Redis pane is to read the keys again.
+    refresh_keys: bool,
+    keys_page: usize,
+    /// Settings and endpoints the help overlay lists after the view's own.
+    pub about: Vec<(&'static str, String)>,
+    /// Every cell redrawn in ASCII, from `[ui] glyphs`.
+    pub ascii: bool,
+    /// Index into [`ZOOMS`]; none charts the points in memory.
+    zoom: Option<usize>,
+    /// Half spans the zoomed window ends before now.
+    scroll: u32,
+}
+
+impl View {
+    pub fn new(symbols: Arc<Vec<Symbol>>, cfg: &UiConfig, keymap: Keymap) -> io::Result<Self> {
+        let watchlist = cfg
+            .watchlist
+            .iter()
+            .map(|ticker| {
+                symbols.iter().position(|s| &s.ticker == ticker).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, format!("[ui] watchlist: unknown ticker {}", ticker))
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(View {
+            symbols,
+            mode: Mode::Charts,
+            chart_page: 0,
+            grid_page: 0,
+            grid_page_size: 0,
+            filter: String::new(),
+            searching: false,
+            watchlist,
+            watchlist_only: false,
+            overlays: false,
+            candle_interval: 0,
+            log_backend: false,
+            log_frontend: false,
+            layout: cfg.layout,
+            marker: cfg.marker,
+            palette: cfg.palette,
+            glyphs: cfg.glyphs,
+            keymap,
+            help: false,
+            command: None,
+            entered: None,
+            message: None,
+            sql: String::new(),
+            editing_sql: false,
+            entered_sql: None,
+            sql_page: 0,
+            refresh_keys: false,
+            keys_page: 0,
+            about: Vec::new(),
+            ascii: cfg.glyphs.ascii(),
+            zoom: None,
+            scroll: 0,
+        })
+    }
+
+    /// Outside a search, the view actions of the keymap: by default `g`
+    /// toggles the grid, `s` the Statistics panel, `h` the heatmap, `c` the
+    /// candlesticks and `v` the combined price and moving-average chart, `i`
+    /// moves to the next candle interval, `n`/`p`, PageDown/PageUp, Home and
+    /// End flip pages, `/` starts a search, `+`/`-` add or remove the
+    /// matching symbols from the watchlist, `w` shows only the watchlist,
+    /// `m` the microprice overlays, `y`/`Y` log scale on the backend/frontend
+    /// chart, `[`/`]` zoom the charts out/in, Left/Right scroll a zoomed
+    /// chart back/forward, `l` moves to the next chart layout and `b` to the
+    /// next line marker. While searching every key edits the filter; Enter
+    /// keeps it, Esc clears it. On the command line Enter leaves the
+    /// command for [`Self::take_command`] and Esc drops it. In the SQL pane
+    /// `x` opens, Enter edits the statement and, editing, leaves it for
+    /// [`Self::take_sql`]; Esc stops editing. `k` opens the Redis pane and
+    /// Enter there reads the keys again, see [`Self::take_refresh_keys`].
+    /// Returns `false` for keys left to the caller.
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        self.message = None;
+        if let Some(command) = &mut self.command {
+            match code {
+                KeyCode::Char(c) => command.push(c),
+                KeyCode::Backspace if command.pop().is_none() => self.command = None,
+                KeyCode::Enter => self.entered = self.command.take(),
+                KeyCode::Esc => self.command = None,
+                _ => {}
+            }
+            return true;
+        }
+        if self.mode == Mode::Query && self.editing_sql {
+            match code {
+                KeyCode::Char(c) => self.sql.push(c),
+                KeyCode::Backspace => {
+                    self.sql.pop();
+                }
+                KeyCode::Enter => {
+                    self.entered_sql = Some(self.sql.clone());
+                    self.editing_sql = false;
+                    self.sql_page = 0;
+                }
+                KeyCode::Esc => self.editing_sql = false,
+                _ => {}
+            }
+            return true;
+        }
+        if self.mode == Mode::Query && code == KeyCode::Enter {
+            self.editing_sql = true;
+            return true;
+        }
+        if self.mode == Mode::Keys && code == KeyCode::Enter {
+            self.refresh_keys = true;
+            return true;
+        }
+        if self.searching {
+            match code {
+                KeyCode::Char(c) => self.filter.push(c),
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.searching = false;
+                }
+                _ => {}
+            }
+            self.chart_page = 0;
+            self.grid_page = 0;
+            return true;
+        }
+
+        if self.help && code == KeyCode::Esc {
+            self.help = false;
+            return true;
+        }
+        match self.keymap.action(code) {
+            Some(action) => self.apply(action),
+            None => false,
+        }
+    }
+
+    /// Carries out a view action; returns `false` for those left to the
+    /// caller.
+    pub fn apply(&mut self, action: Action) -> bool {
+        let pages = pages(self.visible().len(), self.page_size());
+        let (page, pages) = match self.mode {
+            Mode::Grid => (&mut self.grid_page, pages),
+            // Clamped to the result when drawn.
+            Mode::Query => (&mut self.sql_page, usize::MAX),
+            Mode::Keys => (&mut self.keys_page, usize::MAX),
+            _ => (&mut self.chart_page, pages),
+        };
+        match action {
+            Action::Grid => self.toggle(Mode::Grid),
+            Action::Stats => self.toggle(Mode::Stats),
+            Action::Heatmap => self.toggle(Mode::Heatmap),
+            Action::Positions => self.toggle(Mode::Portfolio),
+            Action::Candles => self.toggle(Mode::Candles),
+            Action::Combined => self.toggle(Mode::Combined),
+            Action::Query => {
+                self.toggle(Mode::Query);
+                self.editing_sql = self.sql.is_empty();
+            }
+            Action::RedisKeys => {
+                self.toggle(Mode::Keys);
+                self.refresh_keys = self.mode == Mode::Keys;
+            }
+            Action::CandleInterval => self.candle_interval += 1,
+            Action::NextPage => *page = (*page + 1).min(pages - 1),
+            Action::PreviousPage => *page = page.saturating_sub(1),
+            Action::FirstPage => *page = 0,
+            Action::LastPage => *page = pages - 1,
+            Action::Search => self.searching = true,
+            Action::ClearFilter => self.filter.clear(),
+            Action::Watch => self.watchlist.extend(self.matching()),
+            Action::Unwatch => {
+                for id in self.matching() {
+                    self.watchlist.remove(&id);
+                }
+            }
+            Action::Watchlist => self.watchlist_only = !self.watchlist_only,
+            Action::Microprice => self.overlays = !self.overlays,
+            Action::LogBackend => self.log_backend = !self.log_backend,
+            Action::LogFrontend => self.log_frontend = !self.log_frontend,
+            Action::Layout => self.layout = self.layout.next(),
+            Action::Marker => self.marker = self.marker.next(),
+            Action::ZoomOut => self.zoom = Some(self.zoom.map_or(0, |z| (z + 1).min(ZOOMS.len() - 1))),
+            Action::ZoomIn => {
+                self.zoom = self.zoom.and_then(|z| z.checked_sub(1));
+                if self.zoom.is_none() {
+                    self.scroll = 0;
+                }
+            }
+            Action::ScrollBack if self.zoom.is_some() => self.scroll += 1,
+            Action::ScrollForward => self.scroll = self.scroll.saturating_sub(1),
+            Action::Help => self.help = !self.help,
+            Action::Command => self.command = Some(String::new()),
+            _ => return false,
+        }
+        // The visible set may have shrunk.
+        if matches!(action, Action::ClearFilter | Action::Unwatch | Action::Watchlist) {
+            self.chart_page = 0;
+            self.grid_page = 0;
+        }
+        true
+    }
+
+    /// The command entered on the command line, once.
+    pub fn take_command(&mut self) -> Option<String> {
+        self.entered.take()
+    }
+
+    /// The statement run in the SQL pane, once.
+    pub fn take_sql(&mut self) -> Option<String> {
+        self.entered_sql.take()
+    }
+
+    /// The SQL pane's input line, and its page clamped to `pages`.
+    pub fn sql(&mut self, pages: usize) -> (Line<'static>, usize) {
+        self.sql_page = self.sql_page.min(pages.saturating_sub(1));
+        let cursor = if self.editing_sql { "_" } else { "" };
+        (Line::from(format!("SQL> {}{}", self.sql, cursor)), self.sql_page)
+    }
+
+    /// Whether the Redis pane was opened or asked to read the keys again
+    /// since the last call.
+    pub fn take_refresh_keys(&mut self) -> bool {
+        std::mem::take(&mut self.refresh_keys)
+    }
+
+    /// The Redis pane's page clamped to `pages`.
+    pub fn keys_page(&mut self, pages: usize) -> usize {
+        self.keys_page = self.keys_page.min(pages.saturating_sub(1));
+        self.keys_page
+    }
+
+    /// Shows `message` on the command line until the next key.
+    pub fn report(&mut self, message: impl Into<String>) {
+        self.message = Some(message.into());
+    }
+
+    /// The command being typed, or the last report.
+    pub fn prompt(&self) -> Option<Line<'static>> {
+        match (&self.command, &self.message) {
+            (Some(command), _) => Some(Line::from(format!(":{}_", command))),
+            (None, Some(message)) => Some(Line::from(message.clone())),
+            (None, None) => None,
+        }
+    }
+
+    /// Moves the charts to the page with `ticker`, clearing a search or
+    /// watchlist that hides it.
+    pub fn show_symbol(&mut self, ticker: &str) -> Result<(), String> {
+        let id = self
+            .symbols
+            .iter()
+            .position(|s| s.ticker.eq_ignore_ascii_case(ticker))
+            .ok_or_else(|| format!("unknown ticker {}", ticker))?;
+        if !self.visible().contains(&id) {
+            self.filter.clear();
+            self.watchlist_only = false;
+        }
+        let visible = self.visible();
+        self.chart_page = visible.iter().position(|&v| v == id).unwrap_or(0) / CHART_PAGE_SIZE;
+        if self.mode == Mode::Grid {
+            self.mode = Mode::Charts;
+        }
+        Ok(())
+    }
+
+    /// While the help overlay is up, its two columns: every key binding,
+    /// then the view's settings and [`Self::about`].
+    pub fn help(&self) -> Option<[Vec<Line<'static>>; 2]> {
+        if !self.help {
+            return None;
+        }
+        let keys = self.keymap.bindings().into_iter().map(|(keys, what)| {
+            Line::from(vec![Span::styled(format!("{:<16}", keys), Style::default().fg(Color::Cyan)), Span::raw(what)])
+        });
+        let settings = [
+            ("Layout", format!("{:?}", self.layout)),
+            ("Marker", format!("{:?}", self.marker)),
+            ("Palette", format!("{:?}", self.palette)),
+            ("Glyphs", format!("{:?}{}", self.glyphs, if self.ascii { ", ASCII" } else { "" })),
+        ];
+        let settings = settings.into_iter().chain(self.about.iter().cloned()).map(|(name, value)| {
+            Line::from(vec![Span::styled(format!("{:<14}", name), Style::default().fg(Color::Cyan)), Span::raw(value)])
+        });
+        Some([keys.collect(), settings.collect()])
+    }
+
+    /// The marker of the symbol at `stock_id`'s price line: [`Self::marker`],
+    /// or one after it for the palettes that tell lines apart by style.
+    pub fn marker_of(&self, stock_id: usize) -> Marker {
+        let mut marker = self.marker;
+        for _ in 0..symbols::line_style(self.palette, stock_id) {
+            marker = marker.next();
+        }
+        marker.marker()
+    }
+
+    /// Switches to `mode`, or back to the charts if already there.
+    fn toggle(&mut self, mode: Mode) {
+        self.mode = if self.mode == mode { Mode::Charts } else { mode };
+    }
+
+    fn page_size(&self) -> usize {
+        if self.mode == Mode::Grid {
+            self.grid_page_size.max(1)
+        } else {
+            CHART_PAGE_SIZE
+        }
+    }
+
+    /// Stock ids whose ticker contains the filter.
+    fn matching(&self) -> Vec<usize> {
+        let filter = self.filter.to_uppercase();
+        (0..self.symbols.len()).filter(|&id| self.symbols[id].ticker.to_uppercase().contains(&filter)).collect()
+    }
+
+    /// Stock ids passing the filter and, if on, the watchlist.
+    fn visible(&self) -> Vec<usize> {
+        let mut ids = self.matching();
+        if self.watchlist_only {
+            ids.retain(|id| self.watchlist.contains(id));
+        }
+        ids
+    }
+
+    /// Stock ids on the current chart page.
+    pub fn chart_ids(&self) -> Vec<usize> {
+        let visible = self.visible();
+        let page = self.chart_page.min(pages(visible.len(), CHART_PAGE_SIZE) - 1);
+        visible.into_iter().skip(page * CHART_PAGE_SIZE).take(CHART_PAGE_SIZE).collect()
+    }
+
+    /// The span a zoomed chart covers, ending `scroll` half spans before
+    /// `now`; none when not zoomed out.
+    pub fn window(&self, now: SystemTime) -> Option<(SystemTime, SystemTime)> {
+        let span = ZOOMS[self.zoom?];
+        let to = now - span / 2 * self.scroll;
+        Some((to - span, to))
+    }
+
+    /// `, last 30m` or `, 30m to 13:45:00`, for chart titles; empty when
+    /// not zoomed out.
+    pub fn zoom_label(&self) -> String {
+        let Some(zoom) = self.zoom else { return String::new() };
+        let span = ZOOMS[zoom].as_secs();
+        let span = if span >= 3600 { format!("{}h", span / 3600) } else { format!("{}m", span / 60) };
+        match self.window(SystemTime::now()) {
+            Some((_, to)) if self.scroll > 0 => {
+                format!(", {} to {}", span, DateTime::<Utc>::from(to).format("%H:%M:%S"))
+            }
+            _ => format!(", last {}", span),
+        }
+    }
+
+    /// `page 2/84, /AA, watchlist (3)`, for panel titles.
+    pub fn chart_page_label(&self) -> String {
+        let pages = pages(self.visible().len(), CHART_PAGE_SIZE);
+        format!("page {}/{}{}", self.chart_page.min(pages - 1) + 1, pages, self.filter_label())
+    }
+
+    fn filter_label(&self) -> String {
+        let mut label = String::new();
+        if self.searching || !self.filter.is_empty() {
+            label += &format!(", /{}{}", self.filter, if self.searching { "_" } else { "" });
+        }
+        if self.watchlist_only {
+            label += &format!(", watchlist ({})", self.watchlist.len());
+        }
+        label
+    }
+
+    /// Every visible symbol as `ticker price change`, colored by the change
+    /// over the chart window, in as many columns as fit. Watched symbols are
+    /// starred.
+    pub fn render_grid(&mut self, f: &mut Frame, area: Rect, md_vec: &[MarketData]) {
+        let columns = (area.width.saturating_sub(2) / GRID_CELL_WIDTH).max(1) as usize;
+        let rows = area.height.saturating_sub(2).max(1) as usize;
+        self.grid_page_size = columns * rows;
+        let visible = self.visible();
+        let pages = pages(visible.len(), self.grid_page_size);
+        self.grid_page = self.grid_page.min(pages - 1);
+
+        let cells: Vec<Vec<Span>> = visible
+            .iter()
+            .skip(self.grid_page * self.grid_page_size)
+            .take(self.grid_page_size)
+            .map(|&id| {
+                let md = &md_vec[id];
+                let price = *md.price.read().unwrap();
+                let first = md.history.first().unwrap_or(price.to_f64());
+                let change = if first != 0.0 { (price.to_f64() / first - 1.0) * 100.0 } else { 0.0 };
+                let color = match change {
+                    c if c > 0.0 => Color::Green,
+                    c if c < 0.0 => Color::Red,
+                    _ => Color::Gray,
+                };
+                let symbol = &self.symbols[id];
+                let star = if self.watchlist.contains(&id) { "*" } else { " " };
+                vec![
+                    Span::styled(format!("{}{:<7}", star, symbol.ticker), Style::default().fg(symbol.color)),
+                    Span::raw(format!("{:>10} ", price)),
+                    Span::styled(format!("{:>+7.2}%", change), Style::default().fg(color)),
+                ]
+            })
+            .collect();
+
+        // Column-major, so tickers read top to bottom like a listing.
+        let lines: Vec<Line> = (0..rows.min(cells.len()))
+            .map(|row| {
+                let mut spans = Vec::new();
+                for cell in cells.iter().skip(row).step_by(rows) {
+                    let width: usize = cell.iter().map(|s| s.content.chars().count()).sum();
+                    spans.extend(cell.iter().cloned());
+                    spans.push(Span::raw(" ".repeat((GRID_CELL_WIDTH as usize).saturating_sub(width))));
+                }
+                Line::from(spans)
+            })
+            .collect();
+
+        let title = self.keymap.title(
+            &format!("All symbols ({}, page {}/{}{})", visible.len(), self.grid_page + 1, pages, self.filter_label()),
+            &[
+                (&[Action::Grid], "charts"),
+                (&[Action::NextPage, Action::PreviousPage], "page"),
+                (&[Action::Search], "search"),
+                (&[Action::Watch, Action::Unwatch], "watch"),
+                (&[Action::Watchlist], "watchlist"),
+            ],
+        );
+        f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+    }
+}
+
+/// `points` as drawn on a log axis if `log`: the natural log of each price,
+/// without those at or below zero, which have none.
+pub fn scale(points: Vec<(f64, f64)>, log: bool) -> Vec<(f64, f64)> {
+    if !log {
+        return points;
+    }
+    points.into_iter().filter(|&(_, y)| y > 0.0).map(|(x, y)| (x, y.ln())).collect()
+}
+
+/// `points` as drawn in a chart `width` cells wide: [`scale`]d, then
+/// downsampled to two points per Braille column when there are more.
+pub fn plot(points: Vec<(f64, f64)>, log: bool, width: u16) -> Vec<(f64, f64)> {
+    let points = scale(points, log);
+    let fit = width as usize * 2;
+    if points.len() <= fit {
+        return points;
+    }
+    lttb::downsample(&points, fit)
+}
+
+/// A y axis over the values of `series`, already [`scale`]d: a point either
+/// side of them on a linear axis, or 1% on a log axis, which is labelled
+/// with the prices at the bottom, the geometric middle and the top.
+pub fn price_axis<'a>(series: impl IntoIterator<Item = &'a [(f64, f64)]>, log: bool) -> Axis<'static> {
+    let (low, high) = series
+        .into_iter()
+        .flatten()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &(_, y)| (low.min(y), high.max(y)));
+    let (low, high) = if low <= high { (low, high) } else { (0.0, 0.0) };
+    if !log {
+        return Axis::default().bounds([low - 1.0, high + 1.0]);
+    }
+    let (low, high) = (low - 0.01, high + 0.01);
+    let label = |y: f64| format!("{:.2}", y.exp());
+    Axis::default()
+        .title("log")
+        .bounds([low, high])
+        .labels([label(low), label((low + high) / 2.0), label(high)])
+}
+
+/// An x axis over the times of the points of `series`, labelled at both
+/// ends and the middle with the UTC wall-clock time, to the millisecond.
+pub fn time_axis<'a>(series: impl IntoIterator<Item = &'a [(f64, f64)]>) -> Axis<'static> {
+    let (first, last) = series
+        .into_iter()
+        .flatten()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(first, last), &(x, _)| (first.min(x), last.max(x)));
+    let (first, last) = if first <= last {
+        (first.min(last - 1.0), last)
+    } else {
+        let now = market::chart_x(SystemTime::now());
+        (now - 1.0, now)
+    };
+    let label = |x: f64| {
+        let at = DateTime::<Utc>::from_timestamp_micros((x * 1e6) as i64).unwrap_or_default();
+        at.format("%H:%M:%S%.3f").to_string()
+    };
+    Axis::default().bounds([first, last]).labels([label(first), label((first + last) / 2.0), label(last)])
+}
+
+fn pages(n: usize, page_size: usize) -> usize {
+    n.div_ceil(page_size).max(1)
+}
@@ -0,0 +1,103 @@
+//! Realized volatility of each symbol: the root of its summed squared log
+//! returns from tick to tick over the last `vol_window`, scaled to a trading
+//! year of 252 days of 6.5 hours. Until a symbol has ticked for a whole
+//! window the returns are scaled by the time they do cover. Returns from or
+//! to a price at or below zero are undefined and skipped. The Statistics
+//! panel shows the estimate of the symbols on the chart page, and exports
+//! carry it with every tick; `attach` estimates it from the ticks it
+//! streams, over its own config's window.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+use crate::price::Price;
+use crate::symbols::Symbol;
+use crate::tick::Tick;
+
+/// Seconds of trading in a year.
+const YEAR_S: f64 = 252.0 * 6.5 * 3600.0;
+
+#[derive(Default)]
+struct Window {
+    last: Option<Price>,
+    /// When the symbol first ticked.
+    started: Option<SystemTime>,
+    /// Each log return, oldest first, with when it ended.
+    returns: VecDeque<(SystemTime, f64)>,
+    sum_sq: f64,
+    /// As of the last tick.
+    vol: Option<f64>,
+}
+
+#[derive(Clone)]
+pub struct RealizedVol {
+    /// By stock id.
+    windows: Arc<Vec<Duration>>,
+    inner: Arc<Mutex<Vec<Window>>>,
+}
+
+impl RealizedVol {
+    pub fn new(symbols: &[Symbol]) -> Self {
+        RealizedVol {
+            windows: Arc::new(symbols.iter().map(|s| s.vol_window).collect()),
+            inner: Arc::new(Mutex::new(symbols.iter().map(|_| Window::default()).collect())),
+        }
+    }
+
+    /// Adds the return to `tick` to its symbol's window. Returns the
+    /// annualized volatility after it, as a fraction; none before the
+    /// symbol's first return or for an unknown stock id.
+    pub fn record(&self, tick: &Tick) -> Option<f64> {
+        let id = usize::try_from(tick.stock_id).ok()?;
+        let window = *self.windows.get(id)?;
+        let mut inner = self.inner.lock().unwrap();
+        let w = &mut inner[id];
+        let started = *w.started.get_or_insert(tick.ts);
+        if let Some(last) = w.last.replace(tick.price) {
+            if last > Price::default() && tick.price > Price::default() {
+                let r = (tick.price.to_f64() / last.to_f64()).ln();
+                w.returns.push_back((tick.ts, r));
+                w.sum_sq += r * r;
+            }
+        }
+        while let Some(&(at, r)) = w.returns.front() {
+            if tick.ts.duration_since(at).unwrap_or_default() <= window {
+                break;
+            }
+            w.sum_sq -= r * r;
+            w.returns.pop_front();
+        }
+        if w.returns.is_empty() {
+            // Drops what rounding has left over.
+            w.sum_sq = 0.0;
+        }
+        let covered = tick.ts.duration_since(started).unwrap_or_default().min(window).as_secs_f64();
+        w.vol = (!w.returns.is_empty() && covered > 0.0).then(|| (w.sum_sq.max(0.0) * YEAR_S / covered).sqrt());
+        w.vol
+    }
+
+    /// Records the ticks of a bus, for a TUI that does not publish them.
+    pub async fn run(self, mut rx: broadcast::Receiver<Tick>) {
+        loop {
+            match rx.recv().await {
+                Ok(tick) => {
+                    self.record(&tick);
+                }
+                Err(RecvError::Lagged(n)) => warn!("Realized volatility lagged, skipped {} ticks", n),
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// The estimate as of the symbol's last tick, with the returns in its
+    /// window.
+    pub fn current(&self, stock_id: usize) -> Option<(f64, usize)> {
+        let inner = self.inner.lock().unwrap();
+        let w = inner.get(stock_id)?;
+        w.vol.map(|vol| (vol, w.returns.len()))
+    }
+}